@@ -2,7 +2,16 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::path::Path;
+
+use iota_transactional_test_runner::suite::{SuiteConfig, run_test_suite};
+
 pub const TEST_DIR: &str = "tests";
-use iota_transactional_test_runner::run_test;
 
-datatest_stable::harness!(run_test, TEST_DIR, r".*\.(mvir|move)$");
+fn main() -> anyhow::Result<()> {
+    run_test_suite(
+        Path::new(TEST_DIR),
+        r".*\.(mvir|move)$",
+        &SuiteConfig::from_env()?,
+    )
+}
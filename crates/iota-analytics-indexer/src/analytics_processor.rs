@@ -22,9 +22,9 @@ use tokio::sync::{Mutex, mpsc, oneshot};
 use tracing::{error, info};
 
 use crate::{
-    AnalyticsIndexerConfig, EPOCH_DIR_PREFIX, FileMetadata, MaxCheckpointReader, ParquetSchema,
-    analytics_metrics::AnalyticsMetrics, handlers::AnalyticsHandler, join_paths,
-    writers::AnalyticsWriter,
+    AnalyticsIndexerConfig, EPOCH_DIR_PREFIX, FileFormat, FileMetadata, MaxCheckpointReader,
+    ParquetSchema, analytics_metrics::AnalyticsMetrics, delta_table::DeltaTable,
+    handlers::AnalyticsHandler, join_paths, writers::AnalyticsWriter,
 };
 
 struct State<S: Serialize + ParquetSchema> {
@@ -119,11 +119,21 @@ impl<S: Serialize + ParquetSchema + 'static> AnalyticsProcessor<S> {
         let name: String = handler.name().parse()?;
         let checkpoint_dir = config.checkpoint_dir.clone();
         let cloned_metrics = metrics.clone();
+        let delta_table = (config.file_format == FileFormat::DELTA).then(|| {
+            DeltaTable::new(
+                remote_object_store.clone(),
+                join_paths(
+                    config.remote_store_path_prefix.clone(),
+                    &config.file_type.dir_prefix(),
+                ),
+            )
+        });
         tokio::task::spawn(Self::start_syncing_with_remote(
             remote_object_store,
             local_object_store.clone(),
             checkpoint_dir,
             config.remote_store_path_prefix.clone(),
+            delta_table,
             receiver,
             kill_receiver,
             cloned_metrics,
@@ -219,6 +229,7 @@ impl<S: Serialize + ParquetSchema + 'static> AnalyticsProcessor<S> {
         local_object_store: Arc<DynObjectStore>,
         local_staging_root_dir: PathBuf,
         remote_store_path_prefix: Option<Path>,
+        mut delta_table: Option<DeltaTable>,
         mut file_recv: mpsc::Receiver<FileMetadata>,
         mut recv: oneshot::Receiver<()>,
         metrics: AnalyticsMetrics,
@@ -231,15 +242,26 @@ impl<S: Serialize + ParquetSchema + 'static> AnalyticsProcessor<S> {
                     if let Some(file_metadata) = file {
                         info!("Received {name} file with checkpoints: {:?}", &file_metadata.checkpoint_seq_range);
                         let checkpoint_seq_num = file_metadata.checkpoint_seq_range.end;
-                        Self::sync_file_to_remote(
-                                local_staging_root_dir.clone(),
-                                file_metadata.file_path(),
-                                remote_store_path_prefix.clone(),
-                                local_object_store.clone(),
-                                remote_object_store.clone()
-                            )
-                            .await
-                            .expect("Syncing checkpoint should not fail");
+                        if let Some(delta_table) = delta_table.as_mut() {
+                            Self::append_file_to_delta_table(
+                                    delta_table,
+                                    local_staging_root_dir.clone(),
+                                    &file_metadata,
+                                    local_object_store.clone(),
+                                )
+                                .await
+                                .expect("Appending to the delta table should not fail");
+                        } else {
+                            Self::sync_file_to_remote(
+                                    local_staging_root_dir.clone(),
+                                    file_metadata.file_path(),
+                                    remote_store_path_prefix.clone(),
+                                    local_object_store.clone(),
+                                    remote_object_store.clone()
+                                )
+                                .await
+                                .expect("Syncing checkpoint should not fail");
+                        }
                         metrics.last_uploaded_checkpoint.with_label_values(&[&name]).set(checkpoint_seq_num as i64);
                     } else {
                         info!("Terminating upload sync loop");
@@ -291,4 +313,25 @@ impl<S: Serialize + ParquetSchema + 'static> AnalyticsProcessor<S> {
         fs::remove_file(path_to_filesystem(dir, &path)?)?;
         Ok(())
     }
+
+    async fn append_file_to_delta_table(
+        delta_table: &mut DeltaTable,
+        dir: PathBuf,
+        file_metadata: &FileMetadata,
+        local_object_store: Arc<DynObjectStore>,
+    ) -> Result<()> {
+        let path = file_metadata.file_path();
+        let local_path = path_to_filesystem(dir, &path)?;
+        delta_table
+            .append(
+                &local_object_store,
+                &path,
+                &local_path,
+                file_metadata.epoch_num,
+                file_metadata.checkpoint_seq_range.clone(),
+            )
+            .await?;
+        fs::remove_file(local_path)?;
+        Ok(())
+    }
 }
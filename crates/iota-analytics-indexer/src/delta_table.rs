@@ -0,0 +1,378 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Maintains an append-only Delta Lake table per handler.
+//!
+//! Data files are the parquet files cut by the analytics processor, stored
+//! under `<table>/epoch=<epoch>/<start>_<end>.parquet`. Each file is added to
+//! the table by a new commit in `<table>/_delta_log`, which is created with a
+//! put-if-absent so that a commit is either fully visible or not at all. When
+//! a file has columns the table does not know yet, the same commit updates the
+//! table schema.
+
+use std::{fs, ops::Range, sync::Arc, time::SystemTime};
+
+use anyhow::{Context, Result, anyhow, bail};
+use arrow::datatypes::{DataType, SchemaRef};
+use bytes::Bytes;
+use iota_storage::object_store::util::{copy_file, get};
+use object_store::{DynObjectStore, ObjectStore, PutMode, PutOptions, path::Path};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::info;
+
+const DELTA_LOG_DIR: &str = "_delta_log";
+const PARTITION_COLUMN: &str = "epoch";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct DeltaField {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    nullable: bool,
+    #[serde(default)]
+    metadata: serde_json::Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DeltaSchema {
+    #[serde(rename = "type")]
+    schema_type: String,
+    fields: Vec<DeltaField>,
+}
+
+/// State of the table as of its latest commit.
+#[derive(Clone, Debug, Default)]
+struct DeltaTableState {
+    version: Option<u64>,
+    table_id: Option<String>,
+    fields: Vec<DeltaField>,
+    next_checkpoint_seq_num: u64,
+}
+
+pub(crate) struct DeltaTable {
+    store: Arc<DynObjectStore>,
+    root: Path,
+    state: Option<DeltaTableState>,
+}
+
+impl DeltaTable {
+    pub(crate) fn new(store: Arc<DynObjectStore>, root: Path) -> Self {
+        Self {
+            store,
+            root,
+            state: None,
+        }
+    }
+
+    /// Checkpoint following the last one added to the table, 0 for a new
+    /// table.
+    pub(crate) async fn next_checkpoint_seq_num(&mut self) -> Result<u64> {
+        Ok(self.state().await?.next_checkpoint_seq_num)
+    }
+
+    /// Uploads the local parquet file and commits it to the table.
+    pub(crate) async fn append(
+        &mut self,
+        local_store: &Arc<DynObjectStore>,
+        local_file: &Path,
+        local_file_path: &std::path::Path,
+        epoch: u64,
+        checkpoint_range: Range<u64>,
+    ) -> Result<()> {
+        let file_schema =
+            ParquetRecordBatchReaderBuilder::try_new(fs::File::open(local_file_path)?)?
+                .schema()
+                .clone();
+        let size = fs::metadata(local_file_path)?.len();
+        let relative_path = format!(
+            "{PARTITION_COLUMN}={epoch}/{}_{}.parquet",
+            checkpoint_range.start, checkpoint_range.end
+        );
+        copy_file(
+            local_file,
+            &self.data_path(&relative_path),
+            local_store,
+            &self.store,
+        )
+        .await?;
+
+        loop {
+            let state = self.state().await?.clone();
+            let version = state.version.map_or(0, |v| v + 1);
+            let commit = Self::commit_actions(&state, &file_schema, &relative_path, epoch, size)?;
+            if self.try_commit(version, commit).await? {
+                info!(
+                    "Committed {relative_path} as version {version} of {}",
+                    self.root
+                );
+                let state = self.state.as_mut().expect("state is loaded");
+                state.version = Some(version);
+                state.next_checkpoint_seq_num =
+                    state.next_checkpoint_seq_num.max(checkpoint_range.end);
+                return Ok(());
+            }
+            // another writer committed this version, retry on top of it
+            self.state = None;
+        }
+    }
+
+    fn data_path(&self, relative_path: &str) -> Path {
+        relative_path
+            .split('/')
+            .fold(self.root.clone(), |path, part| path.child(part))
+    }
+
+    fn log_path(&self, version: u64) -> Path {
+        self.root
+            .child(DELTA_LOG_DIR)
+            .child(format!("{version:020}.json"))
+    }
+
+    async fn state(&mut self) -> Result<&DeltaTableState> {
+        if self.state.is_none() {
+            self.state = Some(self.load().await?);
+        }
+        Ok(self.state.as_ref().expect("state is loaded"))
+    }
+
+    /// Replays the commits of the table.
+    async fn load(&self) -> Result<DeltaTableState> {
+        let mut versions = self
+            .store
+            .list_with_delimiter(Some(&self.root.child(DELTA_LOG_DIR)))
+            .await?
+            .objects
+            .into_iter()
+            .filter_map(|meta| {
+                meta.location
+                    .filename()?
+                    .strip_suffix(".json")?
+                    .parse::<u64>()
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        versions.sort_unstable();
+
+        let mut state = DeltaTableState::default();
+        for version in versions {
+            let bytes = get(&self.store, &self.log_path(version)).await?;
+            Self::apply_commit(&mut state, &bytes)
+                .with_context(|| format!("Invalid commit {version} in {}", self.root))?;
+            state.version = Some(version);
+        }
+        Ok(state)
+    }
+
+    fn apply_commit(state: &mut DeltaTableState, commit: &[u8]) -> Result<()> {
+        for line in std::str::from_utf8(commit)?.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let action: Value = serde_json::from_str(line)?;
+            if let Some(metadata) = action.get("metaData") {
+                let schema: DeltaSchema = serde_json::from_str(
+                    metadata["schemaString"]
+                        .as_str()
+                        .ok_or(anyhow!("Missing schemaString"))?,
+                )?;
+                state.fields = schema.fields;
+                state.table_id = metadata["id"].as_str().map(str::to_string);
+            }
+            if let Some(add) = action.get("add") {
+                let path = add["path"].as_str().ok_or(anyhow!("Missing add path"))?;
+                if let Some(end) = checkpoint_range_end(path) {
+                    state.next_checkpoint_seq_num = state.next_checkpoint_seq_num.max(end);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn commit_actions(
+        state: &DeltaTableState,
+        file_schema: &SchemaRef,
+        relative_path: &str,
+        epoch: u64,
+        size: u64,
+    ) -> Result<Vec<Value>> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_millis() as u64;
+        let mut actions = vec![];
+        if state.version.is_none() {
+            actions.push(json!({
+                "protocol": { "minReaderVersion": 1, "minWriterVersion": 2 }
+            }));
+        }
+        let fields = merge_fields(&state.fields, file_schema)?;
+        if state.version.is_none() || fields != state.fields {
+            let schema = DeltaSchema {
+                schema_type: "struct".to_string(),
+                fields,
+            };
+            actions.push(json!({
+                "metaData": {
+                    "id": state
+                        .table_id
+                        .clone()
+                        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                    "format": { "provider": "parquet", "options": {} },
+                    "schemaString": serde_json::to_string(&schema)?,
+                    "partitionColumns": [PARTITION_COLUMN],
+                    "configuration": {},
+                    "createdTime": now,
+                }
+            }));
+        }
+        actions.push(json!({
+            "add": {
+                "path": relative_path,
+                "partitionValues": { (PARTITION_COLUMN): epoch.to_string() },
+                "size": size,
+                "modificationTime": now,
+                "dataChange": true,
+            }
+        }));
+        actions.push(json!({
+            "commitInfo": {
+                "timestamp": now,
+                "operation": "WRITE",
+                "operationParameters": { "mode": "Append", "partitionBy": "[\"epoch\"]" },
+                "isBlindAppend": true,
+                "engineInfo": "iota-analytics-indexer",
+            }
+        }));
+        Ok(actions)
+    }
+
+    /// Writes the commit file of `version` unless it already exists. Returns
+    /// `false` if another writer created it first.
+    async fn try_commit(&self, version: u64, actions: Vec<Value>) -> Result<bool> {
+        let path = self.log_path(version);
+        let mut commit = actions
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+        commit.push('\n');
+        let options = PutOptions {
+            mode: PutMode::Create,
+            ..Default::default()
+        };
+        match self
+            .store
+            .put_opts(&path, Bytes::from(commit.clone()).into(), options)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::AlreadyExists { .. }) => Ok(false),
+            // Stores without conditional puts, e.g. S3 without a conditional put
+            // configuration, are only safe with a single writer per table.
+            Err(object_store::Error::NotImplemented) => {
+                if self.store.head(&path).await.is_ok() {
+                    return Ok(false);
+                }
+                self.store.put(&path, Bytes::from(commit).into()).await?;
+                Ok(true)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Appends the columns of `file_schema` missing from the table schema.
+fn merge_fields(table_fields: &[DeltaField], file_schema: &SchemaRef) -> Result<Vec<DeltaField>> {
+    let mut fields = table_fields.to_vec();
+    for file_field in file_schema.fields() {
+        let data_type = match file_field.data_type() {
+            DataType::Int64 => "long",
+            DataType::Utf8 => "string",
+            DataType::Boolean => "boolean",
+            data_type => bail!("Unsupported column type {data_type} for delta tables"),
+        };
+        match fields.iter().find(|f| &f.name == file_field.name()) {
+            Some(field) if field.data_type != data_type => bail!(
+                "Column {} changed type from {} to {data_type}",
+                field.name,
+                field.data_type
+            ),
+            Some(_) => {}
+            None => fields.push(DeltaField {
+                name: file_field.name().clone(),
+                data_type: data_type.to_string(),
+                nullable: true,
+                metadata: Default::default(),
+            }),
+        }
+    }
+    Ok(fields)
+}
+
+/// Parses the end of the checkpoint range from a `<start>_<end>.parquet` data
+/// file path.
+fn checkpoint_range_end(path: &str) -> Option<u64> {
+    path.rsplit('/')
+        .next()?
+        .strip_suffix(".parquet")?
+        .split_once('_')?
+        .1
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn evolve_schema() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("checkpoint", DataType::Int64, false),
+            Field::new("epoch", DataType::Int64, false),
+        ]));
+        let fields = merge_fields(&[], &schema).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(merge_fields(&fields, &schema).unwrap(), fields);
+
+        let evolved = Arc::new(Schema::new(vec![
+            Field::new("checkpoint", DataType::Int64, false),
+            Field::new("epoch", DataType::Int64, false),
+            Field::new("sender", DataType::Utf8, true),
+        ]));
+        let evolved_fields = merge_fields(&fields, &evolved).unwrap();
+        assert_eq!(evolved_fields[..2], fields[..]);
+        assert_eq!(evolved_fields[2].data_type, "string");
+
+        let changed = Arc::new(Schema::new(vec![Field::new(
+            "epoch",
+            DataType::Utf8,
+            false,
+        )]));
+        assert!(merge_fields(&fields, &changed).is_err());
+    }
+
+    #[test]
+    fn replay_commits() {
+        let mut state = DeltaTableState::default();
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "epoch",
+            DataType::Int64,
+            false,
+        )]));
+        let commit =
+            DeltaTable::commit_actions(&state, &schema, "epoch=3/100_250.parquet", 3, 10).unwrap();
+        let commit = commit
+            .iter()
+            .map(|action| action.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        DeltaTable::apply_commit(&mut state, commit.as_bytes()).unwrap();
+        assert_eq!(state.next_checkpoint_seq_num, 250);
+        assert_eq!(state.fields.len(), 1);
+        assert!(state.table_id.is_some());
+    }
+}
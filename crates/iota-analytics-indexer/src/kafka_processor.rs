@@ -0,0 +1,597 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streams the rows of an analytics handler to a Kafka-protocol broker.
+//!
+//! Every checkpoint is published in its own Kafka transaction, together with a
+//! progress record holding the next checkpoint to process. Consumers reading
+//! with `isolation.level=read_committed` see the rows of a checkpoint exactly
+//! once, and a restarted processor resumes from the last committed progress
+//! record.
+//!
+//! The row topic and its `<topic>.progress` topic are expected to exist, the
+//! latter with a single partition. For local testing a broker can be started
+//! with `docker run -p 9092:9092 apache/kafka` and passed as
+//! `--kafka-brokers localhost:9092`.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::{Context, Result, anyhow, bail};
+use apache_avro::{Schema as AvroSchema, to_avro_datum, types::Value as AvroValue};
+use clap::{Args, ValueEnum};
+use iota_data_ingestion_core::Worker;
+use iota_rest_api::CheckpointData;
+use iota_types::messages_checkpoint::CheckpointSequenceNumber;
+use rdkafka::{
+    ClientConfig, Message, Offset, TopicPartitionList,
+    consumer::{BaseConsumer, Consumer},
+    error::{KafkaError, RDKafkaErrorCode},
+    message::{Header, OwnedHeaders},
+    producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer},
+};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::{
+    FileType, ParquetSchema, ParquetValue, analytics_metrics::AnalyticsMetrics,
+    handlers::AnalyticsHandler,
+};
+
+const KAFKA_TIMEOUT: Duration = Duration::from_secs(30);
+const AVRO_SCHEMA_HEADER: &str = "avro.schema";
+
+#[derive(Args, Clone, Debug)]
+#[clap(rename_all = "kebab-case")]
+pub struct KafkaSinkConfig {
+    /// Comma separated list of Kafka brokers. When set, rows are streamed to
+    /// Kafka instead of being written to files.
+    #[clap(long = "kafka-brokers")]
+    pub brokers: Option<String>,
+    /// Topic the rows are published to. Defaults to `iota.<file type>`, e.g.
+    /// `iota.transactions`.
+    #[clap(long = "kafka-topic")]
+    pub topic: Option<String>,
+    /// Encoding of the published records.
+    #[clap(long = "kafka-record-format", value_enum, default_value = "json")]
+    pub record_format: KafkaRecordFormat,
+    /// Transactional id of the producer. It must be stable across restarts
+    /// and unique per pipeline, defaults to `iota-analytics-<file type>`.
+    #[clap(long = "kafka-transactional-id")]
+    pub transactional_id: Option<String>,
+}
+
+impl KafkaSinkConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.brokers.is_some()
+    }
+
+    fn topic(&self, file_type: FileType) -> String {
+        self.topic
+            .clone()
+            .unwrap_or_else(|| format!("iota.{}", file_type.dir_prefix()))
+    }
+
+    /// Single partition topic the committed progress of the pipeline is
+    /// stored in.
+    fn progress_topic(&self, file_type: FileType) -> String {
+        format!("{}.progress", self.topic(file_type))
+    }
+
+    fn transactional_id(&self, file_type: FileType) -> String {
+        self.transactional_id
+            .clone()
+            .unwrap_or_else(|| format!("iota-analytics-{}", file_type.dir_prefix()))
+    }
+
+    fn brokers(&self) -> Result<&str> {
+        self.brokers
+            .as_deref()
+            .ok_or(anyhow!("Missing kafka brokers"))
+    }
+
+    /// Creates a transactional producer, fencing off any previous instance of
+    /// the pipeline and aborting its pending transaction.
+    async fn make_producer(&self, file_type: FileType) -> Result<FutureProducer> {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", self.brokers()?)
+            .set("transactional.id", self.transactional_id(file_type))
+            .set("enable.idempotence", "true")
+            .create()?;
+        let cloned_producer = producer.clone();
+        tokio::task::spawn_blocking(move || cloned_producer.init_transactions(KAFKA_TIMEOUT))
+            .await??;
+        Ok(producer)
+    }
+
+    /// Reads the next checkpoint to process from the last committed progress
+    /// record, or `None` if nothing was published yet.
+    pub async fn read_progress(
+        &self,
+        file_type: FileType,
+    ) -> Result<Option<CheckpointSequenceNumber>> {
+        // Initializing the transactions aborts a transaction left open by a
+        // crashed instance, which would otherwise hide the end of the topic
+        // from committed reads until it times out.
+        drop(self.make_producer(file_type).await?);
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", self.brokers()?)
+            .set("group.id", self.transactional_id(file_type))
+            .set("isolation.level", "read_committed")
+            .set("enable.auto.commit", "false")
+            .set("enable.partition.eof", "true")
+            .create()?;
+        let topic = self.progress_topic(file_type);
+        tokio::task::spawn_blocking(move || read_last_progress(&consumer, &topic)).await?
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum KafkaRecordFormat {
+    /// One JSON object per row.
+    #[default]
+    Json,
+    /// One Avro datum per row, with the writer schema in the `avro.schema`
+    /// record header.
+    Avro,
+}
+
+fn read_last_progress(
+    consumer: &BaseConsumer,
+    topic: &str,
+) -> Result<Option<CheckpointSequenceNumber>> {
+    let (low, high) = consumer.fetch_watermarks(topic, 0, KAFKA_TIMEOUT)?;
+    // Every transaction appends a progress record followed by a commit or
+    // abort marker, widen the window until a committed record is found.
+    let mut lookback = 16;
+    loop {
+        let start = high.saturating_sub(lookback).max(low);
+        let mut partitions = TopicPartitionList::new();
+        partitions.add_partition_offset(topic, 0, Offset::Offset(start))?;
+        consumer.assign(&partitions)?;
+        let mut progress = None;
+        loop {
+            match consumer.poll(KAFKA_TIMEOUT) {
+                Some(Ok(message)) => {
+                    let payload = message
+                        .payload()
+                        .ok_or(anyhow!("Empty progress record in {topic}"))?;
+                    let checkpoint = std::str::from_utf8(payload)?
+                        .parse::<CheckpointSequenceNumber>()
+                        .context("Invalid progress record")?;
+                    progress = Some(checkpoint);
+                }
+                Some(Err(KafkaError::PartitionEOF(_))) => break,
+                Some(Err(e)) => return Err(e.into()),
+                None => bail!("Timed out reading progress from {topic}"),
+            }
+        }
+        if progress.is_some() || start == low {
+            return Ok(progress);
+        }
+        lookback *= 2;
+    }
+}
+
+/// Maximum number of checkpoints buffered while waiting for an earlier one.
+const MAX_PENDING_CHECKPOINTS: usize = 1000;
+
+/// A serialized row and its record headers.
+type KafkaRecord = (Vec<u8>, Option<OwnedHeaders>);
+
+/// Destination of the rows of a pipeline, which publishes the records of a
+/// checkpoint together with the progress record atomically.
+#[async_trait::async_trait]
+trait CheckpointSink: Send + Sync {
+    async fn publish_checkpoint(
+        &self,
+        checkpoint_num: CheckpointSequenceNumber,
+        records: &[KafkaRecord],
+    ) -> Result<()>;
+}
+
+pub struct KafkaProcessor<S: Serialize + ParquetSchema> {
+    handler: Box<dyn AnalyticsHandler<S>>,
+    sink: Box<dyn CheckpointSink>,
+    record_format: KafkaRecordFormat,
+    metrics: AnalyticsMetrics,
+    state: Mutex<PublishState>,
+}
+
+/// Checkpoints must be published in order for the progress record to be
+/// meaningful, checkpoints received ahead of the next one are buffered.
+struct PublishState {
+    next_checkpoint_seq_num: CheckpointSequenceNumber,
+    pending: BTreeMap<CheckpointSequenceNumber, Vec<KafkaRecord>>,
+}
+
+impl PublishState {
+    /// Whether the checkpoint was already published or is waiting to be.
+    fn is_queued(&self, checkpoint_num: CheckpointSequenceNumber) -> bool {
+        checkpoint_num < self.next_checkpoint_seq_num || self.pending.contains_key(&checkpoint_num)
+    }
+
+    fn queue(
+        &mut self,
+        checkpoint_num: CheckpointSequenceNumber,
+        records: Vec<KafkaRecord>,
+    ) -> Result<()> {
+        if checkpoint_num != self.next_checkpoint_seq_num
+            && self.pending.len() >= MAX_PENDING_CHECKPOINTS
+        {
+            bail!(
+                "Checkpoint {checkpoint_num} received while {} checkpoints are waiting for \
+                 checkpoint {}",
+                self.pending.len(),
+                self.next_checkpoint_seq_num
+            );
+        }
+        self.pending.insert(checkpoint_num, records);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Serialize + ParquetSchema + 'static> Worker for KafkaProcessor<S> {
+    async fn process_checkpoint(&self, checkpoint_data: CheckpointData) -> Result<()> {
+        let checkpoint_num = *checkpoint_data.checkpoint_summary.sequence_number();
+        // The lock also keeps the rows of concurrently processed checkpoints
+        // apart in the handler
+        let mut state = self.state.lock().await;
+        if state.is_queued(checkpoint_num) {
+            debug!("Checkpoint {checkpoint_num} was already received");
+            return self.flush(&mut state).await;
+        }
+        self.metrics
+            .total_received
+            .with_label_values(&[self.handler.name()])
+            .inc();
+        self.handler.process_checkpoint(checkpoint_data).await?;
+        let rows = self.handler.read().await?;
+        let records = self.encode(&rows)?;
+        state.queue(checkpoint_num, records)?;
+        self.flush(&mut state).await
+    }
+}
+
+impl<S: Serialize + ParquetSchema + 'static> KafkaProcessor<S> {
+    pub async fn new(
+        handler: Box<dyn AnalyticsHandler<S>>,
+        next_checkpoint_seq_num: CheckpointSequenceNumber,
+        metrics: AnalyticsMetrics,
+        config: &KafkaSinkConfig,
+    ) -> Result<Self> {
+        let file_type = handler.file_type()?;
+        let sink = KafkaProducerSink {
+            producer: config.make_producer(file_type).await?,
+            topic: config.topic(file_type),
+            progress_topic: config.progress_topic(file_type),
+            progress_key: handler.name().to_string(),
+        };
+        Ok(Self::with_sink(
+            handler,
+            Box::new(sink),
+            next_checkpoint_seq_num,
+            metrics,
+            config.record_format,
+        ))
+    }
+
+    fn with_sink(
+        handler: Box<dyn AnalyticsHandler<S>>,
+        sink: Box<dyn CheckpointSink>,
+        next_checkpoint_seq_num: CheckpointSequenceNumber,
+        metrics: AnalyticsMetrics,
+        record_format: KafkaRecordFormat,
+    ) -> Self {
+        Self {
+            handler,
+            sink,
+            record_format,
+            metrics,
+            state: Mutex::new(PublishState {
+                next_checkpoint_seq_num,
+                pending: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Publishes the buffered checkpoints that are next in order. A
+    /// checkpoint which fails to publish stays buffered for the next attempt.
+    async fn flush(&self, state: &mut PublishState) -> Result<()> {
+        while let Some(records) = state.pending.remove(&state.next_checkpoint_seq_num) {
+            let checkpoint_num = state.next_checkpoint_seq_num;
+            if let Err(e) = self.sink.publish_checkpoint(checkpoint_num, &records).await {
+                state.pending.insert(checkpoint_num, records);
+                return Err(e);
+            }
+            state.next_checkpoint_seq_num = checkpoint_num + 1;
+            self.metrics
+                .last_uploaded_checkpoint
+                .with_label_values(&[self.handler.name()])
+                .set(checkpoint_num as i64);
+            info!(
+                "Published {} {} rows for checkpoint {checkpoint_num}",
+                records.len(),
+                self.handler.name()
+            );
+        }
+        Ok(())
+    }
+
+    fn encode(&self, rows: &[S]) -> Result<Vec<KafkaRecord>> {
+        match self.record_format {
+            KafkaRecordFormat::Json => rows
+                .iter()
+                .map(|row| Ok((serde_json::to_vec(row)?, None)))
+                .collect(),
+            KafkaRecordFormat::Avro => {
+                let Some(first_row) = rows.first() else {
+                    return Ok(vec![]);
+                };
+                let schema = avro_schema(self.handler.name(), first_row)?;
+                let canonical_schema = schema.canonical_form();
+                let headers = OwnedHeaders::new().insert(Header {
+                    key: AVRO_SCHEMA_HEADER,
+                    value: Some(canonical_schema.as_str()),
+                });
+                rows.iter()
+                    .map(|row| {
+                        Ok((
+                            to_avro_datum(&schema, avro_record(row))?,
+                            Some(headers.clone()),
+                        ))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Publishes every checkpoint in its own Kafka transaction.
+struct KafkaProducerSink {
+    producer: FutureProducer,
+    topic: String,
+    progress_topic: String,
+    progress_key: String,
+}
+
+#[async_trait::async_trait]
+impl CheckpointSink for KafkaProducerSink {
+    async fn publish_checkpoint(
+        &self,
+        checkpoint_num: CheckpointSequenceNumber,
+        records: &[KafkaRecord],
+    ) -> Result<()> {
+        self.producer.begin_transaction()?;
+        if let Err(e) = self.publish(checkpoint_num, records).await {
+            let producer = self.producer.clone();
+            tokio::task::spawn_blocking(move || producer.abort_transaction(KAFKA_TIMEOUT))
+                .await??;
+            return Err(e);
+        }
+        let producer = self.producer.clone();
+        tokio::task::spawn_blocking(move || producer.commit_transaction(KAFKA_TIMEOUT)).await??;
+        Ok(())
+    }
+}
+
+impl KafkaProducerSink {
+    async fn publish(
+        &self,
+        checkpoint_num: CheckpointSequenceNumber,
+        records: &[KafkaRecord],
+    ) -> Result<()> {
+        // Rows of a checkpoint share the key so they land on the same partition
+        // and keep their order.
+        let key = checkpoint_num.to_string();
+        let mut deliveries = vec![];
+        for (payload, headers) in records {
+            let mut record = FutureRecord::to(&self.topic)
+                .key(key.as_str())
+                .payload(payload.as_slice());
+            if let Some(headers) = headers {
+                record = record.headers(headers.clone());
+            }
+            loop {
+                match self.producer.send_result(record) {
+                    Ok(delivery) => {
+                        deliveries.push(delivery);
+                        break;
+                    }
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), rejected)) => {
+                        // wait for the queued records to be delivered before retrying
+                        await_deliveries(std::mem::take(&mut deliveries)).await?;
+                        record = rejected;
+                    }
+                    Err((e, _)) => return Err(e.into()),
+                }
+            }
+        }
+        let progress = (checkpoint_num + 1).to_string();
+        let progress_record = FutureRecord::to(&self.progress_topic)
+            .key(self.progress_key.as_str())
+            .payload(progress.as_str())
+            .partition(0);
+        deliveries.push(
+            self.producer
+                .send_result(progress_record)
+                .map_err(|(e, _)| e)?,
+        );
+        await_deliveries(deliveries).await
+    }
+}
+
+async fn await_deliveries(deliveries: Vec<DeliveryFuture>) -> Result<()> {
+    for delivery in deliveries {
+        delivery
+            .await?
+            .map_err(|(e, _)| anyhow!("Failed to deliver record: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Avro record schema of a table, derived from the column values of a row
+/// since [`ParquetSchema`] only exposes the column names.
+fn avro_schema<S: ParquetSchema>(name: &str, row: &S) -> Result<AvroSchema> {
+    let fields = S::schema()
+        .into_iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let avro_type = match row.get_column(idx) {
+                ParquetValue::U64(_) | ParquetValue::I64(_) => serde_json::json!("long"),
+                ParquetValue::Str(_) => serde_json::json!("string"),
+                ParquetValue::Bool(_) => serde_json::json!("boolean"),
+                ParquetValue::OptionU64(_) => serde_json::json!(["null", "long"]),
+                ParquetValue::OptionStr(_) => serde_json::json!(["null", "string"]),
+            };
+            serde_json::json!({ "name": column, "type": avro_type })
+        })
+        .collect::<Vec<_>>();
+    let schema = serde_json::json!({
+        "type": "record",
+        "name": name,
+        "namespace": "iota.analytics",
+        "fields": fields,
+    });
+    Ok(AvroSchema::parse(&schema)?)
+}
+
+fn avro_record<S: ParquetSchema>(row: &S) -> AvroValue {
+    AvroValue::Record(
+        S::schema()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let value = match row.get_column(idx) {
+                    ParquetValue::U64(v) => AvroValue::Long(v as i64),
+                    ParquetValue::I64(v) => AvroValue::Long(v),
+                    ParquetValue::Str(v) => AvroValue::String(v),
+                    ParquetValue::Bool(v) => AvroValue::Boolean(v),
+                    ParquetValue::OptionU64(v) => match v {
+                        Some(v) => AvroValue::Union(1, Box::new(AvroValue::Long(v as i64))),
+                        None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+                    },
+                    ParquetValue::OptionStr(v) => match v {
+                        Some(v) => AvroValue::Union(1, Box::new(AvroValue::String(v))),
+                        None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+                    },
+                };
+                (column, value)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use apache_avro::from_avro_datum;
+    use prometheus::Registry;
+
+    use super::*;
+    use crate::{
+        handlers::checkpoint_handler::CheckpointHandler,
+        tables::{CheckpointEntry, MovePackageEntry},
+    };
+
+    /// Records the published checkpoints, failing once for `fail_on`.
+    #[derive(Default)]
+    struct MockSink {
+        published: Arc<StdMutex<Vec<CheckpointSequenceNumber>>>,
+        fail_on: StdMutex<Option<CheckpointSequenceNumber>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CheckpointSink for MockSink {
+        async fn publish_checkpoint(
+            &self,
+            checkpoint_num: CheckpointSequenceNumber,
+            _records: &[KafkaRecord],
+        ) -> Result<()> {
+            if self
+                .fail_on
+                .lock()
+                .unwrap()
+                .take_if(|fail_on| *fail_on == checkpoint_num)
+                .is_some()
+            {
+                bail!("Failed to publish checkpoint {checkpoint_num}");
+            }
+            self.published.lock().unwrap().push(checkpoint_num);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn publishes_checkpoints_in_order() -> anyhow::Result<()> {
+        let published = Arc::new(StdMutex::new(vec![]));
+        let sink = MockSink {
+            published: published.clone(),
+            fail_on: StdMutex::new(Some(14)),
+        };
+        let processor = KafkaProcessor::<CheckpointEntry>::with_sink(
+            Box::new(CheckpointHandler::new()),
+            Box::new(sink),
+            10,
+            AnalyticsMetrics::new(&Registry::default()),
+            KafkaRecordFormat::Json,
+        );
+        let mut state = processor.state.lock().await;
+
+        // Checkpoints ahead of the next one are buffered until the gap is filled
+        state.queue(12, vec![])?;
+        processor.flush(&mut state).await?;
+        state.queue(11, vec![])?;
+        processor.flush(&mut state).await?;
+        assert!(published.lock().unwrap().is_empty());
+        state.queue(10, vec![])?;
+        processor.flush(&mut state).await?;
+        assert_eq!(*published.lock().unwrap(), [10, 11, 12]);
+        assert!(state.is_queued(12));
+        assert!(!state.is_queued(13));
+
+        // A checkpoint failing to publish stays buffered and is retried
+        state.queue(14, vec![])?;
+        state.queue(13, vec![])?;
+        assert!(processor.flush(&mut state).await.is_err());
+        assert_eq!(state.next_checkpoint_seq_num, 14);
+        assert!(state.is_queued(14));
+        processor.flush(&mut state).await?;
+        assert_eq!(*published.lock().unwrap(), [10, 11, 12, 13, 14]);
+        assert!(state.pending.is_empty());
+
+        // The buffer is bounded, but always accepts the next checkpoint
+        for checkpoint_num in 16..16 + MAX_PENDING_CHECKPOINTS as u64 {
+            state.queue(checkpoint_num, vec![])?;
+        }
+        assert!(state.queue(10_000, vec![]).is_err());
+        state.queue(15, vec![])?;
+        processor.flush(&mut state).await?;
+        assert_eq!(
+            state.next_checkpoint_seq_num,
+            16 + MAX_PENDING_CHECKPOINTS as u64
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn avro_round_trip() -> anyhow::Result<()> {
+        let row = MovePackageEntry {
+            package_id: "0x2".to_string(),
+            checkpoint: 10,
+            epoch: 1,
+            timestamp_ms: 1_700_000_000_000,
+            bcs: "AQID".to_string(),
+            transaction_digest: "digest".to_string(),
+            package_version: Some(1),
+            original_package_id: None,
+        };
+        let schema = avro_schema("package", &row)?;
+        let datum = to_avro_datum(&schema, avro_record(&row))?;
+        let decoded = from_avro_datum(&schema, &mut datum.as_slice(), None)?;
+        assert_eq!(decoded, avro_record(&row));
+        Ok(())
+    }
+}
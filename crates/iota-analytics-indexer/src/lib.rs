@@ -28,6 +28,7 @@ use tracing::info;
 use crate::{
     analytics_metrics::AnalyticsMetrics,
     analytics_processor::AnalyticsProcessor,
+    delta_table::DeltaTable,
     handlers::{
         AnalyticsHandler, checkpoint_handler::CheckpointHandler, df_handler::DynamicFieldHandler,
         event_handler::EventHandler, move_call_handler::MoveCallHandler,
@@ -36,6 +37,7 @@ use crate::{
         transaction_objects_handler::TransactionObjectsHandler,
         wrapped_object_handler::WrappedObjectHandler,
     },
+    kafka_processor::{KafkaProcessor, KafkaSinkConfig},
     tables::{
         CheckpointEntry, DynamicFieldEntry, EventEntry, InputObjectKind, MoveCallEntry,
         MovePackageEntry, ObjectEntry, ObjectStatus, OwnerType, TransactionEntry,
@@ -46,9 +48,12 @@ use crate::{
 
 pub mod analytics_metrics;
 pub mod analytics_processor;
+mod delta_table;
 pub mod errors;
 mod handlers;
+pub mod kafka_processor;
 mod package_store;
+pub mod query;
 pub mod tables;
 mod writers;
 
@@ -75,85 +80,78 @@ pub struct AnalyticsIndexerConfig {
     #[clap(long)]
     pub rest_url: String,
     /// The url of the metrics client to connect to.
-    #[clap(long, default_value = "127.0.0.1", global = true)]
+    #[clap(long, default_value = "127.0.0.1")]
     pub client_metric_host: String,
     /// The port of the metrics client to connect to.
-    #[clap(long, default_value = "8081", global = true)]
+    #[clap(long, default_value = "8081")]
     pub client_metric_port: u16,
     /// Directory to contain the temporary files for checkpoint entries.
-    #[clap(long, global = true, default_value = "/tmp")]
+    #[clap(long, default_value = "/tmp")]
     pub checkpoint_dir: PathBuf,
     /// Number of checkpoints to process before uploading to the datastore.
-    #[clap(long, default_value = "10000", global = true)]
+    #[clap(long, default_value = "10000")]
     pub checkpoint_interval: u64,
     /// Maximum file size in mb before uploading to the datastore.
-    #[clap(long, default_value = "100", global = true)]
+    #[clap(long, default_value = "100")]
     pub max_file_size_mb: u64,
     /// Checkpoint sequence number to start the download from
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub starting_checkpoint_seq_num: Option<u64>,
     /// Time to process in seconds before uploading to the datastore.
-    #[clap(long, default_value = "600", global = true)]
+    #[clap(long, default_value = "600")]
     pub time_interval_s: u64,
     // Remote object store where data gets written to
     #[command(flatten)]
     pub remote_store_config: ObjectStoreConfig,
     // Remote object store path prefix to use while writing
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub remote_store_path_prefix: Option<Path>,
     // File format to store data in i.e. csv, parquet, etc
-    #[clap(long, value_enum, default_value = "csv", global = true)]
+    #[clap(long, value_enum, default_value = "csv")]
     pub file_format: FileFormat,
     // Type of data to write i.e. checkpoint, object, transaction, etc
-    #[clap(long, value_enum, long, global = true)]
+    #[clap(long, value_enum, long)]
     pub file_type: FileType,
-    #[clap(
-        long,
-        default_value = "https://checkpoints.mainnet.iota.io",
-        global = true
-    )]
+    #[clap(long, default_value = "https://checkpoints.mainnet.iota.io")]
     pub remote_store_url: String,
     // Directory to contain the package cache for pipelines
-    #[clap(
-        long,
-        value_enum,
-        long,
-        global = true,
-        default_value = "/opt/iota/db/package_cache"
-    )]
+    #[clap(long, value_enum, long, default_value = "/opt/iota/db/package_cache")]
     pub package_cache_path: PathBuf,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub bq_service_account_key_file: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub bq_project_id: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub bq_dataset_id: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub bq_table_id: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub bq_checkpoint_col_id: Option<String>,
-    #[clap(long, global = true)]
+    #[clap(long)]
     pub report_bq_max_table_checkpoint: bool,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_account_identifier: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_warehouse: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_database: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_schema: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_username: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_role: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_password: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_table_id: Option<String>,
-    #[clap(long, default_value = None, global = true)]
+    #[clap(long, default_value = None)]
     pub sf_checkpoint_col_id: Option<String>,
-    #[clap(long, global = true)]
+    #[clap(long)]
     pub report_sf_max_table_checkpoint: bool,
+    // Kafka broker rows get streamed to instead of files
+    #[command(flatten)]
+    pub kafka_config: KafkaSinkConfig,
 }
 
 #[async_trait::async_trait]
@@ -288,13 +286,15 @@ impl MaxCheckpointReader for NoOpCheckpointReader {
 pub enum FileFormat {
     CSV = 0,
     PARQUET = 1,
+    // Parquet files appended to a Delta Lake table per file type
+    DELTA = 2,
 }
 
 impl FileFormat {
     pub fn file_suffix(&self) -> &str {
         match self {
             FileFormat::CSV => "csv",
-            FileFormat::PARQUET => "parquet",
+            FileFormat::PARQUET | FileFormat::DELTA => "parquet",
         }
     }
 }
@@ -522,6 +522,22 @@ impl Processor {
         })
     }
 
+    pub async fn new_kafka<S: Serialize + ParquetSchema + 'static>(
+        handler: Box<dyn AnalyticsHandler<S>>,
+        starting_checkpoint_seq_num: CheckpointSequenceNumber,
+        metrics: AnalyticsMetrics,
+        config: &KafkaSinkConfig,
+    ) -> Result<Self> {
+        let processor = Box::new(
+            KafkaProcessor::new(handler, starting_checkpoint_seq_num, metrics, config).await?,
+        );
+
+        Ok(Processor {
+            processor,
+            starting_checkpoint_seq_num,
+        })
+    }
+
     pub fn last_committed_checkpoint(&self) -> Option<u64> {
         Some(self.starting_checkpoint_seq_num.saturating_sub(1)).filter(|x| *x > 0)
     }
@@ -853,6 +869,113 @@ pub async fn make_wrapped_object_processor(
     .await
 }
 
+/// Makes a processor streaming the rows of the configured file type to Kafka
+/// instead of writing them to files.
+pub async fn make_kafka_processor(
+    config: AnalyticsIndexerConfig,
+    metrics: AnalyticsMetrics,
+) -> Result<Processor> {
+    let file_type = config.file_type;
+    let starting_checkpoint_seq_num =
+        get_starting_checkpoint_seq_num(config.clone(), file_type).await?;
+    let kafka_config = &config.kafka_config;
+    match file_type {
+        FileType::Checkpoint => {
+            Processor::new_kafka::<CheckpointEntry>(
+                Box::new(CheckpointHandler::new()),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::Object => {
+            Processor::new_kafka::<ObjectEntry>(
+                Box::new(ObjectHandler::new(
+                    &config.package_cache_path,
+                    &config.rest_url,
+                )),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::Transaction => {
+            Processor::new_kafka::<TransactionEntry>(
+                Box::new(TransactionHandler::new()),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::Event => {
+            Processor::new_kafka::<EventEntry>(
+                Box::new(EventHandler::new(
+                    &config.package_cache_path,
+                    &config.rest_url,
+                )),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::TransactionObjects => {
+            Processor::new_kafka::<TransactionObjectEntry>(
+                Box::new(TransactionObjectsHandler::new()),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::MoveCall => {
+            Processor::new_kafka::<MoveCallEntry>(
+                Box::new(MoveCallHandler::new()),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::MovePackage => {
+            Processor::new_kafka::<MovePackageEntry>(
+                Box::new(PackageHandler::new()),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::DynamicField => {
+            Processor::new_kafka::<DynamicFieldEntry>(
+                Box::new(DynamicFieldHandler::new(
+                    &config.package_cache_path,
+                    &config.rest_url,
+                )),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+        FileType::WrappedObject => {
+            Processor::new_kafka::<WrappedObjectEntry>(
+                Box::new(WrappedObjectHandler::new(
+                    &config.package_cache_path,
+                    &config.rest_url,
+                )),
+                starting_checkpoint_seq_num,
+                metrics,
+                kafka_config,
+            )
+            .await
+        }
+    }
+}
+
 pub fn make_writer<S: Serialize + ParquetSchema>(
     config: AnalyticsIndexerConfig,
     file_type: FileType,
@@ -864,9 +987,10 @@ pub fn make_writer<S: Serialize + ParquetSchema>(
             file_type,
             starting_checkpoint_seq_num,
         )?),
-        FileFormat::PARQUET => Box::new(ParquetWriter::new(
+        FileFormat::PARQUET | FileFormat::DELTA => Box::new(ParquetWriter::new(
             &config.checkpoint_dir,
             file_type,
+            config.file_format,
             starting_checkpoint_seq_num,
         )?),
     })
@@ -878,6 +1002,17 @@ pub async fn get_starting_checkpoint_seq_num(
 ) -> Result<u64> {
     let checkpoint = if let Some(starting_checkpoint_seq_num) = config.starting_checkpoint_seq_num {
         starting_checkpoint_seq_num
+    } else if config.file_format == FileFormat::DELTA {
+        let table_root = join_paths(config.remote_store_path_prefix, &file_type.dir_prefix());
+        DeltaTable::new(config.remote_store_config.make()?, table_root)
+            .next_checkpoint_seq_num()
+            .await?
+    } else if config.kafka_config.is_enabled() {
+        config
+            .kafka_config
+            .read_progress(file_type)
+            .await?
+            .unwrap_or_default()
     } else {
         read_store_for_checkpoint(
             config.remote_store_config.clone(),
@@ -893,6 +1028,9 @@ pub async fn make_analytics_processor(
     config: AnalyticsIndexerConfig,
     metrics: AnalyticsMetrics,
 ) -> Result<Processor> {
+    if config.kafka_config.is_enabled() {
+        return make_kafka_processor(config, metrics).await;
+    }
     match config.file_type {
         FileType::Checkpoint => make_checkpoint_processor(config, metrics).await,
         FileType::Object => make_object_processor(config, metrics).await,
@@ -5,21 +5,42 @@
 use anyhow::Result;
 use clap::*;
 use iota_analytics_indexer::{
-    AnalyticsIndexerConfig, analytics_metrics::AnalyticsMetrics, errors::AnalyticsIndexerError,
+    AnalyticsIndexerConfig,
+    analytics_metrics::AnalyticsMetrics,
+    errors::AnalyticsIndexerError,
     make_analytics_processor,
+    query::{QueryConfig, run_query},
 };
 use iota_data_ingestion_core::{ReaderOptions, setup_single_workflow};
 use prometheus::Registry;
 use tokio::signal;
 use tracing::info;
 
+#[derive(Subcommand)]
+enum AnalyticsIndexerCommand {
+    /// Run SQL queries or canned reports over the indexer output with DuckDB.
+    Query(QueryConfig),
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let _guard = telemetry_subscribers::TelemetryConfig::new()
         .with_env()
         .init();
 
-    let config = AnalyticsIndexerConfig::parse();
+    // Running the indexer takes no subcommand, so its arguments are only
+    // required when no subcommand is given.
+    let matches = AnalyticsIndexerCommand::augment_subcommands(AnalyticsIndexerConfig::command())
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .get_matches();
+    if matches.subcommand().is_some() {
+        let AnalyticsIndexerCommand::Query(config) =
+            AnalyticsIndexerCommand::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        return tokio::task::spawn_blocking(move || run_query(config)).await?;
+    }
+
+    let config = AnalyticsIndexerConfig::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     info!("Parsed config: {:#?}", config);
     let registry_service = iota_metrics::start_prometheus_server(
         format!(
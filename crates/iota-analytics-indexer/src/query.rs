@@ -0,0 +1,390 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local SQL access to the analytics output.
+//!
+//! Every file type found in the configured store is mounted as a DuckDB view
+//! named after its BigQuery table, with the columns and types of the matching
+//! schema in `store/bq/schemas`. File types without a BigQuery schema are
+//! mounted with the columns of their table entry as read from the files.
+
+use anyhow::{Result, anyhow, bail};
+use clap::*;
+use duckdb::{Connection, arrow::util::pretty::print_batches};
+use iota_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
+use object_store::path::Path;
+use strum::IntoEnumIterator;
+use tracing::info;
+
+use crate::{
+    FileFormat, FileType, ParquetSchema, join_paths,
+    tables::{
+        CheckpointEntry, DynamicFieldEntry, EventEntry, MoveCallEntry, MovePackageEntry,
+        ObjectEntry, TransactionEntry, TransactionObjectEntry, WrappedObjectEntry,
+    },
+};
+
+#[derive(Parser, Clone, Debug)]
+pub struct QueryConfig {
+    /// Object store the analytics indexer wrote its output to.
+    #[command(flatten)]
+    pub remote_store_config: ObjectStoreConfig,
+    /// Path prefix the analytics indexer wrote its output under.
+    #[clap(long, default_value = None)]
+    pub remote_store_path_prefix: Option<Path>,
+    /// File format the output was written in.
+    #[clap(long, value_enum, default_value = "parquet")]
+    pub file_format: FileFormat,
+    /// SQL query to run against the mounted tables.
+    #[clap(long, conflicts_with = "report", required_unless_present = "report")]
+    pub sql: Option<String>,
+    /// Canned report to run instead of a SQL query.
+    #[clap(long, value_enum)]
+    pub report: Option<Report>,
+    /// Maximum number of rows returned by ranked reports.
+    #[clap(long, default_value = "20")]
+    pub limit: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Report {
+    /// Packages ranked by the gas spent by transactions calling them.
+    TopPackagesByGas,
+    /// Distinct senders of user transactions per day.
+    DailyActiveSenders,
+    /// Objects created, mutated and deleted per epoch.
+    ObjectChurn,
+}
+
+impl Report {
+    fn sql(&self, limit: usize) -> String {
+        match self {
+            Report::TopPackagesByGas => format!(
+                "SELECT package, count(*) AS transactions, sum(total_gas_cost) AS total_gas_cost \
+                 FROM (SELECT unnest(string_split(packages, '-')) AS package, total_gas_cost \
+                       FROM \"transaction\" WHERE packages <> '') \
+                 GROUP BY package ORDER BY total_gas_cost DESC LIMIT {limit}"
+            ),
+            Report::DailyActiveSenders => "SELECT CAST(epoch_ms(timestamp_ms) AS DATE) AS day, \
+                 count(DISTINCT sender) AS active_senders, count(*) AS transactions \
+                 FROM \"transaction\" WHERE NOT is_system_txn \
+                 GROUP BY day ORDER BY day"
+                .to_string(),
+            Report::ObjectChurn => "SELECT epoch, \
+                 count(*) FILTER (WHERE object_status = 'Created') AS created, \
+                 count(*) FILTER (WHERE object_status = 'Mutated') AS mutated, \
+                 count(*) FILTER (WHERE object_status = 'Deleted') AS deleted, \
+                 count(DISTINCT object_id) AS objects_touched \
+                 FROM \"object\" GROUP BY epoch ORDER BY epoch"
+                .to_string(),
+        }
+    }
+}
+
+/// Mounts the analytics output and prints the result of the query or report.
+pub fn run_query(config: QueryConfig) -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    let root = configure_store(&conn, &config.remote_store_config)?;
+    for file_type in FileType::iter() {
+        mount_view(
+            &conn,
+            &root,
+            config.remote_store_path_prefix.clone(),
+            config.file_format,
+            file_type,
+        )?;
+    }
+    let sql = match (&config.sql, config.report) {
+        (Some(sql), _) => sql.clone(),
+        (None, Some(report)) => report.sql(config.limit),
+        (None, None) => bail!("Either a SQL query or a report is required"),
+    };
+    let batches = conn.prepare(&sql)?.query_arrow([])?.collect::<Vec<_>>();
+    print_batches(&batches)?;
+    Ok(())
+}
+
+/// Loads the extensions and credentials DuckDB needs to read from the store
+/// and returns the url of its root.
+fn configure_store(conn: &Connection, config: &ObjectStoreConfig) -> Result<String> {
+    match config.object_store.unwrap_or(ObjectStoreType::File) {
+        ObjectStoreType::File => {
+            let directory = config
+                .directory
+                .as_ref()
+                .ok_or(anyhow!("No directory provided for local storage"))?;
+            Ok(directory.display().to_string())
+        }
+        ObjectStoreType::S3 => {
+            let bucket = config
+                .bucket
+                .as_ref()
+                .ok_or(anyhow!("Bucket name is required for S3"))?;
+            let mut options = vec![
+                "TYPE S3".to_string(),
+                format!(
+                    "URL_STYLE {}",
+                    quote(if config.aws_virtual_hosted_style_request {
+                        "vhost"
+                    } else {
+                        "path"
+                    })
+                ),
+            ];
+            match (&config.aws_access_key_id, &config.aws_secret_access_key) {
+                (Some(key_id), Some(secret)) => {
+                    options.push(format!("KEY_ID {}", quote(key_id)));
+                    options.push(format!("SECRET {}", quote(secret)));
+                }
+                _ => options.push("PROVIDER CREDENTIAL_CHAIN".to_string()),
+            }
+            if let Some(region) = &config.aws_region {
+                options.push(format!("REGION {}", quote(region)));
+            }
+            if let Some(endpoint) = &config.aws_endpoint {
+                let (use_ssl, host) = match endpoint.split_once("://") {
+                    Some((scheme, host)) => (scheme != "http", host),
+                    None => (true, endpoint.as_str()),
+                };
+                options.push(format!("ENDPOINT {}", quote(host)));
+                options.push(format!("USE_SSL {use_ssl}"));
+            }
+            conn.execute_batch(&format!(
+                "INSTALL httpfs; LOAD httpfs; CREATE SECRET ({});",
+                options.join(", ")
+            ))?;
+            Ok(format!("s3://{bucket}"))
+        }
+        ObjectStoreType::Azure => {
+            let container = config
+                .bucket
+                .as_ref()
+                .ok_or(anyhow!("Container name is required for Azure"))?;
+            let account = config
+                .azure_storage_account
+                .as_ref()
+                .ok_or(anyhow!("Account name is required for Azure"))?;
+            let options = match &config.azure_storage_access_key {
+                Some(key) => format!(
+                    "CONNECTION_STRING {}",
+                    quote(&format!("AccountName={account};AccountKey={key}"))
+                ),
+                None => format!("PROVIDER CREDENTIAL_CHAIN, ACCOUNT_NAME {}", quote(account)),
+            };
+            conn.execute_batch(&format!(
+                "INSTALL azure; LOAD azure; CREATE SECRET (TYPE AZURE, {options});"
+            ))?;
+            Ok(format!("az://{container}"))
+        }
+        // DuckDB only authenticates to GCS with HMAC keys, which the object
+        // store config has no notion of.
+        ObjectStoreType::GCS => {
+            bail!("Querying GCS is not supported, sync the output to a local directory first")
+        }
+    }
+}
+
+/// Creates the view of `file_type` if the store has any file for it.
+fn mount_view(
+    conn: &Connection,
+    root: &str,
+    prefix: Option<Path>,
+    file_format: FileFormat,
+    file_type: FileType,
+) -> Result<()> {
+    let glob = format!(
+        "{root}/{}/**/*.{}",
+        join_paths(prefix, &file_type.dir_prefix()),
+        file_format.file_suffix()
+    );
+    let files: i64 = conn.query_row(
+        &format!("SELECT count(*) FROM glob({})", quote(&glob)),
+        [],
+        |row| row.get(0),
+    )?;
+    if files == 0 {
+        info!("No {} files found under {glob}", view_name(file_type));
+        return Ok(());
+    }
+
+    let entry_columns = entry_columns(file_type);
+    let source = match file_format {
+        FileFormat::CSV => format!(
+            "read_csv({}, delim = '|', header = false, names = [{}])",
+            quote(&glob),
+            entry_columns
+                .iter()
+                .map(|column| quote(column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        // delta tables also carry the epoch as a hive partition, the column
+        // written in the files is the one to use
+        FileFormat::PARQUET | FileFormat::DELTA => format!(
+            "read_parquet({}, union_by_name = true, hive_partitioning = false)",
+            quote(&glob)
+        ),
+    };
+    let projection = match bq_schema(file_type) {
+        Some(schema) => {
+            let bq_columns = parse_bq_columns(schema)?;
+            if bq_columns.len() != entry_columns.len() {
+                bail!(
+                    "BigQuery schema of {} has {} columns, expected {}",
+                    view_name(file_type),
+                    bq_columns.len(),
+                    entry_columns.len()
+                );
+            }
+            entry_columns
+                .iter()
+                .zip(bq_columns)
+                .map(|(column, (name, data_type))| {
+                    format!("CAST(\"{column}\" AS {data_type}) AS \"{name}\"")
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        None => "*".to_string(),
+    };
+    conn.execute_batch(&format!(
+        "CREATE VIEW \"{}\" AS SELECT {projection} FROM {source};",
+        view_name(file_type)
+    ))?;
+    info!("Mounted {files} files as {}", view_name(file_type));
+    Ok(())
+}
+
+fn view_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Checkpoint => "checkpoint",
+        FileType::Object => "object",
+        FileType::Transaction => "transaction",
+        FileType::TransactionObjects => "transaction_object",
+        FileType::Event => "event",
+        FileType::MoveCall => "move_call",
+        FileType::MovePackage => "move_package",
+        FileType::DynamicField => "dynamic_field",
+        FileType::WrappedObject => "wrapped_object",
+    }
+}
+
+fn entry_columns(file_type: FileType) -> Vec<String> {
+    match file_type {
+        FileType::Checkpoint => CheckpointEntry::schema(),
+        FileType::Object => ObjectEntry::schema(),
+        FileType::Transaction => TransactionEntry::schema(),
+        FileType::TransactionObjects => TransactionObjectEntry::schema(),
+        FileType::Event => EventEntry::schema(),
+        FileType::MoveCall => MoveCallEntry::schema(),
+        FileType::MovePackage => MovePackageEntry::schema(),
+        FileType::DynamicField => DynamicFieldEntry::schema(),
+        FileType::WrappedObject => WrappedObjectEntry::schema(),
+    }
+}
+
+fn bq_schema(file_type: FileType) -> Option<&'static str> {
+    match file_type {
+        FileType::Checkpoint => Some(include_str!("store/bq/schemas/checkpoint.sql")),
+        FileType::Object => Some(include_str!("store/bq/schemas/object.sql")),
+        FileType::Transaction => Some(include_str!("store/bq/schemas/transaction.sql")),
+        FileType::TransactionObjects => {
+            Some(include_str!("store/bq/schemas/transaction_objects.sql"))
+        }
+        FileType::Event => Some(include_str!("store/bq/schemas/event.sql")),
+        FileType::MoveCall => Some(include_str!("store/bq/schemas/move_call.sql")),
+        FileType::MovePackage => Some(include_str!("store/bq/schemas/package.sql")),
+        FileType::DynamicField | FileType::WrappedObject => None,
+    }
+}
+
+/// Extracts the column names of a BigQuery `CREATE TABLE` statement along
+/// with the equivalent DuckDB types.
+fn parse_bq_columns(schema: &str) -> Result<Vec<(String, String)>> {
+    let start = schema.find('(').ok_or(anyhow!("Missing column list"))? + 1;
+    let mut depth = 0;
+    let mut definitions = vec![];
+    let mut definition = String::new();
+    for c in schema[start..].chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                definitions.push(std::mem::take(&mut definition));
+                continue;
+            }
+            _ => {}
+        }
+        definition.push(c);
+    }
+    definitions.push(definition);
+
+    definitions
+        .iter()
+        .map(|definition| {
+            let definition = definition.trim();
+            let (name, data_type) = definition
+                .split_once(char::is_whitespace)
+                .ok_or(anyhow!("Invalid column definition {definition}"))?;
+            let data_type = data_type.trim();
+            let data_type = data_type
+                .strip_suffix("NOT NULL")
+                .unwrap_or(data_type)
+                .trim();
+            let duckdb_type = match data_type {
+                "INT64" => "BIGINT".to_string(),
+                "STRING" => "VARCHAR".to_string(),
+                "BOOL" => "BOOLEAN".to_string(),
+                "JSON" => "JSON".to_string(),
+                numeric if numeric.starts_with("NUMERIC") => {
+                    numeric.replacen("NUMERIC", "DECIMAL", 1)
+                }
+                other => bail!("Unsupported BigQuery type {other}"),
+            };
+            Ok((name.to_string(), duckdb_type))
+        })
+        .collect()
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bq_schemas_match_entries() {
+        for file_type in FileType::iter() {
+            let Some(schema) = bq_schema(file_type) else {
+                continue;
+            };
+            let columns = parse_bq_columns(schema).unwrap();
+            assert_eq!(
+                columns.len(),
+                entry_columns(file_type).len(),
+                "{}",
+                view_name(file_type)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_columns() {
+        let columns = parse_bq_columns(include_str!("store/bq/schemas/checkpoint.sql")).unwrap();
+        assert_eq!(
+            columns[0],
+            ("checkpoint_digest".to_string(), "VARCHAR".to_string())
+        );
+        assert_eq!(
+            columns[5],
+            ("end_of_epoch".to_string(), "BOOLEAN".to_string())
+        );
+        assert_eq!(
+            columns[6],
+            ("total_gas_cost".to_string(), "DECIMAL(20, 0)".to_string())
+        );
+    }
+}
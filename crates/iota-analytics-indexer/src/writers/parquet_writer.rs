@@ -10,6 +10,10 @@ use std::{
 };
 
 use anyhow::{Result, anyhow};
+use arrow::{
+    compute::{CastOptions, cast_with_options},
+    datatypes::DataType,
+};
 use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray, UInt64Array};
 use iota_storage::object_store::util::path_to_filesystem;
 use iota_types::base_types::EpochId;
@@ -22,6 +26,7 @@ use crate::{AnalyticsWriter, FileFormat, FileType, ParquetSchema, ParquetValue};
 pub(crate) struct ParquetWriter {
     root_dir_path: PathBuf,
     file_type: FileType,
+    file_format: FileFormat,
     epoch: EpochId,
     checkpoint_range: Range<u64>,
     data: Vec<Vec<ParquetValue>>,
@@ -31,12 +36,14 @@ impl ParquetWriter {
     pub(crate) fn new(
         root_dir_path: &Path,
         file_type: FileType,
+        file_format: FileFormat,
         start_checkpoint_seq_num: u64,
     ) -> Result<Self> {
         let checkpoint_range = start_checkpoint_seq_num..u64::MAX;
         Ok(Self {
             root_dir_path: root_dir_path.to_path_buf(),
             file_type,
+            file_format,
             epoch: 0,
             checkpoint_range,
             data: vec![],
@@ -46,11 +53,9 @@ impl ParquetWriter {
     fn file(&self) -> Result<File> {
         let file_path = path_to_filesystem(
             self.root_dir_path.clone(),
-            &self.file_type.file_path(
-                FileFormat::PARQUET,
-                self.epoch,
-                self.checkpoint_range.clone(),
-            ),
+            &self
+                .file_type
+                .file_path(self.file_format, self.epoch, self.checkpoint_range.clone()),
         )?;
         create_dir_all(file_path.parent().ok_or(anyhow!("Bad directory path"))?)?;
         if file_path.exists() {
@@ -83,7 +88,7 @@ macro_rules! convert_to_arrow_array {
 
 impl<S: Serialize + ParquetSchema> AnalyticsWriter<S> for ParquetWriter {
     fn file_format(&self) -> Result<FileFormat> {
-        Ok(FileFormat::PARQUET)
+        Ok(self.file_format)
     }
 
     fn write(&mut self, rows: &[S]) -> Result<()> {
@@ -109,6 +114,21 @@ impl<S: Serialize + ParquetSchema> AnalyticsWriter<S> for ParquetWriter {
                 ParquetValue::U64 => UInt64Array, ParquetValue::Str => StringArray, ParquetValue::OptionU64 => UInt64Array, ParquetValue::OptionStr => StringArray, ParquetValue::Bool => BooleanArray, ParquetValue::I64 => Int64Array
             );
         }
+        if self.file_format == FileFormat::DELTA {
+            // delta tables have no unsigned types, fail on values which don't fit
+            // instead of writing them as nulls
+            let cast_options = CastOptions {
+                safe: false,
+                ..Default::default()
+            };
+            batch_data = batch_data
+                .into_iter()
+                .map(|array| match array.data_type() {
+                    DataType::UInt64 => cast_with_options(&array, &DataType::Int64, &cast_options),
+                    _ => Ok(array),
+                })
+                .collect::<Result<_, _>>()?;
+        }
         let batch = RecordBatch::try_from_iter(S::schema().iter().zip(batch_data.into_iter()))?;
 
         let properties = WriterProperties::builder()
@@ -0,0 +1,491 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    io::{self, Cursor, Read},
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result, anyhow, ensure};
+use bytes::{Buf, Bytes};
+use futures::{StreamExt, TryStreamExt};
+use iota_config::object_storage_config::ObjectStoreConfig;
+use iota_storage::{
+    blob::Blob,
+    compute_sha3_checksum_for_bytes,
+    object_store::{ObjectStoreGetExt, util::get},
+};
+use iota_types::{
+    base_types::ExecutionData,
+    digests::{TransactionDigest, TransactionEventsDigest},
+    effects::{TransactionEffectsAPI, TransactionEvents},
+    messages_checkpoint::{
+        CertifiedCheckpointSummary, CheckpointSequenceNumber, FullCheckpointContents,
+    },
+    storage::ReadStore,
+};
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::info;
+use typed_store::{
+    DBMapUtils, Map,
+    rocks::{DBMap, MetricConf},
+    traits::{TableSummary, TypedStoreDebug},
+};
+
+use crate::{CHECKPOINT_FILE_MAGIC, FileMetadata, FileType, SUMMARY_FILE_MAGIC, read_manifest};
+
+/// Number of decompressed archive files kept in memory by default.
+const DEFAULT_FILE_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(16) {
+    Some(size) => size,
+    None => unreachable!(),
+};
+
+/// A pair of summary and content files of the archive, with the offsets of
+/// every checkpoint in the decompressed content of each file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFiles {
+    summary_file: FileMetadata,
+    content_file: FileMetadata,
+    summary_offsets: Vec<u64>,
+    content_offsets: Vec<u64>,
+}
+
+/// Lookup tables over the files of an archive, persisted in a local database.
+/// They map checkpoint sequence numbers to the file and offset of their summary
+/// and contents, and transaction digests to the checkpoint which includes them.
+#[derive(DBMapUtils)]
+pub struct ArchiveIndexTables {
+    /// Indexed pairs of archive files by the first checkpoint they contain.
+    files: DBMap<CheckpointSequenceNumber, IndexedFiles>,
+    /// Checkpoint which includes each archived transaction.
+    transactions: DBMap<TransactionDigest, CheckpointSequenceNumber>,
+}
+
+impl ArchiveIndexTables {
+    pub fn open(path: PathBuf) -> Self {
+        Self::open_tables_read_write(path, MetricConf::new("archive_index"), None, None)
+    }
+
+    /// Returns the sequence number of the first checkpoint not covered by the
+    /// index.
+    pub fn next_checkpoint_seq_num(&self) -> CheckpointSequenceNumber {
+        self.files
+            .unbounded_iter()
+            .skip_to_last()
+            .next()
+            .map(|(_, files)| files.summary_file.checkpoint_seq_range.end)
+            .unwrap_or(0)
+    }
+
+    /// Returns the checkpoint which includes the given transaction.
+    pub fn checkpoint_of(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Result<Option<CheckpointSequenceNumber>> {
+        Ok(self.transactions.get(digest)?)
+    }
+
+    /// Returns the files and offsets of the summary and contents of the given
+    /// checkpoint.
+    fn locate(&self, seq: CheckpointSequenceNumber) -> Result<Option<(IndexedFiles, usize)>> {
+        let Some((_, files)) = self.files.unbounded_iter().skip_prior_to(&seq)?.next() else {
+            return Ok(None);
+        };
+        let range = files.summary_file.checkpoint_seq_range.clone();
+        Ok(range
+            .contains(&seq)
+            .then(|| (files, (seq - range.start) as usize)))
+    }
+
+    /// Adds a pair of files and the transactions they include to the index.
+    /// Both are written in a single batch, so lookups never see a file
+    /// without its transactions.
+    fn append(
+        &self,
+        files: &IndexedFiles,
+        transactions: Vec<(TransactionDigest, CheckpointSequenceNumber)>,
+    ) -> Result<()> {
+        let mut batch = self.files.batch();
+        batch.insert_batch(&self.files, [(
+            files.summary_file.checkpoint_seq_range.start,
+            files,
+        )])?;
+        batch.insert_batch(&self.transactions, transactions)?;
+        batch.write()?;
+        Ok(())
+    }
+}
+
+/// Random access reader over an archive. Unlike
+/// [`crate::reader::ArchiveReader`] which streams checkpoints in order, this
+/// reader answers point lookups of checkpoints and transactions using the
+/// [`ArchiveIndexTables`] persisted on the local filesystem. Lookups download
+/// the whole archive file which contains the requested checkpoint, since files
+/// may be compressed, and keep the most recently used files in memory.
+pub struct IndexedArchiveReader {
+    remote_object_store: Arc<dyn ObjectStoreGetExt>,
+    index: ArchiveIndexTables,
+    /// Serializes index syncs, so files are appended to the index in order.
+    sync_lock: AsyncMutex<()>,
+    /// Decompressed content of recently read archive files, by file path.
+    files: Mutex<LruCache<String, Arc<Vec<u8>>>>,
+    /// Store the events of archived transactions are read from.
+    events_store: Option<Arc<dyn ReadStore + Send + Sync>>,
+    concurrency: usize,
+}
+
+impl IndexedArchiveReader {
+    /// Creates a reader over the archive in the given remote store, opening
+    /// the index database at `index_path` or creating it if it doesn't exist.
+    /// Call
+    /// [`IndexedArchiveReader::sync_index`] to index newly archived files.
+    pub fn new(
+        remote_store_config: &ObjectStoreConfig,
+        index_path: PathBuf,
+        concurrency: NonZeroUsize,
+    ) -> Result<Self> {
+        let remote_object_store = if remote_store_config.no_sign_request {
+            remote_store_config.make_http()?
+        } else {
+            remote_store_config.make().map(Arc::new)?
+        };
+        Ok(Self {
+            remote_object_store,
+            index: ArchiveIndexTables::open(index_path),
+            sync_lock: AsyncMutex::new(()),
+            files: Mutex::new(LruCache::new(DEFAULT_FILE_CACHE_SIZE)),
+            events_store: None,
+            concurrency: concurrency.get(),
+        })
+    }
+
+    /// Sets the number of decompressed archive files kept in memory.
+    pub fn with_file_cache_size(self, size: NonZeroUsize) -> Self {
+        self.files.lock().unwrap().resize(size);
+        self
+    }
+
+    /// Sets the store the events of archived transactions are read from, as
+    /// the archive itself only contains their digests.
+    pub fn with_events_store(mut self, events_store: Arc<dyn ReadStore + Send + Sync>) -> Self {
+        self.events_store = Some(events_store);
+        self
+    }
+
+    /// Indexes all files listed in the remote manifest which are not covered
+    /// by the index yet. Returns the sequence number of the first checkpoint
+    /// not covered by the index.
+    ///
+    /// Each pair of files is appended to the index as soon as it is indexed,
+    /// so an interrupted sync resumes after the last indexed pair and lookups
+    /// see new checkpoints while the sync is still running.
+    pub async fn sync_index(&self) -> Result<CheckpointSequenceNumber> {
+        let _sync_guard = self.sync_lock.lock().await;
+        let manifest = read_manifest(self.remote_object_store.clone()).await?;
+        let mut next_checkpoint_seq_num = self.index.next_checkpoint_seq_num();
+        let mut summary_files: Vec<_> = manifest
+            .files()
+            .into_iter()
+            .filter(|f| {
+                f.file_type == FileType::CheckpointSummary
+                    && f.checkpoint_seq_range.start >= next_checkpoint_seq_num
+            })
+            .collect();
+        let mut content_files: Vec<_> = manifest
+            .files()
+            .into_iter()
+            .filter(|f| {
+                f.file_type == FileType::CheckpointContent
+                    && f.checkpoint_seq_range.start >= next_checkpoint_seq_num
+            })
+            .collect();
+        if summary_files.len() != content_files.len() {
+            return Err(anyhow!(
+                "Mismatching number of summary and content files in manifest"
+            ));
+        }
+        if summary_files.is_empty() {
+            return Ok(next_checkpoint_seq_num);
+        }
+        summary_files.sort_by_key(|f| f.checkpoint_seq_range.start);
+        content_files.sort_by_key(|f| f.checkpoint_seq_range.start);
+
+        let remote_object_store = self.remote_object_store.clone();
+        let mut new_files = futures::stream::iter(summary_files.into_iter().zip(content_files))
+            .map(|(summary_file, content_file)| {
+                let remote_object_store = remote_object_store.clone();
+                async move {
+                    let summary_data = get_verified(&remote_object_store, &summary_file).await?;
+                    let content_data = get_verified(&remote_object_store, &content_file).await?;
+                    index_files(summary_file, content_file, summary_data, content_data)
+                }
+            })
+            .boxed()
+            .buffered(self.concurrency);
+
+        // Only syncs change the index, so it still ends at the same checkpoint
+        while let Some((files, transactions)) = new_files.try_next().await? {
+            if files.summary_file.checkpoint_seq_range.start != next_checkpoint_seq_num {
+                return Err(anyhow!(
+                    "Archive files are not contiguous at checkpoint {}",
+                    next_checkpoint_seq_num
+                ));
+            }
+            self.index.append(&files, transactions)?;
+            next_checkpoint_seq_num = files.summary_file.checkpoint_seq_range.end;
+        }
+        info!(
+            "Archive index covers checkpoints up to {}",
+            next_checkpoint_seq_num
+        );
+        Ok(next_checkpoint_seq_num)
+    }
+
+    /// Returns the sequence number of the first checkpoint not covered by the
+    /// index.
+    pub fn next_checkpoint_seq_num(&self) -> CheckpointSequenceNumber {
+        self.index.next_checkpoint_seq_num()
+    }
+
+    /// Returns the checkpoint which includes the given transaction, without
+    /// reading the archive.
+    pub fn checkpoint_of(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Result<Option<CheckpointSequenceNumber>> {
+        self.index.checkpoint_of(digest)
+    }
+
+    pub async fn get_checkpoint(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<Option<CertifiedCheckpointSummary>> {
+        let Some((files, position)) = self.index.locate(seq)? else {
+            return Ok(None);
+        };
+        let (file, offset) = (files.summary_file, files.summary_offsets[position]);
+        let summary: CertifiedCheckpointSummary =
+            self.read_blob_at(&file, SUMMARY_FILE_MAGIC, offset).await?;
+        if summary.sequence_number != seq {
+            return Err(anyhow!(
+                "Archive index is inconsistent, expected checkpoint {} but found {}",
+                seq,
+                summary.sequence_number
+            ));
+        }
+        Ok(Some(summary))
+    }
+
+    pub async fn get_checkpoint_contents(
+        &self,
+        seq: CheckpointSequenceNumber,
+    ) -> Result<Option<FullCheckpointContents>> {
+        let Some((files, position)) = self.index.locate(seq)? else {
+            return Ok(None);
+        };
+        let (file, offset) = (files.content_file, files.content_offsets[position]);
+        self.read_blob_at(&file, CHECKPOINT_FILE_MAGIC, offset)
+            .await
+            .map(Some)
+    }
+
+    /// Returns the transaction and its effects, along with the checkpoint
+    /// which includes it.
+    pub async fn get_transaction(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Result<Option<(CheckpointSequenceNumber, ExecutionData)>> {
+        let Some(seq) = self.index.checkpoint_of(digest)? else {
+            return Ok(None);
+        };
+        let contents = self
+            .get_checkpoint_contents(seq)
+            .await?
+            .with_context(|| format!("Missing contents of checkpoint {}", seq))?;
+        let data = contents
+            .iter()
+            .find(|data| data.transaction.digest() == digest)
+            .cloned()
+            .with_context(|| format!("Transaction {} not found in checkpoint {}", digest, seq))?;
+        Ok(Some((seq, data)))
+    }
+
+    /// Returns the digest of the events emitted by the given transaction.
+    pub async fn get_events_digest(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Result<Option<TransactionEventsDigest>> {
+        Ok(self
+            .get_transaction(digest)
+            .await?
+            .and_then(|(_, data)| data.effects.events_digest().copied()))
+    }
+
+    /// Returns the events emitted by the given transaction. The archive only
+    /// contains the digest of the events, which are read from the store set
+    /// with [`Self::with_events_store`] and checked against the digest.
+    pub async fn get_events(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Result<Option<TransactionEvents>> {
+        let Some(events_digest) = self.get_events_digest(digest).await? else {
+            return Ok(None);
+        };
+        let events_store = self
+            .events_store
+            .as_ref()
+            .context("No events store to read the events of archived transactions from")?;
+        let events = events_store.get_events(&events_digest)?.with_context(|| {
+            format!("Missing events {} of transaction {}", events_digest, digest)
+        })?;
+        ensure!(
+            events.digest() == events_digest,
+            "Events of transaction {} don't match their digest {}",
+            digest,
+            events_digest
+        );
+        Ok(Some(events))
+    }
+
+    /// Decodes the blob at the given offset of the decompressed content of the
+    /// given file.
+    async fn read_blob_at<T: serde::de::DeserializeOwned>(
+        &self,
+        file: &FileMetadata,
+        magic: u32,
+        offset: u64,
+    ) -> Result<T> {
+        let content = self.read_file(file, magic).await?;
+        let blob = content.get(offset as usize..).with_context(|| {
+            format!("Offset {} out of bounds of {:?}", offset, file.file_path())
+        })?;
+        Blob::read(&mut Cursor::new(blob))?.decode()
+    }
+
+    /// Returns the decompressed content of the given file, downloading and
+    /// verifying it against its checksum in the manifest if it isn't cached.
+    async fn read_file(&self, file: &FileMetadata, magic: u32) -> Result<Arc<Vec<u8>>> {
+        let path = file.file_path().to_string();
+        if let Some(content) = self.files.lock().unwrap().get(&path) {
+            return Ok(content.clone());
+        }
+        let bytes = get_verified(&self.remote_object_store, file).await?;
+        let (mut reader, _) = iota_storage::read(magic, bytes.reader())?;
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        let content = Arc::new(content);
+        self.files.lock().unwrap().put(path, content.clone());
+        Ok(content)
+    }
+}
+
+/// Downloads the given file and ensures its checksum matches the manifest.
+async fn get_verified(
+    remote_object_store: &Arc<dyn ObjectStoreGetExt>,
+    file: &FileMetadata,
+) -> Result<Bytes> {
+    let bytes = get(remote_object_store, &file.file_path()).await?;
+    if compute_sha3_checksum_for_bytes(bytes.clone())? != file.sha3_digest {
+        return Err(anyhow!(
+            "Checksum doesn't match for file: {:?}",
+            file.file_path()
+        ));
+    }
+    Ok(bytes)
+}
+
+/// Records the offsets of the checkpoints in the given summary and content
+/// files, along with the transactions of each checkpoint.
+fn index_files(
+    summary_file: FileMetadata,
+    content_file: FileMetadata,
+    summary_data: Bytes,
+    content_data: Bytes,
+) -> Result<(
+    IndexedFiles,
+    Vec<(TransactionDigest, CheckpointSequenceNumber)>,
+)> {
+    if summary_file.checkpoint_seq_range != content_file.checkpoint_seq_range {
+        return Err(anyhow!(
+            "Mismatching checkpoint ranges in files {:?} and {:?}",
+            summary_file.file_path(),
+            content_file.file_path()
+        ));
+    }
+    let range = summary_file.checkpoint_seq_range.clone();
+    let mut summary_reader = OffsetReader::new(SUMMARY_FILE_MAGIC, summary_data)?;
+    let mut content_reader = OffsetReader::new(CHECKPOINT_FILE_MAGIC, content_data)?;
+    let mut summary_offsets = Vec::with_capacity((range.end - range.start) as usize);
+    let mut content_offsets = Vec::with_capacity((range.end - range.start) as usize);
+    let mut transactions = vec![];
+    for seq in range {
+        summary_offsets.push(summary_reader.offset);
+        let summary: CertifiedCheckpointSummary = summary_reader.next_blob()?;
+        if summary.sequence_number != seq {
+            return Err(anyhow!(
+                "Expected checkpoint {} but found {} in file {:?}",
+                seq,
+                summary.sequence_number,
+                summary_file.file_path()
+            ));
+        }
+        content_offsets.push(content_reader.offset);
+        let contents: FullCheckpointContents = content_reader.next_blob()?;
+        contents.verify_digests(summary.content_digest)?;
+        transactions.extend(
+            contents
+                .iter()
+                .map(|data| (*data.transaction.digest(), seq)),
+        );
+    }
+    Ok((
+        IndexedFiles {
+            summary_file,
+            content_file,
+            summary_offsets,
+            content_offsets,
+        },
+        transactions,
+    ))
+}
+
+/// Reads blobs from the decompressed content of an archive file, keeping track
+/// of the offset of the next blob.
+struct OffsetReader {
+    reader: Box<dyn Read>,
+    offset: u64,
+}
+
+impl OffsetReader {
+    fn new(magic: u32, bytes: Bytes) -> Result<Self> {
+        let (reader, _) = iota_storage::read(magic, bytes.reader())?;
+        Ok(Self { reader, offset: 0 })
+    }
+
+    fn next_blob<T: serde::de::DeserializeOwned>(&mut self) -> Result<T> {
+        let mut reader = CountingReader {
+            inner: &mut self.reader,
+            count: 0,
+        };
+        let blob = Blob::read(&mut reader)?;
+        self.offset += reader.count;
+        blob.decode()
+    }
+}
+
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
@@ -4,6 +4,7 @@
 
 #![allow(dead_code)]
 
+pub mod index;
 pub mod reader;
 pub mod writer;
 
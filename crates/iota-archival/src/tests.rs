@@ -20,6 +20,8 @@ use iota_config::{
 use iota_storage::{FileCompression, StorageFormat, object_store::util::path_to_filesystem};
 use iota_swarm_config::test_utils::{CommitteeFixture, empty_contents};
 use iota_types::{
+    digests::TransactionDigest,
+    effects::TransactionEffectsAPI,
     messages_checkpoint::{VerifiedCheckpoint, VerifiedCheckpointContents},
     storage::{ReadStore, SharedInMemoryStore, SingleCheckpointSharedInMemoryStore},
 };
@@ -29,7 +31,9 @@ use prometheus::Registry;
 use tempfile::tempdir;
 
 use crate::{
-    Manifest, read_manifest,
+    Manifest,
+    index::IndexedArchiveReader,
+    read_manifest,
     reader::{ArchiveReader, ArchiveReaderMetrics},
     verify_archive_with_local_store, write_manifest,
     writer::ArchiveWriter,
@@ -386,3 +390,97 @@ async fn test_verify_archive_with_oneshot_store_bad_data() -> Result<(), anyhow:
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_indexed_archive_reader() -> Result<(), anyhow::Error> {
+    let test_store = SharedInMemoryStore::default();
+    let test_state = setup_test_state(temp_dir()).await?;
+    let kill = test_state.archive_writer.start(test_store.clone()).await?;
+    let mut latest_archived_checkpoint_seq_num = 0;
+    while latest_archived_checkpoint_seq_num < 10 {
+        insert_checkpoints_and_verify_manifest(&test_state, test_store.clone(), None).await?;
+        latest_archived_checkpoint_seq_num = test_state
+            .archive_reader
+            .latest_available_checkpoint()
+            .await?;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    kill.send(())?;
+
+    let index_path = temp_dir().join("index");
+    let reader = IndexedArchiveReader::new(
+        &test_state.remote_store_config,
+        index_path.clone(),
+        NonZeroUsize::new(2).unwrap(),
+    )?
+    .with_file_cache_size(NonZeroUsize::new(1).unwrap())
+    .with_events_store(Arc::new(test_store.clone()));
+    let next_checkpoint_seq_num = reader.sync_index().await?;
+    ma::assert_gt!(next_checkpoint_seq_num, latest_archived_checkpoint_seq_num);
+    for seq in 0..next_checkpoint_seq_num {
+        let expected = test_store
+            .get_checkpoint_by_sequence_number(seq)?
+            .context("Missing checkpoint")?;
+        let summary = reader
+            .get_checkpoint(seq)
+            .await?
+            .context("Missing archived checkpoint")?;
+        assert_eq!(summary.digest(), expected.digest());
+        let contents = reader
+            .get_checkpoint_contents(seq)
+            .await?
+            .context("Missing archived checkpoint contents")?;
+        contents.verify_digests(summary.content_digest)?;
+        for data in contents.iter() {
+            let (tx_seq, tx_data) = reader
+                .get_transaction(data.transaction.digest())
+                .await?
+                .context("Missing archived transaction")?;
+            assert_eq!(tx_seq, seq);
+            assert_eq!(&tx_data, data);
+            let expected_events = match data.effects.events_digest() {
+                Some(events_digest) => test_store.get_events(events_digest)?,
+                None => None,
+            };
+            assert_eq!(
+                reader.get_events(data.transaction.digest()).await?,
+                expected_events
+            );
+        }
+    }
+    assert!(
+        reader
+            .get_checkpoint(next_checkpoint_seq_num)
+            .await?
+            .is_none()
+    );
+    assert!(
+        reader
+            .get_transaction(&TransactionDigest::random())
+            .await?
+            .is_none()
+    );
+
+    // The index is loaded back from the local filesystem
+    drop(reader);
+    let reader = IndexedArchiveReader::new(
+        &test_state.remote_store_config,
+        index_path,
+        NonZeroUsize::new(2).unwrap(),
+    )?;
+    assert_eq!(reader.next_checkpoint_seq_num(), next_checkpoint_seq_num);
+    assert_eq!(reader.sync_index().await?, next_checkpoint_seq_num);
+    assert!(reader.get_checkpoint(0).await?.is_some());
+    // Events can't be read without an events store
+    let digest = test_store
+        .get_checkpoint_contents_by_sequence_number(0)?
+        .context("Missing checkpoint contents")?
+        .iter()
+        .next()
+        .context("Missing transaction")?
+        .transaction;
+    if reader.get_events_digest(&digest).await?.is_some() {
+        assert!(reader.get_events(&digest).await.is_err());
+    }
+    Ok(())
+}
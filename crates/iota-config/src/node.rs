@@ -5,7 +5,7 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     net::SocketAddr,
-    num::NonZeroUsize,
+    num::{NonZeroU64, NonZeroUsize},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
@@ -15,7 +15,7 @@ use anyhow::Result;
 use consensus_config::Parameters as ConsensusParameters;
 use iota_keys::keypair_file::{read_authority_keypair_from_file, read_keypair_from_file};
 use iota_types::{
-    base_types::IotaAddress,
+    base_types::{IotaAddress, ObjectID},
     committee::EpochId,
     crypto::{
         AccountKeyPair, AuthorityKeyPair, AuthorityPublicKeyBytes, IotaKeyPair, KeypairTraits,
@@ -244,6 +244,43 @@ pub struct NodeConfig {
 
     #[serde(default = "bool_true")]
     pub enable_validator_tx_finalizer: bool,
+
+    /// If set, the node runs as a sparse full node which only stores the
+    /// transactions touching the tracked addresses, packages and objects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sparse_node_config: Option<SparseNodeConfig>,
+
+    /// If set, the REST API reads checkpoints and transactions which were
+    /// pruned from the local store from this archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rest_archive_config: Option<RestArchiveConfig>,
+}
+
+/// Configuration of a sparse full node. State sync still verifies every
+/// checkpoint, but instead of executing all transactions, the node fetches the
+/// full data of each checkpoint from an upstream full node and only stores the
+/// transactions, effects, events and output objects of the transactions
+/// touching the tracked addresses, packages or objects, after verifying them
+/// against the certified checkpoint contents.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SparseNodeConfig {
+    /// REST API URL of the full node serving the full checkpoint data, e.g.
+    /// `http://127.0.0.1:9000/api/v1`. The upstream node must not have pruned
+    /// the objects of the checkpoints this node still has to process.
+    pub upstream_rest_url: String,
+    /// Addresses whose transactions are tracked, either as sender or as owner
+    /// of an input or output object.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracked_addresses: Vec<IotaAddress>,
+    /// Packages whose transactions are tracked, either when called or when
+    /// defining the type of an input or output object.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracked_packages: Vec<ObjectID>,
+    /// Objects whose transactions are tracked, either when used as input or
+    /// when changed by the transaction.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracked_objects: Vec<ObjectID>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
@@ -700,6 +737,45 @@ pub struct AuthorityStorePruningConfig {
     pub num_epochs_to_retain_for_checkpoints: Option<u64>,
     #[serde(default = "default_smoothing", skip_serializing_if = "is_true")]
     pub smooth: bool,
+    /// objects for which the pruner keeps all versions, while only the latest
+    /// version is kept for all other objects once they are older than
+    /// `num_epochs_to_retain`
+    #[serde(
+        default,
+        skip_serializing_if = "ObjectHistoryRetentionConfig::is_empty"
+    )]
+    pub retain_object_history: ObjectHistoryRetentionConfig,
+}
+
+/// Selects the objects whose full version history is kept by the objects
+/// pruner. An object version is kept if it matches any of the configured
+/// types, packages or owners.
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ObjectHistoryRetentionConfig {
+    /// Move struct types of the objects to keep, e.g.
+    /// `0x2::coin::Coin<0x2::iota::IOTA>`. A type given without type
+    /// parameters matches all of its instantiations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub move_types: Vec<String>,
+    /// Packages whose Move types are kept. Note that objects of a type
+    /// defined in an upgraded version of a package are still matched by the
+    /// ID of the original package, as types keep their defining package ID
+    /// across upgrades.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<ObjectID>,
+    /// Addresses or object IDs owning the objects to keep. Ownership is
+    /// checked for each version separately, so versions owned by a different
+    /// address after a transfer are pruned, and child objects are only kept
+    /// when their parent object ID is listed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<IotaAddress>,
+}
+
+impl ObjectHistoryRetentionConfig {
+    pub fn is_empty(&self) -> bool {
+        self.move_types.is_empty() && self.packages.is_empty() && self.owners.is_empty()
+    }
 }
 
 fn default_num_latest_epoch_dbs_to_retain() -> usize {
@@ -734,6 +810,7 @@ impl Default for AuthorityStorePruningConfig {
             periodic_compaction_threshold_days: None,
             num_epochs_to_retain_for_checkpoints: if cfg!(msim) { Some(2) } else { None },
             smooth: true,
+            retain_object_history: Default::default(),
         }
     }
 }
@@ -788,12 +865,41 @@ pub struct StateArchiveConfig {
     pub use_for_pruning_watermark: bool,
 }
 
+/// Configuration of the archive the REST API falls back to for historical
+/// lookups.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestArchiveConfig {
+    pub object_store_config: ObjectStoreConfig,
+    /// Directory of the local index over the files of the archive.
+    pub index_path: PathBuf,
+    #[serde(default = "default_rest_archive_concurrency")]
+    pub concurrency: usize,
+    /// Interval in seconds between syncs of the index with the manifest of
+    /// the archive.
+    #[serde(default = "default_rest_archive_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+fn default_rest_archive_concurrency() -> usize {
+    5
+}
+
+fn default_rest_archive_sync_interval_secs() -> u64 {
+    60
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct StateSnapshotConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub object_store_config: Option<ObjectStoreConfig>,
     pub concurrency: usize,
+    /// If set, only the snapshots of epochs which are a multiple of this
+    /// interval are full snapshots. The snapshots of the other epochs are
+    /// written as deltas on top of the snapshot of the previous epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_snapshot_interval: Option<NonZeroU64>,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
@@ -787,7 +787,7 @@ pub struct AuthorityState {
     tx_execution_shutdown: Mutex<Option<oneshot::Sender<()>>>,
 
     pub metrics: Arc<AuthorityMetrics>,
-    _pruner: AuthorityStorePruner,
+    pruner: AuthorityStorePruner,
     _authority_per_epoch_pruner: AuthorityPerEpochStorePruner,
 
     /// Take db checkpoints of different dbs
@@ -2648,7 +2648,7 @@ impl AuthorityState {
             epoch_store.get_parent_path(),
             &config.authority_store_pruning_config,
         );
-        let _pruner = AuthorityStorePruner::new(
+        let pruner = AuthorityStorePruner::new(
             store.perpetual_tables.clone(),
             checkpoint_store.clone(),
             rest_index.clone(),
@@ -2678,7 +2678,7 @@ impl AuthorityState {
             transaction_manager,
             tx_execution_shutdown: Mutex::new(Some(tx_execution_shutdown)),
             metrics,
-            _pruner,
+            pruner,
             _authority_per_epoch_pruner,
             db_checkpoint_config: db_checkpoint_config.clone(),
             config,
@@ -2743,6 +2743,10 @@ impl AuthorityState {
         &self.execution_cache_trait_pointers.cache_commit
     }
 
+    pub fn pruner(&self) -> &AuthorityStorePruner {
+        &self.pruner
+    }
+
     pub fn database_for_testing(&self) -> Arc<AuthorityStore> {
         self.execution_cache_trait_pointers
             .testing_api
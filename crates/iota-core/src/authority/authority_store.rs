@@ -13,7 +13,7 @@ use iota_macros::fail_point_arg;
 use iota_storage::mutex_table::{MutexGuard, MutexTable, RwLockGuard, RwLockTable};
 use iota_types::{
     accumulator::Accumulator,
-    base_types::SequenceNumber,
+    base_types::{ExecutionDigests, SequenceNumber},
     digests::TransactionEventsDigest,
     effects::{TransactionEffects, TransactionEvents},
     error::UserInputError,
@@ -34,7 +34,7 @@ use tokio::{
 use tracing::{debug, info, trace};
 use typed_store::{
     TypedStoreError,
-    rocks::{DBBatch, DBMap, util::is_ref_count_value},
+    rocks::{DBBatch, DBMap, RocksDB, util::is_ref_count_value},
     traits::Map,
 };
 
@@ -50,7 +50,7 @@ use crate::{
         },
         authority_store_tables::TotalIotaSupplyCheck,
         authority_store_types::{
-            ObjectContentDigest, StoreObject, StoreObjectPair, StoreObjectWrapper,
+            ObjectContentDigest, StoreData, StoreObject, StoreObjectPair, StoreObjectWrapper,
             get_store_object_pair,
         },
         epoch_start_configuration::{EpochFlag, EpochStartConfiguration},
@@ -384,6 +384,11 @@ impl AuthorityStore {
         Ok(store)
     }
 
+    /// Returns the database backing all perpetual tables of the store.
+    pub fn rocksdb(&self) -> &Arc<RocksDB> {
+        &self.perpetual_tables.objects.rocksdb
+    }
+
     pub fn get_recovery_epoch_at_restart(&self) -> IotaResult<EpochId> {
         self.perpetual_tables.get_recovery_epoch_at_restart()
     }
@@ -778,6 +783,47 @@ impl AuthorityStore {
         Ok(())
     }
 
+    /// Removes object versions inserted by `bulk_insert_live_objects`, along
+    /// with their live object markers, and releases the indirect objects they
+    /// reference. This is used to restore a delta state snapshot on top of its
+    /// base, where these versions have been mutated, wrapped or deleted.
+    pub fn bulk_remove_live_objects(
+        perpetual_db: &AuthorityPerpetualTables,
+        object_refs: impl Iterator<Item = ObjectRef>,
+    ) -> IotaResult<()> {
+        let object_refs: Vec<_> = object_refs.collect();
+        let object_keys: Vec<_> = object_refs.iter().map(|r| ObjectKey::from(*r)).collect();
+        let mut batch = perpetual_db.objects.batch();
+        let mut indirect_objects: HashMap<_, i64> = HashMap::new();
+        for object in perpetual_db
+            .objects
+            .multi_get(object_keys.iter())?
+            .into_iter()
+            .flatten()
+        {
+            if let StoreObject::Value(object) = object.into_inner() {
+                if let StoreData::IndirectObject(indirect_object) = object.data {
+                    *indirect_objects.entry(indirect_object.digest).or_default() -= 1;
+                }
+            }
+        }
+        batch.delete_batch(&perpetual_db.objects, object_keys.iter())?;
+        batch.delete_batch(
+            &perpetual_db.live_owned_object_markers,
+            object_refs.iter().filter(|r| !r.2.is_wrapped()),
+        )?;
+        if !indirect_objects.is_empty() {
+            batch.partial_merge_batch(
+                &perpetual_db.indirect_move_objects,
+                indirect_objects
+                    .iter()
+                    .map(|(digest, delta)| (digest, delta.to_le_bytes())),
+            )?;
+        }
+        batch.write()?;
+        Ok(())
+    }
+
     pub fn set_epoch_start_configuration(
         &self,
         epoch_start_configuration: &EpochStartConfiguration,
@@ -1434,6 +1480,25 @@ impl AuthorityStore {
         Ok(())
     }
 
+    /// Removes transactions and their effects which were inserted without
+    /// being executed, e.g. by state sync on a sparse full node.
+    pub fn multi_remove_transaction_and_effects(
+        &self,
+        transactions: &[ExecutionDigests],
+    ) -> Result<(), TypedStoreError> {
+        let mut write_batch = self.perpetual_tables.transactions.batch();
+        write_batch.delete_batch(
+            &self.perpetual_tables.transactions,
+            transactions.iter().map(|digests| digests.transaction),
+        )?;
+        write_batch.delete_batch(
+            &self.perpetual_tables.effects,
+            transactions.iter().map(|digests| digests.effects),
+        )?;
+        write_batch.write()?;
+        Ok(())
+    }
+
     pub fn multi_get_transaction_blocks(
         &self,
         tx_digests: &[TransactionDigest],
@@ -4,31 +4,37 @@
 
 use std::{
     cmp::{max, min},
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet},
     sync::{Arc, Mutex},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::anyhow;
 use iota_archival::reader::ArchiveReaderBalancer;
-use iota_config::node::AuthorityStorePruningConfig;
+use iota_config::node::{AuthorityStorePruningConfig, ObjectHistoryRetentionConfig};
 use iota_metrics::{monitored_scope, spawn_monitored_task};
 use iota_storage::mutex_table::RwLockTable;
 use iota_types::{
-    base_types::{ObjectID, SequenceNumber, VersionNumber},
+    base_types::{IotaAddress, MoveObjectType, ObjectID, SequenceNumber, VersionNumber},
     committee::EpochId,
     effects::{TransactionEffects, TransactionEffectsAPI},
     message_envelope::Message,
     messages_checkpoint::{CheckpointContents, CheckpointDigest, CheckpointSequenceNumber},
+    object::Owner,
+    parse_iota_struct_tag,
     storage::ObjectKey,
 };
+use move_core_types::language_storage::StructTag;
 use once_cell::sync::Lazy;
 use prometheus::{
     IntCounter, IntGauge, Registry, register_int_counter_with_registry,
     register_int_gauge_with_registry,
 };
 use tokio::{
-    sync::oneshot::{self, Sender},
+    sync::{
+        Mutex as AsyncMutex,
+        oneshot::{self, Sender},
+    },
     time::Instant,
 };
 use tracing::{debug, error, info, warn};
@@ -36,7 +42,9 @@ use typed_store::{Map, TypedStoreError, rocksdb::LiveFile};
 
 use super::authority_store_tables::AuthorityPerpetualTables;
 use crate::{
-    authority::authority_store_types::{ObjectContentDigest, StoreData, StoreObject},
+    authority::authority_store_types::{
+        ObjectContentDigest, StoreData, StoreObject, StoreObjectWrapper,
+    },
     checkpoints::{CheckpointStore, CheckpointWatermark},
     rest_index::RestIndexStore,
 };
@@ -58,9 +66,21 @@ pub const EPOCH_DURATION_MS_FOR_TESTING: u64 = 24 * 60 * 60 * 1000;
 
 /// The `AuthorityStorePruner` manages the pruning process for object stores
 /// within the `AuthorityStore`. It includes a cancellation handle that can be
-/// used to stop the pruning task for objects.
+/// used to stop the pruning task for objects, and the handles needed to run
+/// pruning passes on demand.
 pub struct AuthorityStorePruner {
     _objects_pruner_cancel_handle: oneshot::Sender<()>,
+    perpetual_db: Arc<AuthorityPerpetualTables>,
+    checkpoint_store: Arc<CheckpointStore>,
+    rest_index: Option<Arc<RestIndexStore>>,
+    objects_lock_table: Arc<RwLockTable<ObjectContentDigest>>,
+    config: AuthorityStorePruningConfig,
+    metrics: Arc<AuthorityStorePruningMetrics>,
+    indirect_objects_threshold: usize,
+    archive_readers: ArchiveReaderBalancer,
+    /// Serializes the periodic and on demand pruning passes, which must not
+    /// prune the same checkpoints concurrently.
+    pruning_lock: Arc<AsyncMutex<()>>,
 }
 
 static MIN_PRUNING_TICK_DURATION_MS: u64 = 10 * 1000;
@@ -71,6 +91,7 @@ pub struct AuthorityStorePruningMetrics {
     pub last_pruned_checkpoint: IntGauge,
     pub num_pruned_objects: IntCounter,
     pub num_pruned_tombstones: IntCounter,
+    pub num_retained_object_versions: IntCounter,
     pub last_pruned_effects_checkpoint: IntGauge,
     pub num_epochs_to_retain_for_objects: IntGauge,
     pub num_epochs_to_retain_for_checkpoints: IntGauge,
@@ -100,6 +121,12 @@ impl AuthorityStorePruningMetrics {
                 registry
             )
             .unwrap(),
+            num_retained_object_versions: register_int_counter_with_registry!(
+                "num_retained_object_versions",
+                "Number of object versions kept by the object history retention policy",
+                registry
+            )
+            .unwrap(),
             last_pruned_effects_checkpoint: register_int_gauge_with_registry!(
                 "last_pruned_effects_checkpoint",
                 "Last pruned effects checkpoint",
@@ -136,6 +163,79 @@ pub enum PruningMode {
     Checkpoints,
 }
 
+/// Decides which object versions are kept by the objects pruner, based on the
+/// `ObjectHistoryRetentionConfig`.
+#[derive(Default)]
+struct ObjectHistoryRetention {
+    move_types: Vec<StructTag>,
+    packages: HashSet<ObjectID>,
+    owners: HashSet<IotaAddress>,
+}
+
+impl ObjectHistoryRetention {
+    fn new(config: &ObjectHistoryRetentionConfig) -> anyhow::Result<Self> {
+        let move_types = config
+            .move_types
+            .iter()
+            .map(|move_type| {
+                parse_iota_struct_tag(move_type)
+                    .map_err(|err| anyhow!("invalid Move type {move_type} to retain: {err}"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            move_types,
+            packages: config.packages.iter().copied().collect(),
+            owners: config.owners.iter().copied().collect(),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.move_types.is_empty() && self.packages.is_empty() && self.owners.is_empty()
+    }
+
+    fn matches_type(&self, type_: &MoveObjectType) -> bool {
+        self.packages.contains(&ObjectID::from(type_.address()))
+            || self.move_types.iter().any(|tag| {
+                if tag.type_params.is_empty() {
+                    type_.address() == tag.address
+                        && type_.module() == tag.module.as_ident_str()
+                        && type_.name() == tag.name.as_ident_str()
+                } else {
+                    type_.is(tag)
+                }
+            })
+    }
+
+    /// Returns whether the given version of an object must be kept. Deleted
+    /// and wrapped markers carry no type or owner and are never matched.
+    fn retains(
+        &self,
+        perpetual_db: &AuthorityPerpetualTables,
+        object: &StoreObjectWrapper,
+    ) -> Result<bool, TypedStoreError> {
+        let StoreObject::Value(object) = object.inner() else {
+            return Ok(false);
+        };
+        if let Owner::AddressOwner(owner) | Owner::ObjectOwner(owner) = &object.owner {
+            if self.owners.contains(owner) {
+                return Ok(true);
+            }
+        }
+        if self.move_types.is_empty() && self.packages.is_empty() {
+            return Ok(false);
+        }
+        Ok(match &object.data {
+            StoreData::Move(move_object) => self.matches_type(move_object.type_()),
+            StoreData::Coin(_) => self.matches_type(&MoveObjectType::gas_coin()),
+            StoreData::IndirectObject(metadata) => perpetual_db
+                .indirect_move_objects
+                .get(&metadata.digest)?
+                .is_some_and(|indirect_object| self.matches_type(&indirect_object.inner().type_)),
+            StoreData::Package(_) => false,
+        })
+    }
+}
+
 impl AuthorityStorePruner {
     /// prunes old versions of objects based on transaction effects
     async fn prune_objects(
@@ -145,6 +245,7 @@ impl AuthorityStorePruner {
         checkpoint_number: CheckpointSequenceNumber,
         metrics: Arc<AuthorityStorePruningMetrics>,
         indirect_objects_threshold: usize,
+        retention: &ObjectHistoryRetention,
     ) -> anyhow::Result<()> {
         let _scope = monitored_scope("ObjectsLivePruner");
         let mut wb = perpetual_db.objects.batch();
@@ -163,6 +264,26 @@ impl AuthorityStorePruner {
             }
         }
 
+        // Objects with versions kept by the retention policy are pruned with point
+        // deletes, since a range delete would also remove their retained versions.
+        let mut objects_with_retained_versions = HashSet::new();
+        if !retention.is_empty() {
+            let objects = perpetual_db
+                .objects
+                .multi_get(live_object_keys_to_prune.iter())?;
+            let mut object_keys_to_prune = vec![];
+            for (object_key, object) in live_object_keys_to_prune.into_iter().zip(objects) {
+                match object {
+                    Some(object) if retention.retains(perpetual_db, &object)? => {
+                        objects_with_retained_versions.insert(object_key.0);
+                        metrics.num_retained_object_versions.inc();
+                    }
+                    _ => object_keys_to_prune.push(object_key),
+                }
+            }
+            live_object_keys_to_prune = object_keys_to_prune;
+        }
+
         metrics
             .num_pruned_objects
             .inc_by(live_object_keys_to_prune.len() as u64);
@@ -187,7 +308,12 @@ impl AuthorityStorePruner {
         }
 
         let mut updates: HashMap<ObjectID, (VersionNumber, VersionNumber)> = HashMap::new();
+        let mut object_keys_to_delete = vec![];
         for ObjectKey(object_id, seq_number) in live_object_keys_to_prune {
+            if objects_with_retained_versions.contains(&object_id) {
+                object_keys_to_delete.push(ObjectKey(object_id, seq_number));
+                continue;
+            }
             updates
                 .entry(object_id)
                 .and_modify(|range| *range = (min(range.0, seq_number), max(range.1, seq_number)))
@@ -203,6 +329,7 @@ impl AuthorityStorePruner {
             let end_range = ObjectKey(object_id, (max_version.value() + 1).into());
             wb.schedule_delete_range(&perpetual_db.objects, &start_range, &end_range)?;
         }
+        wb.delete_batch(&perpetual_db.objects, object_keys_to_delete)?;
 
         // Instead of using range deletes, we
         // need to do a scan of all the keys for the deleted objects and then do
@@ -212,17 +339,30 @@ impl AuthorityStorePruner {
         // (imagine a tombstone is compacted away, but earlier version is still not).
         // Using point deletes guarantees that all earlier versions are deleted
         // in the database.
+        //
+        // Versions kept by the retention policy are skipped, together with the
+        // tombstone itself so that the deletion remains part of the history.
         if !object_tombstones_to_prune.is_empty() {
             let mut object_keys_to_delete = vec![];
             for ObjectKey(object_id, seq_number) in object_tombstones_to_prune {
+                let mut has_retained_versions = false;
                 for result in perpetual_db.objects.safe_iter_with_bounds(
                     Some(ObjectKey(object_id, VersionNumber::MIN)),
                     Some(ObjectKey(object_id, seq_number.next())),
                 ) {
-                    let (object_key, _) = result?;
+                    let (object_key, object) = result?;
                     assert_eq!(object_key.0, object_id);
+                    if !retention.is_empty() && retention.retains(perpetual_db, &object)? {
+                        has_retained_versions = true;
+                        metrics.num_retained_object_versions.inc();
+                        continue;
+                    }
                     object_keys_to_delete.push(object_key);
                 }
+                let tombstone_key = ObjectKey(object_id, seq_number);
+                if has_retained_versions && object_keys_to_delete.last() == Some(&tombstone_key) {
+                    object_keys_to_delete.pop();
+                }
             }
 
             wb.delete_batch(&perpetual_db.objects, object_keys_to_delete)?;
@@ -448,6 +588,10 @@ impl AuthorityStorePruner {
         indirect_objects_threshold: usize,
     ) -> anyhow::Result<()> {
         let _scope = monitored_scope("PruneForEligibleEpochs");
+        let retention = match mode {
+            PruningMode::Objects => ObjectHistoryRetention::new(&config.retain_object_history)?,
+            PruningMode::Checkpoints => ObjectHistoryRetention::default(),
+        };
 
         let mut checkpoint_number = starting_checkpoint_number;
         let current_epoch = checkpoint_store
@@ -508,6 +652,7 @@ impl AuthorityStorePruner {
                             checkpoint_number,
                             metrics.clone(),
                             indirect_objects_threshold,
+                            &retention,
                         )
                         .await?
                     }
@@ -540,6 +685,7 @@ impl AuthorityStorePruner {
                         checkpoint_number,
                         metrics.clone(),
                         indirect_objects_threshold,
+                        &retention,
                     )
                     .await?
                 }
@@ -660,6 +806,7 @@ impl AuthorityStorePruner {
         metrics: Arc<AuthorityStorePruningMetrics>,
         indirect_objects_threshold: usize,
         archive_readers: ArchiveReaderBalancer,
+        pruning_lock: Arc<AsyncMutex<()>>,
     ) -> Sender<()> {
         let (sender, mut recv) = tokio::sync::oneshot::channel();
         debug!(
@@ -717,11 +864,13 @@ impl AuthorityStorePruner {
             loop {
                 tokio::select! {
                     _ = objects_prune_interval.tick(), if config.num_epochs_to_retain != u64::MAX => {
+                        let _guard = pruning_lock.lock().await;
                         if let Err(err) = Self::prune_objects_for_eligible_epochs(&perpetual_db, &checkpoint_store, rest_index.as_deref(), &objects_lock_table, config.clone(), metrics.clone(), indirect_objects_threshold, epoch_duration_ms).await {
                             error!("Failed to prune objects: {:?}", err);
                         }
                     },
                     _ = checkpoints_prune_interval.tick(), if !matches!(config.num_epochs_to_retain_for_checkpoints(), None | Some(u64::MAX) | Some(0)) => {
+                        let _guard = pruning_lock.lock().await;
                         if let Err(err) = Self::prune_checkpoints_for_eligible_epochs(&perpetual_db, &checkpoint_store, rest_index.as_deref(), &objects_lock_table, config.clone(), metrics.clone(), indirect_objects_threshold, archive_readers.clone(), epoch_duration_ms).await {
                             error!("Failed to prune checkpoints: {:?}", err);
                         }
@@ -733,6 +882,14 @@ impl AuthorityStorePruner {
         sender
     }
 
+    /// Checks the parts of the pruning configuration which are only parsed
+    /// when pruning, so that a node doesn't start with a configuration every
+    /// pruning pass would fail on.
+    pub fn validate_config(config: &AuthorityStorePruningConfig) -> anyhow::Result<()> {
+        ObjectHistoryRetention::new(&config.retain_object_history)?;
+        Ok(())
+    }
+
     /// Initializes a new instance of `AuthorityStorePruner` with the provided
     /// configuration, database connections, and metrics registry.
     pub fn new(
@@ -760,21 +917,140 @@ impl AuthorityStorePruner {
                 warn!("Consider using an aggressive pruner (num_epochs_to_retain = 0)");
             }
         }
+        let metrics = AuthorityStorePruningMetrics::new(registry);
+        let pruning_lock = Arc::new(AsyncMutex::new(()));
         AuthorityStorePruner {
             _objects_pruner_cancel_handle: Self::setup_pruning(
-                pruning_config,
+                pruning_config.clone(),
                 epoch_duration_ms,
-                perpetual_db,
-                checkpoint_store,
-                rest_index,
-                objects_lock_table,
-                AuthorityStorePruningMetrics::new(registry),
+                perpetual_db.clone(),
+                checkpoint_store.clone(),
+                rest_index.clone(),
+                objects_lock_table.clone(),
+                metrics.clone(),
                 indirect_objects_threshold,
-                archive_readers,
+                archive_readers.clone(),
+                pruning_lock.clone(),
             ),
+            perpetual_db,
+            checkpoint_store,
+            rest_index,
+            objects_lock_table,
+            config: pruning_config,
+            metrics,
+            indirect_objects_threshold,
+            archive_readers,
+            pruning_lock,
+        }
+    }
+
+    /// Immediately prunes objects or checkpoints up to and including the given
+    /// checkpoint and returns the new pruning watermark. The pass respects the
+    /// same limits as the periodic one: checkpoints of the last epochs to
+    /// retain are kept, pruning stops before the highest executed checkpoint,
+    /// and checkpoints are only pruned once they have been archived and their
+    /// objects have been pruned.
+    pub async fn prune_up_to_checkpoint(
+        &self,
+        mode: PruningMode,
+        checkpoint: CheckpointSequenceNumber,
+    ) -> anyhow::Result<CheckpointSequenceNumber> {
+        let _scope = monitored_scope("PruneUpToCheckpoint");
+        let _guard = self.pruning_lock.lock().await;
+        let highest_executed_checkpoint = self
+            .checkpoint_store
+            .get_highest_executed_checkpoint_seq_number()?
+            .unwrap_or_default();
+        let archive_watermark = match mode {
+            PruningMode::Objects => None,
+            PruningMode::Checkpoints => self.archive_readers.get_archive_watermark().await?,
+        };
+        let (num_epochs_to_retain, max_eligible_checkpoint) = Self::on_demand_pruning_bounds(
+            &self.config,
+            mode,
+            checkpoint,
+            highest_executed_checkpoint,
+            self.perpetual_db.get_highest_pruned_checkpoint()?,
+            archive_watermark,
+        )?;
+        let pruned_checkpoint_number = self.highest_pruned_checkpoint(mode)?;
+        info!(
+            ?mode,
+            "Pruning on demand from checkpoint {} up to {}",
+            pruned_checkpoint_number,
+            max_eligible_checkpoint
+        );
+        Self::prune_for_eligible_epochs(
+            &self.perpetual_db,
+            &self.checkpoint_store,
+            self.rest_index.as_deref(),
+            mode,
+            num_epochs_to_retain,
+            pruned_checkpoint_number,
+            max_eligible_checkpoint,
+            &self.objects_lock_table,
+            self.config.clone(),
+            self.metrics.clone(),
+            self.indirect_objects_threshold,
+        )
+        .await?;
+        self.highest_pruned_checkpoint(mode)
+    }
+
+    /// Returns the number of epochs to retain and the exclusive upper bound
+    /// of an on demand pruning pass up to and including `checkpoint`. Fails if
+    /// pruning is disabled for `mode`, or if checkpoints are to be pruned
+    /// without knowing which ones have been archived.
+    fn on_demand_pruning_bounds(
+        config: &AuthorityStorePruningConfig,
+        mode: PruningMode,
+        checkpoint: CheckpointSequenceNumber,
+        highest_executed_checkpoint: CheckpointSequenceNumber,
+        highest_pruned_objects_checkpoint: CheckpointSequenceNumber,
+        archive_watermark: Option<CheckpointSequenceNumber>,
+    ) -> anyhow::Result<(u64, CheckpointSequenceNumber)> {
+        // The upper bound of `prune_for_eligible_epochs` is exclusive
+        let max_eligible_checkpoint =
+            min(checkpoint.saturating_add(1), highest_executed_checkpoint);
+        match mode {
+            PruningMode::Objects => {
+                if config.num_epochs_to_retain == u64::MAX {
+                    return Err(anyhow!("object pruning is disabled"));
+                }
+                Ok((config.num_epochs_to_retain, max_eligible_checkpoint))
+            }
+            PruningMode::Checkpoints => {
+                let num_epochs_to_retain = config
+                    .num_epochs_to_retain_for_checkpoints()
+                    .filter(|num_epochs| *num_epochs != u64::MAX)
+                    .ok_or_else(|| anyhow!("checkpoint pruning is disabled"))?;
+                let archive_watermark = archive_watermark.ok_or_else(|| {
+                    anyhow!("no archive watermark available, refusing to prune checkpoints")
+                })?;
+                Ok((
+                    num_epochs_to_retain,
+                    min(
+                        max_eligible_checkpoint,
+                        min(archive_watermark, highest_pruned_objects_checkpoint),
+                    ),
+                ))
+            }
         }
     }
 
+    /// Returns the highest checkpoint pruned in the given mode.
+    pub fn highest_pruned_checkpoint(
+        &self,
+        mode: PruningMode,
+    ) -> anyhow::Result<CheckpointSequenceNumber> {
+        Ok(match mode {
+            PruningMode::Objects => self.perpetual_db.get_highest_pruned_checkpoint()?,
+            PruningMode::Checkpoints => self
+                .checkpoint_store
+                .get_highest_pruned_checkpoint_seq_number()?,
+        })
+    }
+
     /// Compacts the entire range of objects stored in the `AuthorityStore` by
     /// invoking a range compaction on the database.
     pub fn compact(perpetual_db: &Arc<AuthorityPerpetualTables>) -> Result<(), TypedStoreError> {
@@ -789,9 +1065,11 @@ impl AuthorityStorePruner {
 mod tests {
     use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
 
+    use iota_config::node::{AuthorityStorePruningConfig, ObjectHistoryRetentionConfig};
     use iota_storage::mutex_table::RwLockTable;
     use iota_types::{
-        base_types::{ObjectDigest, ObjectID, SequenceNumber},
+        IOTA_FRAMEWORK_PACKAGE_ID,
+        base_types::{IotaAddress, ObjectDigest, ObjectID, SequenceNumber},
         effects::{TransactionEffects, TransactionEffectsAPI},
         object::Object,
         storage::ObjectKey,
@@ -804,7 +1082,7 @@ mod tests {
         rocks::{DBMap, MetricConf, ReadWriteOptions, util::reference_count_merge_operator},
     };
 
-    use super::AuthorityStorePruner;
+    use super::{AuthorityStorePruner, ObjectHistoryRetention, PruningMode};
     use crate::authority::{
         authority_store_pruner::AuthorityStorePruningMetrics,
         authority_store_tables::AuthorityPerpetualTables,
@@ -962,6 +1240,7 @@ mod tests {
                 0,
                 metrics,
                 indirect_object_threshold,
+                &ObjectHistoryRetention::default(),
             )
             .await
             .unwrap();
@@ -997,6 +1276,158 @@ mod tests {
         assert_eq!(get_keys_after_pruning(&path).unwrap().len(), 0);
     }
 
+    // Tests that the versions of objects matched by the retention policy are not
+    // pruned.
+    #[tokio::test]
+    async fn test_pruning_retains_object_history() {
+        let path = tempfile::tempdir().unwrap().into_path();
+        let retained_owner = IotaAddress::random_for_testing_only();
+        let mut to_keep = HashSet::new();
+        {
+            let db = Arc::new(AuthorityPerpetualTables::open(&path, None));
+            let mut batch = db.objects.batch();
+            let mut effects = TransactionEffects::default();
+            let ids = ObjectID::in_range(ObjectID::ZERO, 100).unwrap();
+            for (i, id) in ids.into_iter().enumerate() {
+                for seq in 0..3 {
+                    // Every other version of every other object is owned by the retained
+                    // owner, so that pruned and retained versions are interleaved
+                    let retained = i % 2 == 0 && seq % 2 == 0;
+                    let owner = if retained {
+                        retained_owner
+                    } else {
+                        IotaAddress::ZERO
+                    };
+                    let object_key = ObjectKey(id, SequenceNumber::from_u64(seq));
+                    let StoreObjectPair(obj, _) =
+                        get_store_object_pair(Object::with_id_owner_for_testing(id, owner), 0);
+                    batch
+                        .insert_batch(&db.objects, [(object_key, obj)])
+                        .unwrap();
+                    if retained || seq == 2 {
+                        to_keep.insert(object_key);
+                    } else {
+                        effects.unsafe_add_deleted_live_object_for_testing((
+                            object_key.0,
+                            object_key.1,
+                            ObjectDigest::MIN,
+                        ));
+                    }
+                }
+            }
+            batch.write().unwrap();
+
+            let retention = ObjectHistoryRetention::new(&ObjectHistoryRetentionConfig {
+                owners: vec![retained_owner],
+                ..Default::default()
+            })
+            .unwrap();
+            AuthorityStorePruner::prune_objects(
+                vec![effects],
+                &db,
+                &lock_table(),
+                0,
+                AuthorityStorePruningMetrics::new_for_test(),
+                0,
+                &retention,
+            )
+            .await
+            .unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(to_keep, get_keys_after_pruning(&path).unwrap());
+    }
+
+    #[test]
+    fn test_object_history_retention_by_type() {
+        let path = tempfile::tempdir().unwrap().into_path();
+        let db = AuthorityPerpetualTables::open(&path, None);
+        let StoreObjectPair(gas_coin, _) = get_store_object_pair(Object::new_gas_for_testing(), 0);
+        let retains = |config: ObjectHistoryRetentionConfig| {
+            ObjectHistoryRetention::new(&config)
+                .unwrap()
+                .retains(&db, &gas_coin)
+                .unwrap()
+        };
+        let move_types = |move_type: &str| ObjectHistoryRetentionConfig {
+            move_types: vec![move_type.to_string()],
+            ..Default::default()
+        };
+
+        assert!(retains(move_types("0x2::coin::Coin")));
+        assert!(retains(move_types("0x2::coin::Coin<0x2::iota::IOTA>")));
+        assert!(!retains(move_types("0x2::coin::Coin<0x2::foo::FOO>")));
+        assert!(!retains(move_types("0x2::balance::Balance")));
+        assert!(retains(ObjectHistoryRetentionConfig {
+            packages: vec![IOTA_FRAMEWORK_PACKAGE_ID],
+            ..Default::default()
+        }));
+        assert!(!retains(ObjectHistoryRetentionConfig::default()));
+        assert!(ObjectHistoryRetention::new(&move_types("not a type")).is_err());
+        assert!(
+            AuthorityStorePruner::validate_config(&AuthorityStorePruningConfig {
+                retain_object_history: move_types("not a type"),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_on_demand_pruning_bounds() {
+        let mut config = AuthorityStorePruningConfig {
+            num_epochs_to_retain: 1,
+            num_epochs_to_retain_for_checkpoints: Some(3),
+            ..Default::default()
+        };
+        let bounds = |config: &AuthorityStorePruningConfig, mode, archive_watermark| {
+            AuthorityStorePruner::on_demand_pruning_bounds(
+                config,
+                mode,
+                100,
+                50,
+                30,
+                archive_watermark,
+            )
+        };
+
+        // Objects are pruned up to the highest executed checkpoint, keeping the
+        // configured number of epochs
+        assert_eq!(
+            bounds(&config, PruningMode::Objects, None).unwrap(),
+            (1, 50)
+        );
+        // Checkpoints are pruned up to the archive watermark and the pruned
+        // objects
+        assert_eq!(
+            bounds(&config, PruningMode::Checkpoints, Some(20)).unwrap(),
+            (3, 20)
+        );
+        assert_eq!(
+            bounds(&config, PruningMode::Checkpoints, Some(40)).unwrap(),
+            (3, 30)
+        );
+        assert!(bounds(&config, PruningMode::Checkpoints, None).is_err());
+        // A target below all limits is pruned up to and including it
+        assert_eq!(
+            AuthorityStorePruner::on_demand_pruning_bounds(
+                &config,
+                PruningMode::Objects,
+                9,
+                50,
+                30,
+                None
+            )
+            .unwrap(),
+            (1, 10)
+        );
+
+        config.num_epochs_to_retain = u64::MAX;
+        config.num_epochs_to_retain_for_checkpoints = None;
+        assert!(bounds(&config, PruningMode::Objects, None).is_err());
+        assert!(bounds(&config, PruningMode::Checkpoints, Some(20)).is_err());
+    }
+
     #[tokio::test]
     async fn test_ref_count_pruning() {
         let path = tempfile::tempdir().unwrap().into_path();
@@ -1087,6 +1518,7 @@ mod tests {
             0,
             metrics,
             0,
+            &ObjectHistoryRetention::default(),
         )
         .await;
         info!("Total pruned keys = {:?}", total_pruned);
@@ -1121,7 +1553,7 @@ mod pprof_tests {
     use tracing::log::{error, info};
     use typed_store::{Map, rocks::DBMap};
 
-    use super::AuthorityStorePruner;
+    use super::{AuthorityStorePruner, ObjectHistoryRetention};
     use crate::authority::{
         authority_store_pruner::{AuthorityStorePruningMetrics, tests, tests::lock_table},
         authority_store_tables::AuthorityPerpetualTables,
@@ -1202,6 +1634,7 @@ mod pprof_tests {
             0,
             metrics,
             1,
+            &ObjectHistoryRetention::default(),
         )
         .await?;
         let guard = pprof::ProfilerGuardBuilder::default()
@@ -1239,6 +1672,7 @@ mod pprof_tests {
             0,
             metrics,
             1,
+            &ObjectHistoryRetention::default(),
         )
         .await?;
         if let Ok(()) = perpetual_db.objects.flush() {
@@ -6,6 +6,7 @@ mod causal_order;
 pub mod checkpoint_executor;
 mod checkpoint_output;
 mod metrics;
+pub mod sparse_checkpoint_executor;
 
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
@@ -63,7 +64,7 @@ use tokio::{
 use tracing::{debug, error, info, instrument, warn};
 use typed_store::{
     DBMapUtils, Map, TypedStoreError,
-    rocks::{DBMap, MetricConf},
+    rocks::{DBMap, MetricConf, RocksDB},
     traits::{TableSummary, TypedStoreDebug},
 };
 
@@ -724,6 +725,11 @@ impl CheckpointStore {
             .map_err(Into::into)
     }
 
+    /// Returns the database backing all tables of the store.
+    pub fn rocksdb(&self) -> &Arc<RocksDB> {
+        &self.checkpoint_content.rocksdb
+    }
+
     pub fn delete_highest_executed_checkpoint_test_only(&self) -> Result<(), TypedStoreError> {
         let mut wb = self.watermarks.batch();
         wb.delete_batch(
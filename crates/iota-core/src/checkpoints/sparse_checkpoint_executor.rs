@@ -0,0 +1,801 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! SparseCheckpointExecutor replaces the CheckpointExecutor on sparse full
+//! nodes. Instead of executing every checkpoint, it takes the full data of
+//! each checkpoint synced by state sync, as served by an upstream full node,
+//! verifies it against the certified checkpoint contents and only stores the
+//! transactions touching the tracked addresses, packages or objects.
+//!
+//! The effects of a transaction commit to the references of all the objects it
+//! mutates and writes, so the stored objects are proved by the checkpoint
+//! certificate without executing the transaction. Like the CheckpointExecutor,
+//! `run_epoch` returns once the last checkpoint of the epoch was processed, so
+//! that the node can reconfigure. The IOTA system state object is always
+//! tracked for that purpose. Since a sparse node doesn't hold the full live
+//! object set, it doesn't compute the state accumulator and can't execute
+//! transactions itself.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::ensure;
+use futures::future::BoxFuture;
+use iota_config::node::SparseNodeConfig;
+use iota_types::{
+    IOTA_SYSTEM_STATE_OBJECT_ID,
+    base_types::{ExecutionDigests, IotaAddress, ObjectID, ObjectRef},
+    effects::TransactionEffectsAPI,
+    full_checkpoint_content::{CheckpointData, CheckpointTransaction},
+    inner_temporary_store::InnerTemporaryStore,
+    messages_checkpoint::{CheckpointSequenceNumber, VerifiedCheckpoint},
+    object::{Object, Owner},
+    transaction::{TransactionDataAPI, TransactionKey, VerifiedTransaction},
+};
+use move_binary_format::binary_config::BinaryConfig;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, info, warn};
+use typed_store::Map;
+
+use crate::{
+    authority::{
+        AuthorityState, authority_per_epoch_store::AuthorityPerEpochStore,
+        authority_store::AuthorityStore,
+    },
+    checkpoints::CheckpointStore,
+    transaction_outputs::TransactionOutputs,
+};
+
+/// Delay before retrying a checkpoint which failed to be fetched or processed,
+/// doubled after every failure up to [`MAX_RETRY_BACKOFF`].
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Source of the full data of the checkpoints processed by a sparse full node,
+/// usually the REST API of an upstream full node.
+pub trait CheckpointDataSource: Send + Sync {
+    fn get_full_checkpoint(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> BoxFuture<'_, anyhow::Result<CheckpointData>>;
+}
+
+/// Selects the transactions stored by a sparse full node.
+pub struct SparseTransactionFilter {
+    addresses: HashSet<IotaAddress>,
+    packages: HashSet<ObjectID>,
+    objects: HashSet<ObjectID>,
+}
+
+impl SparseTransactionFilter {
+    pub fn new(config: &SparseNodeConfig) -> Self {
+        Self {
+            addresses: config.tracked_addresses.iter().copied().collect(),
+            packages: config.tracked_packages.iter().copied().collect(),
+            objects: config
+                .tracked_objects
+                .iter()
+                .copied()
+                // The node reads the system state at the end of every epoch to reconfigure
+                .chain([IOTA_SYSTEM_STATE_OBJECT_ID])
+                .collect(),
+        }
+    }
+
+    /// Returns whether an object with the given owner is tracked. Objects
+    /// owned by tracked objects, like their dynamic fields, are tracked too.
+    fn tracks_owner(&self, owner: &Owner) -> bool {
+        match owner {
+            Owner::AddressOwner(address) => self.addresses.contains(address),
+            Owner::ObjectOwner(address) => {
+                self.addresses.contains(address) || self.objects.contains(&ObjectID::from(*address))
+            }
+            Owner::Shared { .. } | Owner::Immutable => false,
+        }
+    }
+
+    fn tracks_package_of(&self, object: &Object) -> bool {
+        match object.type_() {
+            Some(type_) => self.packages.contains(&ObjectID::from(type_.address())),
+            None => self.packages.contains(&object.id()),
+        }
+    }
+
+    /// Returns whether the transaction touches any of the tracked addresses,
+    /// packages or objects. Only the data committed to by the transaction and
+    /// its effects is used, so the transaction must have been verified with
+    /// [`verify_checkpoint_data`] first.
+    pub fn matches(&self, transaction: &CheckpointTransaction) -> bool {
+        let data = transaction.transaction.transaction_data();
+        let effects = &transaction.effects;
+        let mutated_inputs: HashSet<ObjectID> = effects
+            .old_object_metadata()
+            .into_iter()
+            .map(|(object_ref, _)| object_ref.0)
+            .collect();
+
+        self.addresses.contains(&data.sender())
+            || data
+                .move_calls()
+                .into_iter()
+                .any(|(package, _, _)| self.packages.contains(package))
+            // Objects mutated, wrapped or deleted, with their owner before the transaction
+            || effects
+                .old_object_metadata()
+                .iter()
+                .any(|(object_ref, owner)| {
+                    self.objects.contains(&object_ref.0) || self.tracks_owner(owner)
+                })
+            // Objects created, mutated or unwrapped, with their owner after the transaction
+            || effects
+                .all_changed_objects()
+                .iter()
+                .any(|(object_ref, owner, _)| {
+                    self.objects.contains(&object_ref.0) || self.tracks_owner(owner)
+                })
+            || effects
+                .unwrapped_then_deleted()
+                .iter()
+                .any(|object_ref| self.objects.contains(&object_ref.0))
+            // Read-only inputs are not committed to by the effects
+            || transaction
+                .input_objects
+                .iter()
+                .filter(|object| mutated_inputs.contains(&object.id()))
+                .chain(&transaction.output_objects)
+                .any(|object| self.tracks_package_of(object))
+    }
+}
+
+/// Verifies that the full checkpoint data matches the given checkpoint, which
+/// was certified and verified by state sync.
+pub fn verify_checkpoint_data(
+    checkpoint: &VerifiedCheckpoint,
+    data: &CheckpointData,
+) -> anyhow::Result<()> {
+    let sequence_number = *checkpoint.sequence_number();
+    ensure!(
+        data.checkpoint_summary.digest() == checkpoint.digest(),
+        "checkpoint summary digest mismatch for checkpoint {sequence_number}"
+    );
+    ensure!(
+        data.checkpoint_contents.digest() == &checkpoint.content_digest,
+        "checkpoint contents digest mismatch for checkpoint {sequence_number}"
+    );
+    ensure!(
+        data.transactions.len() == data.checkpoint_contents.size(),
+        "expected {} transactions in checkpoint {sequence_number}, got {}",
+        data.checkpoint_contents.size(),
+        data.transactions.len()
+    );
+    for (transaction, digests) in data
+        .transactions
+        .iter()
+        .zip(data.checkpoint_contents.iter())
+    {
+        verify_checkpoint_transaction(transaction, digests)?;
+    }
+    Ok(())
+}
+
+/// Verifies a transaction against its digests in the checkpoint contents, and
+/// its events and objects against its effects. Only the inputs mutated by the
+/// transaction are committed to by its effects, the other inputs are not
+/// verified and must not be stored.
+fn verify_checkpoint_transaction(
+    transaction: &CheckpointTransaction,
+    digests: &ExecutionDigests,
+) -> anyhow::Result<()> {
+    let tx_digest = transaction.transaction.digest();
+    ensure!(
+        tx_digest == &digests.transaction,
+        "transaction digest mismatch: expected {}, got {tx_digest}",
+        digests.transaction
+    );
+    ensure!(
+        transaction.effects.execution_digests() == *digests,
+        "effects digest mismatch for transaction {tx_digest}"
+    );
+    ensure!(
+        transaction
+            .events
+            .as_ref()
+            .map(|events| events.digest())
+            .as_ref()
+            == transaction.effects.events_digest(),
+        "events digest mismatch for transaction {tx_digest}"
+    );
+
+    let output_refs: HashSet<ObjectRef> = transaction
+        .effects
+        .all_changed_objects()
+        .into_iter()
+        .map(|(object_ref, _, _)| object_ref)
+        .collect();
+    ensure!(
+        transaction.output_objects.len() == output_refs.len(),
+        "expected {} output objects for transaction {tx_digest}, got {}",
+        output_refs.len(),
+        transaction.output_objects.len()
+    );
+    for object in &transaction.output_objects {
+        let object_ref = object.compute_object_reference();
+        ensure!(
+            output_refs.contains(&object_ref),
+            "output object {object_ref:?} of transaction {tx_digest} is not in its effects"
+        );
+    }
+
+    let mutated_input_refs: HashMap<ObjectID, ObjectRef> = transaction
+        .effects
+        .old_object_metadata()
+        .into_iter()
+        .map(|(object_ref, _)| (object_ref.0, object_ref))
+        .collect();
+    for object in &transaction.input_objects {
+        if let Some(object_ref) = mutated_input_refs.get(&object.id()) {
+            ensure!(
+                &object.compute_object_reference() == object_ref,
+                "input object {object_ref:?} of transaction {tx_digest} doesn't match its effects"
+            );
+        }
+    }
+    Ok(())
+}
+
+pub struct SparseCheckpointExecutor {
+    mailbox: broadcast::Receiver<VerifiedCheckpoint>,
+    state: Arc<AuthorityState>,
+    store: Arc<AuthorityStore>,
+    checkpoint_store: Arc<CheckpointStore>,
+    source: Arc<dyn CheckpointDataSource>,
+    filter: SparseTransactionFilter,
+}
+
+impl SparseCheckpointExecutor {
+    pub fn new(
+        mailbox: broadcast::Receiver<VerifiedCheckpoint>,
+        state: Arc<AuthorityState>,
+        store: Arc<AuthorityStore>,
+        checkpoint_store: Arc<CheckpointStore>,
+        source: Arc<dyn CheckpointDataSource>,
+        config: &SparseNodeConfig,
+    ) -> Self {
+        Self {
+            mailbox,
+            state,
+            store,
+            checkpoint_store,
+            source,
+            filter: SparseTransactionFilter::new(config),
+        }
+    }
+
+    /// Processes the synced checkpoints of the epoch of `epoch_store` in
+    /// order, and returns once the last checkpoint of the epoch was processed.
+    /// A checkpoint which fails to be fetched or processed is retried with
+    /// exponential backoff until it succeeds.
+    pub async fn run_epoch(&mut self, epoch_store: Arc<AuthorityPerEpochStore>) {
+        info!(
+            epoch = epoch_store.epoch(),
+            "sparse checkpoint executor running"
+        );
+        loop {
+            let highest_executed = self
+                .checkpoint_store
+                .get_highest_executed_checkpoint()
+                .expect("read cannot fail");
+            if let Some(highest_executed) = &highest_executed {
+                if highest_executed.epoch() == epoch_store.epoch()
+                    && highest_executed.is_last_checkpoint_of_epoch()
+                {
+                    info!(
+                        seq = ?highest_executed.sequence_number,
+                        "final checkpoint of epoch has been processed"
+                    );
+                    return;
+                }
+            }
+            let next_checkpoint =
+                highest_executed.map_or(0, |checkpoint| checkpoint.sequence_number() + 1);
+            let checkpoint = self.wait_for_synced_checkpoint(next_checkpoint).await;
+            self.process_checkpoint_with_retries(&epoch_store, &checkpoint)
+                .await;
+        }
+    }
+
+    /// Waits until state sync has synced the checkpoint and its contents.
+    async fn wait_for_synced_checkpoint(
+        &mut self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> VerifiedCheckpoint {
+        loop {
+            let highest_synced = self
+                .checkpoint_store
+                .get_highest_synced_checkpoint()
+                .expect("read cannot fail");
+            if highest_synced
+                .is_some_and(|checkpoint| *checkpoint.sequence_number() >= sequence_number)
+            {
+                return self
+                    .checkpoint_store
+                    .get_checkpoint_by_sequence_number(sequence_number)
+                    .expect("read cannot fail")
+                    .expect("synced checkpoint must exist");
+            }
+            match self.mailbox.recv().await {
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => {
+                    panic!("Checkpoint Execution Sender (StateSync) closed channel unexpectedly");
+                }
+            }
+        }
+    }
+
+    /// Processing a checkpoint is idempotent, so a checkpoint which partially
+    /// failed can be retried from the start.
+    async fn process_checkpoint_with_retries(
+        &self,
+        epoch_store: &Arc<AuthorityPerEpochStore>,
+        checkpoint: &VerifiedCheckpoint,
+    ) {
+        let sequence_number = *checkpoint.sequence_number();
+        let mut backoff = INITIAL_RETRY_BACKOFF;
+        loop {
+            let result = match self.source.get_full_checkpoint(sequence_number).await {
+                Ok(data) => self.process_checkpoint(epoch_store, checkpoint, data).await,
+                Err(e) => Err(e.context("failed to fetch full checkpoint data")),
+            };
+            match result {
+                Ok(_) => return,
+                Err(e) => {
+                    warn!(
+                        checkpoint = sequence_number,
+                        ?backoff,
+                        "failed to process sparse checkpoint, retrying: {e:?}"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Verifies the full data of a checkpoint synced by state sync, stores the
+    /// outputs of its tracked transactions and marks it as executed. Returns
+    /// the number of stored transactions. Checkpoints must be processed in
+    /// order.
+    pub async fn process_checkpoint(
+        &self,
+        epoch_store: &Arc<AuthorityPerEpochStore>,
+        checkpoint: &VerifiedCheckpoint,
+        data: CheckpointData,
+    ) -> anyhow::Result<usize> {
+        let epoch = epoch_store.epoch();
+        ensure!(
+            checkpoint.epoch() == epoch,
+            "checkpoint {} of epoch {} can't be processed in epoch {epoch}",
+            checkpoint.sequence_number(),
+            checkpoint.epoch()
+        );
+        verify_checkpoint_data(checkpoint, &data)?;
+
+        let mut tracked_digests = vec![];
+        let mut untracked_digests = vec![];
+        for transaction in data.transactions {
+            if !self.filter.matches(&transaction) {
+                untracked_digests.push(transaction.effects.execution_digests());
+                continue;
+            }
+            let tx_digest = *transaction.transaction.digest();
+            let outputs = self.build_transaction_outputs(transaction)?;
+            self.state
+                .get_cache_writer()
+                .write_transaction_outputs(epoch, Arc::new(outputs))
+                .await?;
+            // Commit every transaction right away, so that the live markers of the
+            // objects it writes are visible to the next transactions
+            self.state
+                .get_cache_commit()
+                .commit_transaction_outputs(epoch, &[tx_digest])
+                .await?;
+            epoch_store.insert_tx_key_and_digest(&TransactionKey::Digest(tx_digest), &tx_digest)?;
+            tracked_digests.push(tx_digest);
+        }
+
+        epoch_store
+            .insert_finalized_transactions(&tracked_digests, *checkpoint.sequence_number())?;
+        // State sync stores the transactions and effects of all checkpoints, only the
+        // tracked ones are kept
+        self.store
+            .multi_remove_transaction_and_effects(&untracked_digests)?;
+        self.checkpoint_store
+            .delete_full_checkpoint_contents(*checkpoint.sequence_number())?;
+        if checkpoint.is_last_checkpoint_of_epoch() {
+            self.checkpoint_store
+                .insert_epoch_last_checkpoint(epoch, checkpoint)?;
+        }
+        self.checkpoint_store
+            .update_highest_executed_checkpoint(checkpoint)?;
+
+        debug!(
+            checkpoint = *checkpoint.sequence_number(),
+            tracked = tracked_digests.len(),
+            untracked = untracked_digests.len(),
+            "processed sparse checkpoint"
+        );
+        Ok(tracked_digests.len())
+    }
+
+    fn build_transaction_outputs(
+        &self,
+        transaction: CheckpointTransaction,
+    ) -> anyhow::Result<TransactionOutputs> {
+        let CheckpointTransaction {
+            transaction,
+            effects,
+            events,
+            input_objects,
+            output_objects,
+        } = transaction;
+
+        let mutable_inputs: BTreeMap<_, _> = effects
+            .old_object_metadata()
+            .into_iter()
+            .map(|((object_id, version, digest), owner)| (object_id, ((version, digest), owner)))
+            .collect();
+        let input_objects = input_objects
+            .into_iter()
+            .filter(|object| mutable_inputs.contains_key(&object.id()))
+            .map(|object| (object.id(), object))
+            .collect();
+        let lamport_version = effects.lamport_version();
+        let mut outputs = TransactionOutputs::build_transaction_outputs(
+            VerifiedTransaction::new_unchecked(transaction),
+            effects,
+            InnerTemporaryStore {
+                input_objects,
+                mutable_inputs,
+                written: output_objects
+                    .into_iter()
+                    .map(|object| (object.id(), object))
+                    .collect(),
+                loaded_runtime_objects: BTreeMap::new(),
+                events: events.unwrap_or_default(),
+                binary_config: BinaryConfig::standard(),
+                runtime_packages_loaded_from_db: BTreeMap::new(),
+                lamport_version,
+            },
+        );
+
+        // Owned inputs which were never written by a tracked transaction have no live
+        // marker on this node
+        let live_markers = self
+            .store
+            .perpetual_tables
+            .live_owned_object_markers
+            .multi_get(&outputs.live_object_markers_to_delete)?;
+        outputs.live_object_markers_to_delete = outputs
+            .live_object_markers_to_delete
+            .into_iter()
+            .zip(live_markers)
+            .filter_map(|(object_ref, live_marker)| live_marker.map(|_| object_ref))
+            .collect();
+        Ok(outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+    use futures::FutureExt;
+    use iota_swarm_config::{network_config_builder::ConfigBuilder, test_utils::CommitteeFixture};
+    use iota_types::{
+        base_types::{ObjectDigest, SequenceNumber},
+        crypto::get_account_key_pair,
+        effects::{TransactionEffects, TransactionEvents},
+        messages_checkpoint::CheckpointContents,
+        transaction::{TEST_ONLY_GAS_UNIT_FOR_TRANSFER, Transaction, TransactionData},
+        utils::to_sender_signed_transaction,
+    };
+
+    use super::*;
+    use crate::authority::{
+        authority_test_utils::send_and_confirm_transaction,
+        test_authority_builder::TestAuthorityBuilder,
+    };
+
+    fn checkpoint_transaction(
+        sender: IotaAddress,
+        input_objects: Vec<Object>,
+        output_objects: Vec<Object>,
+    ) -> CheckpointTransaction {
+        let (_, keypair) = get_account_key_pair();
+        let gas = Object::with_owner_for_testing(sender);
+        let data = TransactionData::new_transfer_iota(
+            IotaAddress::ZERO,
+            sender,
+            None,
+            gas.compute_object_reference(),
+            1_000_000,
+            1_000,
+        );
+        let transaction: Transaction = to_sender_signed_transaction(data, &keypair);
+        CheckpointTransaction {
+            transaction,
+            effects: TransactionEffects::default(),
+            events: None,
+            input_objects,
+            output_objects,
+        }
+    }
+
+    #[test]
+    fn test_sparse_transaction_filter() {
+        let tracked_address = IotaAddress::random_for_testing_only();
+        let tracked_object = ObjectID::random();
+        let filter = SparseTransactionFilter::new(&SparseNodeConfig {
+            tracked_addresses: vec![tracked_address],
+            tracked_objects: vec![tracked_object],
+            ..Default::default()
+        });
+        let other_address = IotaAddress::random_for_testing_only();
+
+        // Sent by a tracked address
+        assert!(filter.matches(&checkpoint_transaction(tracked_address, vec![], vec![])));
+        // Mutates a tracked object
+        let mut transaction = checkpoint_transaction(other_address, vec![], vec![]);
+        transaction
+            .effects
+            .unsafe_add_deleted_live_object_for_testing((
+                tracked_object,
+                SequenceNumber::from_u64(1),
+                ObjectDigest::MIN,
+            ));
+        assert!(filter.matches(&transaction));
+        // Only reads a tracked object, which is not committed to by the effects
+        assert!(!filter.matches(&checkpoint_transaction(
+            other_address,
+            vec![Object::with_id_owner_for_testing(
+                tracked_object,
+                tracked_address
+            )],
+            vec![]
+        )));
+        // Doesn't touch anything tracked
+        assert!(!filter.matches(&checkpoint_transaction(other_address, vec![], vec![])));
+    }
+
+    #[test]
+    fn test_verify_checkpoint_transaction() {
+        let sender = IotaAddress::random_for_testing_only();
+        let mut transaction = checkpoint_transaction(sender, vec![], vec![]);
+        *transaction.effects.transaction_digest_mut_for_testing() =
+            *transaction.transaction.digest();
+        let digests = transaction.effects.execution_digests();
+        verify_checkpoint_transaction(&transaction, &digests).unwrap();
+
+        // Objects which are not committed to by the effects are rejected
+        transaction
+            .output_objects
+            .push(Object::with_owner_for_testing(sender));
+        assert!(verify_checkpoint_transaction(&transaction, &digests).is_err());
+        transaction.output_objects.clear();
+
+        // Events which are not committed to by the effects are rejected
+        transaction.events = Some(TransactionEvents::default());
+        assert!(verify_checkpoint_transaction(&transaction, &digests).is_err());
+    }
+
+    /// Serves the data of a single checkpoint, after failing a number of times.
+    struct MockCheckpointDataSource {
+        data: CheckpointData,
+        remaining_failures: AtomicUsize,
+    }
+
+    impl CheckpointDataSource for MockCheckpointDataSource {
+        fn get_full_checkpoint(
+            &self,
+            sequence_number: CheckpointSequenceNumber,
+        ) -> BoxFuture<'_, anyhow::Result<CheckpointData>> {
+            let result = if self
+                .remaining_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                Err(anyhow!("upstream unavailable"))
+            } else {
+                assert_eq!(
+                    sequence_number,
+                    *self.data.checkpoint_summary.sequence_number()
+                );
+                Ok(self.data.clone())
+            };
+            async move { result }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sparse_checkpoint_executor() {
+        telemetry_subscribers::init_for_testing();
+
+        let network_config = ConfigBuilder::new_with_temp_dir().build();
+        let (sender, sender_key) = get_account_key_pair();
+        let (other_sender, other_sender_key) = get_account_key_pair();
+        let recipient = IotaAddress::random_for_testing_only();
+        let tracked_object = Object::with_owner_for_testing(sender);
+        let gas = Object::with_owner_for_testing(sender);
+        let other_gas = Object::with_owner_for_testing(other_sender);
+        let starting_objects = [tracked_object.clone(), gas.clone(), other_gas.clone()];
+        let upstream = TestAuthorityBuilder::new()
+            .with_network_config(&network_config, 0)
+            .with_starting_objects(&starting_objects)
+            .build()
+            .await;
+        let sparse = TestAuthorityBuilder::new()
+            .with_network_config(&network_config, 0)
+            .with_starting_objects(&starting_objects)
+            .build()
+            .await;
+
+        // An untracked sender mutates the tracked object, and another one only
+        // touches untracked objects
+        let gas_price = upstream.reference_gas_price_for_testing().unwrap();
+        let gas_budget = TEST_ONLY_GAS_UNIT_FOR_TRANSFER * gas_price;
+        let tracked_transaction = to_sender_signed_transaction(
+            TransactionData::new_transfer(
+                recipient,
+                tracked_object.compute_object_reference(),
+                sender,
+                gas.compute_object_reference(),
+                gas_budget,
+                gas_price,
+            ),
+            &sender_key,
+        );
+        let untracked_transaction = to_sender_signed_transaction(
+            TransactionData::new_transfer_iota(
+                recipient,
+                other_sender,
+                None,
+                other_gas.compute_object_reference(),
+                gas_budget,
+                gas_price,
+            ),
+            &other_sender_key,
+        );
+        let mut transactions = vec![];
+        for (transaction, input_objects) in [
+            (tracked_transaction, vec![
+                tracked_object.clone(),
+                gas.clone(),
+            ]),
+            (untracked_transaction, vec![other_gas.clone()]),
+        ] {
+            let (_, effects) = send_and_confirm_transaction(&upstream, transaction.clone())
+                .await
+                .unwrap();
+            let effects = effects.into_data();
+            let output_objects = effects
+                .all_changed_objects()
+                .into_iter()
+                .map(|((object_id, version, _), _, _)| {
+                    upstream
+                        .get_object_store()
+                        .get_object_by_key(&object_id, version)
+                        .unwrap()
+                        .unwrap()
+                })
+                .collect();
+            transactions.push(CheckpointTransaction {
+                transaction,
+                effects,
+                events: None,
+                input_objects,
+                output_objects,
+            });
+        }
+        let tracked_digest = *transactions[0].transaction.digest();
+        let untracked_digest = *transactions[1].transaction.digest();
+
+        // Sync the checkpoint following genesis, which contains both transactions
+        let contents = CheckpointContents::new_with_digests_only_for_tests(
+            transactions
+                .iter()
+                .map(|transaction| transaction.effects.execution_digests()),
+        );
+        let genesis_checkpoint = network_config.genesis.checkpoint();
+        let checkpoint = CommitteeFixture::from_network_config(&network_config)
+            .make_checkpoint_with_contents(&genesis_checkpoint, &contents);
+        let checkpoint_store = sparse.get_checkpoint_store().clone();
+        checkpoint_store
+            .insert_verified_checkpoint(&genesis_checkpoint)
+            .unwrap();
+        checkpoint_store
+            .update_highest_executed_checkpoint(&genesis_checkpoint)
+            .unwrap();
+        checkpoint_store
+            .insert_verified_checkpoint(&checkpoint)
+            .unwrap();
+        checkpoint_store
+            .update_highest_synced_checkpoint(&checkpoint)
+            .unwrap();
+
+        let source = MockCheckpointDataSource {
+            data: CheckpointData {
+                checkpoint_summary: checkpoint.clone().into_inner(),
+                checkpoint_contents: contents,
+                transactions,
+            },
+            remaining_failures: AtomicUsize::new(1),
+        };
+        let (_checkpoint_sender, mailbox) = broadcast::channel(1);
+        let mut executor = SparseCheckpointExecutor::new(
+            mailbox,
+            sparse.clone(),
+            sparse.database_for_testing(),
+            checkpoint_store.clone(),
+            Arc::new(source),
+            &SparseNodeConfig {
+                tracked_objects: vec![tracked_object.id()],
+                ..Default::default()
+            },
+        );
+        let epoch_store = sparse.epoch_store_for_testing().clone();
+        let executor_handle = tokio::spawn(async move { executor.run_epoch(epoch_store).await });
+
+        // The first fetch fails and is retried
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while checkpoint_store
+                .get_highest_executed_checkpoint_seq_number()
+                .unwrap()
+                != Some(1)
+            {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await
+        .expect("checkpoint should be processed");
+        executor_handle.abort();
+
+        let transaction_cache = sparse.get_transaction_cache_reader();
+        assert_eq!(
+            transaction_cache
+                .get_executed_effects(&tracked_digest)
+                .unwrap(),
+            upstream
+                .get_transaction_cache_reader()
+                .get_executed_effects(&tracked_digest)
+                .unwrap()
+        );
+        assert!(
+            transaction_cache
+                .get_executed_effects(&untracked_digest)
+                .unwrap()
+                .is_none()
+        );
+        // The tracked object was updated, the untracked objects were not
+        let object_store = sparse.get_object_store();
+        let stored_object = object_store
+            .get_object(&tracked_object.id())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored_object.owner, Owner::AddressOwner(recipient));
+        assert_eq!(
+            upstream
+                .get_object_store()
+                .get_object(&tracked_object.id())
+                .unwrap(),
+            Some(stored_object)
+        );
+        assert_eq!(
+            object_store.get_object(&other_gas.id()).unwrap(),
+            Some(other_gas)
+        );
+    }
+}
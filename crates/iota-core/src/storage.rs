@@ -2,8 +2,9 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
+use iota_archival::index::IndexedArchiveReader;
 use iota_types::{
     base_types::{IotaAddress, ObjectID, TransactionDigest},
     committee::{Committee, EpochId},
@@ -364,11 +365,41 @@ impl WriteStore for RocksDbStore {
 pub struct RestReadStore {
     state: Arc<AuthorityState>,
     rocks: RocksDbStore,
+    archive: Option<Arc<IndexedArchiveReader>>,
 }
 
 impl RestReadStore {
     pub fn new(state: Arc<AuthorityState>, rocks: RocksDbStore) -> Self {
-        Self { state, rocks }
+        Self {
+            state,
+            rocks,
+            archive: None,
+        }
+    }
+
+    /// Sets the archive checkpoints and transactions are read from once they
+    /// were pruned from the local store. Reading from the archive blocks the
+    /// calling thread, so the store must then be used from a multi-threaded
+    /// runtime.
+    pub fn with_archive(mut self, archive: Arc<IndexedArchiveReader>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Runs the given lookup against the archive, if one is set, and waits
+    /// for its result.
+    fn read_archive<'a, T, F>(
+        &'a self,
+        lookup: impl FnOnce(&'a IndexedArchiveReader) -> F,
+    ) -> Result<Option<T>>
+    where
+        F: Future<Output = anyhow::Result<Option<T>>>,
+    {
+        let Some(archive) = self.archive.as_deref() else {
+            return Ok(None);
+        };
+        tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(lookup(archive)))
+            .map_err(StorageError::custom)
     }
 
     fn index(&self) -> iota_types::storage::error::Result<&RestIndexStore> {
@@ -436,8 +467,23 @@ impl ReadStore for RestReadStore {
         &self,
         sequence_number: CheckpointSequenceNumber,
     ) -> iota_types::storage::error::Result<Option<VerifiedCheckpoint>> {
-        self.rocks
-            .get_checkpoint_by_sequence_number(sequence_number)
+        if let Some(checkpoint) = self
+            .rocks
+            .get_checkpoint_by_sequence_number(sequence_number)?
+        {
+            return Ok(Some(checkpoint));
+        }
+        let Some(summary) = self.read_archive(|archive| archive.get_checkpoint(sequence_number))?
+        else {
+            return Ok(None);
+        };
+        let committee = self.rocks.get_committee(summary.epoch)?.ok_or_else(|| {
+            StorageError::missing(format!("missing committee for epoch {}", summary.epoch))
+        })?;
+        summary
+            .try_into_verified(&committee)
+            .map(Some)
+            .map_err(StorageError::custom)
     }
 
     fn get_checkpoint_contents_by_digest(
@@ -455,22 +501,39 @@ impl ReadStore for RestReadStore {
     ) -> iota_types::storage::error::Result<
         Option<iota_types::messages_checkpoint::CheckpointContents>,
     > {
-        self.rocks
-            .get_checkpoint_contents_by_sequence_number(sequence_number)
+        if let Some(contents) = self
+            .rocks
+            .get_checkpoint_contents_by_sequence_number(sequence_number)?
+        {
+            return Ok(Some(contents));
+        }
+        Ok(self
+            .read_archive(|archive| archive.get_checkpoint_contents(sequence_number))?
+            .map(|contents| contents.checkpoint_contents()))
     }
 
     fn get_transaction(
         &self,
         digest: &TransactionDigest,
     ) -> iota_types::storage::error::Result<Option<Arc<VerifiedTransaction>>> {
-        self.rocks.get_transaction(digest)
+        if let Some(transaction) = self.rocks.get_transaction(digest)? {
+            return Ok(Some(transaction));
+        }
+        Ok(self
+            .read_archive(|archive| archive.get_transaction(digest))?
+            .map(|(_, data)| Arc::new(VerifiedTransaction::new_unchecked(data.transaction))))
     }
 
     fn get_transaction_effects(
         &self,
         digest: &TransactionDigest,
     ) -> iota_types::storage::error::Result<Option<TransactionEffects>> {
-        self.rocks.get_transaction_effects(digest)
+        if let Some(effects) = self.rocks.get_transaction_effects(digest)? {
+            return Ok(Some(effects));
+        }
+        Ok(self
+            .read_archive(|archive| archive.get_transaction(digest))?
+            .map(|(_, data)| data.effects))
     }
 
     fn get_events(
@@ -484,8 +547,13 @@ impl ReadStore for RestReadStore {
         &self,
         sequence_number: CheckpointSequenceNumber,
     ) -> iota_types::storage::error::Result<Option<FullCheckpointContents>> {
-        self.rocks
-            .get_full_checkpoint_contents_by_sequence_number(sequence_number)
+        if let Some(contents) = self
+            .rocks
+            .get_full_checkpoint_contents_by_sequence_number(sequence_number)?
+        {
+            return Ok(Some(contents));
+        }
+        self.read_archive(|archive| archive.get_checkpoint_contents(sequence_number))
     }
 
     fn get_full_checkpoint_contents(
@@ -501,10 +569,15 @@ impl RestStateReader for RestReadStore {
         &self,
         digest: &TransactionDigest,
     ) -> iota_types::storage::error::Result<Option<CheckpointSequenceNumber>> {
-        self.index()?
+        let checkpoint = self
+            .index()?
             .get_transaction_info(digest)
             .map(|maybe_info| maybe_info.map(|info| info.checkpoint))
-            .map_err(StorageError::custom)
+            .map_err(StorageError::custom)?;
+        match (checkpoint, &self.archive) {
+            (None, Some(archive)) => archive.checkpoint_of(digest).map_err(StorageError::custom),
+            (checkpoint, _) => Ok(checkpoint),
+        }
     }
 
     fn get_lowest_available_checkpoint_objects(
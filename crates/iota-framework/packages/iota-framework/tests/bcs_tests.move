@@ -243,4 +243,10 @@ module iota::bcs_tests {
         cases!(opt_cases, |bytes| bytes.peel_option!(|bytes| bytes.peel_info()));
     }
 
+    #[random_test]
+    fun test_struct_input(info: Info, opt: Option<Info>) {
+        cases!(vector[info], |bytes| bytes.peel_info());
+        cases!(vector[opt], |bytes| bytes.peel_option!(|bytes| bytes.peel_info()));
+    }
+
 }
@@ -13,6 +13,23 @@ module iota::pay_tests {
 
     const TEST_SENDER_ADDR: address = @0xA11CE;
 
+    #[random_test]
+    fun test_coin_split_random(value: u64, split_amount: u64) {
+        let split_amount = if (value == 0) 0 else split_amount % value;
+        let mut scenario = test_scenario::begin(TEST_SENDER_ADDR);
+        let mut coin = coin::mint_for_testing<IOTA>(value, scenario.ctx());
+        pay::split(&mut coin, split_amount, scenario.ctx());
+
+        scenario.next_tx(TEST_SENDER_ADDR);
+        let split_coin = scenario.take_from_sender<Coin<IOTA>>();
+        assert!(split_coin.value() == split_amount);
+        assert!(coin.value() == value - split_amount);
+
+        test_utils::destroy(coin);
+        test_utils::destroy(split_coin);
+        scenario.end();
+    }
+
     #[test]
     fun test_coin_split_n() {
         let mut scenario = test_scenario::begin(TEST_SENDER_ADDR);
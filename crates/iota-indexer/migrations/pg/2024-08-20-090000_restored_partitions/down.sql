@@ -0,0 +1,2 @@
+DROP FUNCTION IF EXISTS reject_restored_partition_writes;
+DROP TABLE IF EXISTS restored_partitions;
@@ -0,0 +1,16 @@
+-- Epoch partitions re-attached from a Parquet export, skipped by the pruner.
+CREATE TABLE restored_partitions
+(
+    table_name  TEXT       NOT NULL,
+    epoch       BIGINT     NOT NULL,
+    PRIMARY KEY(table_name, epoch)
+);
+
+CREATE OR REPLACE FUNCTION reject_restored_partition_writes()
+RETURNS TRIGGER
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    RAISE EXCEPTION 'partition % is restored from an archive and read-only', TG_TABLE_NAME;
+END;
+$$;
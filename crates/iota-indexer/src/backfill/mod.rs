@@ -0,0 +1,394 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recomputes derived indexer tables for a range of checkpoints.
+//!
+//! A backfill runs as a separate indexer process next to the live writer. It
+//! reads checkpoints from a local checkpoint store or a remote archive,
+//! recomputes the rows of a single table and writes them with the same
+//! idempotent inserts the live pipeline uses, so the two never conflict.
+//! Progress is persisted in a local file and a restarted backfill resumes
+//! from the last fully processed checkpoint.
+
+mod worker;
+
+use std::{ops::RangeInclusive, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use diesel::r2d2::R2D2Connection;
+use futures::StreamExt;
+use iota_data_ingestion_core::{
+    DataIngestionMetrics, FileProgressStore, IndexerExecutor, ProgressStore, ReaderOptions,
+    WorkerPool,
+};
+use prometheus::Registry;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::{
+    IndexerConfig,
+    backfill::worker::{BackfillProgressStore, BackfillWorker},
+    errors::IndexerError,
+    metrics::IndexerMetrics,
+    store::{IndexerAnalyticalStore, IndexerStore},
+};
+
+/// The tables a backfill can recompute.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+#[clap(rename_all = "snake_case")]
+pub enum BackfillTable {
+    /// `tx_*` index tables.
+    TxIndices,
+    /// `event_*` index tables.
+    EventIndices,
+    /// `objects_version` table.
+    ObjIndices,
+    /// `display` table.
+    Display,
+    /// `tx_count_metrics` table, computed from already indexed transactions.
+    Metrics,
+}
+
+impl BackfillTable {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::TxIndices => "tx_indices",
+            Self::EventIndices => "event_indices",
+            Self::ObjIndices => "obj_indices",
+            Self::Display => "display",
+            Self::Metrics => "metrics",
+        }
+    }
+
+    /// Whether the existing rows of a range can be deleted before writing
+    /// the recomputed ones. Display and object version rows aren't keyed by
+    /// checkpoint or transaction, so their rows of a range are unknown.
+    fn supports_overwrite(&self) -> bool {
+        matches!(self, Self::TxIndices | Self::EventIndices | Self::Metrics)
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+#[clap(rename_all = "kebab-case")]
+pub struct BackfillConfig {
+    /// The table to recompute.
+    #[clap(long, value_enum)]
+    pub table: BackfillTable,
+    /// Inclusive checkpoint range to recompute, formatted as `<start>..<end>`.
+    #[clap(long, value_parser = parse_checkpoint_range)]
+    pub range: RangeInclusive<u64>,
+    /// Number of checkpoints, or metrics chunks, processed in parallel.
+    #[clap(long, default_value = "10")]
+    pub concurrency: usize,
+    /// Number of checkpoints recomputed by a single metrics query.
+    #[clap(long, default_value = "1000")]
+    pub chunk_size: u64,
+    /// Delete the existing rows of the range before writing the recomputed
+    /// ones, e.g. after a migration added a column or a bug corrupted rows.
+    /// Not supported for the display and obj_indices tables.
+    #[clap(long)]
+    pub overwrite: bool,
+    /// File the backfill progress is persisted to. Defaults to
+    /// `backfill_progress.json` in the working directory.
+    #[clap(long)]
+    pub progress_file: Option<PathBuf>,
+    /// Local checkpoint store to read checkpoint files from.
+    #[clap(long)]
+    pub checkpoints_path: Option<PathBuf>,
+    /// Remote archive or full node REST API to fetch checkpoints from.
+    /// Defaults to the remote store of the indexer.
+    #[clap(long)]
+    pub archive_url: Option<String>,
+}
+
+impl BackfillConfig {
+    /// Name under which the progress of this backfill is stored, unique per
+    /// table and range so that several backfills can share a progress file.
+    fn task_name(&self) -> String {
+        format!(
+            "backfill_{}_{}_{}",
+            self.table.name(),
+            self.range.start(),
+            self.range.end()
+        )
+    }
+
+    fn validate(&self) -> Result<(), IndexerError> {
+        if self.overwrite && !self.table.supports_overwrite() {
+            return Err(IndexerError::InvalidArgument(format!(
+                "--overwrite is not supported for the {} table",
+                self.table.name()
+            )));
+        }
+        Ok(())
+    }
+
+    fn progress_file(&self) -> Result<PathBuf, IndexerError> {
+        let path = self
+            .progress_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("backfill_progress.json"));
+        if !path.exists() {
+            std::fs::write(&path, "{}").map_err(|e| {
+                IndexerError::Generic(format!(
+                    "Failed to create backfill progress file {}: {e}",
+                    path.display()
+                ))
+            })?;
+        }
+        Ok(path)
+    }
+}
+
+fn parse_checkpoint_range(s: &str) -> Result<RangeInclusive<u64>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid range `{s}`, expected `<start>..<end>`"))?;
+    let start = start
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("invalid range start `{start}`: {e}"))?;
+    let end = end
+        .trim()
+        .trim_start_matches('=')
+        .parse::<u64>()
+        .map_err(|e| format!("invalid range end `{end}`: {e}"))?;
+    if start > end {
+        return Err(format!(
+            "range start {start} is greater than range end {end}"
+        ));
+    }
+    Ok(start..=end)
+}
+
+pub struct Backfill;
+
+impl Backfill {
+    /// Recomputes a table derived from checkpoint data by re-ingesting the
+    /// checkpoints of the configured range.
+    pub async fn run_ingestion<S, T>(
+        config: &IndexerConfig,
+        backfill_config: &BackfillConfig,
+        store: S,
+        metrics: IndexerMetrics,
+    ) -> Result<(), IndexerError>
+    where
+        S: IndexerStore + Sync + Send + Clone + 'static,
+        T: R2D2Connection + 'static,
+    {
+        if backfill_config.table == BackfillTable::Metrics {
+            return Err(IndexerError::InvalidArgument(
+                "metrics are computed from indexed transactions, not from checkpoints".to_string(),
+            ));
+        }
+        backfill_config.validate()?;
+        let task_name = backfill_config.task_name();
+        info!(
+            "Starting backfill {} for checkpoints {:?}",
+            task_name, backfill_config.range
+        );
+
+        let (exit_sender, exit_receiver) = oneshot::channel();
+        let progress_store = BackfillProgressStore::new(
+            FileProgressStore::new(backfill_config.progress_file()?),
+            backfill_config.range.clone(),
+            exit_sender,
+        );
+        let mut executor = IndexerExecutor::new(
+            progress_store,
+            1,
+            DataIngestionMetrics::new(&Registry::new()),
+        );
+        let worker = BackfillWorker::<S, T>::new(
+            store,
+            metrics,
+            backfill_config.table,
+            *backfill_config.range.end(),
+            backfill_config.overwrite,
+        );
+        executor
+            .register(WorkerPool::new(
+                worker,
+                task_name.clone(),
+                backfill_config.concurrency,
+            ))
+            .await?;
+
+        // A temporary checkpoint directory is removed once the backfill returns
+        let mut temp_checkpoints_dir = None;
+        let checkpoints_path = match backfill_config
+            .checkpoints_path
+            .clone()
+            .or_else(|| config.data_ingestion_path.clone())
+        {
+            Some(path) => path,
+            None => {
+                let dir = tempfile::tempdir().map_err(|e| {
+                    IndexerError::Generic(format!("Failed to create a checkpoint directory: {e}"))
+                })?;
+                temp_checkpoints_dir.insert(dir).path().to_path_buf()
+            }
+        };
+        let progress = executor
+            .run(
+                checkpoints_path,
+                backfill_config
+                    .archive_url
+                    .clone()
+                    .or_else(|| config.remote_store_url.clone()),
+                vec![],
+                ReaderOptions {
+                    batch_size: backfill_config.concurrency,
+                    ..Default::default()
+                },
+                exit_receiver,
+            )
+            .await?;
+        info!(
+            "Backfill {} finished with progress {:?}",
+            task_name, progress
+        );
+        drop(temp_checkpoints_dir);
+        Ok(())
+    }
+
+    /// Recomputes metrics tables from the transactions already indexed in the
+    /// database, in chunks of `chunk_size` checkpoints.
+    pub async fn run_metrics<A>(
+        backfill_config: &BackfillConfig,
+        store: A,
+    ) -> Result<(), IndexerError>
+    where
+        A: IndexerAnalyticalStore + Clone + Send + Sync + 'static,
+    {
+        let task_name = backfill_config.task_name();
+        let mut progress_store = FileProgressStore::new(backfill_config.progress_file()?);
+        let start = progress_store
+            .load(task_name.clone())
+            .await?
+            .max(*backfill_config.range.start());
+        // Never compute metrics past what the live writer has already indexed,
+        // otherwise checkpoints still being written would get partial metrics.
+        let latest_checkpoint = store
+            .get_latest_stored_checkpoint()
+            .await?
+            .ok_or_else(|| IndexerError::PostgresRead("No checkpoint indexed yet".to_string()))?
+            .sequence_number as u64;
+        let end = (*backfill_config.range.end()).min(latest_checkpoint);
+        info!(
+            "Starting backfill {} for checkpoints {}..={}",
+            task_name, start, end
+        );
+
+        let chunk_size = backfill_config.chunk_size.max(1);
+        let overwrite = backfill_config.overwrite;
+        let mut chunks = futures::stream::iter((start..=end).step_by(chunk_size as usize))
+            .map(|chunk_start| {
+                let chunk_end = (chunk_start + chunk_size).min(end + 1);
+                let store = store.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        if overwrite {
+                            store.overwrite_tx_count_metrics(chunk_start as i64, chunk_end as i64)
+                        } else {
+                            store.persist_tx_count_metrics(chunk_start as i64, chunk_end as i64)
+                        }
+                    })
+                    .await??;
+                    Ok::<_, IndexerError>(chunk_end)
+                }
+            })
+            .buffered(backfill_config.concurrency.max(1));
+
+        while let Some(chunk_end) = chunks.next().await {
+            let chunk_end = chunk_end?;
+            progress_store.save(task_name.clone(), chunk_end).await?;
+            info!(
+                "Backfill {} processed checkpoints up to {}",
+                task_name, chunk_end
+            );
+        }
+        info!("Backfill {} finished", task_name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{BackfillConfig, BackfillTable, parse_checkpoint_range};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        config: BackfillConfig,
+    }
+
+    #[test]
+    fn parse_range() {
+        assert_eq!(parse_checkpoint_range("10..20").unwrap(), 10..=20);
+        assert_eq!(parse_checkpoint_range("10..=20").unwrap(), 10..=20);
+        assert_eq!(parse_checkpoint_range("7..7").unwrap(), 7..=7);
+        assert!(parse_checkpoint_range("20..10").is_err());
+        assert!(parse_checkpoint_range("10").is_err());
+        assert!(parse_checkpoint_range("a..b").is_err());
+    }
+
+    #[test]
+    fn parse_config() {
+        let config =
+            Cli::parse_from(["backfill", "--table", "event_indices", "--range", "5..=9"]).config;
+        assert_eq!(config.table, BackfillTable::EventIndices);
+        assert_eq!(config.range, 5..=9);
+        assert_eq!(config.concurrency, 10);
+        assert!(!config.overwrite);
+        assert_eq!(config.task_name(), "backfill_event_indices_5_9");
+
+        assert!(Cli::try_parse_from(["backfill", "--table", "tx_indices"]).is_err());
+        assert!(
+            Cli::try_parse_from(["backfill", "--table", "unknown", "--range", "1..2"]).is_err()
+        );
+    }
+
+    #[test]
+    fn overwrite_is_rejected_for_unsupported_tables() {
+        let validate = |table: &str| {
+            Cli::parse_from([
+                "backfill",
+                "--table",
+                table,
+                "--range",
+                "0..1",
+                "--overwrite",
+            ])
+            .config
+            .validate()
+        };
+        assert!(validate("tx_indices").is_ok());
+        assert!(validate("event_indices").is_ok());
+        assert!(validate("metrics").is_ok());
+        assert!(validate("display").is_err());
+        assert!(validate("obj_indices").is_err());
+    }
+
+    #[test]
+    fn progress_file_is_created_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress.json");
+        let mut config =
+            Cli::parse_from(["backfill", "--table", "metrics", "--range", "0..1"]).config;
+        config.progress_file = Some(path.clone());
+
+        assert_eq!(config.progress_file().unwrap(), path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{}");
+
+        // An existing progress file is kept as is
+        std::fs::write(&path, r#"{"backfill_metrics_0_1":1}"#).unwrap();
+        config.progress_file().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            r#"{"backfill_metrics_0_1":1}"#
+        );
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{marker::PhantomData, ops::RangeInclusive};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use diesel::r2d2::R2D2Connection;
+use iota_data_ingestion_core::{FileProgressStore, ProgressStore, Worker};
+use iota_rest_api::CheckpointData;
+use iota_types::messages_checkpoint::CheckpointSequenceNumber;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::{
+    backfill::BackfillTable, errors::IndexerError, handlers::checkpoint_handler::CheckpointHandler,
+    metrics::IndexerMetrics, models::obj_indices::StoredObjectVersion, store::IndexerStore,
+};
+
+/// Recomputes the rows of a single table for every checkpoint it receives.
+pub(crate) struct BackfillWorker<S, T> {
+    store: S,
+    metrics: IndexerMetrics,
+    table: BackfillTable,
+    end_checkpoint: CheckpointSequenceNumber,
+    overwrite: bool,
+    _connection: PhantomData<fn() -> T>,
+}
+
+impl<S, T> BackfillWorker<S, T>
+where
+    S: IndexerStore + Clone + Sync + Send + 'static,
+    T: R2D2Connection + 'static,
+{
+    pub(crate) fn new(
+        store: S,
+        metrics: IndexerMetrics,
+        table: BackfillTable,
+        end_checkpoint: CheckpointSequenceNumber,
+        overwrite: bool,
+    ) -> Self {
+        Self {
+            store,
+            metrics,
+            table,
+            end_checkpoint,
+            overwrite,
+            _connection: PhantomData,
+        }
+    }
+
+    async fn backfill_transaction_tables(&self, checkpoint: CheckpointData) -> Result<()> {
+        let CheckpointData {
+            transactions,
+            checkpoint_summary,
+            checkpoint_contents,
+        } = checkpoint;
+        let (_, _, tx_indices, event_indices, displays) =
+            CheckpointHandler::<S, T>::index_transactions(
+                transactions,
+                &checkpoint_summary,
+                &checkpoint_contents,
+                &self.metrics,
+            )
+            .await?;
+        let tx_range = tx_indices
+            .iter()
+            .map(|index| index.tx_sequence_number)
+            .fold(None, |range, seq| match range {
+                None => Some((seq, seq)),
+                Some((min, max)) => Some((seq.min(min), seq.max(max))),
+            });
+
+        match (self.table, tx_range.filter(|_| self.overwrite)) {
+            (BackfillTable::TxIndices, Some((min_tx, max_tx))) => {
+                self.store
+                    .overwrite_tx_indices(tx_indices, min_tx, max_tx)
+                    .await?
+            }
+            (BackfillTable::TxIndices, None) => self.store.persist_tx_indices(tx_indices).await?,
+            (BackfillTable::EventIndices, Some((min_tx, max_tx))) => {
+                self.store
+                    .overwrite_event_indices(event_indices, min_tx, max_tx)
+                    .await?
+            }
+            (BackfillTable::EventIndices, None) => {
+                self.store.persist_event_indices(event_indices).await?
+            }
+            (BackfillTable::Display, _) => self.store.persist_displays(displays).await?,
+            (BackfillTable::ObjIndices | BackfillTable::Metrics, _) => {
+                return Err(IndexerError::InvalidArgument(format!(
+                    "{} is not derived from checkpoint transactions",
+                    self.table.name()
+                ))
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    async fn backfill_object_versions(&self, checkpoint: CheckpointData) -> Result<()> {
+        let checkpoint_seq = checkpoint.checkpoint_summary.sequence_number as i64;
+        let mut object_versions = vec![];
+        for tx in &checkpoint.transactions {
+            object_versions.extend(tx.output_objects.iter().map(|o| StoredObjectVersion {
+                object_id: o.id().to_vec(),
+                object_version: o.version().value() as i64,
+                cp_sequence_number: checkpoint_seq,
+            }));
+            object_versions.extend(tx.removed_object_refs_post_version().map(|obj_ref| {
+                StoredObjectVersion {
+                    object_id: obj_ref.0.to_vec(),
+                    object_version: obj_ref.1.value() as i64,
+                    cp_sequence_number: checkpoint_seq,
+                }
+            }));
+        }
+        self.store.persist_object_versions(object_versions).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S, T> Worker for BackfillWorker<S, T>
+where
+    S: IndexerStore + Clone + Sync + Send + 'static,
+    T: R2D2Connection + 'static,
+{
+    async fn process_checkpoint(&self, checkpoint: CheckpointData) -> Result<()> {
+        let checkpoint_seq = checkpoint.checkpoint_summary.sequence_number;
+        // The reader keeps fetching past the end of the range until the executor
+        // exits, those checkpoints are not part of the backfill.
+        if checkpoint_seq > self.end_checkpoint {
+            return Ok(());
+        }
+        match self.table {
+            BackfillTable::ObjIndices => self.backfill_object_versions(checkpoint).await?,
+            _ => self.backfill_transaction_tables(checkpoint).await?,
+        }
+        info!(
+            "Backfilled {:?} for checkpoint {}",
+            self.table, checkpoint_seq
+        );
+        Ok(())
+    }
+}
+
+/// Persists backfill progress into a file, starting from the beginning of the
+/// backfilled range, and signals the executor to exit once the whole range has
+/// been processed.
+pub(crate) struct BackfillProgressStore {
+    inner: FileProgressStore,
+    range: RangeInclusive<CheckpointSequenceNumber>,
+    exit_sender: Option<oneshot::Sender<()>>,
+}
+
+impl BackfillProgressStore {
+    pub(crate) fn new(
+        inner: FileProgressStore,
+        range: RangeInclusive<CheckpointSequenceNumber>,
+        exit_sender: oneshot::Sender<()>,
+    ) -> Self {
+        Self {
+            inner,
+            range,
+            exit_sender: Some(exit_sender),
+        }
+    }
+}
+
+#[async_trait]
+impl ProgressStore for BackfillProgressStore {
+    async fn load(&mut self, task_name: String) -> Result<CheckpointSequenceNumber> {
+        let watermark = self.inner.load(task_name).await?.max(*self.range.start());
+        if watermark > *self.range.end() {
+            info!("Backfill range {:?} already processed", self.range);
+            if let Some(exit_sender) = self.exit_sender.take() {
+                let _ = exit_sender.send(());
+            }
+        }
+        Ok(watermark)
+    }
+
+    async fn save(
+        &mut self,
+        task_name: String,
+        checkpoint_number: CheckpointSequenceNumber,
+    ) -> Result<()> {
+        // The watermark is the next checkpoint to process, anything past the
+        // range end was skipped by the worker and must not be recorded.
+        let checkpoint_number = checkpoint_number.min(*self.range.end() + 1);
+        self.inner.save(task_name, checkpoint_number).await?;
+        if checkpoint_number > *self.range.end() {
+            if let Some(exit_sender) = self.exit_sender.take() {
+                let _ = exit_sender.send(());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use iota_data_ingestion_core::{FileProgressStore, ProgressStore};
+    use iota_types::messages_checkpoint::CheckpointSequenceNumber;
+    use tempfile::TempDir;
+    use tokio::sync::oneshot;
+
+    use super::BackfillProgressStore;
+
+    const TASK: &str = "backfill_tx_indices_10_20";
+
+    fn progress_store(
+        dir: &TempDir,
+        range: RangeInclusive<CheckpointSequenceNumber>,
+    ) -> (BackfillProgressStore, oneshot::Receiver<()>) {
+        let path = dir.path().join("progress.json");
+        if !path.exists() {
+            std::fs::write(&path, "{}").unwrap();
+        }
+        let (exit_sender, exit_receiver) = oneshot::channel();
+        let store = BackfillProgressStore::new(FileProgressStore::new(path), range, exit_sender);
+        (store, exit_receiver)
+    }
+
+    #[tokio::test]
+    async fn starts_from_range_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut store, mut exit_receiver) = progress_store(&dir, 10..=20);
+
+        assert_eq!(store.load(TASK.to_string()).await.unwrap(), 10);
+        assert!(exit_receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn resumes_from_saved_watermark() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut store, mut exit_receiver) = progress_store(&dir, 10..=20);
+        store.save(TASK.to_string(), 15).await.unwrap();
+        assert!(exit_receiver.try_recv().is_err());
+
+        let (mut store, mut exit_receiver) = progress_store(&dir, 10..=20);
+        assert_eq!(store.load(TASK.to_string()).await.unwrap(), 15);
+        assert!(exit_receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn exits_once_range_is_processed() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut store, mut exit_receiver) = progress_store(&dir, 10..=20);
+        // Checkpoints past the range end are not recorded
+        store.save(TASK.to_string(), 25).await.unwrap();
+        assert!(exit_receiver.try_recv().is_ok());
+
+        let (mut store, mut exit_receiver) = progress_store(&dir, 10..=20);
+        assert_eq!(store.load(TASK.to_string()).await.unwrap(), 21);
+        assert!(exit_receiver.try_recv().is_ok());
+    }
+}
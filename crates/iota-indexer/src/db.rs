@@ -202,12 +202,15 @@ pub mod setup_postgres {
     use tracing::{error, info};
 
     use crate::{
-        IndexerConfig,
+        Command, IndexerConfig,
+        backfill::{Backfill, BackfillTable},
         db::{PoolConnection, get_pool_connection, new_connection_pool},
         errors::IndexerError,
         indexer::Indexer,
         metrics::IndexerMetrics,
-        store::{PgIndexerAnalyticalStore, PgIndexerStore},
+        store::{
+            PgIndexerAnalyticalStore, PgIndexerStore, pg_partition_archiver::PgPartitionArchiver,
+        },
     };
 
     const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/pg");
@@ -329,6 +332,37 @@ pub mod setup_postgres {
                 tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
             }
         });
+        if let Some(Command::Backfill(backfill_config)) = &indexer_config.command {
+            return match backfill_config.table {
+                BackfillTable::Metrics => {
+                    let store = PgIndexerAnalyticalStore::new(blocking_cp);
+                    Backfill::run_metrics(backfill_config, store).await
+                }
+                _ => {
+                    let store =
+                        PgIndexerStore::<PgConnection>::new(blocking_cp, indexer_metrics.clone());
+                    Backfill::run_ingestion::<PgIndexerStore<PgConnection>, PgConnection>(
+                        &indexer_config,
+                        backfill_config,
+                        store,
+                        indexer_metrics,
+                    )
+                    .await
+                }
+            };
+        }
+        if let Some(Command::RestorePartition { table, epoch }) = &indexer_config.command {
+            let config_path = indexer_config
+                .partition_archive_config
+                .as_deref()
+                .ok_or_else(|| {
+                    IndexerError::InvalidArgument(
+                        "--partition-archive-config is required to restore a partition".to_string(),
+                    )
+                })?;
+            let archiver = PgPartitionArchiver::from_config_file(blocking_cp, config_path)?;
+            return archiver.restore_partition(table, *epoch).await;
+        }
         if indexer_config.fullnode_sync_worker {
             let store = PgIndexerStore::<PgConnection>::new(blocking_cp, indexer_metrics.clone());
             return Indexer::start_writer::<PgIndexerStore<PgConnection>, PgConnection>(
@@ -363,7 +397,8 @@ pub mod setup_mysql {
     use tracing::{error, info};
 
     use crate::{
-        IndexerConfig,
+        Command, IndexerConfig,
+        backfill::{Backfill, BackfillTable},
         db::{PoolConnection, get_pool_connection, new_connection_pool},
         errors::IndexerError,
         indexer::Indexer,
@@ -458,6 +493,27 @@ pub mod setup_mysql {
                 tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
             }
         });
+        if let Some(Command::Backfill(backfill_config)) = &indexer_config.command {
+            if backfill_config.table == BackfillTable::Metrics {
+                return Err(IndexerError::NotSupported(
+                    "metrics backfill is only supported on Postgres".to_string(),
+                ));
+            }
+            let store =
+                PgIndexerStore::<MysqlConnection>::new(blocking_cp, indexer_metrics.clone());
+            return Backfill::run_ingestion::<PgIndexerStore<MysqlConnection>, MysqlConnection>(
+                &indexer_config,
+                backfill_config,
+                store,
+                indexer_metrics,
+            )
+            .await;
+        }
+        if let Some(Command::RestorePartition { .. }) = &indexer_config.command {
+            return Err(IndexerError::NotSupported(
+                "restoring partitions is only supported on Postgres".to_string(),
+            ));
+        }
         if indexer_config.fullnode_sync_worker {
             let store =
                 PgIndexerStore::<MysqlConnection>::new(blocking_cp, indexer_metrics.clone());
@@ -357,7 +357,7 @@ where
         })
     }
 
-    async fn index_transactions(
+    pub(crate) async fn index_transactions(
         transactions: Vec<CheckpointTransaction>,
         checkpoint_summary: &CertifiedCheckpointSummary,
         checkpoint_contents: &CheckpointContents,
@@ -2,7 +2,7 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::time::Duration;
+use std::{path::Path, time::Duration};
 
 use diesel::r2d2::R2D2Connection;
 use tokio_util::sync::CancellationToken;
@@ -12,13 +12,18 @@ use super::checkpoint_handler::CheckpointHandler;
 use crate::{
     errors::IndexerError,
     metrics::IndexerMetrics,
-    store::{IndexerStore, pg_partition_manager::PgPartitionManager},
+    store::{
+        IndexerStore, pg_partition_archiver::PgPartitionArchiver,
+        pg_partition_manager::PgPartitionManager,
+    },
     types::IndexerResult,
 };
 
 pub struct Pruner<S, T: R2D2Connection + 'static> {
     pub store: S,
     pub partition_manager: PgPartitionManager<T>,
+    /// Exports partitions to an object store before they are dropped.
+    pub partition_archiver: Option<PgPartitionArchiver<T>>,
     pub epochs_to_keep: u64,
     pub metrics: IndexerMetrics,
 }
@@ -31,13 +36,18 @@ where
     pub fn new(
         store: S,
         epochs_to_keep: u64,
+        partition_archive_config: Option<&Path>,
         metrics: IndexerMetrics,
     ) -> Result<Self, IndexerError> {
         let blocking_cp = CheckpointHandler::<S, T>::pg_blocking_cp(store.clone()).unwrap();
         let partition_manager = PgPartitionManager::new(blocking_cp.clone())?;
+        let partition_archiver = partition_archive_config
+            .map(|config_path| PgPartitionArchiver::from_config_file(blocking_cp, config_path))
+            .transpose()?;
         Ok(Self {
             store,
             partition_manager,
+            partition_archiver,
             epochs_to_keep,
             metrics,
         })
@@ -63,6 +73,7 @@ where
             let table_partitions = self.partition_manager.get_table_partitions()?;
             let table_names = table_partitions.keys().cloned().collect::<Vec<_>>();
             for (table_name, (min_partition, max_partition)) in table_partitions {
+                let restored_epochs = self.partition_manager.get_restored_epochs(&table_name)?;
                 if max_epoch != max_partition {
                     error!(
                         "Epochs are out of sync for table {}: max_epoch={}, max_partition={}",
@@ -73,8 +84,10 @@ where
                 // min_epoch would have been pruned already if the pruner was
                 // running.
                 for epoch in min_partition..min_epoch {
-                    self.partition_manager
-                        .drop_table_partition(table_name.clone(), epoch)?;
+                    if restored_epochs.contains(&epoch) {
+                        continue;
+                    }
+                    self.archive_and_drop_partition(&table_name, epoch).await?;
                     info!(
                         "Batch dropped table partition {} epoch {}",
                         table_name, epoch
@@ -89,8 +102,7 @@ where
                 }
                 info!("Pruning epoch {}", epoch);
                 for table_name in table_names.clone() {
-                    self.partition_manager
-                        .drop_table_partition(table_name.clone(), epoch)?;
+                    self.archive_and_drop_partition(&table_name, epoch).await?;
                     info!("Dropped table partition {} epoch {}", table_name, epoch);
                 }
                 self.store.prune_epoch(epoch).await.unwrap_or_else(|e| {
@@ -101,4 +113,28 @@ where
             }
         }
     }
+
+    /// Drops the partition of `table_name` for `epoch`, exporting it first if
+    /// an archive is configured. The partition is kept if the export fails.
+    async fn archive_and_drop_partition(&self, table_name: &str, epoch: u64) -> IndexerResult<()> {
+        if let Some(archiver) = &self.partition_archiver {
+            if self
+                .partition_manager
+                .get_strategy(table_name)
+                .is_epoch_partitioned()
+            {
+                archiver
+                    .export_partition(table_name, epoch)
+                    .await
+                    .inspect_err(|e| {
+                        error!(
+                            "Failed to export table partition {} epoch {}: {}",
+                            table_name, epoch, e
+                        )
+                    })?;
+            }
+        }
+        self.partition_manager
+            .drop_table_partition(table_name.to_string(), epoch)
+    }
 }
@@ -120,7 +120,12 @@ impl Indexer {
                 epochs_to_keep
             );
             assert!(epochs_to_keep > 0, "Epochs to keep must be positive");
-            let pruner: Pruner<S, T> = Pruner::new(store.clone(), epochs_to_keep, metrics.clone())?;
+            let pruner: Pruner<S, T> = Pruner::new(
+                store.clone(),
+                epochs_to_keep,
+                config.partition_archive_config.as_deref(),
+                metrics.clone(),
+            )?;
             spawn_monitored_task!(pruner.start(CancellationToken::new()));
         }
 
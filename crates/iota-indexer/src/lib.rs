@@ -7,7 +7,7 @@
 use std::{net::SocketAddr, path::PathBuf, time::Duration};
 
 use anyhow::{Result, anyhow};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use diesel::r2d2::R2D2Connection;
 use errors::IndexerError;
 use iota_json_rpc::{JsonRpcServerBuilder, ServerHandle, ServerType};
@@ -32,6 +32,7 @@ use crate::{
 };
 
 pub mod apis;
+pub mod backfill;
 pub mod db;
 pub mod errors;
 pub mod handlers;
@@ -87,6 +88,28 @@ pub struct IndexerConfig {
     pub data_ingestion_path: Option<PathBuf>,
     #[clap(long)]
     pub analytical_worker: bool,
+    /// YAML object store config the pruner exports epoch partitions to
+    /// before dropping them.
+    #[clap(long, global = true)]
+    pub partition_archive_config: Option<PathBuf>,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Recompute a derived table for a range of checkpoints, without
+    /// interrupting the live indexer.
+    Backfill(backfill::BackfillConfig),
+    /// Re-attach an epoch partition exported by the pruner as a read-only
+    /// partition.
+    RestorePartition {
+        /// The partitioned table, e.g. `transactions`.
+        #[clap(long)]
+        table: String,
+        #[clap(long)]
+        epoch: u64,
+    },
 }
 
 impl IndexerConfig {
@@ -159,6 +182,8 @@ impl Default for IndexerConfig {
             rpc_server_worker: true,
             data_ingestion_path: None,
             analytical_worker: false,
+            partition_archive_config: None,
+            command: None,
         }
     }
 }
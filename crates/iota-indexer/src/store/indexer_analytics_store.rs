@@ -55,6 +55,13 @@ pub trait IndexerAnalyticalStore {
         start_checkpoint: i64,
         end_checkpoint: i64,
     ) -> IndexerResult<()>;
+    // replace the tx count metrics of the checkpoint range in a single db
+    // transaction, used by backfills that rewrite existing rows
+    fn overwrite_tx_count_metrics(
+        &self,
+        start_checkpoint: i64,
+        end_checkpoint: i64,
+    ) -> IndexerResult<()>;
     async fn persist_epoch_peak_tps(&self, epoch: i64) -> IndexerResult<()>;
 
     // for address metrics
@@ -11,6 +11,7 @@ use crate::{
     handlers::{EpochToCommit, TransactionObjectChangesToCommit},
     models::{
         display::StoredDisplay,
+        obj_indices::StoredObjectVersion,
         objects::{StoredDeletedObject, StoredObject},
     },
     types::{
@@ -88,6 +89,29 @@ pub trait IndexerStore: Any + Clone + Sync + Send + 'static {
 
     async fn persist_packages(&self, packages: Vec<IndexedPackage>) -> Result<(), IndexerError>;
 
+    async fn persist_object_versions(
+        &self,
+        object_versions: Vec<StoredObjectVersion>,
+    ) -> Result<(), IndexerError>;
+
+    // replace the tx_* index rows in the inclusive tx range with `indices` in a
+    // single db transaction, used by backfills that rewrite existing rows
+    async fn overwrite_tx_indices(
+        &self,
+        indices: Vec<TxIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError>;
+
+    // replace the event_* index rows in the inclusive tx range with `indices` in
+    // a single db transaction, used by backfills that rewrite existing rows
+    async fn overwrite_event_indices(
+        &self,
+        indices: Vec<EventIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError>;
+
     async fn persist_epoch(&self, epoch: EpochToCommit) -> Result<(), IndexerError>;
 
     async fn advance_epoch(&self, epoch: EpochToCommit) -> Result<(), IndexerError>;
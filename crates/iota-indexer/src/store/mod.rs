@@ -12,6 +12,7 @@ pub mod indexer_store;
 pub mod package_resolver;
 mod pg_indexer_analytical_store;
 mod pg_indexer_store;
+pub mod pg_partition_archiver;
 pub mod pg_partition_manager;
 
 pub mod diesel_macro {
@@ -218,6 +218,32 @@ impl IndexerAnalyticalStore for PgIndexerAnalyticalStore {
         Ok(())
     }
 
+    fn overwrite_tx_count_metrics(
+        &self,
+        start_checkpoint: i64,
+        end_checkpoint: i64,
+    ) -> IndexerResult<()> {
+        let tx_count_query = construct_checkpoint_tx_count_query(start_checkpoint, end_checkpoint);
+        info!("Overwriting tx count metrics for cp {}", start_checkpoint);
+        transactional_blocking_with_retry!(
+            &self.blocking_cp,
+            |conn| {
+                diesel::delete(
+                    tx_count_metrics::table
+                        .filter(tx_count_metrics::checkpoint_sequence_number.ge(start_checkpoint))
+                        .filter(tx_count_metrics::checkpoint_sequence_number.lt(end_checkpoint)),
+                )
+                .execute(conn)?;
+                diesel::sql_query(tx_count_query.clone()).execute(conn)?;
+                Ok::<(), IndexerError>(())
+            },
+            Duration::from_secs(10)
+        )
+        .context("Failed overwriting tx count metrics in PostgresDB")?;
+        info!("Overwrote tx count metrics for cp {}", start_checkpoint);
+        Ok(())
+    }
+
     async fn persist_epoch_peak_tps(&self, epoch: i64) -> IndexerResult<()> {
         let epoch_peak_tps_query = construct_peak_tps_query(epoch, 1);
         let peak_tps_30d_query = construct_peak_tps_query(epoch, 30);
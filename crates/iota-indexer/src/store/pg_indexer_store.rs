@@ -1573,6 +1573,260 @@ impl<T: R2D2Connection + 'static> PgIndexerStore<T> {
         )
     }
 
+    fn overwrite_event_indices_table(
+        &self,
+        indices: Vec<EventIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError> {
+        let len = indices.len();
+        let mut emit_packages = Vec::with_capacity(len);
+        let mut emit_modules = Vec::with_capacity(len);
+        let mut senders = Vec::with_capacity(len);
+        let mut struct_packages = Vec::with_capacity(len);
+        let mut struct_modules = Vec::with_capacity(len);
+        let mut struct_names = Vec::with_capacity(len);
+        let mut struct_instantiations = Vec::with_capacity(len);
+        for index in indices {
+            let (
+                emit_package,
+                emit_module,
+                sender,
+                struct_package,
+                struct_module,
+                struct_name,
+                struct_instantiation,
+            ) = index.split();
+            emit_packages.push(emit_package);
+            emit_modules.push(emit_module);
+            senders.push(sender);
+            struct_packages.push(struct_package);
+            struct_modules.push(struct_module);
+            struct_names.push(struct_name);
+            struct_instantiations.push(struct_instantiation);
+        }
+
+        let (min_tx, max_tx) = (min_tx as i64, max_tx as i64);
+        transactional_blocking_with_retry!(
+            &self.blocking_cp,
+            |conn| {
+                prune_tx_or_event_indice_table!(
+                    event_emit_package,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_emit_package table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_emit_module,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_emit_module table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_senders,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_senders table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_struct_package,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_struct_package table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_struct_module,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_struct_module table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_struct_name,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_struct_name table"
+                );
+                prune_tx_or_event_indice_table!(
+                    event_struct_instantiation,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune event_struct_instantiation table"
+                );
+                for chunk in emit_packages.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_emit_package::table, chunk, conn);
+                }
+                for chunk in emit_modules.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_emit_module::table, chunk, conn);
+                }
+                for chunk in senders.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_senders::table, chunk, conn);
+                }
+                for chunk in struct_packages.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_struct_package::table, chunk, conn);
+                }
+                for chunk in struct_modules.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_struct_module::table, chunk, conn);
+                }
+                for chunk in struct_names.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_struct_name::table, chunk, conn);
+                }
+                for chunk in struct_instantiations.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(event_struct_instantiation::table, chunk, conn);
+                }
+                Ok::<(), IndexerError>(())
+            },
+            PG_DB_COMMIT_SLEEP_DURATION
+        )
+        .tap_ok(|_| info!("Overwrote {} event indices", len))
+    }
+
+    fn overwrite_tx_indices_table(
+        &self,
+        indices: Vec<TxIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError> {
+        let len = indices.len();
+        let mut senders = vec![];
+        let mut recipients = vec![];
+        let mut input_objects = vec![];
+        let mut changed_objects = vec![];
+        let mut pkgs = vec![];
+        let mut mods = vec![];
+        let mut funs = vec![];
+        let mut digests = vec![];
+        let mut kinds = vec![];
+        for index in indices {
+            let (
+                tx_senders,
+                tx_recipients,
+                tx_input_objects,
+                tx_changed_objects,
+                tx_pkgs,
+                tx_mods,
+                tx_funs,
+                tx_digests,
+                tx_kinds,
+            ) = index.split();
+            senders.extend(tx_senders);
+            recipients.extend(tx_recipients);
+            input_objects.extend(tx_input_objects);
+            changed_objects.extend(tx_changed_objects);
+            pkgs.extend(tx_pkgs);
+            mods.extend(tx_mods);
+            funs.extend(tx_funs);
+            digests.extend(tx_digests);
+            kinds.extend(tx_kinds);
+        }
+
+        let (min_tx, max_tx) = (min_tx as i64, max_tx as i64);
+        transactional_blocking_with_retry!(
+            &self.blocking_cp,
+            |conn| {
+                prune_tx_or_event_indice_table!(
+                    tx_senders,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_senders table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_recipients,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_recipients table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_input_objects,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_input_objects table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_changed_objects,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_changed_objects table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_calls_pkg,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_calls_pkg table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_calls_mod,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_calls_mod table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_calls_fun,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_calls_fun table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_digests,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_digests table"
+                );
+                prune_tx_or_event_indice_table!(
+                    tx_kinds,
+                    conn,
+                    min_tx,
+                    max_tx,
+                    "Failed to prune tx_kinds table"
+                );
+                for chunk in senders.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_senders::table, chunk, conn);
+                }
+                for chunk in recipients.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_recipients::table, chunk, conn);
+                }
+                for chunk in input_objects.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_input_objects::table, chunk, conn);
+                }
+                for chunk in changed_objects.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_changed_objects::table, chunk, conn);
+                }
+                for chunk in pkgs.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_calls_pkg::table, chunk, conn);
+                }
+                for chunk in mods.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_calls_mod::table, chunk, conn);
+                }
+                for chunk in funs.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_calls_fun::table, chunk, conn);
+                }
+                for chunk in digests.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_digests::table, chunk, conn);
+                }
+                for chunk in kinds.chunks(PG_COMMIT_CHUNK_SIZE_INTRA_DB_TX) {
+                    insert_or_ignore_into!(tx_kinds::table, chunk, conn);
+                }
+                Ok::<(), IndexerError>(())
+            },
+            PG_DB_COMMIT_SLEEP_DURATION
+        )
+        .tap_ok(|_| info!("Overwrote {} tx indices", len))
+    }
+
     fn prune_cp_tx_table(&self, cp: u64) -> Result<(), IndexerError> {
         transactional_blocking_with_retry!(
             &self.blocking_cp,
@@ -1975,6 +2229,39 @@ impl<T: R2D2Connection> IndexerStore for PgIndexerStore<T> {
             .await
     }
 
+    async fn persist_object_versions(
+        &self,
+        object_versions: Vec<StoredObjectVersion>,
+    ) -> Result<(), IndexerError> {
+        if object_versions.is_empty() {
+            return Ok(());
+        }
+        let len = object_versions.len();
+        let chunks = chunk!(object_versions, self.config.parallel_objects_chunk_size);
+        let futures = chunks.into_iter().map(|c| {
+            self.spawn_blocking_task(move |this| {
+                persist_chunk_into_table!(objects_version::table, c, &this.blocking_cp)
+            })
+        });
+
+        futures::future::try_join_all(futures)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to join persist object versions futures: {}", e);
+                IndexerError::from(e)
+            })?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                IndexerError::PostgresWrite(format!(
+                    "Failed to persist all object versions chunks: {:?}",
+                    e
+                ))
+            })?;
+        info!("Persisted {} object versions", len);
+        Ok(())
+    }
+
     async fn persist_event_indices(&self, indices: Vec<EventIndex>) -> Result<(), IndexerError> {
         if indices.is_empty() {
             return Ok(());
@@ -2046,6 +2333,30 @@ impl<T: R2D2Connection> IndexerStore for PgIndexerStore<T> {
         Ok(())
     }
 
+    async fn overwrite_tx_indices(
+        &self,
+        indices: Vec<TxIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError> {
+        self.execute_in_blocking_worker(move |this| {
+            this.overwrite_tx_indices_table(indices, min_tx, max_tx)
+        })
+        .await
+    }
+
+    async fn overwrite_event_indices(
+        &self,
+        indices: Vec<EventIndex>,
+        min_tx: u64,
+        max_tx: u64,
+    ) -> Result<(), IndexerError> {
+        self.execute_in_blocking_worker(move |this| {
+            this.overwrite_event_indices_table(indices, min_tx, max_tx)
+        })
+        .await
+    }
+
     async fn persist_epoch(&self, epoch: EpochToCommit) -> Result<(), IndexerError> {
         self.execute_in_blocking_worker(move |this| this.persist_epoch(epoch))
             .await
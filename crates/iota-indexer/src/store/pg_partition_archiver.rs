@@ -0,0 +1,682 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exports epoch partitions to Parquet files in an object store before the
+//! pruner drops them, and re-attaches exported partitions as read-only
+//! partitions for historical queries.
+//!
+//! Files follow the layout of the analytics indexer,
+//! `<table>/epoch_<epoch>/<start>_<end>.parquet`, where `start..end` is the
+//! range of the partition key covered by the partition. Partitions are
+//! uploaded with a multipart upload as they are read from the DB, and
+//! restored one record batch at a time, so a partition is never held in
+//! memory as a whole.
+
+use std::{fs::File, io, io::Write, sync::Arc, time::Duration};
+
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow_array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Int64Array, RecordBatch, StringArray,
+};
+use diesel::{
+    QueryableByName, RunQueryDsl,
+    r2d2::R2D2Connection,
+    sql_types::{BigInt, Text},
+};
+use downcast::Any;
+use futures::StreamExt;
+use iota_config::object_storage_config::ObjectStoreConfig;
+use iota_storage::object_store::util::find_all_files_with_epoch_prefix;
+use object_store::{DynObjectStore, WriteMultipart, path::Path};
+use parquet::{
+    arrow::{ArrowWriter, arrow_reader::ParquetRecordBatchReaderBuilder},
+    basic::Compression,
+    file::properties::WriterProperties,
+};
+use regex::Regex;
+use serde_json::{Map, Value};
+use tokio::{io::AsyncWriteExt, runtime::Handle};
+use tracing::{info, warn};
+
+use crate::{
+    db::ConnectionPool,
+    errors::IndexerError,
+    store::{
+        diesel_macro::*,
+        pg_partition_manager::{PgPartitionManager, PgPartitionStrategy},
+    },
+    types::IndexerResult,
+};
+
+const EPOCH_DIR_PREFIX: &str = "epoch_";
+// Range of the partition key read from the DB and written as a single row
+// group.
+const EXPORT_KEY_RANGE_SIZE: u64 = 10_000;
+// Number of parts of an export uploaded concurrently.
+const EXPORT_MAX_CONCURRENT_PARTS: usize = 4;
+// Number of rows inserted by a single statement when restoring a partition.
+const RESTORE_CHUNK_SIZE: usize = 1_000;
+const PG_TYPE_METADATA_KEY: &str = "pg_type";
+const PARTITION_START_METADATA_KEY: &str = "partition_start";
+const PARTITION_END_METADATA_KEY: &str = "partition_end";
+
+/// Range of the partition key covered by a partition, `start` inclusive and
+/// `end` exclusive as in the `FOR VALUES FROM (start) TO (end)` clause.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PartitionBound {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl PartitionBound {
+    fn parse(expr: &str) -> IndexerResult<Self> {
+        let re = Regex::new(r"FROM \('?(\d+)'?\) TO \('?(\d+)'?\)").unwrap();
+        let captures = re.captures(expr).ok_or_else(|| {
+            IndexerError::PersistentStorageDataCorruption(format!(
+                "Unexpected partition bound `{expr}`"
+            ))
+        })?;
+        let parse = |idx: usize| {
+            captures[idx].parse::<u64>().map_err(|e| {
+                IndexerError::PersistentStorageDataCorruption(format!(
+                    "Invalid partition bound `{expr}`: {e}"
+                ))
+            })
+        };
+        Ok(Self {
+            start: parse(1)?,
+            end: parse(2)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ColumnKind {
+    Int,
+    Bool,
+    Bytes,
+    Text,
+    Json,
+}
+
+impl ColumnKind {
+    fn from_pg_type(pg_type: &str) -> Self {
+        match pg_type {
+            "bigint" | "integer" | "smallint" => Self::Int,
+            "boolean" => Self::Bool,
+            "bytea" => Self::Bytes,
+            "text" | "character varying" => Self::Text,
+            // arrays and json columns are kept as their JSON text representation
+            _ => Self::Json,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::Int => DataType::Int64,
+            Self::Bool => DataType::Boolean,
+            Self::Bytes => DataType::Binary,
+            Self::Text | Self::Json => DataType::Utf8,
+        }
+    }
+}
+
+#[derive(QueryableByName, Debug, Clone)]
+struct ColumnInfo {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+    #[diesel(sql_type = Text)]
+    data_type: String,
+}
+
+/// Sink of the Parquet writer, uploading the written bytes as the parts of a
+/// multipart upload. Must be used from a blocking thread of the runtime.
+struct MultipartSink {
+    runtime: Handle,
+    upload: Option<WriteMultipart>,
+}
+
+impl MultipartSink {
+    fn finish(mut self) -> IndexerResult<()> {
+        let upload = self.upload.take().ok_or_else(|| {
+            IndexerError::Uncategorized(anyhow::anyhow!("Multipart upload was aborted"))
+        })?;
+        self.runtime
+            .block_on(upload.finish())
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    fn abort(&mut self) {
+        if let Some(upload) = self.upload.take() {
+            if let Err(e) = self.runtime.block_on(upload.abort()) {
+                warn!("Failed to abort multipart upload: {}", e);
+            }
+        }
+    }
+}
+
+impl Write for MultipartSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let upload = self
+            .upload
+            .as_mut()
+            .ok_or_else(|| io::Error::other("Multipart upload was aborted"))?;
+        self.runtime
+            .block_on(upload.wait_for_capacity(EXPORT_MAX_CONCURRENT_PARTS))
+            .map_err(io::Error::other)?;
+        upload.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Quotes `name` for use as an identifier in an SQL statement.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub struct PgPartitionArchiver<T: R2D2Connection + 'static> {
+    cp: ConnectionPool<T>,
+    object_store: Arc<DynObjectStore>,
+    partition_manager: PgPartitionManager<T>,
+}
+
+impl<T: R2D2Connection> Clone for PgPartitionArchiver<T> {
+    fn clone(&self) -> PgPartitionArchiver<T> {
+        Self {
+            cp: self.cp.clone(),
+            object_store: self.object_store.clone(),
+            partition_manager: self.partition_manager.clone(),
+        }
+    }
+}
+
+impl<T: R2D2Connection + 'static> PgPartitionArchiver<T> {
+    pub fn new(
+        cp: ConnectionPool<T>,
+        object_store: Arc<DynObjectStore>,
+    ) -> Result<Self, IndexerError> {
+        if !cfg!(feature = "postgres-feature") {
+            return Err(IndexerError::NotSupported(
+                "Partition archival is only supported on Postgres".to_string(),
+            ));
+        }
+        let partition_manager = PgPartitionManager::new(cp.clone())?;
+        Ok(Self {
+            cp,
+            object_store,
+            partition_manager,
+        })
+    }
+
+    /// Creates an archiver for the object store described by the YAML
+    /// [`ObjectStoreConfig`] at `config_path`.
+    pub fn from_config_file(
+        cp: ConnectionPool<T>,
+        config_path: &std::path::Path,
+    ) -> Result<Self, IndexerError> {
+        let config = std::fs::read_to_string(config_path).map_err(|e| {
+            IndexerError::InvalidArgument(format!(
+                "Failed to read partition archive config {}: {e}",
+                config_path.display()
+            ))
+        })?;
+        let config: ObjectStoreConfig =
+            serde_yaml::from_str(&config).map_err(|e| IndexerError::Serde(e.to_string()))?;
+        Self::new(cp, config.make()?)
+    }
+
+    fn partition_name(table: &str, epoch: u64) -> String {
+        format!("{table}_partition_{epoch}")
+    }
+
+    fn epoch_prefix(table: &str, epoch: u64) -> Path {
+        Path::from(table).child(format!("{EPOCH_DIR_PREFIX}{epoch}"))
+    }
+
+    /// Returns the partition key column of `table`. Table names end up in DDL
+    /// statements, so only the known tables partitioned by epoch are
+    /// accepted.
+    fn partition_key_column(&self, table: &str) -> IndexerResult<&'static str> {
+        if !self
+            .partition_manager
+            .get_table_partitions()?
+            .contains_key(table)
+        {
+            return Err(IndexerError::InvalidArgument(format!(
+                "Table {table} is not partitioned"
+            )));
+        }
+        match self.partition_manager.get_strategy(table) {
+            PgPartitionStrategy::CheckpointSequenceNumber => Ok("checkpoint_sequence_number"),
+            PgPartitionStrategy::TxSequenceNumber => Ok("tx_sequence_number"),
+            PgPartitionStrategy::ObjectId => Err(IndexerError::InvalidArgument(format!(
+                "Table {table} is not partitioned by epoch"
+            ))),
+        }
+    }
+
+    /// Writes the partition of `table` for `epoch` into the object store.
+    /// Returns `false` if the partition does not exist, e.g. because it was
+    /// already exported and dropped.
+    pub async fn export_partition(&self, table: &str, epoch: u64) -> IndexerResult<bool> {
+        let this = self.clone();
+        let table = table.to_string();
+        tokio::task::spawn_blocking(move || this.write_partition_to_parquet(&table, epoch)).await?
+    }
+
+    fn get_partition_bound(
+        &self,
+        table: &str,
+        epoch: u64,
+    ) -> IndexerResult<Option<PartitionBound>> {
+        #[derive(QueryableByName, Debug, Clone)]
+        struct Bound {
+            #[diesel(sql_type = Text)]
+            bound: String,
+        }
+
+        let bounds: Vec<Bound> = read_only_blocking!(&self.cp, |conn| RunQueryDsl::load(
+            diesel::sql_query(
+                "SELECT pg_get_expr(child.relpartbound, child.oid) AS bound
+                 FROM pg_inherits
+                          JOIN pg_class parent ON pg_inherits.inhparent = parent.oid
+                          JOIN pg_class child ON pg_inherits.inhrelid = child.oid
+                 WHERE parent.relname = $1 AND child.relname = $2"
+            )
+            .bind::<Text, _>(table)
+            .bind::<Text, _>(Self::partition_name(table, epoch)),
+            conn
+        ))?;
+        bounds
+            .first()
+            .map(|b| PartitionBound::parse(&b.bound))
+            .transpose()
+    }
+
+    fn get_columns(&self, table: &str) -> IndexerResult<Vec<ColumnInfo>> {
+        read_only_blocking!(&self.cp, |conn| RunQueryDsl::load(
+            diesel::sql_query(
+                "SELECT column_name::text AS column_name, data_type::text AS data_type
+                 FROM information_schema.columns
+                 WHERE table_schema = current_schema() AND table_name = $1
+                 ORDER BY ordinal_position"
+            )
+            .bind::<Text, _>(table),
+            conn
+        ))
+    }
+
+    fn write_partition_to_parquet(&self, table: &str, epoch: u64) -> IndexerResult<bool> {
+        let key_column = self.partition_key_column(table)?;
+        let Some(bound) = self.get_partition_bound(table, epoch)? else {
+            return Ok(false);
+        };
+        let columns = self.get_columns(table)?;
+        let schema = Arc::new(Schema::new_with_metadata(
+            columns
+                .iter()
+                .map(|c| {
+                    Field::new(
+                        &c.column_name,
+                        ColumnKind::from_pg_type(&c.data_type).data_type(),
+                        true,
+                    )
+                    .with_metadata([(PG_TYPE_METADATA_KEY.to_string(), c.data_type.clone())].into())
+                })
+                .collect::<Vec<_>>(),
+            [
+                (
+                    PARTITION_START_METADATA_KEY.to_string(),
+                    bound.start.to_string(),
+                ),
+                (
+                    PARTITION_END_METADATA_KEY.to_string(),
+                    bound.end.to_string(),
+                ),
+            ]
+            .into(),
+        ));
+
+        let path = Self::epoch_prefix(table, epoch)
+            .child(format!("{}_{}.parquet", bound.start, bound.end));
+        let runtime = Handle::current();
+        let upload = runtime
+            .block_on(self.object_store.put_multipart(&path))
+            .map_err(anyhow::Error::from)?;
+        let sink = MultipartSink {
+            runtime,
+            upload: Some(WriteMultipart::new(upload)),
+        };
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(sink, schema.clone(), Some(properties))
+            .map_err(anyhow::Error::from)?;
+        if let Err(e) =
+            self.write_partition_rows(table, epoch, key_column, bound, &schema, &mut writer)
+        {
+            writer.inner_mut().abort();
+            return Err(e);
+        }
+        writer.into_inner().map_err(anyhow::Error::from)?.finish()?;
+        info!(
+            "Exported table partition {} epoch {} to {}",
+            table, epoch, path
+        );
+        Ok(true)
+    }
+
+    /// Reads the partition one key range at a time and writes every range as
+    /// a row group, which is flushed to the sink right away.
+    fn write_partition_rows(
+        &self,
+        table: &str,
+        epoch: u64,
+        key_column: &str,
+        bound: PartitionBound,
+        schema: &Arc<Schema>,
+        writer: &mut ArrowWriter<MultipartSink>,
+    ) -> IndexerResult<()> {
+        #[derive(QueryableByName)]
+        struct JsonRow {
+            #[diesel(sql_type = Text)]
+            row: String,
+        }
+
+        let query = format!(
+            "SELECT row_to_json(t)::text AS row FROM {} t WHERE {key_column} >= $1 AND {key_column} < $2",
+            quote_identifier(&Self::partition_name(table, epoch)),
+            key_column = quote_identifier(key_column),
+        );
+        let mut window_start = bound.start;
+        while window_start < bound.end {
+            let window_end = window_start
+                .saturating_add(EXPORT_KEY_RANGE_SIZE)
+                .min(bound.end);
+            let rows: Vec<JsonRow> = read_only_blocking!(&self.cp, |conn| RunQueryDsl::load(
+                diesel::sql_query(&query)
+                    .bind::<BigInt, _>(window_start as i64)
+                    .bind::<BigInt, _>(window_end as i64),
+                conn
+            ))?;
+            if !rows.is_empty() {
+                let rows = rows
+                    .into_iter()
+                    .map(|r| serde_json::from_str::<Map<String, Value>>(&r.row))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| IndexerError::Serde(e.to_string()))?;
+                let batch = rows_to_record_batch(schema.clone(), &rows)?;
+                writer.write(&batch).map_err(anyhow::Error::from)?;
+                writer.flush().map_err(anyhow::Error::from)?;
+            }
+            window_start = window_end;
+        }
+        Ok(())
+    }
+
+    /// Loads the exported partition of `table` for `epoch` back into the DB
+    /// and attaches it to `table`. The restored partition rejects writes and
+    /// is skipped by the pruner.
+    pub async fn restore_partition(&self, table: &str, epoch: u64) -> IndexerResult<()> {
+        let prefix = Self::epoch_prefix(table, epoch);
+        let ranges = find_all_files_with_epoch_prefix(&self.object_store, Some(&prefix)).await?;
+        let range = ranges.first().ok_or_else(|| {
+            IndexerError::InvalidArgument(format!(
+                "No exported partition found for table {table} epoch {epoch}"
+            ))
+        })?;
+        let path = prefix.child(format!("{}_{}.parquet", range.start, range.end));
+        // Streams the export to a local file, from which it is read back one
+        // record batch at a time
+        let mut file =
+            tokio::fs::File::from_std(tempfile::tempfile().map_err(anyhow::Error::from)?);
+        let mut stream = self
+            .object_store
+            .get(&path)
+            .await
+            .map_err(anyhow::Error::from)?
+            .into_stream();
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk.map_err(anyhow::Error::from)?)
+                .await
+                .map_err(anyhow::Error::from)?;
+        }
+        file.flush().await.map_err(anyhow::Error::from)?;
+        let file = file.into_std().await;
+        let this = self.clone();
+        let table = table.to_string();
+        tokio::task::spawn_blocking(move || this.load_partition_from_parquet(&table, epoch, file))
+            .await??;
+        info!("Restored table partition from {}", path);
+        Ok(())
+    }
+
+    fn load_partition_from_parquet(
+        &self,
+        table: &str,
+        epoch: u64,
+        file: File,
+    ) -> IndexerResult<()> {
+        self.partition_key_column(table)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(
+            file.try_clone().map_err(anyhow::Error::from)?,
+        )
+        .map_err(anyhow::Error::from)?;
+        let metadata = builder.schema().metadata().clone();
+        let bound_value = |key: &str| -> IndexerResult<u64> {
+            metadata
+                .get(key)
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| {
+                    IndexerError::PersistentStorageDataCorruption(format!(
+                        "Exported partition is missing `{key}` metadata"
+                    ))
+                })
+        };
+        let bound = PartitionBound {
+            start: bound_value(PARTITION_START_METADATA_KEY)?,
+            end: bound_value(PARTITION_END_METADATA_KEY)?,
+        };
+
+        let partition = quote_identifier(&Self::partition_name(table, epoch));
+        let trigger =
+            quote_identifier(&format!("{}_read_only", Self::partition_name(table, epoch)));
+        let quoted_table = quote_identifier(table);
+        transactional_blocking_with_retry!(
+            &self.cp,
+            |conn| {
+                diesel::sql_query(format!(
+                    "CREATE TABLE {partition} (LIKE {quoted_table} INCLUDING DEFAULTS)"
+                ))
+                .execute(conn)?;
+                let batches = ParquetRecordBatchReaderBuilder::try_new(
+                    file.try_clone().map_err(anyhow::Error::from)?,
+                )
+                .and_then(|builder| builder.build())
+                .map_err(anyhow::Error::from)?;
+                for batch in batches {
+                    let rows = record_batch_to_rows(&batch.map_err(anyhow::Error::from)?)?;
+                    for chunk in rows.chunks(RESTORE_CHUNK_SIZE) {
+                        diesel::sql_query(format!(
+                            "INSERT INTO {partition} SELECT * FROM json_populate_recordset(NULL::{partition}, $1::json)"
+                        ))
+                        .bind::<Text, _>(Value::Array(chunk.to_vec()).to_string())
+                        .execute(conn)?;
+                    }
+                }
+                diesel::sql_query(format!(
+                    "ALTER TABLE {quoted_table} ATTACH PARTITION {partition} FOR VALUES FROM ({}) TO ({})",
+                    bound.start, bound.end
+                ))
+                .execute(conn)?;
+                diesel::sql_query(format!(
+                    "CREATE TRIGGER {trigger} BEFORE INSERT OR UPDATE OR DELETE OR TRUNCATE ON {partition} FOR EACH STATEMENT EXECUTE FUNCTION reject_restored_partition_writes()"
+                ))
+                .execute(conn)?;
+                diesel::sql_query(
+                    "INSERT INTO restored_partitions (table_name, epoch) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                )
+                .bind::<Text, _>(table)
+                .bind::<BigInt, _>(epoch as i64)
+                .execute(conn)?;
+                Ok::<(), IndexerError>(())
+            },
+            Duration::from_secs(60)
+        )
+    }
+}
+
+fn rows_to_record_batch(
+    schema: Arc<Schema>,
+    rows: &[Map<String, Value>],
+) -> IndexerResult<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let kind = ColumnKind::from_pg_type(&field.metadata()[PG_TYPE_METADATA_KEY]);
+            let values = rows
+                .iter()
+                .map(|row| row.get(field.name()).filter(|v| !v.is_null()));
+            let array: ArrayRef = match kind {
+                ColumnKind::Int => Arc::new(
+                    values
+                        .map(|v| v.and_then(Value::as_i64))
+                        .collect::<Int64Array>(),
+                ),
+                ColumnKind::Bool => Arc::new(
+                    values
+                        .map(|v| v.and_then(Value::as_bool))
+                        .collect::<BooleanArray>(),
+                ),
+                ColumnKind::Bytes => Arc::new(
+                    values
+                        .map(|v| v.and_then(Value::as_str).map(decode_bytea).transpose())
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into_iter()
+                        .collect::<BinaryArray>(),
+                ),
+                ColumnKind::Text => Arc::new(
+                    values
+                        .map(|v| v.and_then(Value::as_str))
+                        .collect::<StringArray>(),
+                ),
+                ColumnKind::Json => Arc::new(
+                    values
+                        .map(|v| v.map(Value::to_string))
+                        .collect::<StringArray>(),
+                ),
+            };
+            Ok::<_, IndexerError>(array)
+        })
+        .collect::<IndexerResult<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema, columns).map_err(anyhow::Error::from)?)
+}
+
+fn record_batch_to_rows(batch: &RecordBatch) -> IndexerResult<Vec<Value>> {
+    let mut rows = vec![Map::new(); batch.num_rows()];
+    for (field, column) in batch.schema().fields().iter().zip(batch.columns()) {
+        let kind = ColumnKind::from_pg_type(
+            field
+                .metadata()
+                .get(PG_TYPE_METADATA_KEY)
+                .map(String::as_str)
+                .unwrap_or_default(),
+        );
+        for (idx, row) in rows.iter_mut().enumerate() {
+            let value = if column.is_null(idx) {
+                Value::Null
+            } else {
+                match kind {
+                    ColumnKind::Int => downcast_column::<Int64Array>(column)?.value(idx).into(),
+                    ColumnKind::Bool => downcast_column::<BooleanArray>(column)?.value(idx).into(),
+                    ColumnKind::Bytes => Value::String(format!(
+                        "\\x{}",
+                        hex::encode(downcast_column::<BinaryArray>(column)?.value(idx))
+                    )),
+                    ColumnKind::Text => downcast_column::<StringArray>(column)?.value(idx).into(),
+                    ColumnKind::Json => {
+                        serde_json::from_str(downcast_column::<StringArray>(column)?.value(idx))
+                            .map_err(|e| IndexerError::Serde(e.to_string()))?
+                    }
+                }
+            };
+            row.insert(field.name().clone(), value);
+        }
+    }
+    Ok(rows.into_iter().map(Value::Object).collect())
+}
+
+fn downcast_column<A: Array + 'static>(column: &ArrayRef) -> IndexerResult<&A> {
+    Array::as_any(column.as_ref())
+        .downcast_ref::<A>()
+        .ok_or_else(|| {
+            IndexerError::PersistentStorageDataCorruption(format!(
+                "Unexpected column type {}",
+                column.data_type()
+            ))
+        })
+}
+
+// Postgres renders `bytea` values as `\x` prefixed hex strings in JSON.
+fn decode_bytea(value: &str) -> IndexerResult<Vec<u8>> {
+    hex::decode(value.trim_start_matches("\\x"))
+        .map_err(|e| IndexerError::Serde(format!("Failed to decode bytea column: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_partition_bound() {
+        assert_eq!(
+            PartitionBound::parse("FOR VALUES FROM ('100') TO ('250')").unwrap(),
+            PartitionBound {
+                start: 100,
+                end: 250
+            }
+        );
+        assert_eq!(
+            PartitionBound::parse("FOR VALUES FROM (0) TO (7)").unwrap(),
+            PartitionBound { start: 0, end: 7 }
+        );
+        assert!(PartitionBound::parse("FOR VALUES FROM ('100') TO (MAXVALUE)").is_err());
+    }
+
+    #[test]
+    fn quote_identifiers() {
+        assert_eq!(quote_identifier("transactions"), "\"transactions\"");
+        assert_eq!(
+            quote_identifier("events\"; DROP TABLE events; --"),
+            "\"events\"\"; DROP TABLE events; --\""
+        );
+    }
+
+    #[test]
+    fn record_batch_round_trip() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("seq", DataType::Int64, true)
+                .with_metadata([(PG_TYPE_METADATA_KEY.to_string(), "bigint".to_string())].into()),
+            Field::new("digest", DataType::Binary, true)
+                .with_metadata([(PG_TYPE_METADATA_KEY.to_string(), "bytea".to_string())].into()),
+            Field::new("events", DataType::Utf8, true)
+                .with_metadata([(PG_TYPE_METADATA_KEY.to_string(), "ARRAY".to_string())].into()),
+        ]));
+        let rows = vec![
+            serde_json::json!({"seq": 1, "digest": "\\x0a0b", "events": ["\\x01"]}),
+            serde_json::json!({"seq": 2, "digest": null, "events": []}),
+        ];
+        let maps = rows
+            .iter()
+            .map(|r| r.as_object().unwrap().clone())
+            .collect::<Vec<_>>();
+        let batch = rows_to_record_batch(schema, &maps).unwrap();
+        assert_eq!(record_batch_to_rows(&batch).unwrap(), rows);
+    }
+}
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     time::Duration,
 };
 
@@ -285,4 +285,29 @@ impl<T: R2D2Connection> PgPartitionManager<T> {
         )?;
         Ok(())
     }
+
+    /// Epochs of `table` whose partition was restored from an export. These
+    /// partitions are read-only and must not be pruned again.
+    pub fn get_restored_epochs(&self, table: &str) -> Result<BTreeSet<u64>, IndexerError> {
+        if !cfg!(feature = "postgres-feature") {
+            return Ok(BTreeSet::new());
+        }
+
+        #[derive(QueryableByName, Debug, Clone)]
+        struct RestoredEpoch {
+            #[diesel(sql_type = BigInt)]
+            epoch: i64,
+        }
+
+        Ok(
+            read_only_blocking!(&self.cp, |conn| diesel::RunQueryDsl::load(
+                diesel::sql_query("SELECT epoch FROM restored_partitions WHERE table_name = $1")
+                    .bind::<VarChar, _>(table),
+                conn
+            ))?
+            .into_iter()
+            .map(|restored: RestoredEpoch| restored.epoch as u64)
+            .collect(),
+        )
+    }
 }
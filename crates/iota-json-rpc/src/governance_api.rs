@@ -485,7 +485,9 @@ fn calculate_apy(er: &PoolTokenExchangeRate, er_next: &PoolTokenExchangeRate) ->
     (er.rate() / er_next.rate()).powf(365.0) - 1.0
 }
 
-fn stake_status(
+/// Returns the status of a stake activated at `activation_epoch`, estimating
+/// the rewards of active stakes from the exchange rates of their pool.
+pub fn stake_status(
     epoch: u64,
     activation_epoch: u64,
     principal: u64,
@@ -2,22 +2,662 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::path::Path;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
-use move_cli::base::coverage;
-use move_package::BuildConfig;
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use iota_transactional_test_runner::suite::run_test_files;
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_cli::base::{self, coverage};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use move_coverage::coverage_map::{CoverageMap, ExecCoverageMap};
+use move_package::{BuildConfig, compilation::compiled_package::CompiledUnitWithSource};
+
+/// File the unit test runner writes its coverage map to.
+const UNIT_TEST_COVERAGE_MAP: &str = ".coverage_map.mvcov";
+/// Directory of a package holding its transactional tests.
+const TRANSACTIONAL_TESTS_DIR: &str = "transactional_tests";
+/// Transactional test files, as run by `iota-transactional-test-runner`.
+const TRANSACTIONAL_TEST_PATTERN: &str = r".*\.move$";
+/// File the Move VM traces the execution of the transactional tests to.
+const TRANSACTIONAL_TEST_TRACE: &str = ".transactional_tests.trace";
+const MOVE_VM_TRACE_ENV_VAR: &str = "MOVE_VM_TRACE";
 
 #[derive(Parser)]
 #[group(id = "iota-move-coverage")]
 pub struct Coverage {
+    #[clap(subcommand)]
+    pub command: CoverageCommand,
+}
+
+#[derive(Subcommand)]
+pub enum CoverageCommand {
     #[clap(flatten)]
-    pub coverage: coverage::Coverage,
+    Inspect(coverage::CoverageSummaryOptions),
+    /// Merge the coverage of unit tests and transactional tests into a single
+    /// report for this package
+    #[clap(name = "report")]
+    Report(CoverageReport),
+}
+
+#[derive(Parser)]
+pub struct CoverageReport {
+    /// Directory of transactional test files to run with tracing enabled.
+    /// Defaults to the `transactional_tests` directory of the package, if it
+    /// exists. Modules published by the tests are matched to the modules of
+    /// the package by name.
+    #[clap(long = "transactional-tests", value_name = "DIR")]
+    pub transactional_tests: Option<PathBuf>,
+    /// Don't run the transactional tests of the package.
+    #[clap(
+        long = "no-transactional-tests",
+        conflicts_with = "transactional_tests"
+    )]
+    pub no_transactional_tests: bool,
+    /// Additional Move VM trace files to include, e.g. written by running
+    /// other tests with the `MOVE_VM_TRACE` environment variable set.
+    #[clap(long = "trace", value_name = "FILE")]
+    pub traces: Vec<PathBuf>,
+    /// Ignore the coverage of a previous `iota move test --coverage` run.
+    #[clap(long = "no-unit-tests")]
+    pub no_unit_tests: bool,
+    /// Display a coverage summary per function
+    #[clap(long = "summarize-functions")]
+    pub functions: bool,
+    /// Write the report in lcov format to this file
+    #[clap(long = "lcov", value_name = "FILE")]
+    pub lcov: Option<PathBuf>,
+    /// Write the report as HTML to this directory
+    #[clap(long = "html", value_name = "DIR")]
+    pub html: Option<PathBuf>,
+    /// Fail if the line coverage of the package is below this percentage
+    #[clap(long = "fail-under", value_name = "PERCENT")]
+    pub fail_under: Option<f64>,
 }
 
 impl Coverage {
     pub fn execute(self, path: Option<&Path>, build_config: BuildConfig) -> anyhow::Result<()> {
-        self.coverage.execute(path, build_config)?;
+        match self.command {
+            CoverageCommand::Inspect(options) => {
+                coverage::Coverage { options }.execute(path, build_config)
+            }
+            CoverageCommand::Report(report) => report.execute(path, build_config),
+        }
+    }
+}
+
+impl CoverageReport {
+    pub fn execute(self, path: Option<&Path>, build_config: BuildConfig) -> anyhow::Result<()> {
+        // Paths given on the command line are relative to the working directory
+        // before rerooting to the package
+        let cwd = std::env::current_dir()?;
+        let traces: Vec<_> = self.traces.iter().map(|trace| cwd.join(trace)).collect();
+        let transactional_tests = self.transactional_tests.as_ref().map(|dir| cwd.join(dir));
+        let lcov = self.lcov.as_ref().map(|lcov| cwd.join(lcov));
+        let html = self.html.as_ref().map(|html| cwd.join(html));
+
+        let path = base::reroot_path(path)?;
+        let unit_test_coverage_map = path.join(UNIT_TEST_COVERAGE_MAP);
+        let mut coverage_map = CoverageMap {
+            exec_maps: BTreeMap::new(),
+        };
+        if !self.no_unit_tests && unit_test_coverage_map.exists() {
+            coverage_map = CoverageMap::from_binary_file(&unit_test_coverage_map)?;
+        }
+        let transactional_tests = if self.no_transactional_tests {
+            None
+        } else {
+            transactional_tests
+                .or_else(|| Some(path.join(TRANSACTIONAL_TESTS_DIR)).filter(|dir| dir.is_dir()))
+        };
+        let mut transactional_test_count = None;
+        if let Some(dir) = &transactional_tests {
+            let (map, count) = run_transactional_tests(&path, dir, coverage_map)?;
+            coverage_map = map;
+            transactional_test_count = Some(count);
+        }
+        for trace in &traces {
+            anyhow::ensure!(trace.exists(), "Trace file {} not found", trace.display());
+            coverage_map = coverage_map.update_coverage_from_trace_file(trace);
+        }
+        anyhow::ensure!(
+            !coverage_map.exec_maps.is_empty(),
+            "No coverage found. Run `iota move test --coverage`, add transactional tests to \
+             `{TRANSACTIONAL_TESTS_DIR}` or pass trace files with `--trace`"
+        );
+
+        let package = build_config.compile_package(&path, &mut Vec::new())?;
+        let dependency_addresses: BTreeSet<_> = package
+            .deps_compiled_units
+            .iter()
+            .map(|(_, unit)| *unit.unit.module.self_id().address())
+            .collect();
+        let root_modules: Vec<_> = package.root_modules().collect();
+        let root_ids = root_modules
+            .iter()
+            .map(|unit| {
+                let id = unit.unit.module.self_id();
+                (id.name().to_owned(), *id.address())
+            })
+            .collect();
+        let exec_map =
+            unify_module_addresses(coverage_map.to_unified_exec_map(), &root_ids, |address| {
+                dependency_addresses.contains(address)
+            });
+
+        let modules = root_modules
+            .iter()
+            .map(|unit| ModuleCoverage::new(unit, &exec_map))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut out = std::io::stdout().lock();
+        if let (Some(dir), Some(count)) = (&transactional_tests, transactional_test_count) {
+            writeln!(out, "Ran {count} transactional tests in {}", dir.display())?;
+        }
+        write_summary(&mut out, &modules, self.functions)?;
+        if let Some(lcov) = lcov {
+            write_file(&lcov, |file| write_lcov(file, &modules))?;
+            writeln!(out, "Wrote lcov report to {}", lcov.display())?;
+        }
+        if let Some(html) = html {
+            write_html(&html, &modules)?;
+            writeln!(out, "Wrote HTML report to {}", html.display())?;
+        }
+
+        if let Some(fail_under) = self.fail_under {
+            check_threshold(&modules, fail_under)?;
+        }
         Ok(())
     }
 }
+
+/// Runs the transactional tests in `dir` with Move VM tracing enabled, and
+/// adds the coverage of the traced execution to `coverage_map`. Returns the
+/// updated map and the number of tests run.
+fn run_transactional_tests(
+    package_path: &Path,
+    dir: &Path,
+    coverage_map: CoverageMap,
+) -> anyhow::Result<(CoverageMap, usize)> {
+    anyhow::ensure!(
+        cfg!(debug_assertions),
+        "Running transactional tests for coverage is currently supported only in debug builds. \
+         Please build the Iota CLI from source in debug mode."
+    );
+    anyhow::ensure!(
+        dir.is_dir(),
+        "Transactional test directory {} not found",
+        dir.display()
+    );
+
+    let trace_path = package_path.join(TRANSACTIONAL_TEST_TRACE);
+    if trace_path.exists() {
+        fs::remove_file(&trace_path)?;
+    }
+    std::env::set_var(MOVE_VM_TRACE_ENV_VAR, &trace_path);
+    let count = run_test_files(dir, TRANSACTIONAL_TEST_PATTERN)?;
+    // The trace is only created once the VM executes an instruction
+    if !trace_path.exists() {
+        return Ok((coverage_map, count));
+    }
+    let coverage_map = coverage_map.update_coverage_from_trace_file(&trace_path);
+    fs::remove_file(&trace_path)?;
+    Ok((coverage_map, count))
+}
+
+/// Attributes coverage of modules which are not part of the package, but have
+/// the name of a root module, to that root module. This is the case for
+/// packages published by transactional tests, which are assigned a fresh
+/// address on publication.
+fn unify_module_addresses(
+    exec_map: ExecCoverageMap,
+    root_ids: &BTreeMap<Identifier, AccountAddress>,
+    is_dependency: impl Fn(&AccountAddress) -> bool,
+) -> ExecCoverageMap {
+    let mut unified = ExecCoverageMap::new(exec_map.exec_id);
+    for ((address, name), module_map) in exec_map.module_maps {
+        let address = match root_ids.get(&name) {
+            Some(root_address) if !is_dependency(&address) => *root_address,
+            _ => address,
+        };
+        for (function, counts) in module_map.function_maps {
+            for (pc, count) in counts {
+                unified.insert_multi(address, name.clone(), function.clone(), pc, count);
+            }
+        }
+    }
+    unified
+}
+
+/// Fails if the line coverage of `modules` is below `fail_under` percent.
+fn check_threshold(modules: &[ModuleCoverage], fail_under: f64) -> anyhow::Result<()> {
+    let total = Summary::total(modules);
+    anyhow::ensure!(
+        total.line_percent() >= fail_under,
+        "Line coverage of {:.2}% is below the required {:.2}%",
+        total.line_percent(),
+        fail_under
+    );
+    Ok(())
+}
+
+/// Coverage of a single module, mapped to its source.
+struct ModuleCoverage {
+    name: String,
+    source_path: PathBuf,
+    source: String,
+    functions: Vec<FunctionCoverage>,
+    /// Execution count per executable source line, 1-based.
+    lines: BTreeMap<usize, u64>,
+}
+
+struct FunctionCoverage {
+    name: String,
+    /// Line of the function definition, 1-based.
+    line: usize,
+    calls: u64,
+    lines: BTreeMap<usize, u64>,
+}
+
+impl ModuleCoverage {
+    fn new(unit: &CompiledUnitWithSource, exec_map: &ExecCoverageMap) -> anyhow::Result<Self> {
+        let module = &unit.unit.module;
+        let source_map = &unit.unit.source_map;
+        let id = module.self_id();
+        let source = fs::read_to_string(&unit.source_path)?;
+        let line_starts: Vec<_> = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        let line_of = |offset: u32| line_starts.partition_point(|start| *start <= offset as usize);
+        let module_map = exec_map
+            .module_maps
+            .get(&(*id.address(), id.name().to_owned()));
+
+        let mut functions = vec![];
+        let mut lines = BTreeMap::new();
+        for (index, function_def) in module.function_defs().iter().enumerate() {
+            let Some(code) = &function_def.code else {
+                // Native functions have no code to cover
+                continue;
+            };
+            let index = FunctionDefinitionIndex(index as u16);
+            let name = module.identifier_at(module.function_handle_at(function_def.function).name);
+            let definition_location = source_map
+                .get_function_source_map(index)?
+                .definition_location;
+            let counts = module_map.and_then(|map| map.get_function_coverage(name));
+
+            let mut function_lines = BTreeMap::new();
+            for offset in 0..code.code.len() {
+                let location = source_map.get_code_location(index, offset as CodeOffset)?;
+                // Code inlined from other files, e.g. by macros, is not part of
+                // this module's source
+                if location.file_hash() != definition_location.file_hash() {
+                    continue;
+                }
+                let count = counts
+                    .and_then(|counts| counts.get(&(offset as u64)))
+                    .copied()
+                    .unwrap_or(0);
+                let line = function_lines.entry(line_of(location.start())).or_insert(0);
+                *line = (*line).max(count);
+            }
+            for (line, count) in &function_lines {
+                let total = lines.entry(*line).or_insert(0);
+                *total = (*total).max(*count);
+            }
+            functions.push(FunctionCoverage {
+                name: name.to_string(),
+                line: line_of(definition_location.start()),
+                calls: counts
+                    .and_then(|counts| counts.get(&0))
+                    .copied()
+                    .unwrap_or(0),
+                lines: function_lines,
+            });
+        }
+
+        Ok(Self {
+            name: format!("0x{}::{}", id.address().short_str_lossless(), id.name()),
+            source_path: unit.source_path.clone(),
+            source,
+            functions,
+            lines,
+        })
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            functions: self.functions.len(),
+            functions_hit: self.functions.iter().filter(|f| f.calls > 0).count(),
+            lines: self.lines.len(),
+            lines_hit: self.lines.values().filter(|count| **count > 0).count(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    functions: usize,
+    functions_hit: usize,
+    lines: usize,
+    lines_hit: usize,
+}
+
+impl Summary {
+    fn total(modules: &[ModuleCoverage]) -> Self {
+        modules
+            .iter()
+            .map(ModuleCoverage::summary)
+            .fold(Self::default(), |total, summary| Self {
+                functions: total.functions + summary.functions,
+                functions_hit: total.functions_hit + summary.functions_hit,
+                lines: total.lines + summary.lines,
+                lines_hit: total.lines_hit + summary.lines_hit,
+            })
+    }
+
+    fn function_percent(&self) -> f64 {
+        percent(self.functions_hit, self.functions)
+    }
+
+    fn line_percent(&self) -> f64 {
+        percent(self.lines_hit, self.lines)
+    }
+}
+
+fn percent(hit: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        hit as f64 * 100.0 / total as f64
+    }
+}
+
+fn write_summary(
+    out: &mut impl Write,
+    modules: &[ModuleCoverage],
+    functions: bool,
+) -> io::Result<()> {
+    writeln!(out, "+-------------------------+")?;
+    writeln!(out, "| Move Coverage Summary   |")?;
+    writeln!(out, "+-------------------------+")?;
+    for module in modules {
+        let summary = module.summary();
+        writeln!(
+            out,
+            "Module {}: lines {:.2}% ({}/{}), functions {:.2}% ({}/{})",
+            module.name,
+            summary.line_percent(),
+            summary.lines_hit,
+            summary.lines,
+            summary.function_percent(),
+            summary.functions_hit,
+            summary.functions
+        )?;
+        if functions {
+            for function in &module.functions {
+                let hit = function.lines.values().filter(|count| **count > 0).count();
+                writeln!(
+                    out,
+                    "    fun {}: {} calls, lines {:.2}% ({}/{})",
+                    function.name,
+                    function.calls,
+                    percent(hit, function.lines.len()),
+                    hit,
+                    function.lines.len()
+                )?;
+            }
+        }
+    }
+    let total = Summary::total(modules);
+    writeln!(
+        out,
+        "Total: lines {:.2}% ({}/{}), functions {:.2}% ({}/{})",
+        total.line_percent(),
+        total.lines_hit,
+        total.lines,
+        total.function_percent(),
+        total.functions_hit,
+        total.functions
+    )
+}
+
+/// Writes the coverage as an lcov tracefile.
+fn write_lcov(out: &mut impl Write, modules: &[ModuleCoverage]) -> io::Result<()> {
+    for module in modules {
+        let summary = module.summary();
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{}", module.source_path.display())?;
+        for function in &module.functions {
+            writeln!(out, "FN:{},{}", function.line, function.name)?;
+        }
+        for function in &module.functions {
+            writeln!(out, "FNDA:{},{}", function.calls, function.name)?;
+        }
+        writeln!(out, "FNF:{}", summary.functions)?;
+        writeln!(out, "FNH:{}", summary.functions_hit)?;
+        for (line, count) in &module.lines {
+            writeln!(out, "DA:{},{}", line, count)?;
+        }
+        writeln!(out, "LF:{}", summary.lines)?;
+        writeln!(out, "LH:{}", summary.lines_hit)?;
+        writeln!(out, "end_of_record")?;
+    }
+    Ok(())
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 2px 8px; text-align: left; }
+pre { margin: 0; }
+.hit { background: #d6f5d6; }
+.miss { background: #f5d6d6; }
+.count { color: #888; text-align: right; }";
+
+/// Writes an index page and an annotated source page per module to `dir`.
+fn write_html(dir: &Path, modules: &[ModuleCoverage]) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+    write_file(&dir.join("index.html"), |out| {
+        write_html_index(out, modules)
+    })?;
+    for module in modules {
+        write_file(&dir.join(html_page(module)), |out| {
+            write_html_module(out, module)
+        })?;
+    }
+    Ok(())
+}
+
+fn write_html_index(out: &mut impl Write, modules: &[ModuleCoverage]) -> io::Result<()> {
+    write!(
+        out,
+        "<html><head><title>Move Coverage</title><style>{HTML_STYLE}</style></head><body>\
+         <h1>Move Coverage</h1><table><tr><th>Module</th><th>Lines</th><th>Functions</th></tr>"
+    )?;
+    for module in modules {
+        let summary = module.summary();
+        write!(
+            out,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{:.2}% ({}/{})</td><td>{:.2}% ({}/{})</td></tr>",
+            html_page(module),
+            module.name,
+            summary.line_percent(),
+            summary.lines_hit,
+            summary.lines,
+            summary.function_percent(),
+            summary.functions_hit,
+            summary.functions
+        )?;
+    }
+    let total = Summary::total(modules);
+    write!(
+        out,
+        "<tr><th>Total</th><th>{:.2}%</th><th>{:.2}%</th></tr></table></body></html>",
+        total.line_percent(),
+        total.function_percent()
+    )
+}
+
+fn write_html_module(out: &mut impl Write, module: &ModuleCoverage) -> io::Result<()> {
+    write!(
+        out,
+        "<html><head><title>{0}</title><style>{HTML_STYLE}</style></head><body>\
+         <h1>{0}</h1><p><a href=\"index.html\">Index</a> - {1}</p><table>",
+        module.name,
+        escape_html(&module.source_path.display().to_string())
+    )?;
+    for (index, line) in module.source.lines().enumerate() {
+        let (class, count) = match module.lines.get(&(index + 1)) {
+            Some(0) => ("miss", "0".to_string()),
+            Some(count) => ("hit", count.to_string()),
+            None => ("", String::new()),
+        };
+        write!(
+            out,
+            "<tr class=\"{class}\"><td class=\"count\">{}</td><td class=\"count\">{count}</td>\
+             <td><pre>{}</pre></td></tr>",
+            index + 1,
+            escape_html(line)
+        )?;
+    }
+    write!(out, "</table></body></html>")
+}
+
+/// Name of the HTML page of `module`.
+fn html_page(module: &ModuleCoverage) -> String {
+    format!("{}.html", module.name.replace("::", "_"))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Creates the file at `path` and fills it with `write`.
+fn write_file(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> anyhow::Result<()> {
+    let mut file = BufWriter::new(
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+    );
+    write(&mut file)
+        .and_then(|()| file.flush())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(lines: &[(usize, u64)]) -> ModuleCoverage {
+        let lines: BTreeMap<_, _> = lines.iter().copied().collect();
+        ModuleCoverage {
+            name: "0x1::m".to_string(),
+            source_path: PathBuf::from("sources/m.move"),
+            source: String::new(),
+            functions: vec![
+                FunctionCoverage {
+                    name: "f".to_string(),
+                    line: 2,
+                    calls: 3,
+                    lines: lines.clone().into_iter().take(1).collect(),
+                },
+                FunctionCoverage {
+                    name: "g".to_string(),
+                    line: 6,
+                    calls: 0,
+                    lines: lines.clone().into_iter().skip(1).collect(),
+                },
+            ],
+            lines,
+        }
+    }
+
+    #[test]
+    fn lcov_output() {
+        let mut out = vec![];
+        write_lcov(&mut out, &[module(&[(3, 3), (7, 0)])]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "TN:\n\
+             SF:sources/m.move\n\
+             FN:2,f\n\
+             FN:6,g\n\
+             FNDA:3,f\n\
+             FNDA:0,g\n\
+             FNF:2\n\
+             FNH:1\n\
+             DA:3,3\n\
+             DA:7,0\n\
+             LF:2\n\
+             LH:1\n\
+             end_of_record\n"
+        );
+    }
+
+    #[test]
+    fn merge_unit_and_transactional_coverage() {
+        let root = AccountAddress::from_hex_literal("0x1").unwrap();
+        let published = AccountAddress::from_hex_literal("0x42").unwrap();
+        let m = Identifier::new("m").unwrap();
+        let f = Identifier::new("f").unwrap();
+
+        let mut coverage_map = CoverageMap {
+            exec_maps: BTreeMap::new(),
+        };
+        coverage_map.insert("unit", root, m.clone(), f.clone(), 0);
+        let dir = tempfile::tempdir().unwrap();
+        let trace = dir.path().join("trace");
+        fs::write(
+            &trace,
+            format!(
+                "1-ThreadId(1),{published}::m::f,0,Ret\n\
+                 1-ThreadId(1),{published}::m::f,1,Ret\n\
+                 1-ThreadId(1),0x2::m::f,0,Ret\n",
+                published = published.to_hex_literal()
+            ),
+        )
+        .unwrap();
+        let coverage_map = coverage_map.update_coverage_from_trace_file(&trace);
+
+        let root_ids = BTreeMap::from([(m.clone(), root)]);
+        let unified =
+            unify_module_addresses(coverage_map.to_unified_exec_map(), &root_ids, |address| {
+                *address == AccountAddress::TWO
+            });
+
+        let root_counts = unified.module_maps[&(root, m.clone())]
+            .get_function_coverage(&f)
+            .unwrap();
+        assert_eq!(root_counts, &BTreeMap::from([(0, 2), (1, 1)]));
+        // The published copy is attributed to the package, dependencies are not
+        assert!(!unified.module_maps.contains_key(&(published, m.clone())));
+        assert!(
+            unified
+                .module_maps
+                .contains_key(&(AccountAddress::TWO, m.clone()))
+        );
+    }
+
+    #[test]
+    fn threshold_gate() {
+        let modules = [module(&[(3, 3), (7, 0)])];
+        check_threshold(&modules, 50.0).unwrap();
+        let err = check_threshold(&modules, 50.5).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line coverage of 50.00% is below the required 50.50%"
+        );
+        // A package without executable lines is fully covered
+        check_threshold(&[module(&[])], 100.0).unwrap();
+    }
+}
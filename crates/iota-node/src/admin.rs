@@ -3,9 +3,16 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    collections::BTreeMap,
+    future::Future,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
     str::FromStr,
-    sync::Arc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
 use axum::{
@@ -15,7 +22,9 @@ use axum::{
     routing::{get, post},
 };
 use base64::Engine;
+use fastcrypto::encoding::{Encoding, Hex};
 use humantime::parse_duration;
+use iota_core::authority::authority_store_pruner::PruningMode;
 use iota_types::{
     base_types::AuthorityName,
     crypto::{RandomnessPartialSignature, RandomnessRound, RandomnessSignature},
@@ -24,7 +33,11 @@ use iota_types::{
 use serde::Deserialize;
 use telemetry_subscribers::TracingHandle;
 use tokio::sync::oneshot;
-use tracing::info;
+use tracing::{error, info};
+use typed_store::{
+    DBMetrics, TypedStoreError,
+    rocks::{introspection, list_tables},
+};
 
 use crate::IotaNode;
 
@@ -73,6 +86,44 @@ use crate::IotaNode;
 // Inject a full signature from another node, bypassing validity checks.
 //
 //  $ curl 'http://127.0.0.1:1337/randomness-inject-full-sig?round=123&sigs=base64encodedsig'
+//
+// View the size on disk, estimated key count and pending compaction bytes of
+// every table, optionally of a single database (perpetual, checkpoints or
+// indexes):
+//
+//   $ curl 'http://127.0.0.1:1337/storage-usage?db=perpetual'
+//
+// View the key prefixes using the most space among the first 100000 keys of a
+// table, grouping keys by their first 32 bytes:
+//
+//   $ curl 'http://127.0.0.1:1337/storage-key-prefixes?db=perpetual&table=objects&prefix_len=32&max_keys=100000'
+//
+// Sample one out of every 1000 point reads to track hot keys (0 disables
+// sampling), and view the most read keys:
+//
+//   $ curl -X POST 'http://127.0.0.1:1337/storage-hot-keys?sample_every=1000'
+//   $ curl 'http://127.0.0.1:1337/storage-hot-keys?limit=20'
+//
+// Immediately prune objects (or checkpoints) up to checkpoint 1000, without
+// waiting for the next periodic pruning pass. The configured number of epochs
+// to retain still applies, and checkpoints are only pruned once archived:
+//
+//   $ curl -X POST 'http://127.0.0.1:1337/prune?mode=objects&checkpoint=1000'
+//
+// Compact a table of a database, or all of its tables if no table is given:
+//
+//   $ curl -X POST 'http://127.0.0.1:1337/compact?db=perpetual&table=objects'
+//
+// Take a RocksDB checkpoint of a database, or of all databases into
+// subdirectories named after them if no database is given. The path must not
+// exist yet:
+//
+//   $ curl -X POST 'http://127.0.0.1:1337/db-checkpoint?db=perpetual&path=/opt/iota/db-checkpoint'
+//
+// View the progress of the pruning, compaction and checkpoint jobs started
+// above:
+//
+//   $ curl 'http://127.0.0.1:1337/storage-jobs'
 
 const LOGGING_ROUTE: &str = "/logging";
 const TRACING_ROUTE: &str = "/enable-tracing";
@@ -85,10 +136,22 @@ const NODE_CONFIG: &str = "/node-config";
 const RANDOMNESS_PARTIAL_SIGS_ROUTE: &str = "/randomness-partial-sigs";
 const RANDOMNESS_INJECT_PARTIAL_SIGS_ROUTE: &str = "/randomness-inject-partial-sigs";
 const RANDOMNESS_INJECT_FULL_SIG_ROUTE: &str = "/randomness-inject-full-sig";
+const STORAGE_USAGE_ROUTE: &str = "/storage-usage";
+const STORAGE_KEY_PREFIXES_ROUTE: &str = "/storage-key-prefixes";
+const STORAGE_HOT_KEYS_ROUTE: &str = "/storage-hot-keys";
+const PRUNE_ROUTE: &str = "/prune";
+const COMPACT_ROUTE: &str = "/compact";
+const DB_CHECKPOINT_ROUTE: &str = "/db-checkpoint";
+const STORAGE_JOBS_ROUTE: &str = "/storage-jobs";
+
+const DEFAULT_KEY_PREFIX_LEN: usize = 32;
+const DEFAULT_MAX_SCANNED_KEYS: usize = 100_000;
+const DEFAULT_STORAGE_REPORT_LIMIT: usize = 20;
 
 struct AppState {
     node: Arc<IotaNode>,
     tracing_handle: TracingHandle,
+    storage_jobs: Arc<StorageJobs>,
 }
 
 pub async fn run_admin_server(node: Arc<IotaNode>, port: u16, tracing_handle: TracingHandle) {
@@ -97,6 +160,7 @@ pub async fn run_admin_server(node: Arc<IotaNode>, port: u16, tracing_handle: Tr
     let app_state = AppState {
         node,
         tracing_handle,
+        storage_jobs: Default::default(),
     };
 
     let app = Router::new()
@@ -124,6 +188,14 @@ pub async fn run_admin_server(node: Arc<IotaNode>, port: u16, tracing_handle: Tr
             RANDOMNESS_INJECT_FULL_SIG_ROUTE,
             post(randomness_inject_full_sig),
         )
+        .route(STORAGE_USAGE_ROUTE, get(storage_usage))
+        .route(STORAGE_KEY_PREFIXES_ROUTE, get(storage_key_prefixes))
+        .route(STORAGE_HOT_KEYS_ROUTE, get(storage_hot_keys))
+        .route(STORAGE_HOT_KEYS_ROUTE, post(set_storage_hot_keys_sampling))
+        .route(PRUNE_ROUTE, post(prune))
+        .route(COMPACT_ROUTE, post(compact))
+        .route(DB_CHECKPOINT_ROUTE, post(db_checkpoint))
+        .route(STORAGE_JOBS_ROUTE, get(storage_jobs))
         .with_state(Arc::new(app_state));
 
     let socket_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
@@ -442,3 +514,426 @@ async fn randomness_inject_full_sig(
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
     }
 }
+
+#[derive(Deserialize)]
+struct StorageUsage {
+    db: Option<String>,
+}
+
+async fn storage_usage(
+    State(state): State<Arc<AppState>>,
+    args: Query<StorageUsage>,
+) -> (StatusCode, String) {
+    let Query(StorageUsage { db }) = args;
+
+    let databases = state.node.storage_databases();
+    // Reading the properties of every table can block, keep it off the async
+    // runtime
+    let output = tokio::task::spawn_blocking(move || {
+        let mut output = String::new();
+        for (db_name, rocksdb) in databases {
+            if db.as_ref().is_some_and(|db| db != db_name) {
+                continue;
+            }
+            for stats in introspection::table_storage_stats(&rocksdb)? {
+                output.push_str(&format!(
+                    "{db_name}/{}: sst_files_size={} blob_files_size={} mem_tables_size={} estimated_num_keys={} estimated_pending_compaction_bytes={}\n",
+                    stats.table_name,
+                    stats.total_sst_files_size,
+                    stats.total_blob_files_size,
+                    stats.size_all_mem_tables,
+                    stats.estimated_num_keys,
+                    stats.estimated_pending_compaction_bytes,
+                ));
+            }
+        }
+        Ok::<_, TypedStoreError>(output)
+    })
+    .await;
+    match output {
+        Ok(Ok(output)) => (StatusCode::OK, output),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct StorageKeyPrefixes {
+    db: String,
+    table: String,
+    prefix_len: Option<usize>,
+    max_keys: Option<usize>,
+    limit: Option<usize>,
+}
+
+async fn storage_key_prefixes(
+    State(state): State<Arc<AppState>>,
+    args: Query<StorageKeyPrefixes>,
+) -> (StatusCode, String) {
+    let Query(StorageKeyPrefixes {
+        db,
+        table,
+        prefix_len,
+        max_keys,
+        limit,
+    }) = args;
+
+    let Some((_, rocksdb)) = state
+        .node
+        .storage_databases()
+        .into_iter()
+        .find(|(db_name, _)| *db_name == db)
+    else {
+        return (StatusCode::BAD_REQUEST, format!("unknown db: {db}\n"));
+    };
+
+    // Scanning the table can take a while, keep it off the async runtime
+    let prefixes = tokio::task::spawn_blocking(move || {
+        introspection::largest_key_prefixes(
+            &rocksdb,
+            &table,
+            prefix_len.unwrap_or(DEFAULT_KEY_PREFIX_LEN),
+            max_keys.unwrap_or(DEFAULT_MAX_SCANNED_KEYS),
+            limit.unwrap_or(DEFAULT_STORAGE_REPORT_LIMIT),
+        )
+    })
+    .await;
+    let prefixes = match prefixes {
+        Ok(Ok(prefixes)) => prefixes,
+        Ok(Err(err)) => return (StatusCode::BAD_REQUEST, err.to_string()),
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    let mut output = String::new();
+    for prefix in prefixes {
+        output.push_str(&format!(
+            "{}: total_bytes={} num_keys={} key_bytes={} value_bytes={}\n",
+            Hex::encode(&prefix.prefix),
+            prefix.total_bytes(),
+            prefix.num_keys,
+            prefix.key_bytes,
+            prefix.value_bytes,
+        ));
+    }
+
+    (StatusCode::OK, output)
+}
+
+#[derive(Deserialize)]
+struct StorageHotKeys {
+    limit: Option<usize>,
+}
+
+async fn storage_hot_keys(args: Query<StorageHotKeys>) -> (StatusCode, String) {
+    let Query(StorageHotKeys { limit }) = args;
+
+    let hot_keys = &DBMetrics::get().hot_keys;
+    if hot_keys.sample_every() == 0 {
+        return (StatusCode::OK, "hot key sampling is disabled\n".to_string());
+    }
+    let mut output = String::new();
+    for hot_key in hot_keys.hot_keys(limit.unwrap_or(DEFAULT_STORAGE_REPORT_LIMIT)) {
+        output.push_str(&format!(
+            "{}/{}: sampled_reads={}\n",
+            hot_key.table_name,
+            Hex::encode(&hot_key.key),
+            hot_key.sampled_reads,
+        ));
+    }
+
+    (StatusCode::OK, output)
+}
+
+#[derive(Deserialize)]
+struct StorageHotKeysSampling {
+    sample_every: u64,
+}
+
+async fn set_storage_hot_keys_sampling(
+    args: Query<StorageHotKeysSampling>,
+) -> (StatusCode, String) {
+    let Query(StorageHotKeysSampling { sample_every }) = args;
+
+    DBMetrics::get().hot_keys.set_sample_every(sample_every);
+    let output = if sample_every == 0 {
+        "hot key sampling disabled\n".to_string()
+    } else {
+        format!("sampling one out of every {sample_every} reads for hot keys\n")
+    };
+
+    (StatusCode::OK, output)
+}
+
+enum StorageJobStatus {
+    Running,
+    Completed { result: String, duration: Duration },
+    Failed { error: String, duration: Duration },
+}
+
+struct StorageJob {
+    description: String,
+    started_at: Instant,
+    status: StorageJobStatus,
+}
+
+/// Number of finished storage jobs kept for the storage jobs route, older
+/// ones are evicted.
+const MAX_FINISHED_STORAGE_JOBS: usize = 100;
+
+/// Storage maintenance jobs started through the admin interface, which run in
+/// the background and can be followed through the storage jobs route.
+#[derive(Default)]
+struct StorageJobs {
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, StorageJob>>,
+}
+
+impl StorageJobs {
+    fn start(
+        self: &Arc<Self>,
+        description: String,
+        job: impl Future<Output = anyhow::Result<String>> + Send + 'static,
+    ) -> u64 {
+        let id = self.register(description);
+        let started_at = Instant::now();
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            let result = job.await;
+            let duration = started_at.elapsed();
+            let status = match result {
+                Ok(result) => {
+                    info!(id, "storage job completed in {duration:?}: {result}");
+                    StorageJobStatus::Completed { result, duration }
+                }
+                Err(err) => {
+                    error!(id, "storage job failed after {duration:?}: {err:?}");
+                    StorageJobStatus::Failed {
+                        error: err.to_string(),
+                        duration,
+                    }
+                }
+            };
+            jobs.finish(id, status);
+        });
+        id
+    }
+
+    fn register(&self, description: String) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        info!(id, "starting storage job: {description}");
+        self.jobs.lock().unwrap().insert(id, StorageJob {
+            description,
+            started_at: Instant::now(),
+            status: StorageJobStatus::Running,
+        });
+        id
+    }
+
+    /// Records the final status of a job, and evicts the oldest finished jobs
+    /// beyond [`MAX_FINISHED_STORAGE_JOBS`].
+    fn finish(&self, id: u64, status: StorageJobStatus) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(job) = jobs.get_mut(&id) {
+            job.status = status;
+        }
+        let finished: Vec<_> = jobs
+            .iter()
+            .filter(|(_, job)| !matches!(job.status, StorageJobStatus::Running))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &finished[..finished.len().saturating_sub(MAX_FINISHED_STORAGE_JOBS)] {
+            jobs.remove(id);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PruneMode {
+    Objects,
+    Checkpoints,
+}
+
+#[derive(Deserialize)]
+struct Prune {
+    mode: PruneMode,
+    checkpoint: u64,
+}
+
+async fn prune(State(state): State<Arc<AppState>>, args: Query<Prune>) -> (StatusCode, String) {
+    let Query(Prune { mode, checkpoint }) = args;
+
+    let mode = match mode {
+        PruneMode::Objects => PruningMode::Objects,
+        PruneMode::Checkpoints => PruningMode::Checkpoints,
+    };
+    let authority_state = state.node.state();
+    let id = state.storage_jobs.start(
+        format!("prune {mode:?} up to checkpoint {checkpoint}"),
+        async move {
+            let pruned_checkpoint = authority_state
+                .pruner()
+                .prune_up_to_checkpoint(mode, checkpoint)
+                .await?;
+            Ok(format!("pruned up to checkpoint {pruned_checkpoint}"))
+        },
+    );
+
+    (StatusCode::OK, format!("started storage job {id}\n"))
+}
+
+#[derive(Deserialize)]
+struct Compact {
+    db: String,
+    table: Option<String>,
+}
+
+async fn compact(State(state): State<Arc<AppState>>, args: Query<Compact>) -> (StatusCode, String) {
+    let Query(Compact { db, table }) = args;
+
+    let Some((_, rocksdb)) = state
+        .node
+        .storage_databases()
+        .into_iter()
+        .find(|(db_name, _)| *db_name == db)
+    else {
+        return (StatusCode::BAD_REQUEST, format!("unknown db: {db}\n"));
+    };
+    let tables = match table {
+        Some(table) => vec![table],
+        None => match list_tables(rocksdb.path().to_path_buf()) {
+            Ok(tables) => tables,
+            Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        },
+    };
+    if let Some(table) = tables
+        .iter()
+        .find(|table| rocksdb.cf_handle(table).is_none())
+    {
+        return (StatusCode::BAD_REQUEST, format!("unknown table: {table}\n"));
+    }
+
+    let id = state
+        .storage_jobs
+        .start(format!("compact {db}/{}", tables.join(",")), async move {
+            // Compactions block until they are done, keep them off the async runtime
+            tokio::task::spawn_blocking(move || {
+                for table in &tables {
+                    let cf = rocksdb
+                        .cf_handle(table)
+                        .ok_or_else(|| anyhow::anyhow!("unknown table: {table}"))?;
+                    rocksdb.compact_range_cf::<&[u8]>(&cf, None, None);
+                }
+                Ok(format!("compacted {} tables", tables.len()))
+            })
+            .await?
+        });
+
+    (StatusCode::OK, format!("started storage job {id}\n"))
+}
+
+#[derive(Deserialize)]
+struct DbCheckpoint {
+    db: Option<String>,
+    path: PathBuf,
+}
+
+async fn db_checkpoint(
+    State(state): State<Arc<AppState>>,
+    args: Query<DbCheckpoint>,
+) -> (StatusCode, String) {
+    let Query(DbCheckpoint { db, path }) = args;
+
+    let databases: Vec<_> = state
+        .node
+        .storage_databases()
+        .into_iter()
+        .filter(|(db_name, _)| db.as_ref().map_or(true, |db| db == db_name))
+        .collect();
+    if databases.is_empty() {
+        return (StatusCode::BAD_REQUEST, "unknown db\n".to_string());
+    }
+    if path.exists() {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("path already exists: {}\n", path.display()),
+        );
+    }
+
+    let id = state
+        .storage_jobs
+        .start(format!("checkpoint db to {}", path.display()), async move {
+            tokio::task::spawn_blocking(move || {
+                if db.is_some() {
+                    databases[0].1.checkpoint(&path)?;
+                } else {
+                    std::fs::create_dir_all(&path)?;
+                    for (db_name, rocksdb) in &databases {
+                        rocksdb.checkpoint(&path.join(db_name))?;
+                    }
+                }
+                Ok(format!("db checkpoint created at {}", path.display()))
+            })
+            .await?
+        });
+
+    (StatusCode::OK, format!("started storage job {id}\n"))
+}
+
+async fn storage_jobs(State(state): State<Arc<AppState>>) -> (StatusCode, String) {
+    let mut output = String::new();
+    for (id, job) in state.storage_jobs.jobs.lock().unwrap().iter() {
+        let status = match &job.status {
+            StorageJobStatus::Running => {
+                format!("running for {:?}", job.started_at.elapsed())
+            }
+            StorageJobStatus::Completed { result, duration } => {
+                format!("completed in {duration:?}: {result}")
+            }
+            StorageJobStatus::Failed { error, duration } => {
+                format!("failed after {duration:?}: {error}")
+            }
+        };
+        output.push_str(&format!("{id}: {}: {status}\n", job.description));
+    }
+
+    let authority_state = state.node.state();
+    for mode in [PruningMode::Objects, PruningMode::Checkpoints] {
+        match authority_state.pruner().highest_pruned_checkpoint(mode) {
+            Ok(checkpoint) => output.push_str(&format!(
+                "highest pruned checkpoint ({mode:?}): {checkpoint}\n"
+            )),
+            Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+        }
+    }
+
+    (StatusCode::OK, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finished_storage_jobs_are_evicted() {
+        let jobs = StorageJobs::default();
+        let running = jobs.register("running".to_string());
+        for i in 0..MAX_FINISHED_STORAGE_JOBS + 10 {
+            let id = jobs.register(format!("job {i}"));
+            jobs.finish(id, StorageJobStatus::Completed {
+                result: String::new(),
+                duration: Duration::ZERO,
+            });
+        }
+
+        let jobs = jobs.jobs.lock().unwrap();
+        assert_eq!(jobs.len(), MAX_FINISHED_STORAGE_JOBS + 1);
+        // Running jobs are kept, the oldest finished ones are evicted
+        assert!(matches!(jobs[&running].status, StorageJobStatus::Running));
+        assert_eq!(
+            jobs.keys().nth(1).copied(),
+            Some(running + 11),
+            "the 10 oldest finished jobs should be evicted"
+        );
+    }
+}
@@ -8,6 +8,7 @@ use std::{
     collections::{BTreeSet, HashMap, HashSet},
     fmt,
     net::SocketAddr,
+    num::NonZeroUsize,
     path::PathBuf,
     str::FromStr,
     sync::{Arc, Weak},
@@ -19,12 +20,14 @@ use anemo_tower::{
     callback::CallbackLayer,
     trace::{DefaultMakeSpan, DefaultOnFailure, TraceLayer},
 };
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use arc_swap::ArcSwap;
 use fastcrypto_zkp::bn254::zk_login::{JWK, JwkId, OIDCProvider};
-use futures::TryFutureExt;
+use futures::{FutureExt, TryFutureExt, future::BoxFuture};
 pub use handle::IotaNodeHandle;
-use iota_archival::{reader::ArchiveReaderBalancer, writer::ArchiveWriter};
+use iota_archival::{
+    index::IndexedArchiveReader, reader::ArchiveReaderBalancer, writer::ArchiveWriter,
+};
 use iota_config::{
     ConsensusConfig, NodeConfig,
     node::{DBCheckpointConfig, RunWithRange},
@@ -35,6 +38,7 @@ use iota_core::{
     authority::{
         AuthorityState, AuthorityStore, CHAIN_IDENTIFIER, RandomnessRoundReceiver,
         authority_per_epoch_store::AuthorityPerEpochStore,
+        authority_store_pruner::AuthorityStorePruner,
         authority_store_tables::AuthorityPerpetualTables,
         epoch_start_configuration::{EpochFlag, EpochStartConfigTrait, EpochStartConfiguration},
     },
@@ -45,6 +49,7 @@ use iota_core::{
         CheckpointMetrics, CheckpointService, CheckpointStore, SendCheckpointToStateSync,
         SubmitCheckpointToConsensus,
         checkpoint_executor::{CheckpointExecutor, StopReason, metrics::CheckpointExecutorMetrics},
+        sparse_checkpoint_executor::{CheckpointDataSource, SparseCheckpointExecutor},
     },
     connection_monitor::ConnectionMonitor,
     consensus_adapter::{
@@ -105,10 +110,12 @@ use iota_types::{
     digests::ChainIdentifier,
     error::{IotaError, IotaResult},
     execution_config_utils::to_binary_config,
+    full_checkpoint_content::CheckpointData,
     iota_system_state::{
         IotaSystemState, IotaSystemStateTrait,
         epoch_start_iota_system_state::{EpochStartSystemState, EpochStartSystemStateTrait},
     },
+    messages_checkpoint::CheckpointSequenceNumber,
     messages_consensus::{AuthorityCapabilitiesV1, ConsensusTransaction, check_total_jwk_size},
     quorum_driver_types::QuorumDriverEffectsQueueResult,
     supported_protocol_versions::SupportedProtocolVersions,
@@ -127,7 +134,10 @@ use tokio::{
 };
 use tower::ServiceBuilder;
 use tracing::{Instrument, debug, error, error_span, info, warn};
-use typed_store::{DBMetrics, rocks::default_db_options};
+use typed_store::{
+    DBMetrics,
+    rocks::{RocksDB, default_db_options},
+};
 
 use crate::metrics::{GrpcMetrics, IotaNodeMetrics};
 
@@ -209,6 +219,7 @@ pub struct IotaNode {
     /// experimental rest service
     _http_server: Option<tokio::task::JoinHandle<()>>,
     state: Arc<AuthorityState>,
+    store: Arc<AuthorityStore>,
     transaction_orchestrator: Option<Arc<TransactionOrchestrator<NetworkAuthorityClient>>>,
     registry_service: RegistryService,
     metrics: Arc<IotaNodeMetrics>,
@@ -423,6 +434,20 @@ impl IotaNode {
         let run_with_range = config.run_with_range;
         let is_validator = config.consensus_config().is_some();
         let is_full_node = !is_validator;
+        if config.sparse_node_config.is_some() {
+            if is_validator {
+                return Err(anyhow!("validators can't run as sparse full nodes"));
+            }
+            // A sparse full node only holds the tracked part of the live object set
+            config
+                .expensive_safety_check_config
+                .force_disable_epoch_iota_conservation_check();
+            config
+                .expensive_safety_check_config
+                .force_disable_state_consistency_check();
+        }
+        AuthorityStorePruner::validate_config(&config.authority_store_pruning_config)
+            .context("invalid authority store pruning config")?;
         let prometheus_registry = registry_service.default_registry();
 
         info!(node =? config.authority_public_key(),
@@ -742,17 +767,18 @@ impl IotaNode {
         let (end_of_epoch_channel, end_of_epoch_receiver) =
             broadcast::channel(config.end_of_epoch_broadcast_channel_capacity);
 
-        let transaction_orchestrator = if is_full_node && run_with_range.is_none() {
-            Some(Arc::new(TransactionOrchestrator::new_with_auth_aggregator(
-                auth_agg.load_full(),
-                state.clone(),
-                end_of_epoch_receiver,
-                &config.db_path(),
-                &prometheus_registry,
-            )))
-        } else {
-            None
-        };
+        let transaction_orchestrator =
+            if is_full_node && run_with_range.is_none() && config.sparse_node_config.is_none() {
+                Some(Arc::new(TransactionOrchestrator::new_with_auth_aggregator(
+                    auth_agg.load_full(),
+                    state.clone(),
+                    end_of_epoch_receiver,
+                    &config.db_path(),
+                    &prometheus_registry,
+                )))
+            } else {
+                None
+            };
 
         let http_server = build_http_server(
             state.clone(),
@@ -826,6 +852,7 @@ impl IotaNode {
             validator_components: Mutex::new(validator_components),
             _http_server: http_server,
             state,
+            store,
             transaction_orchestrator,
             registry_service,
             metrics: iota_node_metrics,
@@ -953,6 +980,7 @@ impl IotaNode {
                 &config.db_checkpoint_path(),
                 &config.snapshot_path(),
                 remote_store_config.clone(),
+                config.state_snapshot_write_config.full_snapshot_interval,
                 60,
                 prometheus_registry,
                 checkpoint_store,
@@ -1513,18 +1541,27 @@ impl IotaNode {
     pub async fn monitor_reconfiguration(self: Arc<Self>) -> Result<()> {
         let checkpoint_executor_metrics =
             CheckpointExecutorMetrics::new(&self.registry_service.default_registry());
+        let mut sparse_checkpoint_executor =
+            self.config.sparse_node_config.as_ref().map(|config| {
+                info!(
+                    upstream_rest_url = %config.upstream_rest_url,
+                    "running as sparse full node"
+                );
+                SparseCheckpointExecutor::new(
+                    self.state_sync_handle.subscribe_to_synced_checkpoints(),
+                    self.state.clone(),
+                    self.store.clone(),
+                    self.checkpoint_store.clone(),
+                    Arc::new(RestCheckpointDataSource(iota_rest_api::Client::new(
+                        &config.upstream_rest_url,
+                    ))),
+                    config,
+                )
+            });
 
         loop {
             let mut accumulator_guard = self.accumulator.lock().await;
             let accumulator = accumulator_guard.take().unwrap();
-            let mut checkpoint_executor = CheckpointExecutor::new(
-                self.state_sync_handle.subscribe_to_synced_checkpoints(),
-                self.checkpoint_store.clone(),
-                self.state.clone(),
-                accumulator.clone(),
-                self.config.checkpoint_executor_config.clone(),
-                checkpoint_executor_metrics.clone(),
-            );
 
             let run_with_range = self.config.run_with_range;
 
@@ -1557,10 +1594,22 @@ impl IotaNode {
                     .submit(transaction, None, &cur_epoch_store)?;
             }
 
-            let stop_condition = checkpoint_executor
-                .run_epoch(cur_epoch_store.clone(), run_with_range)
-                .await;
-            drop(checkpoint_executor);
+            let stop_condition = if let Some(executor) = &mut sparse_checkpoint_executor {
+                executor.run_epoch(cur_epoch_store.clone()).await;
+                StopReason::EpochComplete
+            } else {
+                let mut checkpoint_executor = CheckpointExecutor::new(
+                    self.state_sync_handle.subscribe_to_synced_checkpoints(),
+                    self.checkpoint_store.clone(),
+                    self.state.clone(),
+                    accumulator.clone(),
+                    self.config.checkpoint_executor_config.clone(),
+                    checkpoint_executor_metrics.clone(),
+                );
+                checkpoint_executor
+                    .run_epoch(cur_epoch_store.clone(), run_with_range)
+                    .await
+            };
 
             if stop_condition == StopReason::RunWithRangeCondition {
                 IotaNode::shutdown(&self).await;
@@ -1867,6 +1916,18 @@ impl IotaNode {
     pub fn randomness_handle(&self) -> randomness::Handle {
         self.randomness_handle.clone()
     }
+
+    /// Returns the databases of the node by name, for storage introspection.
+    pub fn storage_databases(&self) -> Vec<(&'static str, Arc<RocksDB>)> {
+        let mut databases = vec![
+            ("perpetual", self.store.rocksdb().clone()),
+            ("checkpoints", self.checkpoint_store.rocksdb().clone()),
+        ];
+        if let Some(indexes) = &self.state.indexes {
+            databases.push(("indexes", indexes.rocksdb().clone()));
+        }
+        databases
+    }
 }
 
 #[cfg(not(msim))]
@@ -1924,6 +1985,19 @@ fn send_trusted_peer_change(
         })
 }
 
+/// Fetches the full checkpoint data processed by a sparse full node from the
+/// REST API of an upstream full node.
+struct RestCheckpointDataSource(iota_rest_api::Client);
+
+impl CheckpointDataSource for RestCheckpointDataSource {
+    fn get_full_checkpoint(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> BoxFuture<'_, Result<CheckpointData>> {
+        async move { Ok(self.0.get_full_checkpoint(sequence_number).await?) }.boxed()
+    }
+}
+
 fn build_kv_store(
     state: &Arc<AuthorityState>,
     config: &NodeConfig,
@@ -2053,10 +2127,31 @@ pub async fn build_http_server(
     router = router.merge(json_rpc_router);
 
     if config.enable_rest_api {
-        let mut rest_service = iota_rest_api::RestService::new(
-            Arc::new(RestReadStore::new(state, store)),
-            software_version,
-        );
+        let mut rest_store = RestReadStore::new(state, store.clone());
+        if let Some(archive_config) = &config.rest_archive_config {
+            let archive = Arc::new(
+                IndexedArchiveReader::new(
+                    &archive_config.object_store_config,
+                    archive_config.index_path.clone(),
+                    NonZeroUsize::new(archive_config.concurrency)
+                        .unwrap_or(NonZeroUsize::new(5).unwrap()),
+                )?
+                .with_events_store(Arc::new(store)),
+            );
+            let sync_archive = archive.clone();
+            let sync_interval = Duration::from_secs(archive_config.sync_interval_secs);
+            spawn_monitored_task!(async move {
+                loop {
+                    if let Err(err) = sync_archive.sync_index().await {
+                        warn!("Failed to sync the archive index: {:?}", err);
+                    }
+                    tokio::time::sleep(sync_interval).await;
+                }
+            });
+            rest_store = rest_store.with_archive(archive);
+        }
+        let mut rest_service =
+            iota_rest_api::RestService::new(Arc::new(rest_store), software_version);
 
         rest_service.with_metrics(RestMetrics::new(prometheus_registry));
 
@@ -116,6 +116,15 @@ pub struct Module {
     method_routing: BTreeMap<String, MethodRouting>,
 }
 
+impl Module {
+    /// Removes the method `name`, including its namespace, from the module,
+    /// for servers which do not serve all methods of an API.
+    pub fn remove_method(&mut self, name: &str) {
+        self.methods.retain(|method| method.name != name);
+        self.method_routing.remove(name);
+    }
+}
+
 pub struct RpcModuleDocBuilder {
     schema_generator: SchemaGenerator,
     methods: BTreeMap<String, Method>,
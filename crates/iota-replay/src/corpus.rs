@@ -0,0 +1,429 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! A replay corpus is a self-contained file holding a set of transactions
+//! together with every object and package they need to be executed. It can
+//! be replayed fully offline against the current execution layer, which makes
+//! it possible to check execution changes for regressions without network
+//! access.
+
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    path::Path,
+};
+
+use iota_config::node::ExpensiveSafetyCheckConfig;
+use iota_json_rpc_types::IotaTransactionBlockEffects;
+use iota_types::{
+    base_types::{ObjectID, SequenceNumber},
+    digests::TransactionDigest,
+    object::Object,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    replay::{ExecutionSandboxState, LocalExec},
+    types::{OnChainTransactionInfo, ReplayEngineError},
+};
+
+/// Transactions and all objects required to replay them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedReplayCorpus")]
+pub struct ReplayCorpus {
+    /// Every object required by any transaction of the corpus. Objects shared
+    /// between transactions, such as packages, are only stored once.
+    objects: Vec<Object>,
+    transactions: Vec<CorpusTransaction>,
+}
+
+/// A deserialized corpus whose transactions may refer to missing objects.
+#[derive(Deserialize)]
+struct UncheckedReplayCorpus {
+    objects: Vec<Object>,
+    transactions: Vec<CorpusTransaction>,
+}
+
+impl TryFrom<UncheckedReplayCorpus> for ReplayCorpus {
+    type Error = String;
+
+    fn try_from(corpus: UncheckedReplayCorpus) -> Result<Self, Self::Error> {
+        let corpus = Self {
+            objects: corpus.objects,
+            transactions: corpus.transactions,
+        };
+        let objects = corpus.object_index();
+        for transaction in &corpus.transactions {
+            if let Some((id, version)) = transaction
+                .required_objects
+                .iter()
+                .find(|key| !objects.contains_key(key))
+            {
+                return Err(format!(
+                    "transaction {} requires object {id} at version {version}, which is missing from the corpus",
+                    transaction.transaction_info.tx_digest
+                ));
+            }
+        }
+        Ok(corpus)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CorpusTransaction {
+    transaction_info: OnChainTransactionInfo,
+    /// The objects of [`ReplayCorpus::objects`] required to execute the
+    /// transaction.
+    required_objects: Vec<(ObjectID, SequenceNumber)>,
+}
+
+impl ReplayCorpus {
+    /// Adds a transaction executed by the replay tool to the corpus.
+    pub fn add(&mut self, sandbox_state: &ExecutionSandboxState) {
+        let mut objects = self.object_index();
+        let mut required_objects = Vec::with_capacity(sandbox_state.required_objects.len());
+        for object in &sandbox_state.required_objects {
+            let key = (object.id(), object.version());
+            if let Entry::Vacant(entry) = objects.entry(key) {
+                entry.insert(self.objects.len());
+                self.objects.push(object.clone());
+            }
+            required_objects.push(key);
+        }
+        self.transactions.push(CorpusTransaction {
+            transaction_info: sandbox_state.transaction_info.clone(),
+            required_objects,
+        });
+    }
+
+    /// The digests of all transactions in the corpus.
+    pub fn transaction_digests(&self) -> impl Iterator<Item = TransactionDigest> + '_ {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.transaction_info.tx_digest)
+    }
+
+    /// Splits the corpus into its transactions, each together with all
+    /// objects required to execute it.
+    pub fn into_transactions(self) -> Vec<(OnChainTransactionInfo, Vec<Object>)> {
+        let objects = self.object_index();
+        self.transactions
+            .into_iter()
+            .map(|transaction| {
+                let required_objects = self.required_objects(&objects, &transaction);
+                (transaction.transaction_info, required_objects)
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn read_from_file(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> anyhow::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Replays all transactions of the corpus without accessing the network
+    /// and compares the resulting effects with the on-chain effects.
+    pub async fn replay(&self) -> CorpusReport {
+        let objects = self.object_index();
+        let mut results = Vec::with_capacity(self.transactions.len());
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            let digest = transaction.transaction_info.tx_digest;
+            info!(
+                "[{}/{}] Replaying transaction {:?}...",
+                index + 1,
+                self.transactions.len(),
+                digest
+            );
+            let required_objects = self.required_objects(&objects, transaction);
+            let status = match LocalExec::certificate_execute_with_objects(
+                &transaction.transaction_info,
+                required_objects,
+            )
+            .await
+            {
+                Ok(sandbox_state)
+                    if sandbox_state.transaction_info.effects
+                        == sandbox_state.local_exec_effects =>
+                {
+                    ReplayStatus::Matched
+                }
+                Ok(sandbox_state) => {
+                    error!("Replay tool forked {}", digest);
+                    ReplayStatus::Forked {
+                        diff: sandbox_state.diff_effects(),
+                        on_chain: Box::new(sandbox_state.transaction_info.effects),
+                        local: Box::new(sandbox_state.local_exec_effects),
+                    }
+                }
+                Err(err) => {
+                    error!("Replaying transaction {:?} failed: {:?}", digest, err);
+                    ReplayStatus::Failed {
+                        error: err.to_string(),
+                    }
+                }
+            };
+            results.push(TransactionReport { digest, status });
+        }
+        CorpusReport::new(results)
+    }
+
+    fn object_index(&self) -> BTreeMap<(ObjectID, SequenceNumber), usize> {
+        self.objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| ((object.id(), object.version()), index))
+            .collect()
+    }
+
+    /// The objects required by `transaction`, all of which are part of the
+    /// corpus since deserialization checks them.
+    fn required_objects(
+        &self,
+        objects: &BTreeMap<(ObjectID, SequenceNumber), usize>,
+        transaction: &CorpusTransaction,
+    ) -> Vec<Object> {
+        transaction
+            .required_objects
+            .iter()
+            .filter_map(|key| objects.get(key).and_then(|index| self.objects.get(*index)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Replays the given transactions against the network at `rpc_url` and
+/// records them, together with all objects they required, into a corpus.
+///
+/// Transactions which cannot be replayed are skipped. Transactions whose
+/// local effects already differ from the on-chain effects are recorded, but
+/// a warning is logged.
+pub async fn record_corpus(
+    tx_digests: impl Iterator<Item = TransactionDigest>,
+    rpc_url: &str,
+    expensive_safety_check_config: ExpensiveSafetyCheckConfig,
+    use_authority: bool,
+) -> Result<ReplayCorpus, ReplayEngineError> {
+    let mut corpus = ReplayCorpus::default();
+    let mut executor = LocalExec::new_from_fn_url(rpc_url).await?;
+    for digest in tx_digests {
+        info!("Recording transaction {:?}...", digest);
+        executor = executor.reset_for_new_execution_with_client().await?;
+        let sandbox_state = match executor
+            .execute_transaction(
+                &digest,
+                expensive_safety_check_config.clone(),
+                use_authority,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+        {
+            Ok(sandbox_state) => sandbox_state,
+            Err(err) => {
+                error!("Unable to record transaction {:?}: {:?}", digest, err);
+                continue;
+            }
+        };
+        if sandbox_state.check_effects().is_err() {
+            warn!(
+                "Transaction {:?} already forks against the current execution layer",
+                digest
+            );
+        }
+        corpus.add(&sandbox_state);
+    }
+    Ok(corpus)
+}
+
+/// Machine-readable result of replaying a corpus.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorpusReport {
+    pub total: usize,
+    pub matched: usize,
+    pub forked: usize,
+    pub failed: usize,
+    pub transactions: Vec<TransactionReport>,
+}
+
+impl CorpusReport {
+    fn new(transactions: Vec<TransactionReport>) -> Self {
+        let count = |f: fn(&ReplayStatus) -> bool| {
+            transactions
+                .iter()
+                .filter(|report| f(&report.status))
+                .count()
+        };
+        Self {
+            total: transactions.len(),
+            matched: count(|status| matches!(status, ReplayStatus::Matched)),
+            forked: count(|status| matches!(status, ReplayStatus::Forked { .. })),
+            failed: count(|status| matches!(status, ReplayStatus::Failed { .. })),
+            transactions,
+        }
+    }
+
+    /// Whether all transactions were replayed with matching effects.
+    pub fn is_success(&self) -> bool {
+        self.matched == self.total
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionReport {
+    pub digest: TransactionDigest,
+    #[serde(flatten)]
+    pub status: ReplayStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReplayStatus {
+    /// The local effects are equal to the on-chain effects.
+    Matched,
+    /// The local effects differ from the on-chain effects.
+    Forked {
+        /// Line diff of the on-chain and the local effects.
+        diff: String,
+        on_chain: Box<IotaTransactionBlockEffects>,
+        local: Box<IotaTransactionBlockEffects>,
+    },
+    /// The transaction could not be executed.
+    Failed { error: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use iota_json_rpc_types::IotaTransactionBlockEffects;
+    use iota_protocol_config::{Chain, ProtocolVersion};
+    use iota_types::{
+        base_types::{IotaAddress, ObjectID},
+        effects::TransactionEffects,
+        object::Object,
+        transaction::{SenderSignedData, TransactionData, TransactionDataAPI},
+    };
+
+    use super::{CorpusTransaction, ReplayCorpus};
+    use crate::types::OnChainTransactionInfo;
+
+    fn transaction(gas: &Object, package: &Object) -> CorpusTransaction {
+        let sender = IotaAddress::ZERO;
+        let data = TransactionData::new_transfer_iota(
+            IotaAddress::ZERO,
+            sender,
+            None,
+            gas.compute_object_reference(),
+            1_000_000,
+            1_000,
+        );
+        let gas_ref = gas.compute_object_reference();
+        CorpusTransaction {
+            transaction_info: OnChainTransactionInfo {
+                tx_digest: data.digest(),
+                sender_signed_data: SenderSignedData::new(data.clone(), vec![]),
+                sender,
+                input_objects: data.input_objects().unwrap(),
+                kind: data.kind().clone(),
+                modified_at_versions: vec![],
+                shared_object_refs: vec![],
+                gas: vec![gas_ref],
+                gas_budget: data.gas_budget(),
+                gas_price: data.gas_price(),
+                executed_epoch: 0,
+                dependencies: vec![],
+                receiving_objs: vec![],
+                config_objects: vec![],
+                effects: IotaTransactionBlockEffects::try_from(TransactionEffects::default())
+                    .unwrap(),
+                protocol_version: ProtocolVersion::MAX,
+                epoch_start_timestamp: 0,
+                reference_gas_price: 1_000,
+                chain: Chain::Unknown,
+            },
+            required_objects: vec![(gas.id(), gas.version()), (package.id(), package.version())],
+        }
+    }
+
+    fn corpus() -> ReplayCorpus {
+        let package = Object::immutable_with_id_for_testing(ObjectID::random());
+        let gas = [
+            Object::with_id_owner_for_testing(ObjectID::random(), IotaAddress::ZERO),
+            Object::with_id_owner_for_testing(ObjectID::random(), IotaAddress::ZERO),
+        ];
+        ReplayCorpus {
+            transactions: gas.iter().map(|gas| transaction(gas, &package)).collect(),
+            objects: [package].into_iter().chain(gas).collect(),
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corpus.json");
+        let corpus = corpus();
+        corpus.write_to_file(&path).unwrap();
+
+        let read = ReplayCorpus::read_from_file(&path).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(
+            read.transaction_digests().collect::<Vec<_>>(),
+            corpus.transaction_digests().collect::<Vec<_>>()
+        );
+        for ((info, objects), expected) in read
+            .into_transactions()
+            .into_iter()
+            .zip(corpus.transactions)
+        {
+            assert_eq!(info.tx_digest, expected.transaction_info.tx_digest);
+            assert_eq!(
+                objects
+                    .iter()
+                    .map(|object| (object.id(), object.version()))
+                    .collect::<Vec<_>>(),
+                expected.required_objects
+            );
+        }
+    }
+
+    #[test]
+    fn missing_object_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corpus.json");
+        let mut corpus = corpus();
+        // Drop the package every transaction requires
+        corpus.objects.remove(0);
+        corpus.write_to_file(&path).unwrap();
+
+        let error = ReplayCorpus::read_from_file(&path).unwrap_err();
+        assert!(
+            error.to_string().contains("missing from the corpus"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn malformed_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("corpus.json");
+        std::fs::write(&path, r#"{"objects": [], "transactions": [{}]}"#).unwrap();
+        assert!(ReplayCorpus::read_from_file(&path).is_err());
+
+        std::fs::write(&path, "not a corpus").unwrap();
+        assert!(ReplayCorpus::read_from_file(&path).is_err());
+    }
+}
@@ -32,7 +32,7 @@ use crate::types::{EPOCH_CHANGE_STRUCT_TAG, ReplayEngineError};
 /// This trait defines the interfaces for fetching data from some local or
 /// remote store
 #[async_trait]
-pub(crate) trait DataFetcher {
+pub trait DataFetcher {
     #![allow(implied_bounds_entailment)]
     /// Fetch the specified versions of objects
     async fn multi_get_versioned(
@@ -0,0 +1,150 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{Display, Formatter};
+
+use tabled::{
+    builder::Builder as TableBuilder,
+    settings::{Style as TableStyle, style::HorizontalLine},
+};
+
+use crate::{
+    displays::Pretty,
+    gas_profile::{GasProfileDiff, GasProfileReport},
+};
+
+/// Maximum number of functions and instructions shown in the tables.
+const MAX_ROWS: usize = 30;
+
+impl<'a> Display for Pretty<'a, GasProfileReport> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(report) = self;
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec![format!("Gas Profile of {}", report.tx_digest)]);
+        builder.push_record(vec![format!("Gas Price: {}", report.gas_price)]);
+        builder.push_record(vec![format!(
+            "Computation Cost: {} (bucket of {} gas units)",
+            report.gas_cost_summary.computation_cost, report.computation_bucket
+        )]);
+        builder.push_record(vec![format!("Move VM Gas: {}", report.move_gas)]);
+        builder.push_record(vec![format!(
+            "Storage Cost: {}",
+            report.gas_cost_summary.storage_cost
+        )]);
+        builder.push_record(vec![format!(
+            "Storage Rebate: {}",
+            report.gas_cost_summary.storage_rebate
+        )]);
+        let mut table = builder.build();
+        table.with(TableStyle::rounded());
+        write!(f, "\n{}\n", table)?;
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec![
+            "Function",
+            "Calls",
+            "Inclusive Gas",
+            "Exclusive Gas",
+            "Instructions",
+        ]);
+        for function in report.functions.iter().take(MAX_ROWS) {
+            builder.push_record(vec![
+                function.function.clone(),
+                function.calls.to_string(),
+                function.inclusive_gas.to_string(),
+                function.exclusive_gas.to_string(),
+                function.instructions.to_string(),
+            ]);
+        }
+        write_with_header(f, builder)?;
+
+        if !report.instructions.is_empty() {
+            let mut builder = TableBuilder::default();
+            builder.push_record(vec!["Instruction", "Count", "Gas"]);
+            for instruction in report.instructions.iter().take(MAX_ROWS) {
+                builder.push_record(vec![
+                    instruction.instruction.clone(),
+                    instruction.count.to_string(),
+                    instruction.gas.to_string(),
+                ]);
+            }
+            write_with_header(f, builder)?;
+        }
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec!["Object ID", "Storage Cost", "Storage Rebate"]);
+        for object in &report.objects {
+            builder.push_record(vec![
+                object.object_id.to_string(),
+                object.storage_cost.to_string(),
+                object.storage_rebate.to_string(),
+            ]);
+        }
+        write_with_header(f, builder)
+    }
+}
+
+impl<'a> Display for Pretty<'a, GasProfileDiff> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Pretty(diff) = self;
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec![
+            String::new(),
+            diff.base_label.clone(),
+            diff.target_label.clone(),
+        ]);
+        for (name, base, target) in [
+            (
+                "Computation Cost",
+                diff.base.computation_cost,
+                diff.target.computation_cost,
+            ),
+            ("Move VM Gas", diff.base_move_gas, diff.target_move_gas),
+            (
+                "Storage Cost",
+                diff.base.storage_cost,
+                diff.target.storage_cost,
+            ),
+            (
+                "Storage Rebate",
+                diff.base.storage_rebate,
+                diff.target.storage_rebate,
+            ),
+        ] {
+            builder.push_record(vec![name.to_string(), base.to_string(), target.to_string()]);
+        }
+        write_with_header(f, builder)?;
+
+        let mut builder = TableBuilder::default();
+        builder.push_record(vec![
+            "Function".to_string(),
+            format!("Inclusive Gas ({})", diff.base_label),
+            format!("Inclusive Gas ({})", diff.target_label),
+            "Delta".to_string(),
+            format!("Exclusive Gas ({})", diff.base_label),
+            format!("Exclusive Gas ({})", diff.target_label),
+        ]);
+        for function in diff.functions.iter().take(MAX_ROWS) {
+            builder.push_record(vec![
+                function.function.clone(),
+                function.base_inclusive_gas.to_string(),
+                function.target_inclusive_gas.to_string(),
+                format!("{:+}", function.inclusive_delta()),
+                function.base_exclusive_gas.to_string(),
+                function.target_exclusive_gas.to_string(),
+            ]);
+        }
+        write_with_header(f, builder)
+    }
+}
+
+fn write_with_header(f: &mut Formatter<'_>, builder: TableBuilder) -> std::fmt::Result {
+    let mut table = builder.build();
+    table.with(TableStyle::rounded().horizontals([HorizontalLine::new(
+        1,
+        TableStyle::modern().get_horizontal(),
+    )]));
+    write!(f, "\n{}\n", table)
+}
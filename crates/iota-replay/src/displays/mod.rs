@@ -2,6 +2,7 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+mod gas_profile_displays;
 mod gas_status_displays;
 pub mod transaction_displays;
 
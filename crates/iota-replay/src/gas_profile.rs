@@ -0,0 +1,608 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Analysis of the gas profiles written by the Move VM profiler.
+//!
+//! The profiler writes an evented speedscope profile in which every Move
+//! function call, and optionally every bytecode instruction, opens and closes
+//! a frame at the amount of gas consumed so far. This module turns such a
+//! profile, together with the effects of the profiled transaction, into a
+//! per-function gas breakdown which can be exported to pprof or compared
+//! across protocol versions.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use flate2::{Compression, write::GzEncoder};
+use iota_json_rpc_types::IotaTransactionBlockEffectsAPI;
+use iota_types::{base_types::ObjectID, digests::TransactionDigest, gas::GasCostSummary};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::replay::ExecutionSandboxState;
+
+const OPEN_FRAME: &str = "O";
+const CLOSE_FRAME: &str = "C";
+const ROOT_FRAME: &str = "root";
+
+#[derive(Debug, Deserialize)]
+struct SpeedscopeFile {
+    shared: SpeedscopeShared,
+    profiles: Vec<SpeedscopeProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedscopeShared {
+    frames: Vec<SpeedscopeFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedscopeFrame {
+    /// The full name of the function or the instruction.
+    file: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedscopeProfile {
+    events: Vec<SpeedscopeEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeedscopeEvent {
+    #[serde(rename = "type")]
+    ty: String,
+    frame: usize,
+    at: u64,
+}
+
+/// Gas used by a single Move function over the whole transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionGas {
+    pub function: String,
+    pub calls: u64,
+    /// Gas used by the function including all functions it called.
+    pub inclusive_gas: u64,
+    /// Gas used by the function itself.
+    pub exclusive_gas: u64,
+    /// Number of bytecode instructions executed by the function itself. Only
+    /// known if the profile tracked instructions.
+    pub instructions: u64,
+}
+
+/// Gas used by all executions of a bytecode instruction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionGas {
+    pub instruction: String,
+    pub count: u64,
+    pub gas: u64,
+}
+
+/// Storage charged and refunded for an object written or deleted by the
+/// transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectStorage {
+    pub object_id: ObjectID,
+    /// Storage cost of the new version of the object, zero if it was deleted
+    /// or wrapped.
+    pub storage_cost: u64,
+    /// Storage rebate of the previous version of the object, zero if it was
+    /// created. This is the full rebate before the storage rebate rate is
+    /// applied.
+    pub storage_rebate: u64,
+}
+
+/// Per-function gas breakdown of a transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasProfileReport {
+    pub tx_digest: TransactionDigest,
+    pub gas_price: u64,
+    pub gas_cost_summary: GasCostSummary,
+    /// The computation bucket the transaction was charged for, in gas units.
+    pub computation_bucket: u64,
+    /// Gas metered by the Move VM, which is the computation cost before
+    /// bucketing and without the cost of reading objects.
+    pub move_gas: u64,
+    /// Functions sorted by decreasing inclusive gas.
+    pub functions: Vec<FunctionGas>,
+    /// Instructions sorted by decreasing gas.
+    pub instructions: Vec<InstructionGas>,
+    pub objects: Vec<ObjectStorage>,
+    /// Exclusive gas per call stack, outermost frame first.
+    #[serde(skip)]
+    stacks: BTreeMap<Vec<String>, u64>,
+}
+
+impl GasProfileReport {
+    /// Builds the report for the transaction executed in `sandbox_state`
+    /// from the profile the VM wrote to `profile_path`. If no profile was
+    /// written, e.g. for system transactions, only the storage and
+    /// computation summary is available.
+    pub fn new(
+        sandbox_state: &ExecutionSandboxState,
+        profile_path: Option<&Path>,
+    ) -> anyhow::Result<Self> {
+        let effects = &sandbox_state.local_exec_effects;
+        let gas_price = sandbox_state.transaction_info.gas_price;
+        let gas_cost_summary = effects.gas_cost_summary().clone();
+        let mut report = Self {
+            tx_digest: sandbox_state.transaction_info.tx_digest,
+            gas_price,
+            computation_bucket: gas_cost_summary.computation_cost / gas_price.max(1),
+            gas_cost_summary,
+            move_gas: 0,
+            functions: vec![],
+            instructions: vec![],
+            objects: object_storage(sandbox_state),
+            stacks: BTreeMap::new(),
+        };
+        if let Some(path) = profile_path {
+            let file = std::fs::File::open(path)?;
+            let profile: SpeedscopeFile = serde_json::from_reader(std::io::BufReader::new(file))?;
+            report.add_profile(&profile);
+        }
+        Ok(report)
+    }
+
+    fn add_profile(&mut self, profile: &SpeedscopeFile) {
+        struct OpenFrame {
+            frame: usize,
+            opened_at: u64,
+            children_gas: u64,
+        }
+
+        let frames = &profile.shared.frames;
+        let is_function = |frame: usize| {
+            let name = &frames[frame].file;
+            name == ROOT_FRAME || name.contains("::")
+        };
+        let mut functions: BTreeMap<usize, FunctionGas> = BTreeMap::new();
+        let mut instructions: BTreeMap<String, InstructionGas> = BTreeMap::new();
+        let mut stack: Vec<OpenFrame> = vec![];
+        let events = profile.profiles.iter().flat_map(|profile| &profile.events);
+        for event in events {
+            if event.frame >= frames.len() {
+                continue;
+            }
+            match event.ty.as_str() {
+                OPEN_FRAME => stack.push(OpenFrame {
+                    frame: event.frame,
+                    opened_at: event.at,
+                    children_gas: 0,
+                }),
+                CLOSE_FRAME => {
+                    // Frames left open by an abort are closed together with
+                    // their caller.
+                    let Some(position) = stack.iter().rposition(|open| open.frame == event.frame)
+                    else {
+                        continue;
+                    };
+                    stack.truncate(position + 1);
+                    let open = stack.pop().expect("position is in bounds");
+                    let gas = event.at.saturating_sub(open.opened_at);
+
+                    if !is_function(open.frame) {
+                        // Instruction gas is part of the exclusive gas of the
+                        // function executing it.
+                        let instruction = opcode(&frames[open.frame].file);
+                        let entry = instructions.entry(instruction.to_owned()).or_default();
+                        entry.count += 1;
+                        entry.gas += gas;
+                        if let Some(function) = stack.iter().rev().find(|f| is_function(f.frame)) {
+                            functions.entry(function.frame).or_default().instructions += 1;
+                        }
+                        continue;
+                    }
+
+                    let exclusive_gas = gas.saturating_sub(open.children_gas);
+                    let entry = functions.entry(open.frame).or_default();
+                    entry.calls += 1;
+                    entry.exclusive_gas += exclusive_gas;
+                    // Only count the outermost call of recursive functions.
+                    if !stack.iter().any(|f| f.frame == open.frame) {
+                        entry.inclusive_gas += gas;
+                    }
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children_gas += gas;
+                    }
+
+                    let call_stack = stack
+                        .iter()
+                        .map(|f| f.frame)
+                        .filter(|frame| is_function(*frame))
+                        .chain(std::iter::once(open.frame))
+                        .map(|frame| frames[frame].file.clone())
+                        .collect();
+                    *self.stacks.entry(call_stack).or_default() += exclusive_gas;
+                    if frames[open.frame].file == ROOT_FRAME {
+                        self.move_gas += gas;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.functions = functions
+            .into_iter()
+            .filter(|(frame, _)| frames[*frame].file != ROOT_FRAME)
+            .map(|(frame, function)| FunctionGas {
+                function: frames[frame].file.clone(),
+                ..function
+            })
+            .collect();
+        self.functions
+            .sort_by(|a, b| b.inclusive_gas.cmp(&a.inclusive_gas));
+        self.instructions = instructions
+            .into_iter()
+            .map(|(instruction, gas)| InstructionGas { instruction, ..gas })
+            .collect();
+        self.instructions.sort_by(|a, b| b.gas.cmp(&a.gas));
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Writes the call stacks of the profile as a gzipped pprof profile.
+    pub fn write_pprof(&self, path: &Path) -> anyhow::Result<()> {
+        let mut encoder = GzEncoder::new(std::fs::File::create(path)?, Compression::default());
+        encoder.write_all(&self.to_pprof().encode_to_vec())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    fn to_pprof(&self) -> pprof::Profile {
+        let mut strings = vec![String::new()];
+        let mut string_ids = BTreeMap::new();
+        let mut intern = |s: &str| -> i64 {
+            *string_ids.entry(s.to_owned()).or_insert_with(|| {
+                strings.push(s.to_owned());
+                strings.len() as i64 - 1
+            })
+        };
+
+        let sample_type = vec![pprof::ValueType {
+            r#type: intern("gas"),
+            unit: intern("units"),
+        }];
+        let mut functions = vec![];
+        let mut locations = vec![];
+        let mut location_ids: BTreeMap<&str, u64> = BTreeMap::new();
+        let mut samples = vec![];
+        for (stack, gas) in &self.stacks {
+            let mut location_id = Vec::with_capacity(stack.len());
+            // pprof expects the innermost frame first.
+            for name in stack.iter().rev() {
+                let id = *location_ids.entry(name).or_insert_with(|| {
+                    let id = locations.len() as u64 + 1;
+                    let name = intern(name);
+                    functions.push(pprof::Function {
+                        id,
+                        name,
+                        system_name: name,
+                        filename: 0,
+                    });
+                    locations.push(pprof::Location {
+                        id,
+                        line: vec![pprof::Line {
+                            function_id: id,
+                            line: 0,
+                        }],
+                    });
+                    id
+                });
+                location_id.push(id);
+            }
+            samples.push(pprof::Sample {
+                location_id,
+                value: vec![*gas as i64],
+            });
+        }
+
+        pprof::Profile {
+            sample_type,
+            sample: samples,
+            location: locations,
+            function: functions,
+            string_table: strings,
+        }
+    }
+}
+
+/// The instruction name without its operands, e.g. `LdU64` for `LdU64(5)`.
+fn opcode(instruction: &str) -> &str {
+    instruction.split(['(', ' ']).next().unwrap_or(instruction)
+}
+
+fn object_storage(sandbox_state: &ExecutionSandboxState) -> Vec<ObjectStorage> {
+    let Some(store) = &sandbox_state.local_exec_temporary_store else {
+        return vec![];
+    };
+    let previous_versions: BTreeMap<_, _> = sandbox_state
+        .local_exec_effects
+        .modified_at_versions()
+        .into_iter()
+        .collect();
+    let ids: BTreeSet<ObjectID> = store
+        .written
+        .keys()
+        .chain(previous_versions.keys())
+        .copied()
+        .collect();
+    ids.into_iter()
+        .map(|object_id| {
+            let storage_cost = store
+                .written
+                .get(&object_id)
+                .map_or(0, |object| object.storage_rebate);
+            let storage_rebate = previous_versions
+                .get(&object_id)
+                .and_then(|version| {
+                    sandbox_state
+                        .required_objects
+                        .iter()
+                        .find(|object| object.id() == object_id && object.version() == *version)
+                })
+                .map_or(0, |object| object.storage_rebate);
+            ObjectStorage {
+                object_id,
+                storage_cost,
+                storage_rebate,
+            }
+        })
+        .collect()
+}
+
+/// Finds the profile written by the VM for `tx_digest` when profiling to
+/// `profile_output`. The profiler appends the transaction digest and a
+/// timestamp to the file name, so the most recent matching file is returned.
+pub fn find_profile_file(profile_output: &Path, tx_digest: &TransactionDigest) -> Option<PathBuf> {
+    let directory = match profile_output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let stem = profile_output.file_stem()?.to_str()?;
+    let prefix = format!("{stem}_{tx_digest}_");
+    std::fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Gas of a function in the base and the target profile of a
+/// [`GasProfileDiff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionGasDiff {
+    pub function: String,
+    pub base_inclusive_gas: u64,
+    pub target_inclusive_gas: u64,
+    pub base_exclusive_gas: u64,
+    pub target_exclusive_gas: u64,
+}
+
+impl FunctionGasDiff {
+    pub fn inclusive_delta(&self) -> i128 {
+        self.target_inclusive_gas as i128 - self.base_inclusive_gas as i128
+    }
+}
+
+/// Comparison of the profiles of the same transaction executed with two
+/// different configurations, e.g. two protocol versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasProfileDiff {
+    pub base_label: String,
+    pub target_label: String,
+    pub base: GasCostSummary,
+    pub target: GasCostSummary,
+    pub base_move_gas: u64,
+    pub target_move_gas: u64,
+    /// Functions whose gas changed, sorted by decreasing absolute change of
+    /// their inclusive gas.
+    pub functions: Vec<FunctionGasDiff>,
+}
+
+impl GasProfileDiff {
+    pub fn new(
+        base_label: String,
+        base: &GasProfileReport,
+        target_label: String,
+        target: &GasProfileReport,
+    ) -> Self {
+        fn entry<'a, 'b>(
+            diffs: &'b mut BTreeMap<&'a str, FunctionGasDiff>,
+            function: &'a str,
+        ) -> &'b mut FunctionGasDiff {
+            diffs.entry(function).or_insert_with(|| FunctionGasDiff {
+                function: function.to_owned(),
+                base_inclusive_gas: 0,
+                target_inclusive_gas: 0,
+                base_exclusive_gas: 0,
+                target_exclusive_gas: 0,
+            })
+        }
+
+        let mut diffs: BTreeMap<&str, FunctionGasDiff> = BTreeMap::new();
+        for function in &base.functions {
+            let diff = entry(&mut diffs, &function.function);
+            diff.base_inclusive_gas = function.inclusive_gas;
+            diff.base_exclusive_gas = function.exclusive_gas;
+        }
+        for function in &target.functions {
+            let diff = entry(&mut diffs, &function.function);
+            diff.target_inclusive_gas = function.inclusive_gas;
+            diff.target_exclusive_gas = function.exclusive_gas;
+        }
+        let mut functions: Vec<_> = diffs
+            .into_values()
+            .filter(|diff| {
+                diff.base_inclusive_gas != diff.target_inclusive_gas
+                    || diff.base_exclusive_gas != diff.target_exclusive_gas
+            })
+            .collect();
+        functions.sort_by_key(|diff| std::cmp::Reverse(diff.inclusive_delta().abs()));
+
+        Self {
+            base_label,
+            target_label,
+            base: base.gas_cost_summary.clone(),
+            target: target.gas_cost_summary.clone(),
+            base_move_gas: base.move_gas,
+            target_move_gas: target.move_gas,
+            functions,
+        }
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// The subset of the pprof protobuf schema needed to describe gas profiles,
+/// see <https://github.com/google/pprof/blob/main/proto/profile.proto>.
+mod pprof {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Profile {
+        #[prost(message, repeated, tag = "1")]
+        pub sample_type: Vec<ValueType>,
+        #[prost(message, repeated, tag = "2")]
+        pub sample: Vec<Sample>,
+        #[prost(message, repeated, tag = "4")]
+        pub location: Vec<Location>,
+        #[prost(message, repeated, tag = "5")]
+        pub function: Vec<Function>,
+        #[prost(string, repeated, tag = "6")]
+        pub string_table: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ValueType {
+        #[prost(int64, tag = "1")]
+        pub r#type: i64,
+        #[prost(int64, tag = "2")]
+        pub unit: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sample {
+        #[prost(uint64, repeated, tag = "1")]
+        pub location_id: Vec<u64>,
+        #[prost(int64, repeated, tag = "2")]
+        pub value: Vec<i64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Location {
+        #[prost(uint64, tag = "1")]
+        pub id: u64,
+        #[prost(message, repeated, tag = "4")]
+        pub line: Vec<Line>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Line {
+        #[prost(uint64, tag = "1")]
+        pub function_id: u64,
+        #[prost(int64, tag = "2")]
+        pub line: i64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Function {
+        #[prost(uint64, tag = "1")]
+        pub id: u64,
+        #[prost(int64, tag = "2")]
+        pub name: i64,
+        #[prost(int64, tag = "3")]
+        pub system_name: i64,
+        #[prost(int64, tag = "4")]
+        pub filename: i64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report_from_profile(profile: serde_json::Value) -> GasProfileReport {
+        let mut report = GasProfileReport {
+            tx_digest: TransactionDigest::random(),
+            gas_price: 1000,
+            gas_cost_summary: GasCostSummary::default(),
+            computation_bucket: 0,
+            move_gas: 0,
+            functions: vec![],
+            instructions: vec![],
+            objects: vec![],
+            stacks: BTreeMap::new(),
+        };
+        report.add_profile(&serde_json::from_value(profile).unwrap());
+        report
+    }
+
+    #[test]
+    fn function_and_instruction_gas() {
+        let open = |frame, at| serde_json::json!({ "type": "O", "frame": frame, "at": at });
+        let close = |frame, at| serde_json::json!({ "type": "C", "frame": frame, "at": at });
+        let report = report_from_profile(serde_json::json!({
+            "shared": { "frames": [
+                { "name": "root", "file": "root" },
+                { "name": "main", "file": "0x1::m::main" },
+                { "name": "helper", "file": "0x1::m::helper" },
+                { "name": "LdU64(1)", "file": "LdU64(1)" },
+            ] },
+            "profiles": [{ "events": [
+                open(0, 0),
+                open(1, 0),
+                open(3, 0),
+                close(3, 2),
+                open(2, 2),
+                open(3, 2),
+                close(3, 5),
+                close(2, 10),
+                open(2, 10),
+                close(2, 14),
+                close(1, 20),
+                close(0, 25),
+            ] }],
+        }));
+
+        assert_eq!(report.move_gas, 25);
+        assert_eq!(report.functions, vec![
+            FunctionGas {
+                function: "0x1::m::main".to_owned(),
+                calls: 1,
+                inclusive_gas: 20,
+                exclusive_gas: 8,
+                instructions: 1,
+            },
+            FunctionGas {
+                function: "0x1::m::helper".to_owned(),
+                calls: 2,
+                inclusive_gas: 12,
+                exclusive_gas: 12,
+                instructions: 1,
+            },
+        ]);
+        assert_eq!(report.instructions, vec![InstructionGas {
+            instruction: "LdU64".to_owned(),
+            count: 2,
+            gas: 5,
+        }]);
+
+        let pprof = report.to_pprof();
+        assert_eq!(pprof.sample.len(), 3);
+        let total: i64 = pprof.sample.iter().flat_map(|sample| &sample.value).sum();
+        assert_eq!(total, 25);
+    }
+}
@@ -0,0 +1,490 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coverage-guided fuzzing of programmable transactions.
+//!
+//! The fuzzer runs fully offline on top of a [`ReplayCorpus`]: the
+//! transactions of the corpus are the initial inputs and the objects recorded
+//! with them form the sandbox every mutation is executed against. Mutations
+//! are produced by the mutators of [`crate::fuzz_mutations`]. A mutated
+//! transaction is kept as input for further mutations if it reaches Move
+//! bytecode or an [`ExecutionFailureStatus`] variant which was not reached
+//! before.
+//!
+//! Bytecode coverage is collected from the Move VM execution trace, which is
+//! only written by debug builds or if the `debugging` feature is enabled.
+//! Without it, the fuzzer is only guided by the reached failure statuses.
+//!
+//! Transactions are executed with all expensive checks enabled, so that
+//! violations of IOTA conservation are reported as invariant violations by
+//! the execution layer itself.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+};
+
+use futures::FutureExt;
+use iota_json_rpc_types::{IotaExecutionStatus, IotaTransactionBlockEffectsAPI};
+use iota_types::{
+    base_types::ObjectID, digests::TransactionDigest, execution_status::ExecutionFailureStatus,
+    object::Object, transaction::TransactionKind,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    corpus::ReplayCorpus,
+    fuzz::TransactionKindMutator,
+    replay::{ExecutionSandboxState, LocalExec},
+    types::OnChainTransactionInfo,
+};
+
+/// Environment variable read by the Move VM to decide whether and where to
+/// write the execution trace.
+const MOVE_VM_TRACING_ENV_VAR_NAME: &str = "MOVE_VM_TRACE";
+
+const CORPUS_DIR: &str = "corpus";
+const FINDINGS_DIR: &str = "findings";
+
+pub struct GuidedFuzzerConfig {
+    /// Number of mutated transactions to execute.
+    pub iterations: u64,
+    /// Maximum number of mutations applied on top of each other to produce a
+    /// new input.
+    pub max_stacked_mutations: u64,
+    pub mutator: Box<dyn TransactionKindMutator + Send + Sync>,
+    pub seed: u64,
+    /// Directory interesting inputs and findings are written to. Inputs found
+    /// by previous runs on the same directory are loaded on startup.
+    pub output_dir: PathBuf,
+    /// File the Move VM execution trace is written to.
+    pub trace_file: PathBuf,
+}
+
+pub struct GuidedFuzzer {
+    config: GuidedFuzzerConfig,
+    transactions: Vec<(OnChainTransactionInfo, Vec<Object>)>,
+    inputs: Vec<FuzzInput>,
+    coverage: Coverage,
+    trace: VmTrace,
+    rng: StdRng,
+}
+
+/// A transaction kind to execute in the sandbox of a corpus transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzInput {
+    /// The corpus transaction providing the sandbox.
+    pub base: TransactionDigest,
+    pub kind: TransactionKind,
+}
+
+/// Coverage reached by the fuzzer.
+#[derive(Debug, Default)]
+struct Coverage {
+    /// Executed bytecode instructions, as function name and code offset.
+    instructions: BTreeSet<(String, u16)>,
+    /// Number of executions per reached execution status.
+    statuses: BTreeMap<String, u64>,
+}
+
+impl Coverage {
+    /// Adds the coverage of an execution and returns whether it reached
+    /// anything new.
+    fn merge(&mut self, instructions: BTreeSet<(String, u16)>, status: String) -> bool {
+        let mut new = false;
+        for instruction in instructions {
+            new |= self.instructions.insert(instruction);
+        }
+        let count = self.statuses.entry(status).or_default();
+        new |= *count == 0;
+        *count += 1;
+        new
+    }
+}
+
+/// An invariant violated by a fuzzed transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InvariantViolation {
+    /// Execution panicked.
+    Panic { message: String },
+    /// Execution failed with an invariant violation, which includes the
+    /// IOTA conservation checks.
+    ExecutionInvariant { status: String },
+    /// More gas was charged than the budget allows.
+    GasBudgetExceeded { budget: u64, net_gas_usage: i64 },
+    /// A failed transaction created, unwrapped or wrapped objects.
+    FailedTransactionChangedObjects { objects: Vec<ObjectID> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub input: FuzzInput,
+    pub violation: InvariantViolation,
+}
+
+/// Summary of a fuzzing session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GuidedFuzzReport {
+    pub executions: u64,
+    pub corpus_size: usize,
+    pub covered_instructions: usize,
+    /// Number of executions per reached execution status.
+    pub statuses: BTreeMap<String, u64>,
+    pub findings: Vec<Finding>,
+}
+
+impl GuidedFuzzer {
+    /// Creates a fuzzer seeded with the programmable transactions of
+    /// `corpus`.
+    ///
+    /// Must be called before any transaction is executed in this process, as
+    /// the Move VM reads the tracing configuration only once.
+    pub fn new(corpus: ReplayCorpus, config: GuidedFuzzerConfig) -> anyhow::Result<Self> {
+        let transactions = corpus.into_transactions();
+        let mut inputs: Vec<_> = transactions
+            .iter()
+            .filter(|(info, _)| matches!(info.kind, TransactionKind::ProgrammableTransaction(_)))
+            .map(|(info, _)| FuzzInput {
+                base: info.tx_digest,
+                kind: info.kind.clone(),
+            })
+            .collect();
+        anyhow::ensure!(
+            !inputs.is_empty(),
+            "The corpus does not contain any programmable transaction"
+        );
+
+        fs::create_dir_all(config.output_dir.join(CORPUS_DIR))?;
+        fs::create_dir_all(config.output_dir.join(FINDINGS_DIR))?;
+        for input in read_inputs(&config.output_dir.join(CORPUS_DIR))? {
+            if transactions
+                .iter()
+                .any(|(info, _)| info.tx_digest == input.base)
+            {
+                inputs.push(input);
+            } else {
+                warn!(
+                    "Skipping saved input of transaction {:?}, which is not part of the corpus",
+                    input.base
+                );
+            }
+        }
+
+        let trace = VmTrace::enable(&config.trace_file)?;
+        Ok(Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            transactions,
+            inputs,
+            coverage: Coverage::default(),
+            trace,
+        })
+    }
+
+    pub async fn run(mut self) -> anyhow::Result<GuidedFuzzReport> {
+        let mut findings = vec![];
+        let mut executions = 0;
+
+        // Establish the coverage of the initial inputs
+        for input in self.inputs.clone() {
+            executions += 1;
+            let (_, finding) = self.execute(input).await?;
+            if let Some(finding) = finding {
+                error!(
+                    "Invariant violation of corpus transaction {:?}: {:?}",
+                    finding.input.base, finding.violation
+                );
+                self.save(FINDINGS_DIR, &finding)?;
+                findings.push(finding);
+            }
+        }
+        info!(
+            "Initial inputs cover {} instructions and {} statuses",
+            self.coverage.instructions.len(),
+            self.coverage.statuses.len()
+        );
+        if self.coverage.instructions.is_empty() {
+            warn!(
+                "No bytecode coverage was recorded. Coverage requires a debug build or the `debugging` feature"
+            );
+        }
+
+        for iteration in 0..self.config.iterations {
+            let Some(input) = self.next_input() else {
+                continue;
+            };
+            executions += 1;
+            let (new, finding) = self.execute(input.clone()).await?;
+            if let Some(finding) = finding {
+                error!(
+                    "Invariant violation on top of transaction {:?}: {:?}",
+                    input.base, finding.violation
+                );
+                self.save(FINDINGS_DIR, &finding)?;
+                findings.push(finding);
+            }
+            if new {
+                info!(
+                    "[{}/{}] New coverage: {} instructions, {} statuses",
+                    iteration + 1,
+                    self.config.iterations,
+                    self.coverage.instructions.len(),
+                    self.coverage.statuses.len()
+                );
+                self.save(CORPUS_DIR, &input)?;
+                self.inputs.push(input);
+            }
+        }
+
+        Ok(GuidedFuzzReport {
+            executions,
+            corpus_size: self.inputs.len(),
+            covered_instructions: self.coverage.instructions.len(),
+            statuses: self.coverage.statuses,
+            findings,
+        })
+    }
+
+    /// Mutates a random input. Returns `None` if no mutation could be applied.
+    fn next_input(&mut self) -> Option<FuzzInput> {
+        let parent = self.inputs.choose(&mut self.rng)?.clone();
+        let num_mutations = self
+            .rng
+            .gen_range(1..=self.config.max_stacked_mutations.max(1));
+        self.config.mutator.reset(num_mutations);
+        let mut kind = parent.kind.clone();
+        for _ in 0..num_mutations {
+            if let Some(mutated) = self.config.mutator.mutate(&kind) {
+                kind = mutated;
+            }
+        }
+        (kind != parent.kind).then_some(FuzzInput {
+            base: parent.base,
+            kind,
+        })
+    }
+
+    /// Executes `input`, merges its coverage and checks its invariants.
+    /// Returns whether new coverage was reached.
+    async fn execute(&mut self, input: FuzzInput) -> anyhow::Result<(bool, Option<Finding>)> {
+        let (transaction_info, required_objects) = self
+            .transactions
+            .iter()
+            .find(|(info, _)| info.tx_digest == input.base)
+            .expect("inputs are only created for corpus transactions");
+
+        let result = AssertUnwindSafe(LocalExec::certificate_execute_with_objects_and_kind(
+            transaction_info,
+            required_objects.clone(),
+            Some(input.kind.clone()),
+        ))
+        .catch_unwind()
+        .await;
+        let instructions = self.trace.read_new()?;
+
+        let (status, violation) = match result {
+            Ok(Ok(sandbox_state)) => (
+                execution_status(&sandbox_state),
+                check_invariants(&sandbox_state),
+            ),
+            // The transaction was rejected before execution, e.g. because the
+            // mutation made it invalid
+            Ok(Err(err)) => (format!("Rejected: {}", variant_name(&err)), None),
+            Err(panic) => {
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                (
+                    "Panic".to_string(),
+                    Some(InvariantViolation::Panic { message }),
+                )
+            }
+        };
+
+        let new = self.coverage.merge(instructions, status);
+        Ok((new, violation.map(|violation| Finding { input, violation })))
+    }
+
+    /// Writes `value` to the next free file of `dir`, so that files of
+    /// previous runs are kept.
+    fn save<T: Serialize>(&self, dir: &str, value: &T) -> anyhow::Result<()> {
+        let dir = self.config.output_dir.join(dir);
+        let index = fs::read_dir(&dir)?.count();
+        let path = dir.join(format!("{index:06}.json"));
+        serde_json::to_writer_pretty(File::create(path)?, value)?;
+        Ok(())
+    }
+}
+
+impl GuidedFuzzReport {
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(File::create(path)?, self)?;
+        Ok(())
+    }
+}
+
+fn execution_status(sandbox_state: &ExecutionSandboxState) -> String {
+    match &sandbox_state.local_exec_status {
+        Some(Err(err)) => variant_name(&err.to_execution_status().0),
+        _ => "Success".to_string(),
+    }
+}
+
+fn check_invariants(sandbox_state: &ExecutionSandboxState) -> Option<InvariantViolation> {
+    if let Some(Err(err)) = &sandbox_state.local_exec_status {
+        let status = err.to_execution_status().0;
+        if matches!(
+            status,
+            ExecutionFailureStatus::InvariantViolation
+                | ExecutionFailureStatus::VMInvariantViolation
+        ) {
+            return Some(InvariantViolation::ExecutionInvariant {
+                status: format!("{status:?}"),
+            });
+        }
+    }
+
+    let effects = &sandbox_state.local_exec_effects;
+    let budget = sandbox_state.transaction_info.gas_budget;
+    let net_gas_usage = effects.gas_cost_summary().net_gas_usage();
+    if net_gas_usage > 0 && net_gas_usage as u64 > budget {
+        return Some(InvariantViolation::GasBudgetExceeded {
+            budget,
+            net_gas_usage,
+        });
+    }
+
+    if let IotaExecutionStatus::Failure { .. } = effects.status() {
+        let objects: Vec<_> = effects
+            .created()
+            .iter()
+            .chain(effects.unwrapped())
+            .map(|object| object.object_id())
+            .chain(effects.wrapped().iter().map(|object| object.object_id))
+            .collect();
+        if !objects.is_empty() {
+            return Some(InvariantViolation::FailedTransactionChangedObjects { objects });
+        }
+    }
+    None
+}
+
+/// The name of the enum variant `value` is, based on its `Debug`
+/// representation.
+fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    let debug = format!("{value:?}");
+    debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn read_inputs(dir: &Path) -> anyhow::Result<Vec<FuzzInput>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    paths
+        .iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .map(|path| Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?))
+        .collect()
+}
+
+/// Incremental reader of the Move VM execution trace.
+struct VmTrace {
+    file: File,
+    offset: u64,
+}
+
+impl VmTrace {
+    fn enable(path: &Path) -> anyhow::Result<Self> {
+        // The VM appends to the trace file, so start from an empty one
+        File::create(path)?;
+        std::env::set_var(MOVE_VM_TRACING_ENV_VAR_NAME, path);
+        Ok(Self {
+            file: File::open(path)?,
+            offset: 0,
+        })
+    }
+
+    /// Returns the instructions traced since the last call.
+    fn read_new(&mut self) -> anyhow::Result<BTreeSet<(String, u16)>> {
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(&self.file);
+        let mut instructions = BTreeSet::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            // Only consume complete lines
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            self.offset += read as u64;
+            instructions.extend(parse_trace_line(&line));
+        }
+        Ok(instructions)
+    }
+}
+
+/// Parses a trace line of the form
+/// `<process>-<thread>,<function>,<pc>,<instr>`.
+fn parse_trace_line(line: &str) -> Option<(String, u16)> {
+    let mut fields = line.splitn(4, ',');
+    let _thread = fields.next()?;
+    let function = fields.next()?;
+    let pc = fields.next()?.parse().ok()?;
+    Some((function.to_string(), pc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_line_parsing() {
+        assert_eq!(
+            parse_trace_line("42-ThreadId(1),0x2::coin::value,3,VecPack(SignatureIndex(1), 2)\n"),
+            Some(("0x2::coin::value".to_string(), 3))
+        );
+        assert_eq!(parse_trace_line("garbage\n"), None);
+    }
+
+    #[test]
+    fn coverage_merge() {
+        let mut coverage = Coverage::default();
+        let instructions = BTreeSet::from([("0x2::coin::value".to_string(), 0)]);
+        assert!(coverage.merge(instructions.clone(), "Success".to_string()));
+        assert!(!coverage.merge(instructions.clone(), "Success".to_string()));
+        assert!(coverage.merge(instructions, "InsufficientGas".to_string()));
+        assert_eq!(coverage.statuses["Success"], 2);
+    }
+
+    #[test]
+    fn variant_names() {
+        assert_eq!(
+            variant_name(&ExecutionFailureStatus::InsufficientGas),
+            "InsufficientGas"
+        );
+        assert_eq!(
+            variant_name(&ExecutionFailureStatus::MoveObjectTooBig {
+                object_size: 1,
+                max_object_size: 0
+            }),
+            "MoveObjectTooBig"
+        );
+    }
+}
@@ -2,7 +2,13 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{cmp::max, env, io::BufRead, path::PathBuf, str::FromStr};
+use std::{
+    cmp::max,
+    env,
+    io::BufRead,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use async_recursion::async_recursion;
 use clap::Parser;
@@ -16,27 +22,35 @@ use iota_types::{
     digests::{TransactionDigest, get_mainnet_chain_identifier, get_testnet_chain_identifier},
     message_envelope::Message,
 };
-use move_vm_config::runtime::get_default_output_filepath;
+use move_vm_config::runtime::{VMProfilerConfig, get_default_output_filepath};
 use tracing::{error, info, warn};
 use transaction_provider::{FuzzStartPoint, TransactionSource};
 
 use crate::{
     config::get_rpc_url,
+    displays::Pretty,
+    gas_profile::{GasProfileDiff, GasProfileReport},
+    guided_fuzz::{GuidedFuzzer, GuidedFuzzerConfig},
     replay::{ExecutionSandboxState, LocalExec, ProtocolVersionSummary},
 };
 
 pub mod batch_replay;
 pub mod config;
+pub mod corpus;
 mod data_fetcher;
 mod displays;
 pub mod fuzz;
 pub mod fuzz_mutations;
+pub mod gas_profile;
+pub mod guided_fuzz;
 mod replay;
 #[cfg(test)]
 mod tests;
 pub mod transaction_provider;
 pub mod types;
 
+pub use data_fetcher::{DataFetcher, RemoteFetcher};
+
 static DEFAULT_SANDBOX_BASE_PATH: &str =
     concat!(env!("CARGO_MANIFEST_DIR"), "/tests/sandbox_snapshots");
 
@@ -88,6 +102,44 @@ pub enum ReplayToolCommand {
         /// regulated coin types and that has been denied.
         #[arg(long, num_args = 2..)]
         config_objects: Option<Vec<String>>,
+        /// Record every executed bytecode instruction in the profile, which
+        /// adds per-instruction gas and instruction counts to the breakdown.
+        #[arg(long)]
+        track_instructions: bool,
+        /// Optional output filepath for the call stacks of the profile as a
+        /// gzipped pprof profile.
+        #[arg(long)]
+        pprof_output: Option<PathBuf>,
+        /// Optional output filepath for the per-function gas breakdown as JSON.
+        #[arg(long)]
+        report_output: Option<PathBuf>,
+    },
+
+    /// Profile a transaction with two protocol versions and compare the gas
+    /// used per function
+    #[command(name = "rpd")]
+    ProfileDiff {
+        #[arg(long, short)]
+        tx_digest: String,
+        /// Protocol version of the base profile, -1 for the latest version.
+        #[arg(long, allow_hyphen_values = true)]
+        base_protocol_version: i64,
+        /// Protocol version of the profile compared to the base, -1 for the
+        /// latest version.
+        #[arg(long, allow_hyphen_values = true)]
+        target_protocol_version: i64,
+        /// Optional version of the executor to use for both profiles, if not
+        /// specified defaults to the one originally used for the transaction.
+        #[arg(long, short, allow_hyphen_values = true)]
+        executor_version: Option<i64>,
+        #[arg(long, num_args = 2..)]
+        config_objects: Option<Vec<String>>,
+        /// Record every executed bytecode instruction in the profiles.
+        #[arg(long)]
+        track_instructions: bool,
+        /// Optional output filepath for the comparison as JSON.
+        #[arg(long)]
+        report_output: Option<PathBuf>,
     },
 
     /// Replay transaction
@@ -134,6 +186,30 @@ pub enum ReplayToolCommand {
         persist_path: Option<PathBuf>,
     },
 
+    /// Record the transactions listed in a file, together with all objects
+    /// they require, into a corpus which can be replayed offline
+    #[command(name = "cr")]
+    RecordCorpus {
+        #[arg(long, short)]
+        path: PathBuf,
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+
+    /// Replay a corpus recorded with `cr` without accessing the network
+    #[command(name = "crp")]
+    ReplayCorpus {
+        #[arg(long, short)]
+        path: PathBuf,
+        #[arg(
+            long,
+            short,
+            help = "If provided, write a JSON report of the replay, including the diffs of all \
+            forked effects, to this file."
+        )]
+        report: Option<PathBuf>,
+    },
+
     /// Replay a transaction from a node state dump
     #[command(name = "rd")]
     ReplayDump {
@@ -196,6 +272,33 @@ pub enum ReplayToolCommand {
         num_base_transactions: u64,
     },
 
+    /// Run a coverage-guided fuzzer on top of a corpus recorded with `cr`,
+    /// without accessing the network
+    #[command(name = "gfz")]
+    GuidedFuzz {
+        #[arg(long, short)]
+        path: PathBuf,
+        #[arg(long, short, default_value = "10000")]
+        iterations: u64,
+        #[arg(long, short = 'm', default_value = "4")]
+        max_stacked_mutations: u64,
+        #[arg(long, short, default_value = "0")]
+        seed: u64,
+        #[arg(
+            long,
+            short,
+            help = "Directory to write interesting inputs and invariant violations to. Inputs \
+            found by previous runs on the same directory are reused."
+        )]
+        output_dir: PathBuf,
+        #[arg(
+            long,
+            short,
+            help = "If provided, write a JSON report of the session to this file."
+        )]
+        report: Option<PathBuf>,
+    },
+
     #[command(name = "report")]
     Report,
 }
@@ -273,6 +376,39 @@ pub async fn execute_replay_command(
             fuzzer.run(num_base_transactions).await.unwrap();
             None
         }
+        ReplayToolCommand::GuidedFuzz {
+            path,
+            iterations,
+            max_stacked_mutations,
+            seed,
+            output_dir,
+            report,
+        } => {
+            let corpus = corpus::ReplayCorpus::read_from_file(&path)?;
+            let config = GuidedFuzzerConfig {
+                iterations,
+                max_stacked_mutations,
+                mutator: Box::new(base_fuzzers(max_stacked_mutations)),
+                seed,
+                trace_file: output_dir.join("move_vm_trace.trace"),
+                output_dir,
+            };
+            let fuzz_report = GuidedFuzzer::new(corpus, config)?.run().await?;
+            if let Some(report) = report {
+                fuzz_report.write_json(&report)?;
+            }
+            info!(
+                "Executed {} transactions: {} inputs in corpus, {} instructions covered, {} statuses reached",
+                fuzz_report.executions,
+                fuzz_report.corpus_size,
+                fuzz_report.covered_instructions,
+                fuzz_report.statuses.len()
+            );
+            if !fuzz_report.findings.is_empty() {
+                anyhow::bail!("Found {} invariant violations", fuzz_report.findings.len());
+            }
+            None
+        }
         ReplayToolCommand::ReplayDump { path, show_effects } => {
             let mut lx = LocalExec::new_for_state_dump(&path, rpc_url).await?;
             let (sandbox_state, node_dump_state) = lx.execute_state_dump(safety).await?;
@@ -322,6 +458,42 @@ pub async fn execute_replay_command(
             // TODO: clean this up
             Some((0u64, 0u64))
         }
+        ReplayToolCommand::RecordCorpus { path, output } => {
+            let digests = read_tx_digests(&path)?;
+            let corpus = corpus::record_corpus(
+                digests.into_iter(),
+                &get_rpc_url(rpc_url, cfg_path, chain)?,
+                safety,
+                use_authority,
+            )
+            .await?;
+            corpus.write_to_file(&output)?;
+            info!(
+                "Recorded {} transactions to {}",
+                corpus.len(),
+                output.display()
+            );
+            None
+        }
+        ReplayToolCommand::ReplayCorpus { path, report } => {
+            let corpus = corpus::ReplayCorpus::read_from_file(&path)?;
+            info!("Replaying {} transactions", corpus.len());
+            let corpus_report = corpus.replay().await;
+            if let Some(report) = report {
+                std::fs::write(&report, serde_json::to_string_pretty(&corpus_report)?)?;
+            }
+            info!(
+                "Replayed {} transactions: {} matched, {} forked, {} failed",
+                corpus_report.total,
+                corpus_report.matched,
+                corpus_report.forked,
+                corpus_report.failed
+            );
+            if !corpus_report.is_success() {
+                anyhow::bail!("Corpus replay did not match the on-chain effects");
+            }
+            Some((corpus_report.matched as u64, corpus_report.total as u64))
+        }
         ReplayToolCommand::BatchReplayFromSandbox { path, num_tasks } => {
             let files: Vec<_> = std::fs::read_dir(path)?
                 .filter_map(|entry| {
@@ -359,12 +531,15 @@ pub async fn execute_replay_command(
             protocol_version,
             profile_output,
             config_objects,
+            track_instructions,
+            pprof_output,
+            report_output,
         } => {
-            let output_path = profile_output.or(Some(get_default_output_filepath()));
+            let output_path = profile_output.unwrap_or_else(get_default_output_filepath);
 
             let tx_digest = TransactionDigest::from_str(&tx_digest)?;
             info!("Executing tx: {}", tx_digest);
-            let _sandbox_state = LocalExec::replay_with_network_config(
+            let report = profile_transaction(
                 get_rpc_url(rpc_url, cfg_path, chain)?,
                 tx_digest,
                 safety,
@@ -372,14 +547,76 @@ pub async fn execute_replay_command(
                 executor_version,
                 protocol_version,
                 output_path,
+                track_instructions,
                 parse_configs_versions(config_objects),
             )
             .await?;
+            println!("{}", Pretty(&report));
+            if let Some(path) = pprof_output {
+                report.write_pprof(&path)?;
+                info!("pprof profile written to file: {}", path.display());
+            }
+            if let Some(path) = report_output {
+                report.write_json(&path)?;
+                info!("Gas breakdown written to file: {}", path.display());
+            }
 
             println!("Execution finished successfully.");
             Some((1u64, 1u64))
         }
 
+        ReplayToolCommand::ProfileDiff {
+            tx_digest,
+            base_protocol_version,
+            target_protocol_version,
+            executor_version,
+            config_objects,
+            track_instructions,
+            report_output,
+        } => {
+            let rpc_url = get_rpc_url(rpc_url, cfg_path, chain)?;
+            let tx_digest = TransactionDigest::from_str(&tx_digest)?;
+            let config_objects = parse_configs_versions(config_objects);
+            let profile_dir = tempfile::tempdir()?;
+
+            let mut reports = vec![];
+            for (name, protocol_version) in [
+                ("base", base_protocol_version),
+                ("target", target_protocol_version),
+            ] {
+                info!(
+                    "Executing tx {} with protocol version {}",
+                    tx_digest, protocol_version
+                );
+                reports.push(
+                    profile_transaction(
+                        rpc_url.clone(),
+                        tx_digest,
+                        safety.clone(),
+                        use_authority,
+                        executor_version,
+                        Some(protocol_version),
+                        profile_dir.path().join(format!("{name}.json")),
+                        track_instructions,
+                        config_objects.clone(),
+                    )
+                    .await?,
+                );
+            }
+            let diff = GasProfileDiff::new(
+                format!("protocol version {base_protocol_version}"),
+                &reports[0],
+                format!("protocol version {target_protocol_version}"),
+                &reports[1],
+            );
+            println!("{}", Pretty(&diff));
+            if let Some(path) = report_output {
+                diff.write_json(&path)?;
+                info!("Gas comparison written to file: {}", path.display());
+            }
+            Some((2u64, 2u64))
+        }
+
         ReplayToolCommand::ReplayTransaction {
             tx_digest,
             show_effects,
@@ -590,6 +827,60 @@ pub(crate) fn chain_from_chain_id(chain: &str) -> Chain {
     }
 }
 
+/// Replays `tx_digest` with the gas profiler writing to `profile_output` and
+/// builds the per-function gas breakdown from the written profile. If
+/// `track_instructions` is set, the profile also records every executed
+/// bytecode instruction.
+#[allow(clippy::too_many_arguments)]
+async fn profile_transaction(
+    rpc_url: String,
+    tx_digest: TransactionDigest,
+    safety: ExpensiveSafetyCheckConfig,
+    use_authority: bool,
+    executor_version: Option<i64>,
+    protocol_version: Option<i64>,
+    profile_output: PathBuf,
+    track_instructions: bool,
+    config_objects: Option<Vec<(ObjectID, SequenceNumber)>>,
+) -> anyhow::Result<GasProfileReport> {
+    let profiler_config = VMProfilerConfig {
+        full_path: profile_output.clone(),
+        track_bytecode_instructions: track_instructions,
+        use_long_function_name: false,
+    };
+    let sandbox_state = LocalExec::replay_with_network_config(
+        rpc_url,
+        tx_digest,
+        safety,
+        use_authority,
+        executor_version,
+        protocol_version,
+        Some(profiler_config),
+        config_objects,
+    )
+    .await?;
+    let profile = gas_profile::find_profile_file(&profile_output, &tx_digest);
+    if profile.is_none() {
+        warn!(
+            "No gas profile was written for {}, the gas breakdown per function is not available",
+            tx_digest
+        );
+    }
+    GasProfileReport::new(&sandbox_state, profile.as_deref())
+}
+
+fn read_tx_digests(path: &Path) -> anyhow::Result<Vec<TransactionDigest>> {
+    let file = std::fs::File::open(path)?;
+    std::io::BufReader::new(file)
+        .lines()
+        .map(|line| {
+            let line = line?;
+            TransactionDigest::from_str(line.trim())
+                .map_err(|err| anyhow::anyhow!("Error parsing tx digest {line:?}: {err}"))
+        })
+        .collect()
+}
+
 fn parse_configs_versions(
     configs_and_versions: Option<Vec<String>>,
 ) -> Option<Vec<(ObjectID, SequenceNumber)>> {
@@ -48,6 +48,7 @@ use move_core_types::{
     language_storage::{ModuleId, StructTag},
     resolver::{ModuleResolver, ResourceResolver},
 };
+use move_vm_config::runtime::VMProfilerConfig;
 use prometheus::Registry;
 use serde::{Deserialize, Serialize};
 use similar::{ChangeTag, TextDiff};
@@ -242,9 +243,9 @@ pub struct LocalExec {
     // -1 implies use latest version
     // None implies use the protocol version at the time of execution
     pub protocol_version: Option<i64>,
-    // Whether or not to enable the gas profiler, the PathBuf contains either a user specified
+    // Whether or not to enable the gas profiler, the config contains either a user specified
     // filepath or the default current directory and name format for the profile output
-    pub enable_profiler: Option<PathBuf>,
+    pub enable_profiler: Option<VMProfilerConfig>,
     pub config_and_versions: Option<Vec<(ObjectID, SequenceNumber)>>,
     // Retry policies due to RPC errors
     pub num_retries_for_timeout: u32,
@@ -328,7 +329,7 @@ impl LocalExec {
         use_authority: bool,
         executor_version: Option<i64>,
         protocol_version: Option<i64>,
-        enable_profiler: Option<PathBuf>,
+        enable_profiler: Option<VMProfilerConfig>,
         config_and_versions: Option<Vec<(ObjectID, SequenceNumber)>>,
     ) -> Result<ExecutionSandboxState, ReplayEngineError> {
         info!("Using RPC URL: {}", rpc_url);
@@ -906,19 +907,43 @@ impl LocalExec {
     pub async fn certificate_execute_with_sandbox_state(
         pre_run_sandbox: &ExecutionSandboxState,
     ) -> Result<ExecutionSandboxState, ReplayEngineError> {
-        // These cannot be changed and are inherited from the sandbox state
-        let executed_epoch = pre_run_sandbox.transaction_info.executed_epoch;
-        let reference_gas_price = pre_run_sandbox.transaction_info.reference_gas_price;
-        let epoch_start_timestamp = pre_run_sandbox.transaction_info.epoch_start_timestamp;
+        Self::certificate_execute_with_objects(
+            &pre_run_sandbox.transaction_info,
+            pre_run_sandbox.required_objects.clone(),
+        )
+        .await
+    }
+
+    /// Executes the transaction described by `transaction_info` on top of
+    /// `required_objects` only, without accessing the network.
+    pub async fn certificate_execute_with_objects(
+        transaction_info: &OnChainTransactionInfo,
+        required_objects: Vec<Object>,
+    ) -> Result<ExecutionSandboxState, ReplayEngineError> {
+        Self::certificate_execute_with_objects_and_kind(transaction_info, required_objects, None)
+            .await
+    }
+
+    /// Like [`Self::certificate_execute_with_objects`], but executes
+    /// `override_transaction_kind` instead of the original transaction kind if
+    /// given. The overriding kind must only use the inputs of the original
+    /// transaction.
+    pub async fn certificate_execute_with_objects_and_kind(
+        transaction_info: &OnChainTransactionInfo,
+        required_objects: Vec<Object>,
+        override_transaction_kind: Option<TransactionKind>,
+    ) -> Result<ExecutionSandboxState, ReplayEngineError> {
+        // These cannot be changed and are inherited from the transaction info
+        let executed_epoch = transaction_info.executed_epoch;
+        let reference_gas_price = transaction_info.reference_gas_price;
+        let epoch_start_timestamp = transaction_info.epoch_start_timestamp;
         let protocol_config = ProtocolConfig::get_for_version(
-            pre_run_sandbox.transaction_info.protocol_version,
-            pre_run_sandbox.transaction_info.chain,
+            transaction_info.protocol_version,
+            transaction_info.chain,
         );
-        let required_objects = pre_run_sandbox.required_objects.clone();
         let store = InMemoryStorage::new(required_objects.clone());
 
-        let transaction =
-            Transaction::new(pre_run_sandbox.transaction_info.sender_signed_data.clone());
+        let transaction = Transaction::new(transaction_info.sender_signed_data.clone());
 
         // TODO: This will not work for deleted shared objects. We need to persist that
         // information in the sandbox. TODO: A lot of the following code is
@@ -935,9 +960,9 @@ impl LocalExec {
             input_objects,
             &protocol_config,
             reference_gas_price,
-        )
-        .unwrap();
+        )?;
         let (kind, signer, gas) = executable.transaction_data().execution_parts();
+        let kind = override_transaction_kind.unwrap_or(kind);
         let executor = iota_execution::executor(&protocol_config, true, None).unwrap();
         let (_, _, effects, exec_res) = executor.execute_transaction_to_effects(
             &store,
@@ -959,7 +984,7 @@ impl LocalExec {
             IotaTransactionBlockEffects::try_from(effects).map_err(ReplayEngineError::from)?;
 
         Ok(ExecutionSandboxState {
-            transaction_info: pre_run_sandbox.transaction_info.clone(),
+            transaction_info: transaction_info.clone(),
             required_objects,
             local_exec_temporary_store: None, // We dont capture it for cert exec run
             local_exec_effects: effects,
@@ -1022,7 +1047,7 @@ impl LocalExec {
         use_authority: bool,
         executor_version: Option<i64>,
         protocol_version: Option<i64>,
-        enable_profiler: Option<PathBuf>,
+        enable_profiler: Option<VMProfilerConfig>,
         config_and_versions: Option<Vec<(ObjectID, SequenceNumber)>>,
     ) -> Result<ExecutionSandboxState, ReplayEngineError> {
         self.executor_version = executor_version;
@@ -2159,7 +2184,7 @@ pub fn get_executor(
     executor_version_override: Option<i64>,
     protocol_config: &ProtocolConfig,
     _expensive_safety_check_config: ExpensiveSafetyCheckConfig,
-    enable_profiler: Option<PathBuf>,
+    enable_profiler: Option<VMProfilerConfig>,
 ) -> Arc<dyn Executor + Send + Sync> {
     let protocol_config = executor_version_override
         .map(|q| {
@@ -37,7 +37,7 @@ use iota_storage::{
 };
 use iota_types::{
     accumulator::Accumulator,
-    base_types::ObjectID,
+    base_types::{ObjectID, ObjectRef},
     iota_system_state::{
         IotaSystemStateTrait, epoch_start_iota_system_state::EpochStartSystemStateTrait,
         get_iota_system_state,
@@ -83,6 +83,22 @@ use tokio::time::Instant;
 ///       - 1_1.obj
 ///       - ...
 ///
+/// A snapshot can also be written as a delta on top of the snapshot of an
+/// earlier base epoch, which can itself be a delta. A delta only holds the
+/// objects which were created or mutated since the base epoch in its *.obj
+/// files, and the references of the base objects which are no longer live,
+/// because they were mutated, wrapped or deleted, in *.rem files. The REMOVED
+/// files use the REFERENCE file format. The MANIFEST of a delta names its base
+/// epoch and the root state hash of the live object set at its own epoch,
+/// which is checked after replaying the chain of references from the full
+/// snapshot at its root.
+///  - snapshot/
+///     - epoch_2/
+///        - 1_1.obj
+///        - 1_1.ref
+///        - 1_1.rem
+///        - MANIFEST
+///
 /// Object File Disk Format
 /// ┌──────────────────────────────┐
 /// │  magic(0x00B7EC75) <4 byte>  │
@@ -153,6 +169,8 @@ const FILE_METADATA_BYTES: usize =
 pub enum FileType {
     Object = 0,
     Reference,
+    /// References of base objects that are no longer live in a delta snapshot
+    Removed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -174,6 +192,9 @@ impl FileMetadata {
             FileType::Reference => {
                 dir_path.child(&*format!("{}_{}.ref", self.bucket_num, self.part_num))
             }
+            FileType::Removed => {
+                dir_path.child(&*format!("{}_{}.rem", self.bucket_num, self.part_num))
+            }
         }
     }
     pub fn local_file_path(&self, root_path: &std::path::Path, dir_path: &Path) -> Result<PathBuf> {
@@ -189,30 +210,61 @@ pub struct ManifestV1 {
     pub epoch: u64,
 }
 
+/// Manifest of a delta snapshot on top of the snapshot of `base_epoch`.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ManifestV2 {
+    pub snapshot_version: u8,
+    pub address_length: u64,
+    pub file_metadata: Vec<FileMetadata>,
+    pub epoch: u64,
+    pub base_epoch: u64,
+    pub root_state_hash: ECMHLiveObjectSetDigest,
+}
+
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub enum Manifest {
     V1(ManifestV1),
+    V2(ManifestV2),
 }
 
 impl Manifest {
     pub fn snapshot_version(&self) -> u8 {
         match self {
             Self::V1(manifest) => manifest.snapshot_version,
+            Self::V2(manifest) => manifest.snapshot_version,
         }
     }
     pub fn address_length(&self) -> u64 {
         match self {
             Self::V1(manifest) => manifest.address_length,
+            Self::V2(manifest) => manifest.address_length,
         }
     }
     pub fn file_metadata(&self) -> &Vec<FileMetadata> {
         match self {
             Self::V1(manifest) => &manifest.file_metadata,
+            Self::V2(manifest) => &manifest.file_metadata,
         }
     }
     pub fn epoch(&self) -> u64 {
         match self {
             Self::V1(manifest) => manifest.epoch,
+            Self::V2(manifest) => manifest.epoch,
+        }
+    }
+    /// The epoch of the snapshot this one is a delta of, if any.
+    pub fn base_epoch(&self) -> Option<u64> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2(manifest) => Some(manifest.base_epoch),
+        }
+    }
+    /// The root state hash of the live object set, which is only recorded for
+    /// delta snapshots.
+    pub fn root_state_hash(&self) -> Option<&ECMHLiveObjectSetDigest> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2(manifest) => Some(&manifest.root_state_hash),
         }
     }
 }
@@ -240,6 +292,28 @@ pub fn create_file_metadata(
     Ok(file_metadata)
 }
 
+/// Inserts an object reference into the accumulator the same way
+/// `StateAccumulator::accumulate_live_object` does for the live object it
+/// refers to.
+pub fn accumulate_object_ref(acc: &mut Accumulator, object_ref: &ObjectRef) {
+    acc.insert(accumulator_item(object_ref));
+}
+
+/// Removes an object reference inserted by `accumulate_object_ref` from the
+/// accumulator.
+pub fn remove_object_ref(acc: &mut Accumulator, object_ref: &ObjectRef) {
+    acc.remove(accumulator_item(object_ref));
+}
+
+fn accumulator_item(object_ref: &ObjectRef) -> Vec<u8> {
+    if object_ref.2.is_wrapped() {
+        bcs::to_bytes(&WrappedObject::new(object_ref.0, object_ref.1))
+            .expect("Failed to serialize WrappedObject")
+    } else {
+        object_ref.2.inner().to_vec()
+    }
+}
+
 pub async fn setup_db_state(
     epoch: u64,
     accumulator: Accumulator,
@@ -4,9 +4,10 @@
 
 use std::{
     collections::BTreeMap,
-    fs,
+    fmt, fs,
     fs::File,
     io::{BufReader, Read, Seek, SeekFrom},
+    iter::Peekable,
     num::NonZeroUsize,
     path::PathBuf,
     sync::{
@@ -21,7 +22,7 @@ use bytes::{Buf, Bytes};
 use fastcrypto::hash::{HashFunction, MultisetHash, Sha3_256};
 use futures::{
     StreamExt, TryStreamExt,
-    future::{AbortRegistration, Abortable},
+    future::{AbortHandle, AbortRegistration, Abortable},
 };
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use integer_encoding::VarIntReader;
@@ -41,6 +42,7 @@ use iota_storage::{
 use iota_types::{
     accumulator::Accumulator,
     base_types::{ObjectDigest, ObjectID, ObjectRef, SequenceNumber},
+    messages_checkpoint::ECMHLiveObjectSetDigest,
 };
 use object_store::path::Path;
 use tokio::{
@@ -53,10 +55,69 @@ use tracing::{error, info};
 use crate::{
     FileMetadata, FileType, MAGIC_BYTES, MANIFEST_FILE_MAGIC, Manifest, OBJECT_FILE_MAGIC,
     OBJECT_ID_BYTES, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC, SEQUENCE_NUM_BYTES, SHA3_BYTES,
+    accumulate_object_ref, compute_sha3_checksum, remove_object_ref,
 };
 
 pub type SnapshotChecksums = (DigestByBucketAndPartition, Accumulator);
 pub type DigestByBucketAndPartition = BTreeMap<u32, BTreeMap<u32, [u8; 32]>>;
+/// Summary of a snapshot checked by `StateSnapshotReaderV1::verify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotVerificationReport {
+    pub epoch: u64,
+    pub root_state_hash: ECMHLiveObjectSetDigest,
+    pub num_live_objects: u64,
+    pub num_wrapped_objects: u64,
+    pub num_packages: u64,
+    /// Number of live Move objects by type, without type parameters
+    pub objects_by_type: BTreeMap<String, u64>,
+    /// Number of live Move objects by the package defining their type
+    pub objects_by_package: BTreeMap<ObjectID, u64>,
+}
+
+impl SnapshotVerificationReport {
+    fn record(&mut self, object: &LiveObject) {
+        self.num_live_objects += 1;
+        match object {
+            LiveObject::Wrapped(_) => self.num_wrapped_objects += 1,
+            LiveObject::Normal(object) => match object.type_() {
+                Some(type_) => {
+                    *self
+                        .objects_by_type
+                        .entry(format!("{}::{}", type_.module_id(), type_.name()))
+                        .or_default() += 1;
+                    *self
+                        .objects_by_package
+                        .entry(type_.address().into())
+                        .or_default() += 1;
+                }
+                None => self.num_packages += 1,
+            },
+        }
+    }
+}
+
+impl fmt::Display for SnapshotVerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Snapshot of epoch {} matches root state hash {}",
+            self.epoch, self.root_state_hash.digest
+        )?;
+        writeln!(f, "Live objects: {}", self.num_live_objects)?;
+        writeln!(f, "Wrapped objects: {}", self.num_wrapped_objects)?;
+        writeln!(f, "Packages: {}", self.num_packages)?;
+        writeln!(f, "Objects by type:")?;
+        for (type_, count) in &self.objects_by_type {
+            writeln!(f, "  {type_}: {count}")?;
+        }
+        writeln!(f, "Objects by package:")?;
+        for (package, count) in &self.objects_by_package {
+            writeln!(f, "  {package}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
 pub struct StateSnapshotReaderV1 {
     epoch: u64,
     local_staging_dir_root: PathBuf,
@@ -64,6 +125,14 @@ pub struct StateSnapshotReaderV1 {
     local_object_store: Arc<dyn ObjectStorePutExt>,
     ref_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
     object_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
+    removed_files: BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
+    /// Epoch of the snapshot this one is a delta of
+    base_epoch: Option<u64>,
+    /// Root state hash recorded in the MANIFEST of a delta snapshot
+    root_state_hash: Option<ECMHLiveObjectSetDigest>,
+    /// Readers of the snapshots this one is a delta of, starting with the full
+    /// snapshot at the root of the chain
+    base_layers: Vec<StateSnapshotReaderV1>,
     indirect_objects_threshold: usize,
     multi_progress_bar: MultiProgress,
     concurrency: usize,
@@ -71,7 +140,8 @@ pub struct StateSnapshotReaderV1 {
 
 impl StateSnapshotReaderV1 {
     /// Downloads the MANIFEST, FileMetadata of objects and references from the
-    /// remote store, then creates a StateSnapshotReaderV1 instance.
+    /// remote store, then creates a StateSnapshotReaderV1 instance. If the
+    /// snapshot is a delta, the same is done for every snapshot of its chain.
     pub async fn new(
         epoch: u64,
         remote_store_config: &ObjectStoreConfig,
@@ -80,7 +150,6 @@ impl StateSnapshotReaderV1 {
         download_concurrency: NonZeroUsize,
         multi_progress_bar: MultiProgress,
     ) -> Result<Self> {
-        let epoch_dir = format!("epoch_{}", epoch);
         let remote_object_store = if remote_store_config.no_sign_request {
             remote_store_config.make_http()?
         } else {
@@ -93,6 +162,68 @@ impl StateSnapshotReaderV1 {
             .as_ref()
             .context("No directory specified")?
             .clone();
+        Self::new_from_stores(
+            epoch,
+            remote_object_store,
+            local_object_store,
+            local_staging_dir_root,
+            indirect_objects_threshold,
+            download_concurrency,
+            multi_progress_bar,
+        )
+        .await
+    }
+
+    pub(crate) async fn new_from_stores(
+        epoch: u64,
+        remote_object_store: Arc<dyn ObjectStoreGetExt>,
+        local_object_store: Arc<dyn ObjectStorePutExt>,
+        local_staging_dir_root: PathBuf,
+        indirect_objects_threshold: usize,
+        download_concurrency: NonZeroUsize,
+        multi_progress_bar: MultiProgress,
+    ) -> Result<Self> {
+        let mut reader = Self::new_layer(
+            epoch,
+            remote_object_store.clone(),
+            local_object_store.clone(),
+            local_staging_dir_root.clone(),
+            indirect_objects_threshold,
+            download_concurrency,
+            multi_progress_bar.clone(),
+        )
+        .await?;
+        // Follows the chain of delta snapshots down to the full snapshot at its root
+        let mut base_epoch = reader.base_epoch;
+        while let Some(epoch) = base_epoch {
+            let layer = Self::new_layer(
+                epoch,
+                remote_object_store.clone(),
+                local_object_store.clone(),
+                local_staging_dir_root.clone(),
+                indirect_objects_threshold,
+                download_concurrency,
+                multi_progress_bar.clone(),
+            )
+            .await?;
+            base_epoch = layer.base_epoch;
+            reader.base_layers.insert(0, layer);
+        }
+        Ok(reader)
+    }
+
+    /// Creates a StateSnapshotReaderV1 for the snapshot of a single epoch,
+    /// without the snapshots it is a delta of.
+    async fn new_layer(
+        epoch: u64,
+        remote_object_store: Arc<dyn ObjectStoreGetExt>,
+        local_object_store: Arc<dyn ObjectStorePutExt>,
+        local_staging_dir_root: PathBuf,
+        indirect_objects_threshold: usize,
+        download_concurrency: NonZeroUsize,
+        multi_progress_bar: MultiProgress,
+    ) -> Result<Self> {
+        let epoch_dir = format!("epoch_{}", epoch);
         let local_epoch_dir_path = local_staging_dir_root.join(&epoch_dir);
         if local_epoch_dir_path.exists() {
             fs::remove_dir_all(&local_epoch_dir_path)?;
@@ -113,7 +244,7 @@ impl StateSnapshotReaderV1 {
         )?)?;
         // Verifies MANIFEST
         let snapshot_version = manifest.snapshot_version();
-        if snapshot_version != 1u8 {
+        if snapshot_version != 1u8 && snapshot_version != 2u8 {
             return Err(anyhow!("Unexpected snapshot version: {}", snapshot_version));
         }
         if manifest.address_length() as usize > ObjectID::LENGTH {
@@ -125,10 +256,20 @@ impl StateSnapshotReaderV1 {
         if manifest.epoch() != epoch {
             return Err(anyhow!("Download manifest is not for epoch: {}", epoch,));
         }
+        if let Some(base_epoch) = manifest.base_epoch() {
+            if base_epoch >= epoch {
+                return Err(anyhow!(
+                    "Base epoch {} of delta snapshot is not before epoch {}",
+                    base_epoch,
+                    epoch
+                ));
+            }
+        }
         // Stores the objects and references FileMetadata in MANIFEST to the local
         // directory
         let mut object_files = BTreeMap::new();
         let mut ref_files = BTreeMap::new();
+        let mut removed_files = BTreeMap::new();
         for file_metadata in manifest.file_metadata() {
             match file_metadata.file_type {
                 FileType::Object => {
@@ -149,12 +290,19 @@ impl StateSnapshotReaderV1 {
                     // Inserts the reference FileMetadata with the partition number to the bucket.
                     entry.insert(file_metadata.part_num, file_metadata.clone());
                 }
+                FileType::Removed => {
+                    removed_files
+                        .entry(file_metadata.bucket_num)
+                        .or_insert_with(BTreeMap::new)
+                        .insert(file_metadata.part_num, file_metadata.clone());
+                }
             }
         }
         let epoch_dir_path = Path::from(epoch_dir);
-        // Collects the path of all reference files
+        // Collects the path of all reference and removed files
         let files: Vec<Path> = ref_files
             .values()
+            .chain(removed_files.values())
             .flat_map(|entry| {
                 let files: Vec<_> = entry
                     .values()
@@ -191,18 +339,305 @@ impl StateSnapshotReaderV1 {
             local_object_store,
             ref_files,
             object_files,
+            removed_files,
+            base_epoch: manifest.base_epoch(),
+            root_state_hash: manifest.root_state_hash().cloned(),
+            base_layers: vec![],
             indirect_objects_threshold,
             multi_progress_bar,
             concurrency: download_concurrency.get(),
         })
     }
 
+    /// Restores the snapshot into the AuthorityPerpetualTables. A delta
+    /// snapshot is restored by restoring the full snapshot at the root of its
+    /// chain, then applying every delta in order.
     pub async fn read(
         &mut self,
         perpetual_db: &AuthorityPerpetualTables,
         abort_registration: AbortRegistration,
         sender: Option<tokio::sync::mpsc::Sender<(Accumulator, u64)>>,
     ) -> Result<()> {
+        let accum_handle = if self.base_layers.is_empty() {
+            sender.map(|sender| self.spawn_accumulation_tasks(sender, self.num_part_files()))
+        } else {
+            // Replays the references of the whole chain before restoring anything, so
+            // that a delta which does not apply to its base fails early
+            let mut acc = Accumulator::default();
+            let mut num_live_refs = 0u64;
+            for object_ref in self.live_ref_iter()? {
+                accumulate_object_ref(&mut acc, &object_ref?);
+                num_live_refs += 1;
+            }
+            if let Some(sender) = sender {
+                sender
+                    .send((acc, num_live_refs))
+                    .await
+                    .expect("Unable to send accumulator from snapshot reader");
+            }
+            None
+        };
+
+        self.restore_layers(perpetual_db, abort_registration)
+            .await?;
+
+        if let Some(handle) = accum_handle {
+            handle.await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the live object references at the end of the epoch in
+    /// ObjectID order, streamed from the reference and removed files of the
+    /// chain. The iterator fails if a delta does not apply to its base, or if
+    /// the accumulator of the streamed references does not match the root
+    /// state hash recorded by a delta.
+    pub fn live_ref_iter(&self) -> Result<LiveRefIter> {
+        let mut iter: Option<LiveRefIter> = None;
+        for layer in self.layers() {
+            iter = Some(LiveRefIter {
+                epoch: layer.epoch,
+                root_state_hash: layer.root_state_hash.clone(),
+                base: iter.map(Box::new),
+                base_next: None,
+                removed: layer.sorted_ref_iter(&layer.removed_files)?,
+                added: layer.sorted_ref_iter(&layer.ref_files)?,
+                acc: Accumulator::default(),
+                done: false,
+            });
+        }
+        iter.context("Snapshot chain has no layers")
+    }
+
+    /// Checks the snapshot without restoring it: the SHA3 checksum of every
+    /// file against the MANIFEST, every object against its reference, and the
+    /// accumulator of the live object set against `root_state_hash`, which
+    /// should be the root state commitment of the last checkpoint of the
+    /// epoch. Object files are streamed from the remote store and are not
+    /// kept on disk.
+    pub async fn verify(
+        &self,
+        root_state_hash: &ECMHLiveObjectSetDigest,
+        abort_registration: AbortRegistration,
+    ) -> Result<SnapshotVerificationReport> {
+        let mut acc = Accumulator::default();
+        let mut report = SnapshotVerificationReport {
+            epoch: self.epoch,
+            root_state_hash: root_state_hash.clone(),
+            num_live_objects: 0,
+            num_wrapped_objects: 0,
+            num_packages: 0,
+            objects_by_type: BTreeMap::new(),
+            objects_by_package: BTreeMap::new(),
+        };
+        let num_live_refs = Abortable::new(
+            async {
+                for layer in self.layers() {
+                    layer.verify_local_files()?;
+                }
+                for layer in self.layers() {
+                    layer.verify_objects(self, &mut acc, &mut report).await?;
+                }
+                self.live_ref_iter()?
+                    .try_fold(0u64, |num_live_refs, object_ref| {
+                        object_ref.map(|_| num_live_refs + 1)
+                    })
+            },
+            abort_registration,
+        )
+        .await??;
+        if report.num_live_objects != num_live_refs {
+            return Err(anyhow!(
+                "Found {} live objects in object files, but {} in reference files",
+                report.num_live_objects,
+                num_live_refs
+            ));
+        }
+        let digest = ECMHLiveObjectSetDigest::from(acc.digest());
+        if digest != *root_state_hash {
+            return Err(anyhow!(
+                "Snapshot root state hash {} does not match expected root state hash {}",
+                digest.digest,
+                root_state_hash.digest
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Checks the SHA3 checksums of the downloaded reference and removed files.
+    fn verify_local_files(&self) -> Result<()> {
+        let epoch_dir = self.epoch_dir();
+        for file_metadata in self
+            .ref_files
+            .values()
+            .chain(self.removed_files.values())
+            .flat_map(|part_files| part_files.values())
+        {
+            let path = file_metadata.local_file_path(&self.local_staging_dir_root, &epoch_dir)?;
+            if compute_sha3_checksum(&path)? != file_metadata.sha3_digest {
+                return Err(anyhow!("Checksum mismatch for {}", path.display()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Streams the object files from the remote store bucket by bucket,
+    /// checks them against their checksums and reference files, and records
+    /// the objects which are still live at the end of `chain` in the
+    /// accumulator and the report.
+    async fn verify_objects(
+        &self,
+        chain: &StateSnapshotReaderV1,
+        acc: &mut Accumulator,
+        report: &mut SnapshotVerificationReport,
+    ) -> Result<()> {
+        let epoch_dir = self.epoch_dir();
+        let num_files = self
+            .object_files
+            .values()
+            .map(|part_files| part_files.len())
+            .sum::<usize>();
+        let obj_progress_bar = self.multi_progress_bar.add(
+            ProgressBar::new(num_files as u64).with_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] {wide_bar} {pos} out of {len} .obj files verified ({msg})",
+                )
+                .unwrap(),
+            ),
+        );
+        for part_files in self.object_files.values() {
+            // The objects of a bucket are sorted by ObjectID, so they are matched
+            // against a stream of the live references of the chain
+            let mut live_refs = chain.live_ref_iter()?.peekable();
+            futures::stream::iter(part_files.values())
+                .map(|file_metadata| {
+                    let file_path = file_metadata.file_path(&epoch_dir);
+                    let remote_object_store = self.remote_object_store.clone();
+                    async move {
+                        let bytes = remote_object_store.get_bytes(&file_path).await?;
+                        Ok::<_, anyhow::Error>((file_metadata, bytes))
+                    }
+                })
+                .boxed()
+                .buffered(self.concurrency)
+                .try_for_each(|(file_metadata, bytes)| {
+                    let result =
+                        self.verify_object_file(file_metadata, bytes, &mut live_refs, acc, report);
+                    obj_progress_bar.inc(1);
+                    obj_progress_bar.set_message(format!("Epoch: {}", self.epoch));
+                    futures::future::ready(result)
+                })
+                .await?;
+        }
+        obj_progress_bar.finish_with_message("Objects verification complete");
+        Ok(())
+    }
+
+    fn verify_object_file(
+        &self,
+        file_metadata: &FileMetadata,
+        bytes: Bytes,
+        live_refs: &mut Peekable<LiveRefIter>,
+        acc: &mut Accumulator,
+        report: &mut SnapshotVerificationReport,
+    ) -> Result<()> {
+        let file_path = file_metadata.file_path(&self.epoch_dir());
+        let mut hasher = Sha3_256::default();
+        hasher.update(&bytes);
+        if hasher.finalize().digest != file_metadata.sha3_digest {
+            return Err(anyhow!("Checksum mismatch for {}", file_path));
+        }
+        let mut ref_iter = self.ref_iter(file_metadata.bucket_num, file_metadata.part_num)?;
+        for object in LiveObjectIter::new(file_metadata, bytes)? {
+            let object_ref = object.object_reference();
+            if ref_iter.next() != Some(object_ref) {
+                return Err(anyhow!(
+                    "{:?} in {} does not match its reference file",
+                    object_ref,
+                    file_path
+                ));
+            }
+            // Skips the live references of other buckets and layers
+            while let Some(live_ref) = live_refs.next_if(|live_ref| {
+                live_ref
+                    .as_ref()
+                    .map_or(true, |live_ref| live_ref.0 < object_ref.0)
+            }) {
+                live_ref?;
+            }
+            // Skips the objects superseded by a later delta
+            if live_refs
+                .next_if(|live_ref| matches!(live_ref, Ok(live_ref) if *live_ref == object_ref))
+                .is_some()
+            {
+                accumulate_object_ref(acc, &object_ref);
+                report.record(&object);
+            }
+        }
+        if ref_iter.next().is_some() {
+            return Err(anyhow!(
+                "{} has fewer objects than its reference file",
+                file_path
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the readers of the whole chain, starting with the full snapshot
+    /// at its root and ending with this one.
+    fn layers(&self) -> impl Iterator<Item = &StateSnapshotReaderV1> {
+        self.base_layers.iter().chain(std::iter::once(self))
+    }
+
+    /// Restores the snapshots of the chain in order.
+    async fn restore_layers(
+        &self,
+        perpetual_db: &AuthorityPerpetualTables,
+        abort_registration: AbortRegistration,
+    ) -> Result<()> {
+        // A registration can only be used once, so every layer gets its own and
+        // they are all aborted together with the one of the caller
+        let (abort_handles, layer_registrations): (Vec<_>, Vec<_>) =
+            self.layers().map(|_| AbortHandle::new_pair()).unzip();
+        let abort_watcher = tokio::spawn(async move {
+            let aborted = Abortable::new(futures::future::pending::<()>(), abort_registration);
+            if aborted.await.is_err() {
+                abort_handles.iter().for_each(AbortHandle::abort);
+            }
+        });
+        let result = async {
+            for (layer, abort_registration) in self.layers().zip(layer_registrations) {
+                if !layer.removed_files.is_empty() {
+                    info!("Removing objects of epoch {} snapshot", layer.epoch);
+                }
+                for iter in layer.removed_ref_iters()? {
+                    AuthorityStore::bulk_remove_live_objects(perpetual_db, iter)?;
+                }
+                let sha3_digests = layer.compute_sha3_digests().await?;
+                // Downloads all object files from remote in parallel and inserts the objects
+                // into the AuthorityPerpetualTables
+                layer
+                    .sync_live_objects(perpetual_db, abort_registration, sha3_digests)
+                    .await?;
+            }
+            Ok(())
+        }
+        .await;
+        abort_watcher.abort();
+        result
+    }
+
+    /// Counts the total number of partitions
+    fn num_part_files(&self) -> usize {
+        self.ref_files
+            .values()
+            .map(|part_files| part_files.len())
+            .sum::<usize>()
+    }
+
+    /// Computes the sha3 digest of the object references of every bucket
+    /// partition.
+    async fn compute_sha3_digests(&self) -> Result<Arc<Mutex<DigestByBucketAndPartition>>> {
         // This computes and stores the sha3 digest of object references in REFERENCE
         // file for each bucket partition. When downloading objects, we will
         // compare sha3 digest of object references per *.obj file against this.
@@ -213,14 +648,9 @@ impl StateSnapshotReaderV1 {
         let sha3_digests: Arc<Mutex<DigestByBucketAndPartition>> =
             Arc::new(Mutex::new(BTreeMap::new()));
 
-        // Counts the total number of partitions
-        let num_part_files = self
-            .ref_files
-            .values()
-            .map(|part_files| part_files.len())
-            .sum::<usize>();
+        let num_part_files = self.num_part_files();
 
-        info!("Computing checksums");
+        info!("Computing checksums of epoch {} snapshot", self.epoch);
         // Creates a progress bar for checksumming
         let checksum_progress_bar = self.multi_progress_bar.add(
             ProgressBar::new(num_part_files as u64).with_style(
@@ -266,18 +696,7 @@ impl StateSnapshotReaderV1 {
         }
         checksum_progress_bar.finish_with_message("Checksumming complete");
 
-        let accum_handle =
-            sender.map(|sender| self.spawn_accumulation_tasks(sender, num_part_files));
-
-        // Downloads all object files from remote in parallel and inserts the objects
-        // into the AuthorityPerpetualTables
-        self.sync_live_objects(perpetual_db, abort_registration, sha3_digests)
-            .await?;
-
-        if let Some(handle) = accum_handle {
-            handle.await?;
-        }
-        Ok(())
+        Ok(sha3_digests)
     }
 
     /// Spawns accumulation tasks to accumulate the sha3 digests of all objects
@@ -533,6 +952,53 @@ impl StateSnapshotReaderV1 {
         )
     }
 
+    /// Returns an iterator over the references in every .rem file.
+    fn removed_ref_iters(&self) -> Result<Vec<ObjectRefIter>> {
+        self.removed_files
+            .values()
+            .flat_map(|part_files| part_files.values())
+            .map(|file_metadata| {
+                ObjectRefIter::new(
+                    file_metadata,
+                    self.local_staging_dir_root.clone(),
+                    self.epoch_dir(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over the references of `files` in ObjectID order.
+    /// The parts of a bucket are written in order, so the references of each
+    /// bucket are sorted and only the buckets need to be merged.
+    fn sorted_ref_iter(
+        &self,
+        files: &BTreeMap<u32, BTreeMap<u32, FileMetadata>>,
+    ) -> Result<SortedRefIter> {
+        let buckets = files
+            .values()
+            .map(|part_files| {
+                let parts = part_files
+                    .values()
+                    .map(|file_metadata| {
+                        ObjectRefIter::new(
+                            file_metadata,
+                            self.local_staging_dir_root.clone(),
+                            self.epoch_dir(),
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let refs: Box<dyn Iterator<Item = ObjectRef>> =
+                    Box::new(parts.into_iter().flatten());
+                Ok(refs.peekable())
+            })
+            .collect::<Result<_>>()?;
+        Ok(SortedRefIter {
+            epoch: self.epoch,
+            buckets,
+            last: None,
+        })
+    }
+
     /// Returns a list of all buckets.
     fn buckets(&self) -> Result<Vec<u32>> {
         Ok(self.ref_files.keys().copied().collect())
@@ -625,6 +1091,152 @@ impl Iterator for ObjectRefIter {
     }
 }
 
+/// An iterator over the references of the .ref or .rem files of a snapshot
+/// in ObjectID order, merged across buckets.
+struct SortedRefIter {
+    epoch: u64,
+    buckets: Vec<Peekable<Box<dyn Iterator<Item = ObjectRef>>>>,
+    last: Option<ObjectID>,
+}
+
+impl SortedRefIter {
+    fn peek(&mut self) -> Option<ObjectRef> {
+        self.buckets
+            .iter_mut()
+            .filter_map(|refs| refs.peek().copied())
+            .min_by_key(|object_ref| object_ref.0)
+    }
+
+    /// Returns the next reference if it belongs to `object_id`, and fails if
+    /// the files are not sorted.
+    fn next_if_id(&mut self, object_id: &ObjectID) -> Result<Option<ObjectRef>> {
+        let Some(object_ref) = self
+            .buckets
+            .iter_mut()
+            .find_map(|refs| refs.next_if(|object_ref| object_ref.0 == *object_id))
+        else {
+            return Ok(None);
+        };
+        if self.last.is_some_and(|last| last >= object_ref.0) {
+            return Err(anyhow!(
+                "References of snapshot of epoch {} are not sorted at {:?}",
+                self.epoch,
+                object_ref
+            ));
+        }
+        self.last = Some(object_ref.0);
+        Ok(Some(object_ref))
+    }
+}
+
+/// An iterator over the live object references of a snapshot chain in
+/// ObjectID order. Every layer merges the live references of its base with
+/// its own removed and reference files.
+pub struct LiveRefIter {
+    epoch: u64,
+    root_state_hash: Option<ECMHLiveObjectSetDigest>,
+    base: Option<Box<LiveRefIter>>,
+    base_next: Option<ObjectRef>,
+    removed: SortedRefIter,
+    added: SortedRefIter,
+    /// Accumulates the changes of this layer until it is exhausted, then the
+    /// whole live object set
+    acc: Accumulator,
+    done: bool,
+}
+
+impl LiveRefIter {
+    fn peek_base(&mut self) -> Result<Option<ObjectRef>> {
+        if self.base_next.is_none() {
+            if let Some(base) = &mut self.base {
+                self.base_next = base.next().transpose()?;
+            }
+        }
+        Ok(self.base_next)
+    }
+
+    fn next_ref(&mut self) -> Result<Option<ObjectRef>> {
+        loop {
+            let Some(object_id) = [self.peek_base()?, self.removed.peek(), self.added.peek()]
+                .into_iter()
+                .flatten()
+                .map(|object_ref| object_ref.0)
+                .min()
+            else {
+                self.finish()?;
+                return Ok(None);
+            };
+            let base_ref = self
+                .base_next
+                .take_if(|object_ref| object_ref.0 == object_id);
+            let removed_ref = self.removed.next_if_id(&object_id)?;
+            let added_ref = self.added.next_if_id(&object_id)?;
+            if let Some(removed_ref) = removed_ref {
+                if base_ref != Some(removed_ref) {
+                    return Err(anyhow!(
+                        "Snapshot of epoch {} removes {:?}, which is not live in its base",
+                        self.epoch,
+                        removed_ref
+                    ));
+                }
+                remove_object_ref(&mut self.acc, &removed_ref);
+            }
+            match (base_ref, removed_ref, added_ref) {
+                (Some(base_ref), None, Some(added_ref)) => {
+                    return Err(anyhow!(
+                        "Snapshot of epoch {} adds {:?}, which is already live as {:?}",
+                        self.epoch,
+                        added_ref,
+                        base_ref
+                    ));
+                }
+                (_, _, Some(added_ref)) => {
+                    accumulate_object_ref(&mut self.acc, &added_ref);
+                    return Ok(Some(added_ref));
+                }
+                (Some(base_ref), None, None) => return Ok(Some(base_ref)),
+                // Removed without a new version
+                _ => continue,
+            }
+        }
+    }
+
+    /// Checks the accumulator of the live object set against the root state
+    /// hash of the layer once all references have been streamed.
+    fn finish(&mut self) -> Result<()> {
+        if let Some(base) = &self.base {
+            self.acc.union(&base.acc);
+        }
+        if let Some(root_state_hash) = &self.root_state_hash {
+            let digest = ECMHLiveObjectSetDigest::from(self.acc.digest());
+            if digest != *root_state_hash {
+                return Err(anyhow!(
+                    "Root state hash {} of snapshot of epoch {} does not match {} computed from \
+                     its chain",
+                    root_state_hash.digest,
+                    self.epoch,
+                    digest.digest
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for LiveRefIter {
+    type Item = Result<ObjectRef>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_ref().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
 /// An iterator over all objects in a *.obj file.
 pub struct LiveObjectIter {
     reader: Box<dyn Read>,
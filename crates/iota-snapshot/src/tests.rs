@@ -9,11 +9,13 @@ use futures::future::AbortHandle;
 use indicatif::MultiProgress;
 use iota_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
 use iota_core::{
-    authority::authority_store_tables::AuthorityPerpetualTables,
+    authority::{AuthorityStore, authority_store_tables::AuthorityPerpetualTables},
     state_accumulator::StateAccumulator,
 };
 use iota_types::{
-    accumulator::Accumulator, base_types::ObjectID, messages_checkpoint::ECMHLiveObjectSetDigest,
+    accumulator::Accumulator,
+    base_types::{IotaAddress, ObjectID, SequenceNumber},
+    messages_checkpoint::ECMHLiveObjectSetDigest,
     object::Object,
 };
 use tempfile::tempdir;
@@ -92,7 +94,7 @@ async fn test_snapshot_basic() -> Result<(), anyhow::Error> {
     let root_accumulator =
         ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db).digest());
     snapshot_writer
-        .write_internal(0, perpetual_db.clone(), root_accumulator)
+        .write_internal(0, None, perpetual_db.clone(), root_accumulator)
         .await?;
     let local_store_restore_config = ObjectStoreConfig {
         object_store: Some(ObjectStoreType::File),
@@ -145,7 +147,7 @@ async fn test_snapshot_empty_db() -> Result<(), anyhow::Error> {
     let root_accumulator =
         ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db).digest());
     snapshot_writer
-        .write_internal(0, perpetual_db.clone(), root_accumulator)
+        .write_internal(0, None, perpetual_db.clone(), root_accumulator)
         .await?;
     let local_store_restore_config = ObjectStoreConfig {
         object_store: Some(ObjectStoreType::File),
@@ -169,3 +171,193 @@ async fn test_snapshot_empty_db() -> Result<(), anyhow::Error> {
     compare_live_objects(&perpetual_db, &restored_perpetual_db)?;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_snapshot_delta() -> Result<(), anyhow::Error> {
+    let db_path = temp_dir();
+    let restored_db_path = temp_dir();
+    let local = temp_dir().join("local_dir");
+    let remote = temp_dir().join("remote_dir");
+    let restored_local = temp_dir().join("local_dir_restore");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(local),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(remote),
+        ..Default::default()
+    };
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    let ids = ObjectID::in_range(ObjectID::ZERO, 1200)?;
+    insert_keys(&perpetual_db, 1000)?;
+
+    // Epoch 0 is a full snapshot, epochs 1 and 2 are deltas on top of it
+    for epoch in 0..3u64 {
+        if epoch == 1 {
+            // Deletes some objects, mutates others and creates new ones
+            let deleted = perpetual_db
+                .iter_live_object_set()
+                .take(100)
+                .map(|object| object.object_reference());
+            AuthorityStore::bulk_remove_live_objects(&perpetual_db, deleted)?;
+            for id in &ids[100..200] {
+                perpetual_db.insert_object_test_only(Object::with_id_owner_version_for_testing(
+                    *id,
+                    SequenceNumber::from_u64(5),
+                    IotaAddress::ZERO,
+                ))?;
+            }
+            for id in &ids[1000..1100] {
+                perpetual_db.insert_object_test_only(Object::immutable_with_id_for_testing(*id))?;
+            }
+        } else if epoch == 2 {
+            let deleted = perpetual_db
+                .range_iter_live_object_set(Some(ids[1000]), Some(ids[1049]))
+                .map(|object| object.object_reference());
+            AuthorityStore::bulk_remove_live_objects(&perpetual_db, deleted)?;
+            for id in &ids[1100..1200] {
+                perpetual_db.insert_object_test_only(Object::immutable_with_id_for_testing(*id))?;
+            }
+        }
+        let snapshot_writer = StateSnapshotWriterV1::new(
+            &local_store_config,
+            &remote_store_config,
+            FileCompression::Zstd,
+            NonZeroUsize::new(1).unwrap(),
+        )
+        .await?;
+        let root_accumulator =
+            ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db).digest());
+        let base_epoch = epoch.checked_sub(1);
+        snapshot_writer
+            .write_internal(epoch, base_epoch, perpetual_db.clone(), root_accumulator)
+            .await?;
+    }
+
+    let local_store_restore_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(restored_local),
+        ..Default::default()
+    };
+    let mut snapshot_reader = StateSnapshotReaderV1::new(
+        2,
+        &remote_store_config,
+        &local_store_restore_config,
+        usize::MAX,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+    )
+    .await?;
+    assert_eq!(
+        snapshot_reader
+            .live_ref_iter()?
+            .collect::<Result<Vec<_>, _>>()?,
+        perpetual_db
+            .iter_live_object_set()
+            .map(|object| object.object_reference())
+            .collect::<Vec<_>>()
+    );
+    let root_accumulator =
+        ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db).digest());
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    let report = snapshot_reader
+        .verify(&root_accumulator, abort_registration)
+        .await?;
+    assert_eq!(
+        report.num_live_objects,
+        perpetual_db.iter_live_object_set().count() as u64
+    );
+    let restored_perpetual_db = AuthorityPerpetualTables::open(&restored_db_path, None);
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    snapshot_reader
+        .read(&restored_perpetual_db, abort_registration, None)
+        .await?;
+    compare_live_objects(&perpetual_db, &restored_perpetual_db)?;
+    assert_eq!(
+        accumulate_live_object_set(&perpetual_db).digest(),
+        accumulate_live_object_set(&restored_perpetual_db).digest()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_snapshot_verify() -> Result<(), anyhow::Error> {
+    let db_path = temp_dir();
+    let local = temp_dir().join("local_dir");
+    let remote = temp_dir().join("remote_dir");
+    let verify_local = temp_dir().join("local_dir_verify");
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(local),
+        ..Default::default()
+    };
+    let remote_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(remote.clone()),
+        ..Default::default()
+    };
+    let snapshot_writer = StateSnapshotWriterV1::new(
+        &local_store_config,
+        &remote_store_config,
+        FileCompression::Zstd,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await?;
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&db_path, None));
+    insert_keys(&perpetual_db, 1000)?;
+    let root_accumulator =
+        ECMHLiveObjectSetDigest::from(accumulate_live_object_set(&perpetual_db).digest());
+    snapshot_writer
+        .write_internal(0, None, perpetual_db.clone(), root_accumulator.clone())
+        .await?;
+    let local_store_verify_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(verify_local),
+        ..Default::default()
+    };
+    let snapshot_reader = StateSnapshotReaderV1::new(
+        0,
+        &remote_store_config,
+        &local_store_verify_config,
+        usize::MAX,
+        NonZeroUsize::new(1).unwrap(),
+        MultiProgress::new(),
+    )
+    .await?;
+
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    let report = snapshot_reader
+        .verify(&root_accumulator, abort_registration)
+        .await?;
+    assert_eq!(report.num_live_objects, 1000);
+    assert_eq!(report.num_packages, 0);
+    assert_eq!(report.objects_by_type.values().sum::<u64>(), 1000);
+    assert_eq!(report.objects_by_package.values().sum::<u64>(), 1000);
+
+    // Fails against another root state hash
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    let other_root = ECMHLiveObjectSetDigest::from(Accumulator::default().digest());
+    assert!(
+        snapshot_reader
+            .verify(&other_root, abort_registration)
+            .await
+            .is_err()
+    );
+
+    // Fails if an object file is corrupted
+    let object_file = remote.join("epoch_0").join("1_1.obj");
+    let mut bytes = std::fs::read(&object_file)?;
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    std::fs::write(&object_file, bytes)?;
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    assert!(
+        snapshot_reader
+            .verify(&root_accumulator, abort_registration)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
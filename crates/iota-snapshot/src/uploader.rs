@@ -2,7 +2,12 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Result;
 use bytes::Bytes;
@@ -15,12 +20,12 @@ use iota_core::{
 use iota_storage::{
     FileCompression,
     object_store::util::{
-        find_all_dirs_with_epoch_prefix, find_missing_epochs_dirs, path_to_filesystem, put,
+        exists, find_all_dirs_with_epoch_prefix, find_missing_epochs_dirs, path_to_filesystem, put,
         run_manifest_update_loop,
     },
 };
 use iota_types::messages_checkpoint::CheckpointCommitment::ECMHLiveObjectSetDigest;
-use object_store::DynObjectStore;
+use object_store::{DynObjectStore, path::Path};
 use prometheus::{
     IntCounter, IntGauge, Registry, register_int_counter_with_registry,
     register_int_gauge_with_registry,
@@ -73,6 +78,9 @@ pub struct StateSnapshotUploader {
     /// Time interval to check for presence of new db checkpoint (default: 60
     /// secs)
     interval: Duration,
+    /// If set, the snapshots of epochs which are not a multiple of this
+    /// interval are written as deltas on top of the previous epoch
+    full_snapshot_interval: Option<NonZeroU64>,
     metrics: Arc<StateSnapshotUploaderMetrics>,
 }
 
@@ -81,6 +89,7 @@ impl StateSnapshotUploader {
         db_checkpoint_path: &std::path::Path,
         staging_path: &std::path::Path,
         snapshot_store_config: ObjectStoreConfig,
+        full_snapshot_interval: Option<NonZeroU64>,
         interval_s: u64,
         registry: &Registry,
         checkpoint_store: Arc<CheckpointStore>,
@@ -103,6 +112,7 @@ impl StateSnapshotUploader {
             staging_store: staging_store_config.make()?,
             snapshot_store: snapshot_store_config.make()?,
             interval: Duration::from_secs(interval_s),
+            full_snapshot_interval,
             metrics: StateSnapshotUploaderMetrics::new(registry),
         }))
     }
@@ -152,9 +162,18 @@ impl StateSnapshotUploader {
                     .last()
                     .expect("Expected at least one commitment")
                     .clone();
-                state_snapshot_writer
-                    .write(*epoch, db, state_hash_commitment)
-                    .await?;
+                match self.delta_base_epoch(*epoch).await {
+                    Some(base_epoch) => {
+                        state_snapshot_writer
+                            .write_delta(*epoch, base_epoch, db, state_hash_commitment)
+                            .await?
+                    }
+                    None => {
+                        state_snapshot_writer
+                            .write(*epoch, db, state_hash_commitment)
+                            .await?
+                    }
+                }
                 info!("State snapshot creation successful for epoch: {}", *epoch);
                 // Drops marker in the output directory that upload completed successfully
                 let bytes = Bytes::from_static(b"success");
@@ -216,6 +235,21 @@ impl StateSnapshotUploader {
         Ok(())
     }
 
+    /// Returns the epoch the snapshot of `epoch` should be a delta of, if any.
+    /// Falls back to a full snapshot if the previous epoch has no snapshot in
+    /// the remote store.
+    async fn delta_base_epoch(&self, epoch: u64) -> Option<u64> {
+        let interval = self.full_snapshot_interval?;
+        if epoch % interval.get() == 0 {
+            return None;
+        }
+        let base_epoch = epoch - 1;
+        let base_manifest = Path::from(format!("epoch_{base_epoch}")).child("MANIFEST");
+        exists(&self.snapshot_store, &base_manifest)
+            .await
+            .then_some(base_epoch)
+    }
+
     /// Finds missing epochs in the remote store.
     async fn get_missing_epochs(&self) -> Result<Vec<u64>> {
         let missing_epochs = find_missing_epochs_dirs(&self.snapshot_store, SUCCESS_MARKER).await?;
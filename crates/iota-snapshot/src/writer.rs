@@ -18,8 +18,9 @@ use anyhow::{Context, Result};
 use byteorder::{BigEndian, ByteOrder};
 use fastcrypto::hash::MultisetHash;
 use futures::StreamExt;
+use indicatif::{MultiProgress, ProgressDrawTarget};
 use integer_encoding::VarInt;
-use iota_config::object_storage_config::ObjectStoreConfig;
+use iota_config::object_storage_config::{ObjectStoreConfig, ObjectStoreType};
 use iota_core::{
     authority::authority_store_tables::{AuthorityPerpetualTables, LiveObject},
     state_accumulator::StateAccumulator,
@@ -46,10 +47,14 @@ use tracing::debug;
 
 use crate::{
     FILE_MAX_BYTES, FileCompression, FileMetadata, FileType, MAGIC_BYTES, MANIFEST_FILE_MAGIC,
-    Manifest, ManifestV1, OBJECT_FILE_MAGIC, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC,
-    SEQUENCE_NUM_BYTES, compute_sha3_checksum, create_file_metadata,
+    Manifest, ManifestV1, ManifestV2, OBJECT_FILE_MAGIC, OBJECT_REF_BYTES, REFERENCE_FILE_MAGIC,
+    SEQUENCE_NUM_BYTES, compute_sha3_checksum, create_file_metadata, reader::StateSnapshotReaderV1,
 };
 
+/// Directory of the local staging dir the base snapshots of a delta are
+/// downloaded to
+const BASE_STAGING_DIR: &str = "base";
+
 /// LiveObjectSetWriterV1 writes live object set. It creates multiple *.obj
 /// files and *.ref file
 struct LiveObjectSetWriterV1 {
@@ -236,19 +241,121 @@ impl LiveObjectSetWriterV1 {
 
     /// Writes an object reference to the reference file.
     fn write_object_ref(&mut self, object_ref: &ObjectRef) -> Result<()> {
-        let mut buf = [0u8; OBJECT_REF_BYTES];
-        buf[0..ObjectID::LENGTH].copy_from_slice(object_ref.0.as_ref());
-        BigEndian::write_u64(
-            &mut buf[ObjectID::LENGTH..OBJECT_REF_BYTES],
-            object_ref.1.value(),
-        );
-        buf[ObjectID::LENGTH + SEQUENCE_NUM_BYTES..OBJECT_REF_BYTES]
-            .copy_from_slice(object_ref.2.as_ref());
-        self.ref_wbuf.write_all(&buf)?;
+        self.ref_wbuf.write_all(&encode_object_ref(object_ref))?;
+        Ok(())
+    }
+}
+
+/// RemovedRefsWriterV1 writes the references of the base objects that are no
+/// longer live in a delta snapshot to *.rem files of one bucket.
+struct RemovedRefsWriterV1 {
+    dir_path: PathBuf,
+    bucket_num: u32,
+    current_part_num: u32,
+    wbuf: BufWriter<File>,
+    file_size: usize,
+    files: Vec<FileMetadata>,
+    sender: Sender<FileMetadata>,
+    file_compression: FileCompression,
+}
+
+impl RemovedRefsWriterV1 {
+    fn new(
+        dir_path: PathBuf,
+        bucket_num: u32,
+        file_compression: FileCompression,
+        sender: Sender<FileMetadata>,
+    ) -> Result<Self> {
+        let part_num = 1;
+        let (n, f) = Self::removed_file(dir_path.clone(), bucket_num, part_num)?;
+        Ok(RemovedRefsWriterV1 {
+            dir_path,
+            bucket_num,
+            current_part_num: part_num,
+            wbuf: BufWriter::new(f),
+            file_size: n,
+            files: vec![],
+            sender,
+            file_compression,
+        })
+    }
+
+    /// Writes a removed object reference, creating a new partition if the
+    /// current one exceeds the maximum size.
+    fn write(&mut self, object_ref: &ObjectRef) -> Result<()> {
+        if self.file_size + OBJECT_REF_BYTES > FILE_MAX_BYTES {
+            self.finalize()?;
+            self.current_part_num += 1;
+            let (n, f) = Self::removed_file(
+                self.dir_path.clone(),
+                self.bucket_num,
+                self.current_part_num,
+            )?;
+            self.file_size = n;
+            self.wbuf = BufWriter::new(f);
+        }
+        self.wbuf.write_all(&encode_object_ref(object_ref))?;
+        self.file_size += OBJECT_REF_BYTES;
+        Ok(())
+    }
+
+    /// Finalizes the last removed file and returns the FileMetadata of all
+    /// the files.
+    fn done(mut self) -> Result<Vec<FileMetadata>> {
+        self.finalize()?;
+        Ok(self.files)
+    }
+
+    /// Creates a new removed file for the provided bucket number and part
+    /// number, and returns the file and the number of bytes written to it.
+    fn removed_file(dir_path: PathBuf, bucket_num: u32, part_num: u32) -> Result<(usize, File)> {
+        let path = dir_path.join(format!("{bucket_num}_{part_num}.rem"));
+        let tmp_path = dir_path.join(format!("{bucket_num}_{part_num}.rem.tmp"));
+        let mut f = File::create(tmp_path.clone())?;
+        let mut metab = [0u8; MAGIC_BYTES];
+        BigEndian::write_u32(&mut metab, REFERENCE_FILE_MAGIC);
+        let n = f.write(&metab)?;
+        drop(f);
+        fs::rename(tmp_path, path.clone())?;
+        let mut f = OpenOptions::new().append(true).open(path)?;
+        f.seek(SeekFrom::Start(n as u64))?;
+        Ok((n, f))
+    }
+
+    /// Flushes the current removed file to disk and sends its FileMetadata to
+    /// the channel.
+    fn finalize(&mut self) -> Result<()> {
+        self.wbuf.flush()?;
+        self.wbuf.get_ref().sync_data()?;
+        let file_path = self
+            .dir_path
+            .join(format!("{}_{}.rem", self.bucket_num, self.current_part_num));
+        let file_metadata = create_file_metadata(
+            &file_path,
+            self.file_compression,
+            FileType::Removed,
+            self.bucket_num,
+            self.current_part_num,
+        )?;
+        self.files.push(file_metadata.clone());
+        self.sender.blocking_send(file_metadata)?;
         Ok(())
     }
 }
 
+/// Encodes an object reference in the REFERENCE file format.
+fn encode_object_ref(object_ref: &ObjectRef) -> [u8; OBJECT_REF_BYTES] {
+    let mut buf = [0u8; OBJECT_REF_BYTES];
+    buf[0..ObjectID::LENGTH].copy_from_slice(object_ref.0.as_ref());
+    BigEndian::write_u64(
+        &mut buf[ObjectID::LENGTH..OBJECT_REF_BYTES],
+        object_ref.1.value(),
+    );
+    buf[ObjectID::LENGTH + SEQUENCE_NUM_BYTES..OBJECT_REF_BYTES]
+        .copy_from_slice(object_ref.2.as_ref());
+    buf
+}
+
 /// StateSnapshotWriterV1 writes snapshot files to a local staging dir and
 /// simultaneously uploads them to a remote object store
 pub struct StateSnapshotWriterV1 {
@@ -307,19 +414,46 @@ impl StateSnapshotWriterV1 {
         perpetual_db: Arc<AuthorityPerpetualTables>,
         root_state_hash: ECMHLiveObjectSetDigest,
     ) -> Result<()> {
-        self.write_internal(epoch, perpetual_db, root_state_hash)
+        self.write_internal(epoch, None, perpetual_db, root_state_hash)
+            .await
+    }
+
+    /// Like `write`, but only writes the changes to the live object set since
+    /// the snapshot of `base_epoch`, which must already be in the remote
+    /// store.
+    pub async fn write_delta(
+        self,
+        epoch: u64,
+        base_epoch: u64,
+        perpetual_db: Arc<AuthorityPerpetualTables>,
+        root_state_hash: ECMHLiveObjectSetDigest,
+    ) -> Result<()> {
+        self.write_internal(epoch, Some(base_epoch), perpetual_db, root_state_hash)
             .await
     }
 
     /// Writes the state snapshot for the provided epoch to the local staging
-    /// directory and uploads it to the remote store.
+    /// directory and uploads it to the remote store. The snapshot is a delta
+    /// on top of the snapshot of `base_epoch` if there is one.
     pub(crate) async fn write_internal(
         mut self,
         epoch: u64,
+        base_epoch: Option<u64>,
         perpetual_db: Arc<AuthorityPerpetualTables>,
         root_state_hash: ECMHLiveObjectSetDigest,
     ) -> Result<()> {
+        if let Some(base_epoch) = base_epoch {
+            anyhow::ensure!(
+                base_epoch < epoch,
+                "Base epoch {base_epoch} of delta snapshot is not before epoch {epoch}"
+            );
+        }
         self.setup_epoch_dir(epoch).await?;
+        let base = match base_epoch {
+            Some(base_epoch) => Some((base_epoch, self.base_reader(base_epoch).await?)),
+            None => None,
+        };
+        let base_staging_dir = self.local_staging_dir.join(BASE_STAGING_DIR);
 
         let manifest_file_path = self.epoch_dir(epoch).child("MANIFEST");
         let local_staging_dir = self.local_staging_dir.clone();
@@ -332,6 +466,7 @@ impl StateSnapshotWriterV1 {
         let write_handler = tokio::task::spawn_blocking(move || {
             self.write_live_object_set(
                 epoch,
+                base,
                 perpetual_db,
                 sender,
                 Self::bucket_func,
@@ -340,7 +475,11 @@ impl StateSnapshotWriterV1 {
         });
         // Awaits the object and reference files to be written to the local staging
         // directory and informs the upload loop
-        write_handler.await?.context(format!(
+        let written = write_handler.await?;
+        if base_staging_dir.exists() {
+            fs::remove_dir_all(&base_staging_dir)?;
+        }
+        written.context(format!(
             "Failed to write state snapshot for epoch: {}",
             &epoch
         ))?;
@@ -408,24 +547,75 @@ impl StateSnapshotWriterV1 {
     /// Writes the provided live object set in the form of reference files,
     /// object files, and MANIFEST. These files are stored in the local
     /// staging directory and the FileMetadata is sent to the channel.
+    ///
+    /// If the reader of a base snapshot is provided, only the objects which
+    /// are not part of the base are written, and the base references which are
+    /// no longer live are written to removed files. The live object set and
+    /// the live references of the base are both sorted by ObjectID, so they
+    /// are merged in a single pass.
     fn write_live_object_set<F>(
         &mut self,
         epoch: u64,
+        base: Option<(u64, StateSnapshotReaderV1)>,
         perpetual_db: Arc<AuthorityPerpetualTables>,
         sender: Sender<FileMetadata>,
         bucket_func: F,
         root_state_hash: ECMHLiveObjectSetDigest,
     ) -> Result<()>
     where
-        F: Fn(&LiveObject) -> u32,
+        F: Fn(&ObjectID) -> u32,
     {
         let mut object_writers: HashMap<u32, LiveObjectSetWriterV1> = HashMap::new();
+        let mut removed_writers: HashMap<u32, RemovedRefsWriterV1> = HashMap::new();
         let local_staging_dir_path =
             path_to_filesystem(self.local_staging_dir.clone(), &self.epoch_dir(epoch))?;
+        let base_epoch = base.as_ref().map(|(base_epoch, _)| *base_epoch);
+        let mut base_refs = match &base {
+            Some((_, reader)) => Some(reader.live_ref_iter()?.peekable()),
+            None => None,
+        };
+        let mut write_removed = |object_ref: &ObjectRef| -> Result<()> {
+            let bucket_num = bucket_func(&object_ref.0);
+            if let Vacant(entry) = removed_writers.entry(bucket_num) {
+                entry.insert(RemovedRefsWriterV1::new(
+                    local_staging_dir_path.clone(),
+                    bucket_num,
+                    self.file_compression,
+                    sender.clone(),
+                )?);
+            }
+            let writer = removed_writers
+                .get_mut(&bucket_num)
+                .context("Unexpected missing bucket writer")?;
+            writer.write(object_ref)
+        };
         let mut acc = Accumulator::default();
         for object in perpetual_db.iter_live_object_set() {
             StateAccumulator::accumulate_live_object(&mut acc, &object);
-            let bucket_num = bucket_func(&object);
+            if let Some(base_refs) = &mut base_refs {
+                let object_id = object.object_id();
+                // The base objects before this one are no longer live
+                while let Some(base_ref) = base_refs.next_if(|base_ref| {
+                    base_ref
+                        .as_ref()
+                        .map_or(true, |base_ref| base_ref.0 < object_id)
+                }) {
+                    write_removed(&base_ref?)?;
+                }
+                if let Some(base_ref) = base_refs.next_if(|base_ref| {
+                    base_ref
+                        .as_ref()
+                        .is_ok_and(|base_ref| base_ref.0 == object_id)
+                }) {
+                    let base_ref = base_ref?;
+                    // Unchanged since the base epoch
+                    if base_ref == object.object_reference() {
+                        continue;
+                    }
+                    write_removed(&base_ref)?;
+                }
+            }
+            let bucket_num = bucket_func(&object.object_id());
             // Creates a new LiveObjectSetWriterV1 for the bucket if it does not exist
             if let Vacant(entry) = object_writers.entry(bucket_num) {
                 entry.insert(LiveObjectSetWriterV1::new(
@@ -445,28 +635,51 @@ impl StateSnapshotWriterV1 {
             root_state_hash,
             "Root state hash mismatch!"
         );
+        // The base objects after the last live object are no longer live
+        for base_ref in base_refs.into_iter().flatten() {
+            write_removed(&base_ref?)?;
+        }
         let mut files = vec![];
         // Flushes the object and reference files to disk, informs the file channel of
         // flushed files and get the FileMetadata
         for (_, writer) in object_writers.into_iter() {
             files.extend(writer.done()?);
         }
+        for (_, writer) in removed_writers.into_iter() {
+            files.extend(writer.done()?);
+        }
         // Write the manifest file for the epoch(bucket)
-        self.write_manifest(epoch, files)?;
+        self.write_manifest(epoch, base_epoch, files, root_state_hash)?;
         Ok(())
     }
 
     /// Writes the manifest file for the provided FileMetadata of an epoch and
     /// its sha3 checksum.
-    fn write_manifest(&mut self, epoch: u64, file_metadata: Vec<FileMetadata>) -> Result<()> {
+    fn write_manifest(
+        &mut self,
+        epoch: u64,
+        base_epoch: Option<u64>,
+        file_metadata: Vec<FileMetadata>,
+        root_state_hash: ECMHLiveObjectSetDigest,
+    ) -> Result<()> {
         let (f, manifest_file_path) = self.manifest_file(epoch)?;
         let mut wbuf = BufWriter::new(f);
-        let manifest: Manifest = Manifest::V1(ManifestV1 {
-            snapshot_version: 1,
-            address_length: ObjectID::LENGTH as u64,
-            file_metadata,
-            epoch,
-        });
+        let manifest = match base_epoch {
+            Some(base_epoch) => Manifest::V2(ManifestV2 {
+                snapshot_version: 2,
+                address_length: ObjectID::LENGTH as u64,
+                file_metadata,
+                epoch,
+                base_epoch,
+                root_state_hash,
+            }),
+            None => Manifest::V1(ManifestV1 {
+                snapshot_version: 1,
+                address_length: ObjectID::LENGTH as u64,
+                file_metadata,
+                epoch,
+            }),
+        };
         let serialized_manifest = bcs::to_bytes(&manifest)?;
         wbuf.write_all(&serialized_manifest)?;
         wbuf.flush()?;
@@ -507,7 +720,7 @@ impl StateSnapshotWriterV1 {
         Ok((f, manifest_file_path))
     }
 
-    fn bucket_func(_object: &LiveObject) -> u32 {
+    fn bucket_func(_object_id: &ObjectID) -> u32 {
         // TODO: Use the hash bucketing function used for accumulator tree if there is
         // one
         1u32
@@ -517,6 +730,30 @@ impl StateSnapshotWriterV1 {
         Path::from(format!("epoch_{}", epoch))
     }
 
+    /// Returns a reader of the snapshot of `base_epoch` in the remote store and
+    /// the snapshots it is a delta of, with their reference files downloaded
+    /// to the base staging dir.
+    async fn base_reader(&self, base_epoch: u64) -> Result<StateSnapshotReaderV1> {
+        let staging_dir = self.local_staging_dir.join(BASE_STAGING_DIR);
+        fs::create_dir_all(&staging_dir)?;
+        let local_object_store = ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::File),
+            directory: Some(staging_dir.clone()),
+            ..Default::default()
+        }
+        .make()?;
+        StateSnapshotReaderV1::new_from_stores(
+            base_epoch,
+            Arc::new(self.remote_object_store.clone()),
+            Arc::new(local_object_store),
+            staging_dir.clone(),
+            usize::MAX,
+            NonZeroUsize::new(self.concurrency).unwrap(),
+            MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+        )
+        .await
+    }
+
     /// Creates a new epoch directory and a new staging directory for the epoch
     /// in the local store. Deletes the old ones if they exist.
     async fn setup_epoch_dir(&self, epoch: u64) -> Result<()> {
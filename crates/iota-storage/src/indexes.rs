@@ -40,7 +40,9 @@ use tokio::{sync::OwnedMutexGuard, task::spawn_blocking};
 use tracing::{debug, trace};
 use typed_store::{
     DBMapUtils, TypedStoreError,
-    rocks::{DBBatch, DBMap, DBOptions, MetricConf, default_db_options, read_size_from_env},
+    rocks::{
+        DBBatch, DBMap, DBOptions, MetricConf, RocksDB, default_db_options, read_size_from_env,
+    },
     traits::{Map, TableSummary, TypedStoreDebug},
 };
 
@@ -311,6 +313,11 @@ impl IndexStore {
         &self.tables
     }
 
+    /// Returns the database backing all tables of the store.
+    pub fn rocksdb(&self) -> &Arc<RocksDB> {
+        &self.tables.transaction_order.rocksdb
+    }
+
     pub async fn index_coin(
         &self,
         digest: &TransactionDigest,
@@ -220,6 +220,8 @@ impl ValidatorConfigBuilder {
             firewall_config: self.firewall_config,
             execution_cache: ExecutionCacheConfig::default(),
             enable_validator_tx_finalizer: true,
+            sparse_node_config: None,
+            rest_archive_config: None,
         }
     }
 
@@ -509,6 +511,8 @@ impl FullnodeConfigBuilder {
             execution_cache: ExecutionCacheConfig::default(),
             // This is a validator specific feature.
             enable_validator_tx_finalizer: false,
+            sparse_node_config: None,
+            rest_archive_config: None,
         }
     }
 
@@ -12,9 +12,9 @@ use iota_types::{
         KeypairTraits,
     },
     messages_checkpoint::{
-        CertifiedCheckpointSummary, CheckpointDigest, CheckpointSequenceNumber, CheckpointSummary,
-        CheckpointVersionSpecificData, EndOfEpochData, FullCheckpointContents, VerifiedCheckpoint,
-        VerifiedCheckpointContents,
+        CertifiedCheckpointSummary, CheckpointContents, CheckpointDigest, CheckpointSequenceNumber,
+        CheckpointSummary, CheckpointVersionSpecificData, EndOfEpochData, FullCheckpointContents,
+        VerifiedCheckpoint, VerifiedCheckpointContents,
     },
 };
 use shared_crypto::intent::{Intent, IntentMessage, IntentScope};
@@ -219,6 +219,31 @@ impl CommitteeFixture {
         )
     }
 
+    /// Creates the certified checkpoint following `previous_checkpoint` with
+    /// the given contents.
+    pub fn make_checkpoint_with_contents(
+        &self,
+        previous_checkpoint: &VerifiedCheckpoint,
+        contents: &CheckpointContents,
+    ) -> VerifiedCheckpoint {
+        let summary = CheckpointSummary {
+            epoch: self.epoch,
+            sequence_number: previous_checkpoint.sequence_number + 1,
+            network_total_transactions: previous_checkpoint.network_total_transactions
+                + contents.size() as u64,
+            content_digest: *contents.digest(),
+            previous_digest: Some(*previous_checkpoint.digest()),
+            epoch_rolling_gas_cost_summary: Default::default(),
+            end_of_epoch_data: None,
+            timestamp_ms: 0,
+            version_specific_data: bcs::to_bytes(&CheckpointVersionSpecificData::empty_for_tests())
+                .unwrap(),
+            checkpoint_commitments: Default::default(),
+        };
+
+        self.create_certified_checkpoint(summary)
+    }
+
     pub fn make_end_of_epoch_checkpoint(
         &self,
         previous_checkpoint: VerifiedCheckpoint,
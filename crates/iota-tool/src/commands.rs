@@ -33,7 +33,7 @@ use crate::{
     db_tool::{DbToolCommand, execute_db_tool_command, print_db_all_tables},
     download_db_snapshot, download_formal_snapshot, dump_checkpoints_from_archive,
     get_latest_available_epoch, get_object, get_transaction_block, make_clients,
-    restore_from_db_checkpoint, verify_archive, verify_archive_by_checksum,
+    restore_from_db_checkpoint, verify_archive, verify_archive_by_checksum, verify_formal_snapshot,
 };
 
 #[derive(Parser, Clone, ValueEnum)]
@@ -385,6 +385,63 @@ pub enum ToolCommand {
         all_checkpoints: bool,
     },
 
+    /// Checks a formal snapshot against the root state commitment of the end of
+    /// epoch checkpoint, without restoring it.
+    #[clap(name = "verify-formal-snapshot")]
+    VerifyFormalSnapshot {
+        #[clap(long = "epoch", conflicts_with = "latest")]
+        epoch: Option<u64>,
+        #[clap(long = "genesis")]
+        genesis: PathBuf,
+        /// Directory for the checkpoint summaries and snapshot reference files
+        /// downloaded during verification. They are removed once done.
+        #[clap(long = "path")]
+        path: PathBuf,
+        /// Number of parallel downloads to perform. Defaults to a reasonable
+        /// value based on number of available logical cores.
+        #[clap(long = "num-parallel-downloads")]
+        num_parallel_downloads: Option<usize>,
+        /// Network of the snapshot. Defaults to "mainnet".
+        /// If `--snapshot-bucket` is not specified, the value of this flag is
+        /// used to construct default bucket names.
+        #[clap(long = "network", default_value = "mainnet")]
+        network: Chain,
+        /// Snapshot bucket name. If not specified, defaults are
+        /// based on value of `--network` flag.
+        #[clap(long = "snapshot-bucket", conflicts_with = "no_sign_request")]
+        snapshot_bucket: Option<String>,
+        /// Snapshot bucket type
+        #[clap(
+            long = "snapshot-bucket-type",
+            conflicts_with = "no_sign_request",
+            help = "Required if --no-sign-request is not set"
+        )]
+        snapshot_bucket_type: Option<ObjectStoreType>,
+        /// Path to snapshot directory on local filesystem.
+        /// Only applicable if `--snapshot-bucket-type` is "file".
+        #[clap(long = "snapshot-path")]
+        snapshot_path: Option<PathBuf>,
+        /// If true, no authentication is needed to read the snapshot
+        #[clap(
+            long = "no-sign-request",
+            conflicts_with_all = &["snapshot_bucket", "snapshot_bucket_type"],
+            help = "if set, no authentication is needed to read the snapshot"
+        )]
+        no_sign_request: bool,
+        /// Verify the snapshot of the latest available epoch.
+        /// If `--epoch` is specified, then this flag gets ignored.
+        #[clap(
+            long = "latest",
+            conflicts_with = "epoch",
+            help = "defaults to latest available snapshot in chosen bucket"
+        )]
+        latest: bool,
+        /// If false (default), log level will be overridden to "off",
+        /// and output will be reduced to necessary status information.
+        #[clap(long = "verbose")]
+        verbose: bool,
+    },
+
     #[clap(name = "replay")]
     Replay {
         #[arg(long = "rpc")]
@@ -688,180 +745,14 @@ impl ToolCommand {
                         .checked_sub(1)
                         .expect("Failed to get number of CPUs")
                 });
-                let snapshot_bucket =
-                    snapshot_bucket.or_else(|| match (network, no_sign_request) {
-                        (Chain::Mainnet, false) => Some(
-                            env::var("MAINNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("iota-mainnet-formal".to_string()),
-                        ),
-                        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
-                        (Chain::Testnet, _) => Some(
-                            env::var("TESTNET_FORMAL_SIGNED_BUCKET")
-                                .unwrap_or("iota-testnet-formal".to_string()),
-                        ),
-                        (Chain::Unknown, _) => {
-                            panic!("Cannot generate default snapshot bucket for unknown network");
-                        }
-                    });
-
-                let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
-                    if no_sign_request {
-                        if network == Chain::Mainnet {
-                            Some("https://dbfiles.mainnet.iota.cafe/formal".to_string())
-                        } else if network == Chain::Testnet {
-                            Some("https://dbfiles.testnet.iota.cafe/formal".to_string())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                });
-
-                let snapshot_bucket_type = if no_sign_request {
-                    ObjectStoreType::S3
-                } else {
-                    snapshot_bucket_type
-                        .expect("You must set either --snapshot-bucket-type or --no-sign-request")
-                };
-                let snapshot_store_config = match snapshot_bucket_type {
-                    ObjectStoreType::S3 => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::S3),
-                        bucket: snapshot_bucket.filter(|s| !s.is_empty()),
-                        aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
-                        aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
-                        aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
-                        aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
-                        aws_virtual_hosted_style_request: env::var(
-                            "AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS",
-                        )
-                        .ok()
-                        .and_then(|b| b.parse().ok())
-                        .unwrap_or(no_sign_request),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::GCS => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::GCS),
-                        bucket: snapshot_bucket,
-                        google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::Azure => ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::Azure),
-                        bucket: snapshot_bucket,
-                        azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
-                        azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY")
-                            .ok(),
-                        object_store_connection_limit: 200,
-                        no_sign_request,
-                        ..Default::default()
-                    },
-                    ObjectStoreType::File => {
-                        if snapshot_path.is_some() {
-                            ObjectStoreConfig {
-                                object_store: Some(ObjectStoreType::File),
-                                directory: snapshot_path,
-                                ..Default::default()
-                            }
-                        } else {
-                            panic!(
-                                "--snapshot-path must be specified for --snapshot-bucket-type=file"
-                            );
-                        }
-                    }
-                };
-
-                let archive_bucket = Some(
-                    env::var("FORMAL_SNAPSHOT_ARCHIVE_BUCKET").unwrap_or_else(|_| match network {
-                        Chain::Mainnet => "iota-mainnet-archives".to_string(),
-                        Chain::Testnet => "iota-testnet-archives".to_string(),
-                        Chain::Unknown => {
-                            panic!("Cannot generate default archive bucket for unknown network");
-                        }
-                    }),
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
                 );
-
-                let mut custom_archive_enabled = false;
-                if let Ok(custom_archive_check) = env::var("CUSTOM_ARCHIVE_BUCKET") {
-                    if custom_archive_check == "true" {
-                        custom_archive_enabled = true;
-                    }
-                }
-                let archive_store_config = if custom_archive_enabled {
-                    let aws_region = Some(
-                        env::var("FORMAL_SNAPSHOT_ARCHIVE_REGION")
-                            .unwrap_or("us-west-2".to_string()),
-                    );
-
-                    let archive_bucket_type = env::var("FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE").expect("If setting `CUSTOM_ARCHIVE_BUCKET=true` Must set FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE, and credentials");
-                    match archive_bucket_type.to_ascii_lowercase().as_str() {
-                        "s3" => ObjectStoreConfig {
-                            object_store: Some(ObjectStoreType::S3),
-                            bucket: archive_bucket.filter(|s| !s.is_empty()),
-                            aws_access_key_id: env::var("AWS_ARCHIVE_ACCESS_KEY_ID").ok(),
-                            aws_secret_access_key: env::var("AWS_ARCHIVE_SECRET_ACCESS_KEY").ok(),
-                            aws_region,
-                            aws_endpoint: env::var("AWS_ARCHIVE_ENDPOINT").ok(),
-                            aws_virtual_hosted_style_request: env::var(
-                                "AWS_ARCHIVE_VIRTUAL_HOSTED_REQUESTS",
-                            )
-                            .ok()
-                            .and_then(|b| b.parse().ok())
-                            .unwrap_or(false),
-                            object_store_connection_limit: 50,
-                            no_sign_request: false,
-                            ..Default::default()
-                        },
-                        "gcs" => ObjectStoreConfig {
-                            object_store: Some(ObjectStoreType::GCS),
-                            bucket: archive_bucket,
-                            google_service_account: env::var(
-                                "GCS_ARCHIVE_SERVICE_ACCOUNT_FILE_PATH",
-                            )
-                            .ok(),
-                            object_store_connection_limit: 50,
-                            no_sign_request: false,
-                            ..Default::default()
-                        },
-                        "azure" => ObjectStoreConfig {
-                            object_store: Some(ObjectStoreType::Azure),
-                            bucket: archive_bucket,
-                            azure_storage_account: env::var("AZURE_ARCHIVE_STORAGE_ACCOUNT").ok(),
-                            azure_storage_access_key: env::var("AZURE_ARCHIVE_STORAGE_ACCESS_KEY")
-                                .ok(),
-                            object_store_connection_limit: 50,
-                            no_sign_request: false,
-                            ..Default::default()
-                        },
-                        _ => panic!(
-                            "If setting `CUSTOM_ARCHIVE_BUCKET=true` must set FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE to one of 'gcs', 'azure', or 's3' "
-                        ),
-                    }
-                } else {
-                    // if not explicitly overridden, just default to the permissionless archive
-                    // store
-                    ObjectStoreConfig {
-                        object_store: Some(ObjectStoreType::S3),
-                        bucket: archive_bucket.filter(|s| !s.is_empty()),
-                        aws_region: Some("us-west-2".to_string()),
-                        aws_endpoint: env::var("AWS_ARCHIVE_ENDPOINT").ok(),
-                        aws_virtual_hosted_style_request: env::var(
-                            "AWS_ARCHIVE_VIRTUAL_HOSTED_REQUESTS",
-                        )
-                        .ok()
-                        .and_then(|b| b.parse().ok())
-                        .unwrap_or(false),
-                        object_store_connection_limit: 200,
-                        no_sign_request: true,
-                        ..Default::default()
-                    }
-                };
+                let archive_store_config = formal_snapshot_archive_store_config(network);
                 let latest_available_epoch =
                     latest.then_some(get_latest_available_epoch(&snapshot_store_config).await?);
                 let epoch_to_download = epoch.or(latest_available_epoch).expect(
@@ -891,6 +782,54 @@ impl ToolCommand {
                 )
                 .await?;
             }
+            ToolCommand::VerifyFormalSnapshot {
+                epoch,
+                genesis,
+                path,
+                num_parallel_downloads,
+                network,
+                snapshot_bucket,
+                snapshot_bucket_type,
+                snapshot_path,
+                no_sign_request,
+                latest,
+                verbose,
+            } => {
+                if !verbose {
+                    tracing_handle
+                        .update_log("off")
+                        .expect("Failed to update log level");
+                }
+                let num_parallel_downloads = num_parallel_downloads.unwrap_or_else(|| {
+                    num_cpus::get()
+                        .checked_sub(1)
+                        .expect("Failed to get number of CPUs")
+                });
+                let snapshot_store_config = formal_snapshot_store_config(
+                    network,
+                    snapshot_bucket,
+                    snapshot_bucket_type,
+                    snapshot_path,
+                    no_sign_request,
+                );
+                let archive_store_config = formal_snapshot_archive_store_config(network);
+                let latest_available_epoch =
+                    latest.then_some(get_latest_available_epoch(&snapshot_store_config).await?);
+                let epoch_to_verify = epoch.or(latest_available_epoch).expect(
+                    "Either pass epoch with --epoch <epoch_num> or use latest with --latest",
+                );
+                check_completed_snapshot(&snapshot_store_config, epoch_to_verify).await?;
+                let report = verify_formal_snapshot(
+                    &path,
+                    epoch_to_verify,
+                    &genesis,
+                    snapshot_store_config,
+                    archive_store_config,
+                    num_parallel_downloads,
+                )
+                .await?;
+                println!("{report}");
+            }
             ToolCommand::DownloadDBSnapshot {
                 epoch,
                 path,
@@ -1105,3 +1044,173 @@ impl ToolCommand {
         Ok(())
     }
 }
+
+/// Returns the config of the store holding the formal snapshots of `network`,
+/// unless overridden by the arguments or the environment.
+fn formal_snapshot_store_config(
+    network: Chain,
+    snapshot_bucket: Option<String>,
+    snapshot_bucket_type: Option<ObjectStoreType>,
+    snapshot_path: Option<PathBuf>,
+    no_sign_request: bool,
+) -> ObjectStoreConfig {
+    let snapshot_bucket = snapshot_bucket.or_else(|| match (network, no_sign_request) {
+        (Chain::Mainnet, false) => Some(
+            env::var("MAINNET_FORMAL_SIGNED_BUCKET").unwrap_or("iota-mainnet-formal".to_string()),
+        ),
+        (Chain::Mainnet, true) => env::var("MAINNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, true) => env::var("TESTNET_FORMAL_UNSIGNED_BUCKET").ok(),
+        (Chain::Testnet, _) => Some(
+            env::var("TESTNET_FORMAL_SIGNED_BUCKET").unwrap_or("iota-testnet-formal".to_string()),
+        ),
+        (Chain::Unknown, _) => {
+            panic!("Cannot generate default snapshot bucket for unknown network");
+        }
+    });
+
+    let aws_endpoint = env::var("AWS_SNAPSHOT_ENDPOINT").ok().or_else(|| {
+        if no_sign_request {
+            if network == Chain::Mainnet {
+                Some("https://dbfiles.mainnet.iota.cafe/formal".to_string())
+            } else if network == Chain::Testnet {
+                Some("https://dbfiles.testnet.iota.cafe/formal".to_string())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    });
+
+    let snapshot_bucket_type = if no_sign_request {
+        ObjectStoreType::S3
+    } else {
+        snapshot_bucket_type
+            .expect("You must set either --snapshot-bucket-type or --no-sign-request")
+    };
+    match snapshot_bucket_type {
+        ObjectStoreType::S3 => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::S3),
+            bucket: snapshot_bucket.filter(|s| !s.is_empty()),
+            aws_access_key_id: env::var("AWS_SNAPSHOT_ACCESS_KEY_ID").ok(),
+            aws_secret_access_key: env::var("AWS_SNAPSHOT_SECRET_ACCESS_KEY").ok(),
+            aws_region: env::var("AWS_SNAPSHOT_REGION").ok(),
+            aws_endpoint: aws_endpoint.filter(|s| !s.is_empty()),
+            aws_virtual_hosted_style_request: env::var("AWS_SNAPSHOT_VIRTUAL_HOSTED_REQUESTS")
+                .ok()
+                .and_then(|b| b.parse().ok())
+                .unwrap_or(no_sign_request),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::GCS => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::GCS),
+            bucket: snapshot_bucket,
+            google_service_account: env::var("GCS_SNAPSHOT_SERVICE_ACCOUNT_FILE_PATH").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::Azure => ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::Azure),
+            bucket: snapshot_bucket,
+            azure_storage_account: env::var("AZURE_SNAPSHOT_STORAGE_ACCOUNT").ok(),
+            azure_storage_access_key: env::var("AZURE_SNAPSHOT_STORAGE_ACCESS_KEY").ok(),
+            object_store_connection_limit: 200,
+            no_sign_request,
+            ..Default::default()
+        },
+        ObjectStoreType::File => {
+            if snapshot_path.is_some() {
+                ObjectStoreConfig {
+                    object_store: Some(ObjectStoreType::File),
+                    directory: snapshot_path,
+                    ..Default::default()
+                }
+            } else {
+                panic!("--snapshot-path must be specified for --snapshot-bucket-type=file");
+            }
+        }
+    }
+}
+
+/// Returns the config of the archive store holding the checkpoints of
+/// `network`, unless overridden by the environment.
+fn formal_snapshot_archive_store_config(network: Chain) -> ObjectStoreConfig {
+    let archive_bucket = Some(
+        env::var("FORMAL_SNAPSHOT_ARCHIVE_BUCKET").unwrap_or_else(|_| match network {
+            Chain::Mainnet => "iota-mainnet-archives".to_string(),
+            Chain::Testnet => "iota-testnet-archives".to_string(),
+            Chain::Unknown => {
+                panic!("Cannot generate default archive bucket for unknown network");
+            }
+        }),
+    );
+
+    let mut custom_archive_enabled = false;
+    if let Ok(custom_archive_check) = env::var("CUSTOM_ARCHIVE_BUCKET") {
+        if custom_archive_check == "true" {
+            custom_archive_enabled = true;
+        }
+    }
+    if custom_archive_enabled {
+        let aws_region =
+            Some(env::var("FORMAL_SNAPSHOT_ARCHIVE_REGION").unwrap_or("us-west-2".to_string()));
+
+        let archive_bucket_type = env::var("FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE").expect("If setting `CUSTOM_ARCHIVE_BUCKET=true` Must set FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE, and credentials");
+        match archive_bucket_type.to_ascii_lowercase().as_str() {
+            "s3" => ObjectStoreConfig {
+                object_store: Some(ObjectStoreType::S3),
+                bucket: archive_bucket.filter(|s| !s.is_empty()),
+                aws_access_key_id: env::var("AWS_ARCHIVE_ACCESS_KEY_ID").ok(),
+                aws_secret_access_key: env::var("AWS_ARCHIVE_SECRET_ACCESS_KEY").ok(),
+                aws_region,
+                aws_endpoint: env::var("AWS_ARCHIVE_ENDPOINT").ok(),
+                aws_virtual_hosted_style_request: env::var("AWS_ARCHIVE_VIRTUAL_HOSTED_REQUESTS")
+                    .ok()
+                    .and_then(|b| b.parse().ok())
+                    .unwrap_or(false),
+                object_store_connection_limit: 50,
+                no_sign_request: false,
+                ..Default::default()
+            },
+            "gcs" => ObjectStoreConfig {
+                object_store: Some(ObjectStoreType::GCS),
+                bucket: archive_bucket,
+                google_service_account: env::var("GCS_ARCHIVE_SERVICE_ACCOUNT_FILE_PATH").ok(),
+                object_store_connection_limit: 50,
+                no_sign_request: false,
+                ..Default::default()
+            },
+            "azure" => ObjectStoreConfig {
+                object_store: Some(ObjectStoreType::Azure),
+                bucket: archive_bucket,
+                azure_storage_account: env::var("AZURE_ARCHIVE_STORAGE_ACCOUNT").ok(),
+                azure_storage_access_key: env::var("AZURE_ARCHIVE_STORAGE_ACCESS_KEY").ok(),
+                object_store_connection_limit: 50,
+                no_sign_request: false,
+                ..Default::default()
+            },
+            _ => panic!(
+                "If setting `CUSTOM_ARCHIVE_BUCKET=true` must set FORMAL_SNAPSHOT_ARCHIVE_BUCKET_TYPE to one of 'gcs', 'azure', or 's3' "
+            ),
+        }
+    } else {
+        // if not explicitly overridden, just default to the permissionless archive
+        // store
+        ObjectStoreConfig {
+            object_store: Some(ObjectStoreType::S3),
+            bucket: archive_bucket.filter(|s| !s.is_empty()),
+            aws_region: Some("us-west-2".to_string()),
+            aws_endpoint: env::var("AWS_ARCHIVE_ENDPOINT").ok(),
+            aws_virtual_hosted_style_request: env::var("AWS_ARCHIVE_VIRTUAL_HOSTED_REQUESTS")
+                .ok()
+                .and_then(|b| b.parse().ok())
+                .unwrap_or(false),
+            object_store_connection_limit: 200,
+            no_sign_request: true,
+            ..Default::default()
+        }
+    }
+}
@@ -25,10 +25,12 @@ use typed_store::rocks::MetricConf;
 use self::{
     db_dump::{StoreName, dump_table, duplicate_objects_summary, list_tables, table_summary},
     index_search::{SearchRange, search_index},
+    table_backup::{BackupStoreName, export_table, import_table},
 };
 use crate::db_tool::db_dump::{compact, print_table_metadata, prune_checkpoints, prune_objects};
 pub mod db_dump;
 mod index_search;
+mod table_backup;
 
 #[derive(Parser)]
 #[command(rename_all = "kebab-case")]
@@ -51,6 +53,8 @@ pub enum DbToolCommand {
     PruneObjects,
     PruneCheckpoints,
     SetCheckpointWatermark(SetCheckpointWatermarkOptions),
+    ExportTable(ExportTableOptions),
+    ImportTable(ImportTableOptions),
 }
 
 #[derive(Parser)]
@@ -187,6 +191,38 @@ pub struct SetCheckpointWatermarkOptions {
     highest_synced: Option<CheckpointSequenceNumber>,
 }
 
+#[derive(Parser)]
+#[command(rename_all = "kebab-case")]
+pub struct ExportTableOptions {
+    /// The store which holds the table
+    #[arg(long = "store", short = 's', value_enum)]
+    store_name: BackupStoreName,
+    /// The name of the table to export
+    #[arg(long = "table-name", short = 't')]
+    table_name: String,
+    /// The directory to write the backup to. An incomplete backup in this
+    /// directory is resumed
+    #[arg(long = "backup-path")]
+    backup_path: PathBuf,
+    /// The hex encoded first key to export (inclusive)
+    #[arg(long = "start-key")]
+    start_key: Option<String>,
+    /// The hex encoded key to stop the export at (exclusive)
+    #[arg(long = "end-key")]
+    end_key: Option<String>,
+    /// The number of records per backup file
+    #[arg(long = "records-per-chunk", default_value_t = 100_000)]
+    records_per_chunk: usize,
+}
+
+#[derive(Parser)]
+#[command(rename_all = "kebab-case")]
+pub struct ImportTableOptions {
+    /// The directory holding the backup to import
+    #[arg(long = "backup-path")]
+    backup_path: PathBuf,
+}
+
 pub async fn execute_db_tool_command(db_path: PathBuf, cmd: DbToolCommand) -> anyhow::Result<()> {
     match cmd {
         DbToolCommand::ListTables => print_db_all_tables(db_path),
@@ -242,6 +278,16 @@ pub async fn execute_db_tool_command(db_path: PathBuf, cmd: DbToolCommand) -> an
             Ok(())
         }
         DbToolCommand::SetCheckpointWatermark(d) => set_checkpoint_watermark(&db_path, d),
+        DbToolCommand::ExportTable(d) => export_table(
+            &db_path,
+            d.store_name,
+            &d.table_name,
+            &d.backup_path,
+            d.start_key.map(hex::decode).transpose()?,
+            d.end_key.map(hex::decode).transpose()?,
+            d.records_per_chunk,
+        ),
+        DbToolCommand::ImportTable(d) => import_table(&db_path, &d.backup_path),
     }
 }
 
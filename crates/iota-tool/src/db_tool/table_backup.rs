@@ -0,0 +1,459 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    fs,
+    io::{BufWriter, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail};
+use clap::ValueEnum;
+use iota_core::{
+    authority::authority_store_tables::AuthorityPerpetualTables, checkpoints::CheckpointStore,
+};
+use iota_storage::{
+    blob::{Blob, BlobEncoding},
+    compute_sha3_checksum,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use typed_store::rocks::{DBMap, MetricConf, ReadWriteOptions};
+
+/// Tables are backed up as a directory of chunk files holding the raw BCS
+/// encoded keys and values of the table in key order, along with a manifest
+/// listing the chunks and their checksums.
+///
+/// Table Backup Directory Layout
+///  - backup/
+///     - MANIFEST
+///     - IMPORT_PROGRESS
+///     - 0.rec
+///     - 1.rec
+///     - ...
+///
+/// Chunk File Disk Format
+/// ┌──────────────────────────────┐
+/// │       magic <4 byte>         │
+/// ├──────────────────────────────┤
+/// │ ┌──────────────────────────┐ │
+/// │ │   Blob 1 (key, value)    │ │
+/// │ ├──────────────────────────┤ │
+/// │ │          ...             │ │
+/// │ ├──────────────────────────┤ │
+/// │ │   Blob N (key, value)    │ │
+/// │ └──────────────────────────┘ │
+/// └──────────────────────────────┘
+const TABLE_BACKUP_FILE_MAGIC: u32 = 0x0DB0BACC;
+const MANIFEST_FILENAME: &str = "MANIFEST";
+const IMPORT_PROGRESS_FILENAME: &str = "IMPORT_PROGRESS";
+const CHUNK_FILE_SUFFIX: &str = "rec";
+/// Number of keys deleted per write batch when clearing the key range of a
+/// table before an import.
+const CLEAR_BATCH_SIZE: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+pub enum BackupStoreName {
+    Perpetual,
+    Checkpoints,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ChunkMetadata {
+    num_records: u64,
+    last_key: Vec<u8>,
+    sha3_digest: [u8; 32],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TableBackupManifest {
+    store: BackupStoreName,
+    table_name: String,
+    start_key: Option<Vec<u8>>,
+    end_key: Option<Vec<u8>>,
+    chunks: Vec<ChunkMetadata>,
+    complete: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ImportProgress {
+    db_path: PathBuf,
+    /// Whether the key range of the backup was already cleared in the target
+    /// table.
+    #[serde(default)]
+    range_cleared: bool,
+    num_chunks_imported: usize,
+}
+
+/// Opens the given table of the store under `db_path` as a map of raw keys and
+/// values.
+fn open_raw_table(
+    db_path: &Path,
+    store: BackupStoreName,
+    table_name: &str,
+) -> anyhow::Result<DBMap<Vec<u8>, Vec<u8>>> {
+    let rocksdb = match store {
+        BackupStoreName::Perpetual => {
+            if !AuthorityPerpetualTables::describe_tables().contains_key(table_name) {
+                bail!("Table {table_name} not found in perpetual store");
+            }
+            AuthorityPerpetualTables::open(&db_path.join("store"), None)
+                .objects
+                .rocksdb
+        }
+        BackupStoreName::Checkpoints => {
+            if !CheckpointStore::describe_tables().contains_key(table_name) {
+                bail!("Table {table_name} not found in checkpoint store");
+            }
+            CheckpointStore::open_tables_read_write(
+                db_path.join("checkpoints"),
+                MetricConf::default(),
+                None,
+                None,
+            )
+            .rocksdb()
+            .clone()
+        }
+    };
+    Ok(DBMap::reopen(
+        &rocksdb,
+        Some(table_name),
+        &ReadWriteOptions::default(),
+        false,
+    )?)
+}
+
+fn chunk_path(backup_path: &Path, index: usize) -> PathBuf {
+    backup_path.join(format!("{index}.{CHUNK_FILE_SUFFIX}"))
+}
+
+/// Writes `value` as JSON to `path`, replacing any previous content atomically.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Returns the smallest key which sorts after `key`.
+fn next_key(key: &[u8]) -> Vec<u8> {
+    let mut next = key.to_vec();
+    next.push(0);
+    next
+}
+
+/// Exports the entries of a table with keys in `[start_key, end_key)` to
+/// `backup_path`. If the directory already holds an incomplete export of the
+/// same table and range, the export resumes after its last chunk.
+pub fn export_table(
+    db_path: &Path,
+    store: BackupStoreName,
+    table_name: &str,
+    backup_path: &Path,
+    start_key: Option<Vec<u8>>,
+    end_key: Option<Vec<u8>>,
+    records_per_chunk: usize,
+) -> anyhow::Result<()> {
+    let table = open_raw_table(db_path, store, table_name)?;
+    export_entries(
+        &table,
+        store,
+        table_name,
+        backup_path,
+        start_key,
+        end_key,
+        records_per_chunk,
+    )
+}
+
+fn export_entries(
+    table: &DBMap<Vec<u8>, Vec<u8>>,
+    store: BackupStoreName,
+    table_name: &str,
+    backup_path: &Path,
+    start_key: Option<Vec<u8>>,
+    end_key: Option<Vec<u8>>,
+    records_per_chunk: usize,
+) -> anyhow::Result<()> {
+    let manifest_path = backup_path.join(MANIFEST_FILENAME);
+    let mut manifest = if manifest_path.exists() {
+        let manifest = read_manifest(backup_path)?;
+        if manifest.store != store
+            || manifest.table_name != table_name
+            || manifest.start_key != start_key
+            || manifest.end_key != end_key
+        {
+            bail!(
+                "Backup at {} is for a different table or key range",
+                backup_path.display()
+            );
+        }
+        if manifest.complete {
+            info!("Backup of table {table_name} is already complete");
+            return Ok(());
+        }
+        info!(
+            "Resuming backup of table {table_name} after {} chunks",
+            manifest.chunks.len()
+        );
+        manifest
+    } else {
+        fs::create_dir_all(backup_path)?;
+        TableBackupManifest {
+            store,
+            table_name: table_name.to_string(),
+            start_key: start_key.clone(),
+            end_key: end_key.clone(),
+            chunks: vec![],
+            complete: false,
+        }
+    };
+
+    let lower_bound = match manifest.chunks.last() {
+        Some(chunk) => Some(next_key(&chunk.last_key)),
+        None => start_key,
+    };
+    let mut records = vec![];
+    for entry in table.raw_iterator_cf_with_bounds(lower_bound, end_key) {
+        let (key, value) = entry?;
+        records.push((key.into_vec(), value.into_vec()));
+        if records.len() == records_per_chunk {
+            write_chunk(backup_path, &mut manifest, std::mem::take(&mut records))?;
+            write_json(&manifest_path, &manifest)?;
+        }
+    }
+    if !records.is_empty() {
+        write_chunk(backup_path, &mut manifest, records)?;
+    }
+    manifest.complete = true;
+    write_json(&manifest_path, &manifest)?;
+    info!(
+        "Exported {} records of table {table_name} in {} chunks",
+        manifest.chunks.iter().map(|c| c.num_records).sum::<u64>(),
+        manifest.chunks.len()
+    );
+    Ok(())
+}
+
+fn write_chunk(
+    backup_path: &Path,
+    manifest: &mut TableBackupManifest,
+    records: Vec<(Vec<u8>, Vec<u8>)>,
+) -> anyhow::Result<()> {
+    let path = chunk_path(backup_path, manifest.chunks.len());
+    let mut writer = BufWriter::new(fs::File::create(&path)?);
+    writer.write_all(&TABLE_BACKUP_FILE_MAGIC.to_be_bytes())?;
+    for record in records.iter() {
+        Blob::encode(record, BlobEncoding::Bcs)?.write(&mut writer)?;
+    }
+    writer.into_inner()?.sync_all()?;
+    manifest.chunks.push(ChunkMetadata {
+        num_records: records.len() as u64,
+        last_key: records.last().expect("Chunks are never empty").0.clone(),
+        sha3_digest: compute_sha3_checksum(&path)?,
+    });
+    Ok(())
+}
+
+fn read_chunk(path: &Path, metadata: &ChunkMetadata) -> anyhow::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    if compute_sha3_checksum(path)? != metadata.sha3_digest {
+        bail!("Checksum doesn't match for file: {}", path.display());
+    }
+    let bytes = fs::read(path)?;
+    let len = bytes.len() as u64;
+    let mut reader = Cursor::new(bytes);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if u32::from_be_bytes(magic) != TABLE_BACKUP_FILE_MAGIC {
+        bail!("Unexpected magic string in file: {}", path.display());
+    }
+    let mut records = Vec::with_capacity(metadata.num_records as usize);
+    while reader.position() < len {
+        records.push(Blob::read(&mut reader)?.decode()?);
+    }
+    if records.len() as u64 != metadata.num_records {
+        return Err(anyhow!(
+            "Expected {} records but found {} in file: {}",
+            metadata.num_records,
+            records.len(),
+            path.display()
+        ));
+    }
+    Ok(records)
+}
+
+/// Imports a complete table backup from `backup_path` into the same table of
+/// the store under `db_path`. All existing entries in the key range of the
+/// backup are deleted first, so the range ends up holding exactly the backed
+/// up entries. Progress is recorded in the backup directory after clearing the
+/// range and after every chunk, so an interrupted import into the same
+/// database resumes where it stopped.
+pub fn import_table(db_path: &Path, backup_path: &Path) -> anyhow::Result<()> {
+    let manifest = read_manifest(backup_path)?;
+    let table = open_raw_table(db_path, manifest.store, &manifest.table_name)?;
+    import_entries(&table, db_path, backup_path, &manifest)
+}
+
+fn read_manifest(backup_path: &Path) -> anyhow::Result<TableBackupManifest> {
+    Ok(serde_json::from_slice(&fs::read(
+        backup_path.join(MANIFEST_FILENAME),
+    )?)?)
+}
+
+fn import_entries(
+    table: &DBMap<Vec<u8>, Vec<u8>>,
+    db_path: &Path,
+    backup_path: &Path,
+    manifest: &TableBackupManifest,
+) -> anyhow::Result<()> {
+    if !manifest.complete {
+        bail!(
+            "Backup at {} is incomplete, resume the export first",
+            backup_path.display()
+        );
+    }
+    let progress_path = backup_path.join(IMPORT_PROGRESS_FILENAME);
+    let db_path = db_path.canonicalize()?;
+    let mut progress = match fs::read(&progress_path) {
+        Ok(bytes) => serde_json::from_slice::<ImportProgress>(&bytes)?,
+        Err(_) => ImportProgress::default(),
+    };
+    if progress.db_path != db_path {
+        progress = ImportProgress {
+            db_path,
+            range_cleared: false,
+            num_chunks_imported: 0,
+        };
+    }
+    if !progress.range_cleared {
+        let num_deleted = clear_range(table, manifest.start_key.clone(), manifest.end_key.clone())?;
+        info!(
+            "Deleted {num_deleted} existing records of table {} before the import",
+            manifest.table_name
+        );
+        progress.range_cleared = true;
+        write_json(&progress_path, &progress)?;
+    }
+    for (index, metadata) in manifest
+        .chunks
+        .iter()
+        .enumerate()
+        .skip(progress.num_chunks_imported)
+    {
+        let records = read_chunk(&chunk_path(backup_path, index), metadata)?;
+        let mut batch = table.batch();
+        batch.insert_batch_raw(table, records)?;
+        batch.write()?;
+        progress.num_chunks_imported = index + 1;
+        write_json(&progress_path, &progress)?;
+    }
+    table.flush()?;
+    info!(
+        "Imported {} chunks into table {}",
+        manifest.chunks.len(),
+        manifest.table_name
+    );
+    Ok(())
+}
+
+/// Deletes all entries of `table` with keys in `[start_key, end_key)` and
+/// returns the number of deleted entries.
+fn clear_range(
+    table: &DBMap<Vec<u8>, Vec<u8>>,
+    start_key: Option<Vec<u8>>,
+    end_key: Option<Vec<u8>>,
+) -> anyhow::Result<usize> {
+    let mut num_deleted = 0;
+    let mut keys = Vec::with_capacity(CLEAR_BATCH_SIZE);
+    for entry in table.raw_iterator_cf_with_bounds(start_key, end_key) {
+        keys.push(entry?.0);
+        if keys.len() == CLEAR_BATCH_SIZE {
+            num_deleted += keys.len();
+            delete_keys(table, std::mem::take(&mut keys))?;
+        }
+    }
+    num_deleted += keys.len();
+    delete_keys(table, keys)?;
+    Ok(num_deleted)
+}
+
+fn delete_keys(table: &DBMap<Vec<u8>, Vec<u8>>, keys: Vec<Box<[u8]>>) -> anyhow::Result<()> {
+    let mut batch = table.batch();
+    batch.delete_batch_raw(table, keys)?;
+    batch.write()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use iota_types::{base_types::ObjectID, object::Object};
+    use typed_store::Map;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_table_export_import() -> Result<(), anyhow::Error> {
+        let source_path = tempfile::tempdir()?.into_path();
+        let target_path = tempfile::tempdir()?.into_path();
+        let backup_path = tempfile::tempdir()?.into_path().join("backup");
+
+        let source = AuthorityPerpetualTables::open(&source_path.join("store"), None);
+        for _ in 0..10 {
+            source.insert_object_test_only(Object::immutable_with_id_for_testing(
+                ObjectID::random(),
+            ))?;
+        }
+        let source_table: DBMap<Vec<u8>, Vec<u8>> = DBMap::reopen(
+            &source.objects.rocksdb,
+            Some("objects"),
+            &ReadWriteOptions::default(),
+            false,
+        )?;
+        let export = || {
+            export_entries(
+                &source_table,
+                BackupStoreName::Perpetual,
+                "objects",
+                &backup_path,
+                None,
+                None,
+                3,
+            )
+        };
+
+        // Export in small chunks, then resume an export interrupted half way
+        export()?;
+        let mut manifest = read_manifest(&backup_path)?;
+        assert_eq!(manifest.chunks.len(), 4);
+        assert!(manifest.complete);
+        let complete_manifest = manifest.clone();
+        manifest.chunks.truncate(2);
+        manifest.complete = false;
+        write_json(&backup_path.join(MANIFEST_FILENAME), &manifest)?;
+        export()?;
+        let manifest = read_manifest(&backup_path)?;
+        assert_eq!(manifest.chunks, complete_manifest.chunks);
+
+        // Entries of the target which are not in the backup are removed
+        let target = AuthorityPerpetualTables::open(&target_path.join("store"), None);
+        target
+            .insert_object_test_only(Object::immutable_with_id_for_testing(ObjectID::random()))?;
+        let target_table: DBMap<Vec<u8>, Vec<u8>> = DBMap::reopen(
+            &target.objects.rocksdb,
+            Some("objects"),
+            &ReadWriteOptions::default(),
+            false,
+        )?;
+        import_entries(&target_table, &target_path, &backup_path, &manifest)?;
+        let source_entries: Vec<_> = source.objects.unbounded_iter().collect();
+        let target_entries: Vec<_> = target.objects.unbounded_iter().collect();
+        assert_eq!(source_entries.len(), 10);
+        assert_eq!(source_entries, target_entries);
+
+        // A corrupted chunk is rejected
+        std::fs::write(chunk_path(&backup_path, 0), b"hello_world")?;
+        std::fs::remove_file(backup_path.join(IMPORT_PROGRESS_FILENAME))?;
+        assert!(import_entries(&target_table, &target_path, &backup_path, &manifest).is_err());
+        Ok(())
+    }
+}
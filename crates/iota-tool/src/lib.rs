@@ -47,7 +47,10 @@ use iota_core::{
 use iota_network::default_iota_network_config;
 use iota_protocol_config::Chain;
 use iota_sdk::{IotaClient, IotaClientBuilder};
-use iota_snapshot::{reader::StateSnapshotReaderV1, setup_db_state};
+use iota_snapshot::{
+    reader::{SnapshotVerificationReport, StateSnapshotReaderV1},
+    setup_db_state,
+};
 use iota_storage::{
     object_store::{
         ObjectStoreGetExt,
@@ -1006,6 +1009,100 @@ pub async fn download_formal_snapshot(
     Ok(())
 }
 
+/// Checks the formal snapshot of `epoch` against the root state commitment of
+/// the last checkpoint of the epoch, without restoring it. Only the end of
+/// epoch checkpoint summaries and the reference files of the snapshot are
+/// written to `path`, and removed once done.
+pub async fn verify_formal_snapshot(
+    path: &Path,
+    epoch: EpochId,
+    genesis: &Path,
+    snapshot_store_config: ObjectStoreConfig,
+    archive_store_config: ObjectStoreConfig,
+    num_parallel_downloads: usize,
+) -> Result<SnapshotVerificationReport, anyhow::Error> {
+    let m = MultiProgress::new();
+    m.println(format!(
+        "Beginning formal snapshot verification for end of epoch {}",
+        epoch
+    ))?;
+    let path = path.join("verify-staging");
+    if path.exists() {
+        fs::remove_dir_all(path.clone())?;
+    }
+    let perpetual_db = Arc::new(AuthorityPerpetualTables::open(&path.join("store"), None));
+    let genesis = Genesis::load(genesis)
+        .map_err(|err| err.context(format!("Failed to load genesis from {}", genesis.display())))?;
+    let genesis_committee = genesis.committee()?;
+    let committee_store = Arc::new(CommitteeStore::new(
+        path.join("epochs"),
+        &genesis_committee,
+        None,
+    ));
+    let checkpoint_store = Arc::new(CheckpointStore::open_tables_read_write(
+        path.join("checkpoints"),
+        MetricConf::default(),
+        None,
+        None,
+    ));
+    let summaries_handle = start_summary_sync(
+        perpetual_db,
+        committee_store,
+        checkpoint_store.clone(),
+        m.clone(),
+        genesis,
+        archive_store_config,
+        epoch,
+        num_parallel_downloads,
+        true,
+        false,
+    );
+    let local_store_config = ObjectStoreConfig {
+        object_store: Some(ObjectStoreType::File),
+        directory: Some(path.join("snapshot")),
+        ..Default::default()
+    };
+    let reader = StateSnapshotReaderV1::new(
+        epoch,
+        &snapshot_store_config,
+        &local_store_config,
+        usize::MAX,
+        NonZeroUsize::new(num_parallel_downloads).unwrap(),
+        m.clone(),
+    )
+    .await?;
+    summaries_handle
+        .await?
+        .map_err(|err| err.context("Failed to sync checkpoint summaries"))?;
+
+    let last_checkpoint = checkpoint_store
+        .get_highest_verified_checkpoint()?
+        .ok_or_else(|| anyhow!("No verified checkpoint after syncing summaries"))?;
+    if last_checkpoint.epoch() != epoch {
+        return Err(anyhow!(
+            "Expected highest verified checkpoint ({}) to be for epoch {} but was for epoch {}",
+            last_checkpoint.sequence_number,
+            epoch,
+            last_checkpoint.epoch()
+        ));
+    }
+    let Some(CheckpointCommitment::ECMHLiveObjectSetDigest(root_state_hash)) = last_checkpoint
+        .end_of_epoch_data
+        .as_ref()
+        .and_then(|data| data.epoch_commitments.last())
+    else {
+        return Err(anyhow!(
+            "Last checkpoint of epoch {} has no root state commitment",
+            epoch
+        ));
+    };
+    let (_abort_handle, abort_registration) = AbortHandle::new_pair();
+    let report = reader.verify(root_state_hash, abort_registration).await?;
+    drop(checkpoint_store);
+    fs::remove_dir_all(&path)?;
+    Ok(report)
+}
+
 pub async fn download_db_snapshot(
     path: &Path,
     epoch: u64,
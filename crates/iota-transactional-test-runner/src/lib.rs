@@ -7,7 +7,10 @@
 
 pub mod args;
 pub mod programmable_transaction_test_parser;
+pub mod recorder;
 mod simulator_persisted_store;
+#[cfg(not(msim))]
+pub mod suite;
 pub mod test_adapter;
 
 use std::{path::Path, sync::Arc};
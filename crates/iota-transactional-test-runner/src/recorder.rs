@@ -0,0 +1,688 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Records transactions executed against a Simulacrum or a local network and
+//! converts them into a transactional test script.
+//!
+//! Senders become test accounts and objects are referred to by the fake IDs
+//! the test adapter assigns to them when running the script. Packages are
+//! staged with `stage-package` before the transaction publishing them, and
+//! since their sources cannot be recovered from bytecode they have to be
+//! provided with [`ScenarioRecorder::add_package_source`].
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write,
+};
+
+use anyhow::{Context, anyhow, bail};
+use fastcrypto::encoding::{Encoding, Hex};
+use iota_types::{
+    BRIDGE_ADDRESS, IOTA_FRAMEWORK_ADDRESS, IOTA_SYSTEM_ADDRESS, MOVE_STDLIB_ADDRESS,
+    STARDUST_ADDRESS,
+    base_types::{IotaAddress, ObjectID},
+    digests::TransactionDigest,
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    object::Data,
+    storage::{ObjectStore, ReadStore},
+    transaction::{
+        Argument, CallArg, Command, ObjectArg, ProgrammableTransaction, TransactionData,
+        TransactionDataAPI, TransactionKind,
+    },
+};
+use move_binary_format::CompiledModule;
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{StructTag, TypeTag},
+};
+
+use crate::test_adapter::{FakeID, WELL_KNOWN_OBJECTS};
+
+const FRAMEWORK_PACKAGES: &[(AccountAddress, &str)] = &[
+    (MOVE_STDLIB_ADDRESS, "std"),
+    (IOTA_FRAMEWORK_ADDRESS, "iota"),
+    (IOTA_SYSTEM_ADDRESS, "iota_system"),
+    (STARDUST_ADDRESS, "stardust"),
+    (BRIDGE_ADDRESS, "bridge"),
+];
+
+/// Key by which the test adapter sorts the objects created by a transaction
+/// before assigning fake IDs to them.
+#[derive(Clone, Debug)]
+enum ObjectKey {
+    /// The comma separated module names of a package
+    Package(String),
+    Move(StructTag),
+}
+
+#[derive(Clone, Debug)]
+struct RecordedTransaction {
+    digest: TransactionDigest,
+    sender: IotaAddress,
+    gas_budget: u64,
+    transaction: ProgrammableTransaction,
+    /// Objects created or unwrapped by the transaction, in effects order
+    new_objects: Vec<(ObjectID, ObjectKey)>,
+}
+
+/// Records programmable transactions and writes them out as a transactional
+/// test script.
+#[derive(Debug)]
+pub struct ScenarioRecorder {
+    protocol_version: u64,
+    simulator: bool,
+    package_sources: VecDeque<(String, String)>,
+    transactions: Vec<RecordedTransaction>,
+    skipped: Vec<TransactionDigest>,
+}
+
+impl ScenarioRecorder {
+    /// Creates a recorder whose script runs at the given protocol version,
+    /// against a Simulacrum if `simulator` is set.
+    pub fn new(protocol_version: u64, simulator: bool) -> Self {
+        Self {
+            protocol_version,
+            simulator,
+            package_sources: VecDeque::new(),
+            transactions: vec![],
+            skipped: vec![],
+        }
+    }
+
+    /// Provides the sources of the next package published or upgraded by a
+    /// recorded transaction. The modules in `source` must be declared under
+    /// `named_address`, which is also the name later transactions use to
+    /// refer to the package.
+    pub fn add_package_source(
+        &mut self,
+        named_address: impl Into<String>,
+        source: impl Into<String>,
+    ) {
+        self.package_sources
+            .push_back((named_address.into(), source.into()));
+    }
+
+    /// Records an executed transaction, loading it and its effects from
+    /// `store`.
+    pub fn record_transaction(
+        &mut self,
+        store: &impl ReadStore,
+        digest: &TransactionDigest,
+    ) -> anyhow::Result<()> {
+        let transaction = store
+            .get_transaction(digest)?
+            .ok_or_else(|| anyhow!("Transaction {digest} not found"))?;
+        let effects = store
+            .get_transaction_effects(digest)?
+            .ok_or_else(|| anyhow!("Effects of transaction {digest} not found"))?;
+        self.record(transaction.data().transaction_data(), &effects, store)
+    }
+
+    /// Records an executed transaction. `store` is used to look up the
+    /// objects it created. Transactions other than programmable transactions
+    /// are skipped and listed at the top of the script.
+    pub fn record(
+        &mut self,
+        data: &TransactionData,
+        effects: &TransactionEffects,
+        store: &impl ObjectStore,
+    ) -> anyhow::Result<()> {
+        let TransactionKind::ProgrammableTransaction(transaction) = data.kind() else {
+            self.skipped.push(*effects.transaction_digest());
+            return Ok(());
+        };
+        let new_objects = effects
+            .created()
+            .into_iter()
+            .chain(effects.unwrapped())
+            .map(|((id, version, _), _)| {
+                let object = match store.get_object_by_key(&id, version)? {
+                    Some(object) => object,
+                    None => store
+                        .get_object(&id)?
+                        .with_context(|| format!("Object {id} not found"))?,
+                };
+                let key = match &object.data {
+                    Data::Move(object) => ObjectKey::Move(object.type_().clone().into()),
+                    Data::Package(package) => ObjectKey::Package(
+                        package
+                            .serialized_module_map()
+                            .keys()
+                            .map(|s| s.as_str())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                };
+                Ok((id, key))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        self.transactions.push(RecordedTransaction {
+            digest: *effects.transaction_digest(),
+            sender: data.sender(),
+            gas_budget: data.gas_budget(),
+            transaction: transaction.clone(),
+            new_objects,
+        });
+        Ok(())
+    }
+
+    /// Writes the recorded transactions as a transactional test script.
+    pub fn into_script(self) -> anyhow::Result<String> {
+        let Self {
+            protocol_version,
+            simulator,
+            package_sources,
+            transactions,
+            skipped,
+        } = self;
+
+        let mut writer = ScriptWriter::new(package_sources);
+        for transaction in &transactions {
+            let next = account_name(writer.accounts.len());
+            writer.accounts.entry(transaction.sender).or_insert(next);
+        }
+        for transaction in &transactions {
+            writer
+                .write_transaction(transaction)
+                .with_context(|| format!("Failed to record transaction {}", transaction.digest))?;
+        }
+        if let Some((name, _)) = writer.package_sources.front() {
+            bail!("The source of package '{name}' was not used by any recorded transaction");
+        }
+
+        let mut script = String::new();
+        for digest in skipped {
+            writeln!(
+                script,
+                "// NOTE: skipped non-programmable transaction {digest}"
+            )
+            .unwrap();
+        }
+        for note in &writer.notes {
+            writeln!(script, "// NOTE: {note}").unwrap();
+        }
+        if !script.is_empty() {
+            script.push('\n');
+        }
+        write!(script, "//# init --protocol-version {protocol_version}").unwrap();
+        if !writer.accounts.is_empty() {
+            let mut accounts = writer.accounts.values().cloned().collect::<Vec<_>>();
+            accounts.sort_by_key(|name| (name.len(), name.clone()));
+            write!(script, " --accounts {}", accounts.join(" ")).unwrap();
+        }
+        if !writer.staged.is_empty() {
+            let addresses = writer
+                .staged
+                .iter()
+                .map(|name| format!("{name}=0x0"))
+                .collect::<Vec<_>>();
+            write!(script, " --addresses {}", addresses.join(" ")).unwrap();
+        }
+        if simulator {
+            script.push_str(" --simulator");
+        }
+        script.push('\n');
+        for task in writer.tasks {
+            script.push('\n');
+            script.push_str(&task);
+            script.push('\n');
+        }
+        Ok(script)
+    }
+}
+
+/// Names accounts `A` to `Z`, followed by `A26`, `A27`, ...
+fn account_name(idx: usize) -> String {
+    if idx < 26 {
+        char::from(b'A' + idx as u8).to_string()
+    } else {
+        format!("A{idx}")
+    }
+}
+
+struct ScriptWriter {
+    accounts: BTreeMap<IotaAddress, String>,
+    /// Named addresses of packages and of objects used as pure inputs
+    addresses: BTreeMap<AccountAddress, String>,
+    fake_ids: BTreeMap<ObjectID, FakeID>,
+    package_sources: VecDeque<(String, String)>,
+    /// Named addresses of the staged packages, to be declared by `init`
+    staged: Vec<String>,
+    tasks: Vec<String>,
+    notes: Vec<String>,
+}
+
+impl ScriptWriter {
+    fn new(package_sources: VecDeque<(String, String)>) -> Self {
+        let addresses = FRAMEWORK_PACKAGES
+            .iter()
+            .map(|(address, name)| (*address, name.to_string()))
+            .collect();
+        let fake_ids = WELL_KNOWN_OBJECTS
+            .iter()
+            .map(|id| (*id, FakeID::Known(*id)))
+            .collect();
+        Self {
+            accounts: BTreeMap::new(),
+            addresses,
+            fake_ids,
+            package_sources,
+            staged: vec![],
+            tasks: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Adds a task to the script and returns its number, `init` being task 0.
+    fn push_task(&mut self, task: String) -> u64 {
+        self.tasks.push(task);
+        self.tasks.len() as u64
+    }
+
+    fn write_transaction(&mut self, txn: &RecordedTransaction) -> anyhow::Result<()> {
+        // Stage the packages published or upgraded by the transaction
+        let mut staged = BTreeMap::new();
+        let mut published = BTreeMap::new();
+        for (idx, command) in txn.transaction.commands.iter().enumerate() {
+            let (modules, dependencies) = match command {
+                Command::Publish(modules, dependencies)
+                | Command::Upgrade(modules, dependencies, _, _) => (modules, dependencies),
+                _ => continue,
+            };
+            let Some((name, source)) = self.package_sources.pop_front() else {
+                bail!("No source was provided for the package published by command {idx}");
+            };
+            let dependencies = dependencies
+                .iter()
+                .filter(|id| !is_framework_package(id))
+                .map(|id| self.package_name(id))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut task = "//# stage-package".to_string();
+            if !dependencies.is_empty() {
+                write!(task, " --dependencies {}", dependencies.join(" ")).unwrap();
+            }
+            write!(task, "\n{}", source.trim_end()).unwrap();
+            self.push_task(task);
+            published.insert(module_names(modules)?, name.clone());
+            staged.insert(idx, name.clone());
+            self.staged.push(name);
+        }
+
+        // Name the objects whose IDs are passed as pure inputs
+        for input in &txn.transaction.inputs {
+            let CallArg::Pure(bytes) = input else {
+                continue;
+            };
+            let Ok(address) = AccountAddress::from_bytes(bytes) else {
+                continue;
+            };
+            if self.addresses.contains_key(&address) {
+                continue;
+            }
+            if let Some(fake_id @ FakeID::Enumerated(task, i)) =
+                self.fake_ids.get(&ObjectID::from(address))
+            {
+                let name = format!("o_{task}_{i}");
+                self.push_task(format!("//# set-address {name} object({fake_id})"));
+                self.addresses.insert(address, name);
+            }
+        }
+
+        let inputs = txn
+            .transaction
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(idx, input)| {
+                self.input(input)
+                    .with_context(|| format!("Invalid input {idx}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let commands = txn
+            .transaction
+            .commands
+            .iter()
+            .enumerate()
+            .map(|(idx, command)| {
+                self.command(command, staged.get(&idx))
+                    .with_context(|| format!("Invalid command {idx}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut task = format!(
+            "//# programmable --sender {} --gas-budget {}",
+            self.accounts[&txn.sender], txn.gas_budget
+        );
+        if !inputs.is_empty() {
+            write!(task, " --inputs {}", inputs.join(" ")).unwrap();
+        }
+        let last = commands.len().saturating_sub(1);
+        for (idx, command) in commands.iter().enumerate() {
+            let delimiter = if idx == last { "" } else { ";" };
+            write!(task, "\n//> {idx}: {command}{delimiter}").unwrap();
+        }
+        let task_number = self.push_task(task);
+        self.enumerate(task_number, &txn.new_objects);
+
+        // Name the published packages so that later transactions can call into
+        // them
+        for (id, key) in &txn.new_objects {
+            let ObjectKey::Package(modules) = key else {
+                continue;
+            };
+            if let Some(name) = published.get(modules) {
+                let fake_id = self.fake_ids[id];
+                self.push_task(format!("//# set-address {name} object({fake_id})"));
+                self.addresses.insert((*id).into(), name.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns fake IDs to the objects created by a task the same way the test
+    /// adapter does: in the order of their sorting keys, ties being broken by
+    /// the order of the effects.
+    fn enumerate(&mut self, task: u64, new_objects: &[(ObjectID, ObjectKey)]) {
+        let mut new_objects = new_objects
+            .iter()
+            .filter(|(id, _)| !self.fake_ids.contains_key(id))
+            .map(|(id, key)| (*id, self.sorting_key(key)))
+            .collect::<Vec<_>>();
+        new_objects.sort_by(|(_, a), (_, b)| a.cmp(b));
+        for pair in new_objects.windows(2) {
+            if pair[0].1 == pair[1].1 {
+                self.notes.push(format!(
+                    "objects of type {} created by task {task} might be enumerated in a \
+                    different order",
+                    pair[0].1
+                ));
+            }
+        }
+        self.notes.dedup();
+        for (i, (id, _)) in new_objects.into_iter().enumerate() {
+            self.fake_ids.insert(id, FakeID::Enumerated(task, i as u64));
+        }
+    }
+
+    fn sorting_key(&self, key: &ObjectKey) -> String {
+        match key {
+            ObjectKey::Package(modules) => modules.clone(),
+            ObjectKey::Move(tag) => self
+                .struct_tag(tag, &|address| {
+                    let name = match self.fake_ids.get(&ObjectID::from(address)) {
+                        Some(fake_id) => format!("fake({fake_id})"),
+                        None => "_".to_string(),
+                    };
+                    Ok(name)
+                })
+                .unwrap(),
+        }
+    }
+
+    fn package_name(&self, id: &ObjectID) -> anyhow::Result<String> {
+        let address: AccountAddress = (*id).into();
+        match self.addresses.get(&address) {
+            Some(name) => Ok(name.clone()),
+            None => bail!("Package {id} was not published by a recorded transaction"),
+        }
+    }
+
+    fn fake_id(&self, id: &ObjectID) -> anyhow::Result<FakeID> {
+        match self.fake_ids.get(id) {
+            Some(fake_id) => Ok(*fake_id),
+            None => bail!(
+                "Object {id} was neither created by a recorded transaction nor is it a well-known \
+                object"
+            ),
+        }
+    }
+
+    fn input(&self, input: &CallArg) -> anyhow::Result<String> {
+        Ok(match input {
+            CallArg::Pure(bytes) => self.pure(bytes),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _))) => {
+                format!("object({})", self.fake_id(id)?)
+            }
+            CallArg::Object(ObjectArg::SharedObject { id, mutable, .. }) => {
+                let fake_id = self.fake_id(id)?;
+                if *mutable {
+                    format!("object({fake_id})")
+                } else {
+                    format!("immshared({fake_id})")
+                }
+            }
+            CallArg::Object(ObjectArg::Receiving((id, _, _))) => {
+                format!("receiving({})", self.fake_id(id)?)
+            }
+        })
+    }
+
+    /// Renders pure bytes as a value with the same BCS encoding, as the type
+    /// of pure inputs is not known.
+    fn pure(&self, bytes: &[u8]) -> String {
+        if let Ok(address) = AccountAddress::from_bytes(bytes) {
+            if let Some(name) = self.accounts.get(&address.into()) {
+                return format!("@{name}");
+            }
+            if let Some(name) = self.addresses.get(&address) {
+                return format!("@{name}");
+            }
+            return format!("@{}", address.to_hex_literal());
+        }
+        match bytes.len() {
+            1 => return format!("{}u8", bytes[0]),
+            8 => return format!("{}u64", u64::from_le_bytes(bytes.try_into().unwrap())),
+            16 => return format!("{}u128", u128::from_le_bytes(bytes.try_into().unwrap())),
+            _ => (),
+        }
+        if let Ok(contents) = bcs::from_bytes::<Vec<u8>>(bytes) {
+            if contents
+                .iter()
+                .all(|b| (b.is_ascii_graphic() && *b != b'"' && *b != b'\\') || *b == b' ')
+            {
+                return format!("b\"{}\"", String::from_utf8(contents).unwrap());
+            }
+            return format!("x\"{}\"", Hex::encode(contents));
+        }
+        let bytes = bytes.iter().map(|b| format!("{b}u8")).collect::<Vec<_>>();
+        format!("struct({})", bytes.join(", "))
+    }
+
+    fn command(&self, command: &Command, staged: Option<&String>) -> anyhow::Result<String> {
+        Ok(match command {
+            Command::MoveCall(call) => {
+                let type_arguments = if call.type_arguments.is_empty() {
+                    String::new()
+                } else {
+                    let type_arguments = call
+                        .type_arguments
+                        .iter()
+                        .map(|ty| self.type_tag(ty))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    format!("<{}>", type_arguments.join(", "))
+                };
+                format!(
+                    "{}::{}::{}{type_arguments}({})",
+                    self.package_name(&call.package)?,
+                    call.module,
+                    call.function,
+                    arguments(&call.arguments)
+                )
+            }
+            Command::TransferObjects(objects, recipient) => format!(
+                "TransferObjects([{}], {})",
+                arguments(objects),
+                argument(recipient)
+            ),
+            Command::SplitCoins(coin, amounts) => {
+                format!("SplitCoins({}, [{}])", argument(coin), arguments(amounts))
+            }
+            Command::MergeCoins(target, coins) => {
+                format!("MergeCoins({}, [{}])", argument(target), arguments(coins))
+            }
+            Command::MakeMoveVec(ty, elements) => {
+                let ty = match ty {
+                    Some(ty) => format!("<{}>", self.type_tag(ty)?),
+                    None => String::new(),
+                };
+                format!("MakeMoveVec{ty}([{}])", arguments(elements))
+            }
+            Command::Publish(_, dependencies) => format!(
+                "Publish({}, [{}])",
+                staged.context("Package was not staged")?,
+                self.dependencies(dependencies)?
+            ),
+            Command::Upgrade(_, dependencies, package, ticket) => format!(
+                "Upgrade({}, [{}], {}, {})",
+                staged.context("Package was not staged")?,
+                self.dependencies(dependencies)?,
+                self.package_name(package)?,
+                argument(ticket)
+            ),
+        })
+    }
+
+    fn dependencies(&self, dependencies: &[ObjectID]) -> anyhow::Result<String> {
+        Ok(dependencies
+            .iter()
+            .map(|id| self.package_name(id))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .join(","))
+    }
+
+    fn type_tag(&self, ty: &TypeTag) -> anyhow::Result<String> {
+        self.type_tag_with(ty, &|address| {
+            bail!(
+                "Package {} was not published by a recorded transaction",
+                address.to_hex_literal()
+            )
+        })
+    }
+
+    fn type_tag_with(
+        &self,
+        ty: &TypeTag,
+        unnamed: &impl Fn(AccountAddress) -> anyhow::Result<String>,
+    ) -> anyhow::Result<String> {
+        Ok(match ty {
+            TypeTag::Bool => "bool".to_string(),
+            TypeTag::U8 => "u8".to_string(),
+            TypeTag::U16 => "u16".to_string(),
+            TypeTag::U32 => "u32".to_string(),
+            TypeTag::U64 => "u64".to_string(),
+            TypeTag::U128 => "u128".to_string(),
+            TypeTag::U256 => "u256".to_string(),
+            TypeTag::Address => "address".to_string(),
+            TypeTag::Signer => "signer".to_string(),
+            TypeTag::Vector(ty) => format!("vector<{}>", self.type_tag_with(ty, unnamed)?),
+            TypeTag::Struct(tag) => self.struct_tag(tag, unnamed)?,
+        })
+    }
+
+    fn struct_tag(
+        &self,
+        tag: &StructTag,
+        unnamed: &impl Fn(AccountAddress) -> anyhow::Result<String>,
+    ) -> anyhow::Result<String> {
+        let address = match self.addresses.get(&tag.address) {
+            Some(name) => name.clone(),
+            None => unnamed(tag.address)?,
+        };
+        let mut s = format!("{address}::{}::{}", tag.module, tag.name);
+        if !tag.type_params.is_empty() {
+            let type_params = tag
+                .type_params
+                .iter()
+                .map(|ty| self.type_tag_with(ty, unnamed))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            write!(s, "<{}>", type_params.join(", ")).unwrap();
+        }
+        Ok(s)
+    }
+}
+
+fn is_framework_package(id: &ObjectID) -> bool {
+    let address: AccountAddress = (*id).into();
+    FRAMEWORK_PACKAGES.iter().any(|(a, _)| *a == address)
+}
+
+/// The comma separated module names of a package, as in [`ObjectKey`]
+fn module_names(modules: &[Vec<u8>]) -> anyhow::Result<String> {
+    let mut names = modules
+        .iter()
+        .map(|bytes| {
+            let module = CompiledModule::deserialize_with_defaults(bytes)?;
+            Ok(module.self_id().name().to_string())
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    names.sort();
+    Ok(names.join(","))
+}
+
+fn argument(argument: &Argument) -> String {
+    match argument {
+        Argument::GasCoin => "Gas".to_string(),
+        Argument::Input(i) => format!("Input({i})"),
+        Argument::Result(i) => format!("Result({i})"),
+        Argument::NestedResult(i, j) => format!("NestedResult({i}, {j})"),
+    }
+}
+
+fn arguments(arguments: &[Argument]) -> String {
+    arguments
+        .iter()
+        .map(argument)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use iota_types::iota_system_state::epoch_start_iota_system_state::EpochStartSystemStateTrait;
+    use simulacrum::Simulacrum;
+
+    use super::*;
+
+    #[test]
+    fn record_transfer() {
+        let mut sim = Simulacrum::new();
+        let recipient = IotaAddress::random_for_testing_only();
+        let (transaction, amount) = sim.transfer_txn(recipient);
+        let (effects, _) = sim.execute_transaction(transaction).unwrap();
+
+        let protocol_version = sim.epoch_start_state().protocol_version().as_u64();
+        let mut recorder = ScenarioRecorder::new(protocol_version, true);
+        recorder
+            .record_transaction(&sim, effects.transaction_digest())
+            .unwrap();
+        let script = recorder.into_script().unwrap();
+
+        assert_eq!(
+            script,
+            format!(
+                "//# init --protocol-version {protocol_version} --accounts A --simulator\n\
+                \n\
+                //# programmable --sender A --gas-budget 1000000000 --inputs {amount}u64 @{}\n\
+                //> 0: SplitCoins(Gas, [Input(0)]);\n\
+                //> 1: TransferObjects([Result(0)], Input(1))\n",
+                recipient.to_hex_literal()
+            )
+        );
+    }
+
+    #[test]
+    fn pure_inputs_round_trip() {
+        let writer = ScriptWriter::new(VecDeque::new());
+        assert_eq!(writer.pure(&bcs::to_bytes(&true).unwrap()), "1u8");
+        assert_eq!(writer.pure(&bcs::to_bytes(&42u64).unwrap()), "42u64");
+        assert_eq!(
+            writer.pure(&bcs::to_bytes(&b"hello world".to_vec()).unwrap()),
+            "b\"hello world\""
+        );
+        assert_eq!(
+            writer.pure(&bcs::to_bytes(&vec![0u8, 255]).unwrap()),
+            "x\"00ff\""
+        );
+        assert_eq!(writer.pure(&[1, 2, 3]), "struct(1u8, 2u8, 3u8)");
+    }
+}
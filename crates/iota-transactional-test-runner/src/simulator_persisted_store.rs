@@ -2,11 +2,21 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::BTreeMap, num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
 
+use fastcrypto::traits::ToFromBytes;
 use iota_config::genesis;
 use iota_protocol_config::ProtocolVersion;
-use iota_swarm_config::{genesis_config::AccountConfig, network_config_builder::ConfigBuilder};
+use iota_swarm_config::{
+    genesis_config::AccountConfig, network_config::NetworkConfig,
+    network_config_builder::ConfigBuilder,
+};
 use iota_types::{
     base_types::{IotaAddress, ObjectID, SequenceNumber, VersionNumber},
     committee::{Committee, EpochId},
@@ -31,8 +41,10 @@ use move_core_types::{
     language_storage::{ModuleId, StructTag},
     resolver::ModuleResolver,
 };
+use once_cell::sync::{Lazy, OnceCell};
+use rand::{Rng, rngs::StdRng};
 use simulacrum::Simulacrum;
-use tempfile::tempdir;
+use tempfile::{TempDir, tempdir};
 use typed_store::{
     DBMapUtils, Map,
     metrics::SamplingInterval,
@@ -45,6 +57,11 @@ use super::SimulatorStore;
 pub struct PersistedStore {
     pub path: PathBuf,
     pub read_write: PersistedStoreInner,
+    /// Temporary directory holding the store, removed once the store is closed
+    _tempdir: Option<TempDir>,
+    /// Genesis snapshot the store was forked from, kept for other simulators
+    /// to fork from while this one is alive
+    _genesis_snapshot: Option<Arc<OnceCell<GenesisSnapshot>>>,
 }
 
 pub struct PersistedStoreInnerReadOnlyWrapper {
@@ -59,6 +76,7 @@ pub struct PersistedStoreInner {
         DBMap<CheckpointSequenceNumber, iota_types::messages_checkpoint::TrustedCheckpoint>,
     checkpoint_digest_to_sequence_number: DBMap<CheckpointDigest, CheckpointSequenceNumber>,
     checkpoint_contents: DBMap<CheckpointContentsDigest, CheckpointContents>,
+    transaction_checkpoints: DBMap<TransactionDigest, CheckpointSequenceNumber>,
 
     // Transaction data
     transactions: DBMap<TransactionDigest, iota_types::transaction::TrustedTransaction>,
@@ -74,8 +92,42 @@ pub struct PersistedStoreInner {
     objects: DBMap<ObjectID, BTreeMap<SequenceNumber, Object>>,
 }
 
+/// Genesis states built by this process, shared between the test files
+/// started from the same parameters. A genesis state and its directory are
+/// dropped once no simulator forked from it is left.
+static GENESIS_SNAPSHOTS: Lazy<Mutex<HashMap<GenesisKey, Weak<OnceCell<GenesisSnapshot>>>>> =
+    Lazy::new(Default::default);
+
+/// Everything a genesis state is built from.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct GenesisKey {
+    /// The next bytes the rng produces, standing in for its state
+    rng_fingerprint: [u8; 32],
+    chain_start_timestamp_ms: u64,
+    protocol_version: ProtocolVersion,
+    accounts: Vec<(Option<IotaAddress>, Vec<u64>)>,
+    validator_keys: Option<Vec<Vec<u8>>>,
+    reference_gas_price: Option<u64>,
+}
+
+struct GenesisSnapshot {
+    config: NetworkConfig,
+    /// The rng as left by building the network config
+    rng: StdRng,
+    /// Store holding the genesis state, which is only ever forked
+    store: PersistedStore,
+}
+
 impl PersistedStore {
     pub fn new(genesis: &genesis::Genesis, path: PathBuf) -> Self {
+        let mut res = Self::open(path);
+        res.init_with_genesis(genesis);
+
+        res
+    }
+
+    /// Opens the store at `path` with whatever state it holds.
+    fn open(path: PathBuf) -> Self {
         let samp: SamplingInterval = SamplingInterval::new(Duration::from_secs(60), 0);
         let read_write = PersistedStoreInner::open_tables_read_write(
             path.clone(),
@@ -84,10 +136,44 @@ impl PersistedStore {
             None,
         );
 
-        let mut res = Self { path, read_write };
-        res.init_with_genesis(genesis);
+        Self {
+            path,
+            read_write,
+            _tempdir: None,
+            _genesis_snapshot: None,
+        }
+    }
 
-        res
+    fn index_checkpoint_transactions(
+        &mut self,
+        sequence_number: CheckpointSequenceNumber,
+        contents: &CheckpointContents,
+    ) {
+        self.read_write
+            .transaction_checkpoints
+            .multi_insert(
+                contents
+                    .iter()
+                    .map(|digests| (digests.transaction, sequence_number)),
+            )
+            .expect("Fatal: DB write failed");
+    }
+
+    /// Creates a copy of the store in a new temporary directory, removed when
+    /// the copy is dropped. The copy is a RocksDB checkpoint, so its SST files
+    /// are hard links to the files of this store until either side compacts
+    /// them.
+    fn fork(&self) -> Self {
+        let tempdir = tempdir().unwrap();
+        let path = tempdir.path().join("store");
+        self.read_write
+            .checkpoints
+            .checkpoint_db(&path)
+            .expect("Fatal: DB checkpoint failed");
+        Self {
+            _tempdir: Some(tempdir),
+            ..Self::open(path)
+        }
     }
 
     pub fn read_replica(&self) -> PersistedStoreInnerReadOnlyWrapper {
@@ -117,21 +203,14 @@ impl PersistedStore {
     {
         let path: PathBuf = path.unwrap_or(tempdir().unwrap().into_path());
 
-        let mut builder = ConfigBuilder::new_with_temp_dir()
-            .rng(&mut rng)
-            .with_chain_start_timestamp_ms(chain_start_timestamp_ms)
-            .deterministic_committee_size(NonZeroUsize::new(1).unwrap())
-            .with_protocol_version(protocol_version)
-            .with_accounts(account_configs);
-
-        if let Some(validator_keys) = validator_keys {
-            builder = builder.deterministic_committee_validators(validator_keys)
-        };
-        if let Some(reference_gas_price) = reference_gas_price {
-            builder = builder.with_reference_gas_price(reference_gas_price)
-        };
-
-        let config = builder.build();
+        let config = build_network_config(
+            &mut rng,
+            chain_start_timestamp_ms,
+            protocol_version,
+            account_configs,
+            validator_keys,
+            reference_gas_price,
+        );
 
         let genesis = &config.genesis;
 
@@ -143,6 +222,77 @@ impl PersistedStore {
         )
     }
 
+    /// Same as [`Self::new_sim_replica_with_protocol_version_and_accounts`],
+    /// except that the genesis state is built only once per process for a
+    /// given set of parameters. Every simulator then runs on its own fork of
+    /// that state, so test files sharing their `init` arguments skip
+    /// building the genesis without affecting each other.
+    pub fn new_sim_replica_from_genesis_snapshot(
+        rng: StdRng,
+        chain_start_timestamp_ms: u64,
+        protocol_version: ProtocolVersion,
+        account_configs: Vec<AccountConfig>,
+        validator_keys: Option<Vec<AccountKeyPair>>,
+        reference_gas_price: Option<u64>,
+    ) -> (Simulacrum<StdRng, Self>, PersistedStoreInnerReadOnlyWrapper) {
+        let key = GenesisKey {
+            rng_fingerprint: rng.clone().gen(),
+            chain_start_timestamp_ms,
+            protocol_version,
+            accounts: account_configs
+                .iter()
+                .map(|account| (account.address, account.gas_amounts.clone()))
+                .collect(),
+            validator_keys: validator_keys
+                .as_ref()
+                .map(|keys| keys.iter().map(|key| key.as_bytes().to_vec()).collect()),
+            reference_gas_price,
+        };
+        let cell = {
+            let mut snapshots = GENESIS_SNAPSHOTS.lock().unwrap();
+            match snapshots.get(&key).and_then(Weak::upgrade) {
+                Some(cell) => cell,
+                None => {
+                    let cell = Arc::new(OnceCell::new());
+                    snapshots.insert(key, Arc::downgrade(&cell));
+                    cell
+                }
+            }
+        };
+        let snapshot = cell.get_or_init(|| {
+            let mut rng = rng;
+            let config = build_network_config(
+                &mut rng,
+                chain_start_timestamp_ms,
+                protocol_version,
+                account_configs,
+                validator_keys,
+                reference_gas_price,
+            );
+            let tempdir = tempdir().unwrap();
+            let store = PersistedStore::new(&config.genesis, tempdir.path().to_path_buf());
+            let store = PersistedStore {
+                _tempdir: Some(tempdir),
+                ..store
+            };
+            GenesisSnapshot { config, rng, store }
+        });
+
+        let store = PersistedStore {
+            _genesis_snapshot: Some(cell.clone()),
+            ..snapshot.store.fork()
+        };
+        let read_only_wrapper = store.read_replica();
+        (
+            Simulacrum::new_with_network_config_store(
+                &snapshot.config,
+                snapshot.rng.clone(),
+                store,
+            ),
+            read_only_wrapper,
+        )
+    }
+
     pub fn new_sim_with_protocol_version_and_accounts<R>(
         rng: R,
         chain_start_timestamp_ms: u64,
@@ -166,6 +316,34 @@ impl PersistedStore {
     }
 }
 
+fn build_network_config<R>(
+    rng: &mut R,
+    chain_start_timestamp_ms: u64,
+    protocol_version: ProtocolVersion,
+    account_configs: Vec<AccountConfig>,
+    validator_keys: Option<Vec<AccountKeyPair>>,
+    reference_gas_price: Option<u64>,
+) -> NetworkConfig
+where
+    R: rand::RngCore + rand::CryptoRng,
+{
+    let mut builder = ConfigBuilder::new_with_temp_dir()
+        .rng(rng)
+        .with_chain_start_timestamp_ms(chain_start_timestamp_ms)
+        .deterministic_committee_size(NonZeroUsize::new(1).unwrap())
+        .with_protocol_version(protocol_version)
+        .with_accounts(account_configs);
+
+    if let Some(validator_keys) = validator_keys {
+        builder = builder.deterministic_committee_validators(validator_keys)
+    };
+    if let Some(reference_gas_price) = reference_gas_price {
+        builder = builder.with_reference_gas_price(reference_gas_price)
+    };
+
+    builder.build()
+}
+
 impl SimulatorStore for PersistedStore {
     fn get_checkpoint_by_sequence_number(
         &self,
@@ -213,6 +391,16 @@ impl SimulatorStore for PersistedStore {
             .and_then(|committees| committees.get(epoch as usize).cloned())
     }
 
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.read_write
+            .transaction_checkpoints
+            .get(digest)
+            .expect("Fatal: DB read failed")
+    }
+
     fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction> {
         self.read_write
             .transactions
@@ -271,6 +459,23 @@ impl SimulatorStore for PersistedStore {
             .and_then(|versions| versions.get(&version).cloned())
     }
 
+    fn get_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Option<Object> {
+        self.read_write
+            .objects
+            .get(id)
+            .expect("Fatal: DB read failed")
+            .and_then(|versions| {
+                versions
+                    .range(..=version)
+                    .next_back()
+                    .map(|(_, object)| object.clone())
+            })
+    }
+
     fn get_system_state(&self) -> iota_types::iota_system_state::IotaSystemState {
         iota_types::iota_system_state::get_iota_system_state(self).expect("system state must exist")
     }
@@ -291,11 +496,25 @@ impl SimulatorStore for PersistedStore {
             ))
     }
 
+    fn child_objects(&self, parent: ObjectID) -> Box<dyn Iterator<Item = Object> + '_> {
+        let owner = Owner::ObjectOwner(parent.into());
+        Box::new(
+            self.read_write
+                .live_objects
+                .unbounded_iter()
+                .flat_map(|(id, version)| self.get_object_at_version(&id, version))
+                .filter(move |object| object.owner == owner),
+        )
+    }
+
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
         self.read_write
             .checkpoint_digest_to_sequence_number
             .insert(checkpoint.digest(), checkpoint.sequence_number())
             .expect("Fatal: DB write failed");
+        if let Some(contents) = self.get_checkpoint_contents(&checkpoint.content_digest) {
+            self.index_checkpoint_transactions(*checkpoint.sequence_number(), &contents);
+        }
         self.read_write
             .checkpoints
             .insert(checkpoint.sequence_number(), checkpoint.serializable_ref())
@@ -303,6 +522,13 @@ impl SimulatorStore for PersistedStore {
     }
 
     fn insert_checkpoint_contents(&mut self, contents: CheckpointContents) {
+        // Contents are usually inserted right after their checkpoint
+        if let Some(checkpoint) = self
+            .get_highest_checkpoint()
+            .filter(|checkpoint| checkpoint.content_digest == *contents.digest())
+        {
+            self.index_checkpoint_transactions(*checkpoint.sequence_number(), &contents);
+        }
         self.read_write
             .checkpoint_contents
             .insert(contents.digest(), &contents)
@@ -824,4 +1050,56 @@ mod tests {
             chain3.store().get_committee_by_epoch(0),
         );
     }
+
+    #[tokio::test]
+    async fn genesis_snapshot() {
+        let genesis_checkpoint_digest = |sim: &Simulacrum<StdRng, PersistedStore>| {
+            *sim.store()
+                .get_checkpoint_by_sequence_number(0)
+                .unwrap()
+                .digest()
+        };
+        let fresh = PersistedStore::new_sim_with_protocol_version_and_accounts(
+            StdRng::from_seed([9; 32]),
+            0,
+            ProtocolVersion::MAX,
+            vec![],
+            None,
+        );
+        let (mut fork1, _) = PersistedStore::new_sim_replica_from_genesis_snapshot(
+            StdRng::from_seed([9; 32]),
+            0,
+            ProtocolVersion::MAX,
+            vec![],
+            None,
+            None,
+        );
+        let (fork2, _) = PersistedStore::new_sim_replica_from_genesis_snapshot(
+            StdRng::from_seed([9; 32]),
+            0,
+            ProtocolVersion::MAX,
+            vec![],
+            None,
+            None,
+        );
+
+        assert_eq!(
+            genesis_checkpoint_digest(&fresh),
+            genesis_checkpoint_digest(&fork1)
+        );
+        assert_eq!(
+            genesis_checkpoint_digest(&fork1),
+            genesis_checkpoint_digest(&fork2)
+        );
+
+        // Writes to a fork are not visible to the other forks
+        fork1.create_checkpoint();
+        assert!(fork1.store().get_checkpoint_by_sequence_number(1).is_some());
+        assert!(fork2.store().get_checkpoint_by_sequence_number(1).is_none());
+
+        // Forks are removed when dropped
+        let path = fork2.store().path.clone();
+        drop(fork2);
+        assert!(!path.exists());
+    }
 }
@@ -0,0 +1,339 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Runs a whole directory of transactional test files in one process, on the
+//! worker threads of libtest-mimic.
+//!
+//! Running the files in one process lets them share the precompiled framework
+//! and, in simulator mode, the genesis state. Every file still runs against
+//! its own copy of that state, so its output does not depend on the other
+//! files or on the number of workers. The suite can also be split into
+//! shards, for instance to spread it over several CI jobs.
+
+use std::{
+    future::Future,
+    num::NonZeroUsize,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use futures::FutureExt;
+use libtest_mimic::{Arguments, Failed, Trial};
+use regex::Regex;
+
+use crate::{
+    run_test_impl,
+    test_adapter::{IotaTestAdapter, PRE_COMPILED},
+};
+
+/// Environment variable overriding the number of worker threads
+pub const WORKERS_ENV_VAR: &str = "IOTA_TRANSACTIONAL_TEST_WORKERS";
+/// Environment variable selecting the shard to run, as `<index>/<count>`
+pub const SHARD_ENV_VAR: &str = "IOTA_TRANSACTIONAL_TEST_SHARD";
+
+/// One of `count` disjoint parts of a test suite. Files are assigned to shards
+/// round-robin in path order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: NonZeroUsize,
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s
+            .split_once('/')
+            .with_context(|| format!("Invalid shard '{s}', expected <index>/<count>"))?;
+        let index = index.trim().parse()?;
+        let count = count.trim().parse()?;
+        let shard = Shard { index, count };
+        if shard.index >= shard.count.get() {
+            bail!("Invalid shard '{s}', the index must be less than the count");
+        }
+        Ok(shard)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SuiteConfig {
+    pub workers: NonZeroUsize,
+    pub shard: Option<Shard>,
+}
+
+impl Default for SuiteConfig {
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN),
+            shard: None,
+        }
+    }
+}
+
+impl SuiteConfig {
+    /// The default configuration, overridden by [`WORKERS_ENV_VAR`] and
+    /// [`SHARD_ENV_VAR`] when they are set.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        if let Ok(workers) = std::env::var(WORKERS_ENV_VAR) {
+            config.workers = workers
+                .parse()
+                .with_context(|| format!("Invalid {WORKERS_ENV_VAR} '{workers}'"))?;
+        }
+        if let Ok(shard) = std::env::var(SHARD_ENV_VAR) {
+            config.shard = Some(shard.parse()?);
+        }
+        Ok(config)
+    }
+}
+
+thread_local! {
+    /// Runtime of the worker thread, shared by the files it runs
+    static RUNTIME: tokio::runtime::Runtime = tokio::runtime::Runtime::new().unwrap();
+}
+
+/// Runs the test files under `test_dir` whose path matches `pattern`, as
+/// [`crate::run_test`] does for a single file, and fails if any of them
+/// fails. The files are run and reported by libtest-mimic, so the usual test
+/// harness arguments such as filters and `--test-threads` are supported, the
+/// latter taking precedence over `config.workers`.
+///
+/// This is meant to be called from the `main` of a test target with
+/// `harness = false`, in place of a `datatest_stable::harness!`.
+pub fn run_test_suite(test_dir: &Path, pattern: &str, config: &SuiteConfig) -> anyhow::Result<()> {
+    let (_guard, _filter_handle) = telemetry_subscribers::TelemetryConfig::new()
+        .with_env()
+        .init();
+
+    let mut args = Arguments::from_args();
+    args.test_threads.get_or_insert(config.workers.get());
+    let files = select_files(test_dir, &Regex::new(pattern)?, config.shard)?;
+    let pre_compiled = Arc::new(PRE_COMPILED.clone());
+    let trials = trials(test_dir, files, move |path| {
+        let pre_compiled = pre_compiled.clone();
+        async move {
+            run_test_impl::<IotaTestAdapter>(&path, Some(pre_compiled))
+                .await
+                .map_err(|e| e.to_string())
+        }
+    });
+    if libtest_mimic::run(&args, trials).has_failed() {
+        bail!("Some transactional tests failed");
+    }
+    Ok(())
+}
+
+/// Runs the test files under `test_dir` whose path matches `pattern` one after
+/// another, failing on the first file that fails, and returns the number of
+/// files run. Unlike [`run_test_suite`] this leaves the process arguments
+/// alone, so it can be used by commands that run the tests for their side
+/// effects, e.g. the traces the Move VM writes when `MOVE_VM_TRACE` is set.
+///
+/// The files run on a thread of their own, so this may be called from within
+/// an async context.
+pub fn run_test_files(test_dir: &Path, pattern: &str) -> anyhow::Result<usize> {
+    let files = select_files(test_dir, &Regex::new(pattern)?, None)?;
+    let count = files.len();
+    let test_dir = test_dir.to_owned();
+    std::thread::spawn(move || -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        let pre_compiled = Arc::new(PRE_COMPILED.clone());
+        for path in files {
+            let result = runtime.block_on(
+                AssertUnwindSafe(run_test_impl::<IotaTestAdapter>(
+                    &path,
+                    Some(pre_compiled.clone()),
+                ))
+                .catch_unwind(),
+            );
+            let name = path.strip_prefix(&test_dir).unwrap_or(&path).display();
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => bail!("Transactional test {name} failed: {e}"),
+                Err(panic) => bail!("Transactional test {name} failed: {}", panic_message(panic)),
+            }
+        }
+        Ok(())
+    })
+    .join()
+    .map_err(|panic| anyhow::anyhow!(panic_message(panic)))??;
+    Ok(count)
+}
+
+/// Returns a trial per file, which runs the future returned by `run` on the
+/// runtime of the worker thread.
+fn trials<F, Fut>(test_dir: &Path, files: Vec<PathBuf>, run: F) -> Vec<Trial>
+where
+    F: Fn(PathBuf) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let run = Arc::new(run);
+    files
+        .into_iter()
+        .map(|path| {
+            let name = path
+                .strip_prefix(test_dir)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            let run = run.clone();
+            Trial::test(name, move || {
+                let result = RUNTIME.with(|runtime| {
+                    runtime.block_on(AssertUnwindSafe(run(path.clone())).catch_unwind())
+                });
+                match result {
+                    Ok(result) => result.map_err(Failed::from),
+                    Err(panic) => Err(panic_message(panic).into()),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Collects the files under `test_dir` matching `pattern` that belong to
+/// `shard`, sorted by path.
+fn select_files(
+    test_dir: &Path,
+    pattern: &Regex,
+    shard: Option<Shard>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    collect_files(test_dir, &mut files)
+        .with_context(|| format!("Failed to read {}", test_dir.display()))?;
+    files.retain(|path| pattern.is_match(&path.to_string_lossy()));
+    files.sort();
+    if let Some(Shard { index, count }) = shard {
+        files = files
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| i % count.get() == index)
+            .map(|(_, path)| path)
+            .collect();
+    }
+    Ok(files)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        format!("Test panicked: {s}")
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        format!("Test panicked: {s}")
+    } else {
+        "Test panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Mutex};
+
+    use move_transactional_test_runner::framework::handle_actual_output;
+
+    use super::*;
+
+    const TEST_FILE: &str = r#"//# init --addresses test=0x0 --accounts A
+
+//# publish
+module test::m {
+    public fun value(): u64 { VALUE }
+}
+
+//# create-checkpoint
+"#;
+
+    #[test]
+    fn parse_shard() {
+        assert_eq!("1/3".parse::<Shard>().unwrap(), Shard {
+            index: 1,
+            count: NonZeroUsize::new(3).unwrap(),
+        });
+        assert!("3/3".parse::<Shard>().is_err());
+        assert!("0/0".parse::<Shard>().is_err());
+        assert!("1".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn shards_partition_the_suite() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+        for name in ["a.move", "b.move", "c.mvir", "d.exp", "nested/e.move"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let pattern = Regex::new(r".*\.(mvir|move)$").unwrap();
+
+        let all = select_files(dir.path(), &pattern, None).unwrap();
+        assert_eq!(all.len(), 4);
+
+        let mut sharded = vec![];
+        for index in 0..3 {
+            let shard = Shard {
+                index,
+                count: NonZeroUsize::new(3).unwrap(),
+            };
+            sharded.extend(select_files(dir.path(), &pattern, Some(shard)).unwrap());
+        }
+        sharded.sort();
+        assert_eq!(sharded, all);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_workers() {
+        let dir = tempfile::tempdir().unwrap();
+        for idx in 0..4 {
+            std::fs::write(
+                dir.path().join(format!("test_{idx}.move")),
+                TEST_FILE.replace("VALUE", &idx.to_string()),
+            )
+            .unwrap();
+        }
+        let files = select_files(dir.path(), &Regex::new(r".*\.move$").unwrap(), None).unwrap();
+        let pre_compiled = Arc::new(PRE_COMPILED.clone());
+
+        let outputs_with_workers = |workers: usize| {
+            let outputs = Arc::new(Mutex::new(BTreeMap::new()));
+            let trials = trials(dir.path(), files.clone(), {
+                let outputs = outputs.clone();
+                let pre_compiled = pre_compiled.clone();
+                move |path| {
+                    let outputs = outputs.clone();
+                    let pre_compiled = pre_compiled.clone();
+                    async move {
+                        let (output, _) =
+                            handle_actual_output::<IotaTestAdapter>(&path, Some(pre_compiled))
+                                .await
+                                .map_err(|e| e.to_string())?;
+                        outputs.lock().unwrap().insert(path, output);
+                        Ok(())
+                    }
+                }
+            });
+            let args = Arguments {
+                test_threads: Some(workers),
+                quiet: true,
+                ..Default::default()
+            };
+            assert!(!libtest_mimic::run(&args, trials).has_failed());
+            std::mem::take(&mut *outputs.lock().unwrap())
+        };
+
+        let sequential = outputs_with_workers(1);
+        assert_eq!(sequential.len(), files.len());
+        assert_eq!(outputs_with_workers(4), sequential);
+    }
+}
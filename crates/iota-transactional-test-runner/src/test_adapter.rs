@@ -106,7 +106,7 @@ pub enum FakeID {
 
 const DEFAULT_GAS_PRICE: u64 = 1_000;
 
-const WELL_KNOWN_OBJECTS: &[ObjectID] = &[
+pub(crate) const WELL_KNOWN_OBJECTS: &[ObjectID] = &[
     MOVE_STDLIB_PACKAGE_ID,
     IOTA_FRAMEWORK_PACKAGE_ID,
     IOTA_SYSTEM_PACKAGE_ID,
@@ -2139,16 +2139,14 @@ async fn init_sim_executor(
     // Create the simulator with the specific account configs, which also crates
     // objects
 
-    let (mut sim, read_replica) =
-        PersistedStore::new_sim_replica_with_protocol_version_and_accounts(
-            rng,
-            DEFAULT_CHAIN_START_TIMESTAMP,
-            protocol_config.version,
-            acc_cfgs,
-            key_copy.map(|q| vec![q]),
-            reference_gas_price,
-            None,
-        );
+    let (mut sim, read_replica) = PersistedStore::new_sim_replica_from_genesis_snapshot(
+        rng,
+        DEFAULT_CHAIN_START_TIMESTAMP,
+        protocol_config.version,
+        acc_cfgs,
+        key_copy.map(|q| vec![q]),
+        reference_gas_price,
+    );
     let data_ingestion_path = tempdir().unwrap().into_path();
     sim.set_data_ingestion_path(data_ingestion_path.clone());
 
@@ -10,6 +10,7 @@ use crate::{
     base_types::{AuthorityName, VerifiedExecutionData},
     committee::Committee,
     crypto::{AuthoritySignInfo, AuthoritySignature, IotaAuthoritySignature},
+    digests::TransactionDigest,
     effects::{TransactionEffects, TransactionEffectsAPI},
     gas::GasCostSummary,
     messages_checkpoint::{
@@ -27,7 +28,7 @@ pub trait ValidatorKeypairProvider {
 
 /// A utility to build consecutive checkpoints by adding transactions to the
 /// checkpoint builder. It's mostly used by simulations, tests and benchmarks.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct MockCheckpointBuilder {
     previous_checkpoint: VerifiedCheckpoint,
     transactions: Vec<VerifiedExecutionData>,
@@ -37,9 +38,17 @@ pub struct MockCheckpointBuilder {
 
 impl MockCheckpointBuilder {
     pub fn new(previous_checkpoint: VerifiedCheckpoint) -> Self {
-        let epoch_rolling_gas_cost_summary =
-            previous_checkpoint.epoch_rolling_gas_cost_summary.clone();
-        let epoch = previous_checkpoint.epoch;
+        // The checkpoint following the last checkpoint of an epoch starts the
+        // next epoch with a fresh gas cost summary.
+        let (epoch, epoch_rolling_gas_cost_summary) =
+            if previous_checkpoint.end_of_epoch_data.is_some() {
+                (previous_checkpoint.epoch + 1, GasCostSummary::default())
+            } else {
+                (
+                    previous_checkpoint.epoch,
+                    previous_checkpoint.epoch_rolling_gas_cost_summary.clone(),
+                )
+            };
 
         Self {
             previous_checkpoint,
@@ -53,6 +62,14 @@ impl MockCheckpointBuilder {
         self.transactions.len()
     }
 
+    /// Returns the digests of the buffered transactions, in the order they
+    /// were added.
+    pub fn transaction_digests(&self) -> impl Iterator<Item = &TransactionDigest> {
+        self.transactions
+            .iter()
+            .map(|data| data.transaction.digest())
+    }
+
     pub fn epoch_rolling_gas_cost_summary(&self) -> &GasCostSummary {
         &self.epoch_rolling_gas_cost_summary
     }
@@ -562,6 +562,21 @@ pub enum IotaClientCommands {
         /// directory
         #[arg(long, short)]
         profile_output: Option<PathBuf>,
+
+        /// Record every executed bytecode instruction, which adds
+        /// per-instruction gas and instruction counts to the breakdown.
+        #[arg(long)]
+        track_instructions: bool,
+
+        /// If specified, also writes the call stacks of the profile as a
+        /// gzipped pprof profile to this filepath.
+        #[arg(long)]
+        pprof_output: Option<PathBuf>,
+
+        /// If specified, writes the per-function gas breakdown as JSON to this
+        /// filepath.
+        #[arg(long)]
+        report_output: Option<PathBuf>,
     },
 
     /// Replay a given transaction to view transaction effects. Set environment
@@ -770,6 +785,9 @@ impl IotaClientCommands {
             IotaClientCommands::ProfileTransaction {
                 tx_digest,
                 profile_output,
+                track_instructions,
+                pprof_output,
+                report_output,
             } => {
                 move_vm_profiler::gas_profiler_feature_disabled! {
                     bail!(
@@ -784,6 +802,9 @@ impl IotaClientCommands {
                     protocol_version: None,
                     profile_output,
                     config_objects: None,
+                    track_instructions,
+                    pprof_output,
+                    report_output,
                 };
                 let rpc = context.config().get_active_env()?.rpc().clone();
                 let _command_result =
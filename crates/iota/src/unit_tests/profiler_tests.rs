@@ -57,6 +57,9 @@ async fn test_profiler() {
         protocol_version: None,
         profile_output: Some(profile_output),
         config_objects: None,
+        track_instructions: false,
+        pprof_output: None,
+        report_output: None,
     };
 
     let command_result =
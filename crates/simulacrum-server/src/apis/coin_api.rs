@@ -0,0 +1,191 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use iota_json_rpc::{
+    IotaRpcModule,
+    coin_api::{parse_to_struct_tag, parse_to_type_tag},
+    error::{Error, IotaRpcInputError},
+};
+use iota_json_rpc_api::{CoinReadApiOpenRpc, CoinReadApiServer, cap_page_limit};
+use iota_json_rpc_types::{Balance, Coin as IotaCoin, CoinPage, IotaCoinMetadata};
+use iota_open_rpc::Module;
+use iota_types::{
+    balance::Supply,
+    base_types::{IotaAddress, ObjectID},
+    coin::{CoinMetadata, TreasuryCap},
+    gas_coin::GAS,
+    iota_system_state::IotaSystemStateTrait,
+    object::Object,
+};
+use jsonrpsee::{RpcModule, core::RpcResult};
+use move_core_types::language_storage::TypeTag;
+
+use crate::state::SimulacrumState;
+
+pub(crate) struct CoinReadApi {
+    state: SimulacrumState,
+}
+
+impl CoinReadApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+
+    /// Returns the coins owned by `owner`, optionally restricted to
+    /// `coin_type`, ordered by object id.
+    fn owned_coins(
+        &self,
+        owner: IotaAddress,
+        coin_type: Option<&TypeTag>,
+    ) -> Vec<(TypeTag, Object)> {
+        let mut coins = self
+            .state
+            .read()
+            .store()
+            .owned_objects(owner)
+            .filter_map(|object| Some((object.coin_type_maybe()?, object)))
+            .filter(|(type_, _)| coin_type.map_or(true, |coin_type| coin_type == type_))
+            .collect::<Vec<_>>();
+        coins.sort_by_key(|(_, object)| object.id());
+        coins
+    }
+
+    fn coin_page(
+        &self,
+        owner: IotaAddress,
+        coin_type: Option<&TypeTag>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> CoinPage {
+        let limit = cap_page_limit(limit);
+        let mut data = self
+            .owned_coins(owner, coin_type)
+            .into_iter()
+            .filter(|(_, object)| cursor.map_or(true, |cursor| object.id() > cursor))
+            .take(limit + 1)
+            .map(|(coin_type, object)| IotaCoin {
+                coin_type: coin_type.to_string(),
+                coin_object_id: object.id(),
+                version: object.version(),
+                digest: object.digest(),
+                balance: object.get_coin_value_unsafe(),
+                previous_transaction: object.previous_transaction,
+            })
+            .collect::<Vec<_>>();
+
+        let has_next_page = data.len() > limit;
+        data.truncate(limit);
+        let next_cursor = data.last().map(|coin| coin.coin_object_id);
+
+        CoinPage {
+            data,
+            next_cursor,
+            has_next_page,
+        }
+    }
+}
+
+#[async_trait]
+impl CoinReadApiServer for CoinReadApi {
+    async fn get_coins(
+        &self,
+        owner: IotaAddress,
+        coin_type: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> RpcResult<CoinPage> {
+        let coin_type = parse_to_type_tag(coin_type)?;
+        Ok(self.coin_page(owner, Some(&coin_type), cursor, limit))
+    }
+
+    async fn get_all_coins(
+        &self,
+        owner: IotaAddress,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> RpcResult<CoinPage> {
+        Ok(self.coin_page(owner, None, cursor, limit))
+    }
+
+    async fn get_balance(
+        &self,
+        owner: IotaAddress,
+        coin_type: Option<String>,
+    ) -> RpcResult<Balance> {
+        let coin_type = parse_to_type_tag(coin_type)?;
+        let coins = self.owned_coins(owner, Some(&coin_type));
+        Ok(Balance {
+            coin_type: coin_type.to_string(),
+            coin_object_count: coins.len(),
+            total_balance: coins
+                .iter()
+                .map(|(_, object)| object.get_coin_value_unsafe() as u128)
+                .sum(),
+        })
+    }
+
+    async fn get_all_balances(&self, owner: IotaAddress) -> RpcResult<Vec<Balance>> {
+        let mut balances = BTreeMap::<String, Balance>::new();
+        for (coin_type, object) in self.owned_coins(owner, None) {
+            let coin_type = coin_type.to_string();
+            let balance = balances.entry(coin_type.clone()).or_insert(Balance {
+                coin_type,
+                coin_object_count: 0,
+                total_balance: 0,
+            });
+            balance.coin_object_count += 1;
+            balance.total_balance += object.get_coin_value_unsafe() as u128;
+        }
+        Ok(balances.into_values().collect())
+    }
+
+    async fn get_coin_metadata(&self, coin_type: String) -> RpcResult<Option<IotaCoinMetadata>> {
+        let coin_struct = parse_to_struct_tag(&coin_type)?;
+        let metadata_object = self.state.read().find_package_object(
+            coin_struct.address.into(),
+            &CoinMetadata::type_(coin_struct),
+        );
+        Ok(metadata_object.and_then(|object| object.try_into().ok()))
+    }
+
+    async fn get_total_supply(&self, coin_type: String) -> RpcResult<Supply> {
+        let coin_struct = parse_to_struct_tag(&coin_type)?;
+        let simulacrum = self.state.read();
+        if GAS::is_gas(&coin_struct) {
+            return Ok(Supply {
+                value: simulacrum
+                    .store()
+                    .get_system_state()
+                    .into_iota_system_state_summary()
+                    .iota_total_supply,
+            });
+        }
+        let treasury_cap_object = simulacrum
+            .find_package_object(
+                coin_struct.address.into(),
+                &TreasuryCap::type_(coin_struct.clone()),
+            )
+            .ok_or_else(|| {
+                IotaRpcInputError::GenericNotFound(format!(
+                    "Cannot find the treasury cap of [{coin_struct}]"
+                ))
+            })?;
+        let treasury_cap =
+            TreasuryCap::from_bcs_bytes(treasury_cap_object.data.try_as_move().unwrap().contents())
+                .map_err(Error::from)?;
+        Ok(treasury_cap.total_supply)
+    }
+}
+
+impl IotaRpcModule for CoinReadApi {
+    fn rpc(self) -> RpcModule<Self> {
+        self.into_rpc()
+    }
+
+    fn rpc_doc_module() -> Module {
+        CoinReadApiOpenRpc::module_doc()
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use iota_json_rpc::{
+    IotaRpcModule,
+    error::{Error, IotaRpcInputError},
+    governance_api::{ValidatorExchangeRates, calculate_apys, stake_status},
+};
+use iota_json_rpc_api::{GovernanceReadApiOpenRpc, GovernanceReadApiServer};
+use iota_json_rpc_types::{
+    DelegatedStake, DelegatedTimelockedStake, IotaCommittee, Stake, TimelockedStake, ValidatorApys,
+};
+use iota_open_rpc::Module;
+use iota_types::{
+    base_types::{IotaAddress, ObjectID, SequenceNumber},
+    committee::EpochId,
+    dynamic_field::get_dynamic_field_from_store,
+    error::{IotaError, UserInputError},
+    governance::StakedIota,
+    id::ID,
+    iota_serde::BigInt,
+    iota_system_state::{
+        IotaSystemStateTrait, PoolTokenExchangeRate,
+        epoch_start_iota_system_state::EpochStartSystemStateTrait,
+        get_validator_from_table,
+        iota_system_state_summary::{IotaSystemStateSummary, IotaValidatorSummary},
+    },
+    object::Object,
+    storage::RestStateReader,
+    timelock::timelocked_staked_iota::TimelockedStakedIota,
+};
+use jsonrpsee::{RpcModule, core::RpcResult};
+
+use crate::state::{ServerSimulacrum, SimulacrumState};
+
+pub(crate) struct GovernanceReadApi {
+    state: SimulacrumState,
+}
+
+impl GovernanceReadApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+
+    /// Returns the stakes owned by `owner`, all of which exist.
+    fn owned_stakes<T>(&self, owner: IotaAddress) -> Vec<(T, bool)>
+    where
+        T: for<'a> TryFrom<&'a Object>,
+    {
+        self.state
+            .read()
+            .store()
+            .owned_objects(owner)
+            .filter_map(|object| T::try_from(&object).ok())
+            .map(|stake| (stake, true))
+            .collect()
+    }
+
+    /// Returns the stakes with the given ids, together with whether they still
+    /// exist. Stakes which were withdrawn are returned as of their last
+    /// version.
+    fn stakes_by_ids<T>(&self, ids: Vec<ObjectID>) -> Result<Vec<(T, bool)>, Error>
+    where
+        T: for<'a> TryFrom<&'a Object, Error = IotaError>,
+    {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        ids.into_iter()
+            .map(|id| {
+                let (object, exists) = match store.get_object(&id) {
+                    Some(object) => (object, true),
+                    None => (
+                        store
+                            .get_object_lt_or_eq_version(&id, SequenceNumber::MAX)
+                            .ok_or(IotaRpcInputError::UserInput(
+                                UserInputError::ObjectNotFound {
+                                    object_id: id,
+                                    version: None,
+                                },
+                            ))?,
+                        false,
+                    ),
+                };
+                Ok::<_, Error>((T::try_from(&object)?, exists))
+            })
+            .collect()
+    }
+
+    fn get_delegated_stakes(
+        &self,
+        stakes: Vec<(StakedIota, bool)>,
+    ) -> Result<Vec<DelegatedStake>, Error> {
+        let (epoch, rates) = self.exchange_rates()?;
+        group_by_pool(stakes, |stake| stake.pool_id())
+            .into_iter()
+            .map(|(pool_id, stakes)| {
+                let rate_table = pool_rates(&rates, pool_id)?;
+                let current_rate = rate_table.rates.first().map(|(_, rate)| rate);
+                let stakes = stakes
+                    .into_iter()
+                    .map(|(stake, exists)| Stake {
+                        staked_iota_id: stake.id(),
+                        stake_request_epoch: stake.activation_epoch() - 1,
+                        stake_active_epoch: stake.activation_epoch(),
+                        principal: stake.principal(),
+                        status: stake_status(
+                            epoch,
+                            stake.activation_epoch(),
+                            stake.principal(),
+                            exists,
+                            current_rate,
+                            rate_table,
+                        ),
+                    })
+                    .collect();
+                Ok(DelegatedStake {
+                    validator_address: rate_table.address,
+                    staking_pool: pool_id,
+                    stakes,
+                })
+            })
+            .collect()
+    }
+
+    fn get_delegated_timelocked_stakes(
+        &self,
+        stakes: Vec<(TimelockedStakedIota, bool)>,
+    ) -> Result<Vec<DelegatedTimelockedStake>, Error> {
+        let (epoch, rates) = self.exchange_rates()?;
+        group_by_pool(stakes, |stake| stake.pool_id())
+            .into_iter()
+            .map(|(pool_id, stakes)| {
+                let rate_table = pool_rates(&rates, pool_id)?;
+                let current_rate = rate_table.rates.first().map(|(_, rate)| rate);
+                let stakes = stakes
+                    .into_iter()
+                    .map(|(stake, exists)| TimelockedStake {
+                        timelocked_staked_iota_id: stake.id(),
+                        stake_request_epoch: stake.activation_epoch() - 1,
+                        stake_active_epoch: stake.activation_epoch(),
+                        principal: stake.principal(),
+                        status: stake_status(
+                            epoch,
+                            stake.activation_epoch(),
+                            stake.principal(),
+                            exists,
+                            current_rate,
+                            rate_table,
+                        ),
+                        expiration_timestamp_ms: stake.expiration_timestamp_ms(),
+                        label: stake.label().clone(),
+                    })
+                    .collect();
+                Ok(DelegatedTimelockedStake {
+                    validator_address: rate_table.address,
+                    staking_pool: pool_id,
+                    stakes,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the current epoch together with the exchange rates of the
+    /// staking pools of all active, pending and inactive validators, by pool
+    /// id. Rates are in descending order by epoch.
+    fn exchange_rates(
+        &self,
+    ) -> Result<(EpochId, BTreeMap<ObjectID, ValidatorExchangeRates>), Error> {
+        let simulacrum = self.state.read();
+        let system_state = simulacrum.store().get_system_state();
+        let pending_validators = system_state.get_pending_active_validators(&*simulacrum)?;
+        let summary: IotaSystemStateSummary = system_state.into_iota_system_state_summary();
+
+        let mut validators = summary
+            .active_validators
+            .into_iter()
+            .map(|validator| (validator, true))
+            .chain(
+                pending_validators
+                    .into_iter()
+                    .map(|validator| (validator, false)),
+            )
+            .collect::<Vec<_>>();
+        for (_, field) in simulacrum
+            .dynamic_field_iter(summary.inactive_pools_id, None)
+            .map_err(IotaError::from)?
+        {
+            let pool_id: ID = bcs::from_bytes(&field.name_value).map_err(|e| {
+                IotaError::ObjectDeserialization {
+                    error: e.to_string(),
+                }
+            })?;
+            validators.push((
+                get_validator_from_table(&*simulacrum, summary.inactive_pools_id, &pool_id)?,
+                false,
+            ));
+        }
+
+        let rates = validators
+            .into_iter()
+            .map(|(validator, active)| {
+                let rates = validator_exchange_rates(&simulacrum, &validator, active)?;
+                Ok::<_, Error>((rates.pool_id, rates))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((summary.epoch, rates))
+    }
+}
+
+#[async_trait]
+impl GovernanceReadApiServer for GovernanceReadApi {
+    async fn get_stakes_by_ids(
+        &self,
+        staked_iota_ids: Vec<ObjectID>,
+    ) -> RpcResult<Vec<DelegatedStake>> {
+        let stakes = self.stakes_by_ids(staked_iota_ids)?;
+        Ok(self.get_delegated_stakes(stakes)?)
+    }
+
+    async fn get_stakes(&self, owner: IotaAddress) -> RpcResult<Vec<DelegatedStake>> {
+        let stakes = self.owned_stakes(owner);
+        Ok(self.get_delegated_stakes(stakes)?)
+    }
+
+    async fn get_timelocked_stakes_by_ids(
+        &self,
+        timelocked_staked_iota_ids: Vec<ObjectID>,
+    ) -> RpcResult<Vec<DelegatedTimelockedStake>> {
+        let stakes = self.stakes_by_ids(timelocked_staked_iota_ids)?;
+        Ok(self.get_delegated_timelocked_stakes(stakes)?)
+    }
+
+    async fn get_timelocked_stakes(
+        &self,
+        owner: IotaAddress,
+    ) -> RpcResult<Vec<DelegatedTimelockedStake>> {
+        let stakes = self.owned_stakes(owner);
+        Ok(self.get_delegated_timelocked_stakes(stakes)?)
+    }
+
+    async fn get_committee_info(&self, epoch: Option<BigInt<u64>>) -> RpcResult<IotaCommittee> {
+        let simulacrum = self.state.read();
+        let epoch = epoch.map_or_else(|| simulacrum.epoch_start_state().epoch(), |epoch| *epoch);
+        let committee = simulacrum
+            .store()
+            .get_committee_by_epoch(epoch)
+            .ok_or_else(|| Error::Unexpected(format!("committee for epoch {epoch} not found")))?;
+        Ok(committee.into())
+    }
+
+    async fn get_latest_iota_system_state(&self) -> RpcResult<IotaSystemStateSummary> {
+        Ok(self
+            .state
+            .read()
+            .store()
+            .get_system_state()
+            .into_iota_system_state_summary())
+    }
+
+    async fn get_reference_gas_price(&self) -> RpcResult<BigInt<u64>> {
+        Ok(self.state.read().reference_gas_price().into())
+    }
+
+    async fn get_validators_apy(&self) -> RpcResult<ValidatorApys> {
+        let (epoch, rates) = self.exchange_rates()?;
+        Ok(ValidatorApys {
+            apys: calculate_apys(rates.into_values().collect()),
+            epoch,
+        })
+    }
+}
+
+impl IotaRpcModule for GovernanceReadApi {
+    fn rpc(self) -> RpcModule<Self> {
+        self.into_rpc()
+    }
+
+    fn rpc_doc_module() -> Module {
+        GovernanceReadApiOpenRpc::module_doc()
+    }
+}
+
+/// Groups `stakes` by the staking pool returned by `pool_id`.
+fn group_by_pool<T>(
+    stakes: Vec<(T, bool)>,
+    pool_id: impl Fn(&T) -> ObjectID,
+) -> BTreeMap<ObjectID, Vec<(T, bool)>> {
+    let mut pools = BTreeMap::<_, Vec<_>>::new();
+    for (stake, exists) in stakes {
+        pools
+            .entry(pool_id(&stake))
+            .or_default()
+            .push((stake, exists));
+    }
+    pools
+}
+
+fn pool_rates(
+    rates: &BTreeMap<ObjectID, ValidatorExchangeRates>,
+    pool_id: ObjectID,
+) -> Result<&ValidatorExchangeRates, Error> {
+    rates.get(&pool_id).ok_or_else(|| {
+        IotaRpcInputError::GenericNotFound(format!("Cannot find rates for staking pool {pool_id}"))
+            .into()
+    })
+}
+
+/// Reads the exchange rates of the staking pool of `validator`, in descending
+/// order by epoch.
+fn validator_exchange_rates(
+    simulacrum: &ServerSimulacrum,
+    validator: &IotaValidatorSummary,
+    active: bool,
+) -> Result<ValidatorExchangeRates, Error> {
+    let mut rates = simulacrum
+        .dynamic_field_iter(validator.exchange_rates_id, None)
+        .map_err(IotaError::from)?
+        .map(|(_, field)| {
+            let epoch: EpochId = bcs::from_bytes(&field.name_value).map_err(|e| {
+                IotaError::ObjectDeserialization {
+                    error: e.to_string(),
+                }
+            })?;
+            let rate: PoolTokenExchangeRate =
+                get_dynamic_field_from_store(simulacrum, validator.exchange_rates_id, &epoch)?;
+            Ok::<_, IotaError>((epoch, rate))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    rates.sort_by(|(a, _), (b, _)| a.cmp(b).reverse());
+
+    Ok(ValidatorExchangeRates {
+        address: validator.iota_address,
+        pool_id: validator.staking_pool_id,
+        active,
+        rates,
+    })
+}
@@ -0,0 +1,503 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use iota_json::IotaJsonValue;
+use iota_json_rpc::{
+    IotaRpcModule,
+    error::{Error, IotaRpcInputError},
+};
+use iota_json_rpc_api::{
+    IndexerApiOpenRpc, IndexerApiServer, QUERY_MAX_RESULT_LIMIT, cap_page_limit, validate_limit,
+};
+use iota_json_rpc_types::{
+    DynamicFieldPage, EventFilter, EventPage, Filter, IotaMoveValue, IotaObjectDataOptions,
+    IotaObjectResponse, IotaObjectResponseQuery, IotaTransactionBlockEvents,
+    IotaTransactionBlockResponseQuery, ObjectsPage, TransactionBlocksPage, TransactionFilter,
+};
+use iota_open_rpc::Module;
+use iota_types::{
+    base_types::{IotaAddress, ObjectID, ObjectInfo},
+    digests::TransactionDigest,
+    dynamic_field::{
+        DynamicFieldInfo, DynamicFieldName, DynamicFieldType, derive_dynamic_field_id,
+    },
+    effects::{TransactionEffects, TransactionEffectsAPI},
+    error::{IotaObjectResponseError, UserInputError},
+    event::EventID,
+    layout_resolver::{LayoutResolver, into_struct_layout},
+    messages_checkpoint::CheckpointSequenceNumber,
+    object::{Object, Owner},
+    transaction::{TransactionData, TransactionDataAPI},
+};
+use jsonrpsee::{
+    PendingSubscriptionSink, RpcModule,
+    core::{RpcResult, SubscriptionResult},
+};
+use move_bytecode_utils::layout::TypeLayoutBuilder;
+use move_core_types::language_storage::TypeTag;
+use simulacrum::SimulatorStore;
+
+use crate::{
+    apis::{to_iota_object_data, to_iota_transaction_block_response},
+    state::{ModuleResolver, SimulacrumState},
+};
+
+pub(crate) struct IndexerApi {
+    state: SimulacrumState,
+}
+
+impl IndexerApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl IndexerApiServer for IndexerApi {
+    async fn get_owned_objects(
+        &self,
+        address: IotaAddress,
+        query: Option<IotaObjectResponseQuery>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> RpcResult<ObjectsPage> {
+        let limit =
+            validate_limit(limit, *QUERY_MAX_RESULT_LIMIT).map_err(IotaRpcInputError::from)?;
+        let IotaObjectResponseQuery { filter, options } = query.unwrap_or_default();
+        let options = options.unwrap_or_default();
+
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let mut objects = store
+            .owned_objects(address)
+            .filter(|object| cursor.map_or(true, |cursor| object.id() > cursor))
+            .filter(|object| {
+                filter.as_ref().map_or(true, |filter| {
+                    filter.matches(&ObjectInfo::new(&object.compute_object_reference(), object))
+                })
+            })
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|object| object.id());
+
+        let has_next_page = objects.len() > limit;
+        objects.truncate(limit);
+        let next_cursor = objects.last().map_or(cursor, |object| Some(object.id()));
+
+        let data = objects
+            .into_iter()
+            .map(|object| {
+                Ok(IotaObjectResponse::new_with_data(to_iota_object_data(
+                    store,
+                    object,
+                    options.clone(),
+                )?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(ObjectsPage {
+            data,
+            next_cursor,
+            has_next_page,
+        })
+    }
+
+    async fn query_transaction_blocks(
+        &self,
+        query: IotaTransactionBlockResponseQuery,
+        cursor: Option<TransactionDigest>,
+        limit: Option<usize>,
+        descending_order: Option<bool>,
+    ) -> RpcResult<TransactionBlocksPage> {
+        let limit =
+            validate_limit(limit, *QUERY_MAX_RESULT_LIMIT).map_err(IotaRpcInputError::from)?;
+        let IotaTransactionBlockResponseQuery { filter, options } = query;
+        let options = options.unwrap_or_default();
+
+        // The store lock must not be held while building the responses
+        let mut digests = {
+            let simulacrum = self.state.read();
+            let store = simulacrum.store();
+            let mut transactions = self.state.executed_transactions(&simulacrum);
+            if descending_order.unwrap_or_default() {
+                transactions.reverse();
+            }
+            let start = match cursor {
+                Some(cursor) => {
+                    transactions
+                        .iter()
+                        .position(|(digest, _)| *digest == cursor)
+                        .ok_or(UserInputError::TransactionNotFound { digest: cursor })
+                        .map_err(Error::from)?
+                        + 1
+                }
+                None => 0,
+            };
+            transactions
+                .into_iter()
+                .skip(start)
+                .filter(|(digest, checkpoint)| {
+                    filter.as_ref().map_or(true, |filter| {
+                        let (Some(transaction), Some(effects)) = (
+                            store.get_transaction(digest),
+                            store.get_transaction_effects(digest),
+                        ) else {
+                            return false;
+                        };
+                        transaction_matches(
+                            filter,
+                            *checkpoint,
+                            transaction.data().transaction_data(),
+                            &effects,
+                        )
+                    })
+                })
+                .map(|(digest, _)| digest)
+                .take(limit + 1)
+                .collect::<Vec<_>>()
+        };
+
+        let has_next_page = digests.len() > limit;
+        digests.truncate(limit);
+        let next_cursor = digests.last().copied().or(cursor);
+
+        let mut data = Vec::with_capacity(digests.len());
+        for digest in digests {
+            data.push(to_iota_transaction_block_response(&self.state, digest, &options).await?);
+        }
+
+        Ok(TransactionBlocksPage {
+            data,
+            next_cursor,
+            has_next_page,
+        })
+    }
+
+    async fn query_events(
+        &self,
+        query: EventFilter,
+        cursor: Option<EventID>,
+        limit: Option<usize>,
+        descending_order: Option<bool>,
+    ) -> RpcResult<EventPage> {
+        let limit =
+            validate_limit(limit, *QUERY_MAX_RESULT_LIMIT).map_err(IotaRpcInputError::from)?;
+        let descending = descending_order.unwrap_or_default();
+
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let module_resolver = ModuleResolver(store);
+        let mut transactions = self.state.executed_transactions(&simulacrum);
+        if descending {
+            transactions.reverse();
+        }
+        let start = match cursor {
+            Some(cursor) => transactions
+                .iter()
+                .position(|(digest, _)| *digest == cursor.tx_digest)
+                .ok_or(UserInputError::TransactionNotFound {
+                    digest: cursor.tx_digest,
+                })
+                .map_err(Error::from)?,
+            None => 0,
+        };
+
+        let mut data = vec![];
+        for (digest, checkpoint) in transactions.into_iter().skip(start) {
+            let Some(events) = store.get_transaction_events_by_tx_digest(&digest) else {
+                continue;
+            };
+            let timestamp_ms = checkpoint
+                .and_then(|checkpoint| store.get_checkpoint_by_sequence_number(checkpoint))
+                .map(|checkpoint| checkpoint.timestamp_ms);
+            let mut events = IotaTransactionBlockEvents::try_from_using_module_resolver(
+                events,
+                digest,
+                timestamp_ms,
+                &module_resolver,
+            )
+            .map_err(Error::from)?
+            .data;
+            if descending {
+                events.reverse();
+            }
+            data.extend(
+                events
+                    .into_iter()
+                    // Skip the events of the cursor transaction up to the cursor
+                    .filter(|event| match cursor {
+                        Some(cursor) if event.id.tx_digest == cursor.tx_digest => {
+                            if descending {
+                                event.id.event_seq < cursor.event_seq
+                            } else {
+                                event.id.event_seq > cursor.event_seq
+                            }
+                        }
+                        _ => true,
+                    })
+                    .filter(|event| query.matches(event)),
+            );
+            if data.len() > limit {
+                break;
+            }
+        }
+
+        let has_next_page = data.len() > limit;
+        data.truncate(limit);
+        let next_cursor = data.last().map_or(cursor, |event| Some(event.id));
+
+        Ok(EventPage {
+            data,
+            next_cursor,
+            has_next_page,
+        })
+    }
+
+    // Subscriptions are removed from the served module, as the server only
+    // serves HTTP.
+
+    fn subscribe_event(
+        &self,
+        _sink: PendingSubscriptionSink,
+        _filter: EventFilter,
+    ) -> SubscriptionResult {
+        Err("subscriptions are not supported by the simulacrum server".into())
+    }
+
+    fn subscribe_transaction(
+        &self,
+        _sink: PendingSubscriptionSink,
+        _filter: TransactionFilter,
+    ) -> SubscriptionResult {
+        Err("subscriptions are not supported by the simulacrum server".into())
+    }
+
+    async fn get_dynamic_fields(
+        &self,
+        parent_object_id: ObjectID,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> RpcResult<DynamicFieldPage> {
+        let limit = cap_page_limit(limit);
+
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let mut resolver = simulacrum.type_layout_resolver(Box::new(store));
+        let mut fields = store
+            .child_objects(parent_object_id)
+            .filter(|object| cursor.map_or(true, |cursor| object.id() > cursor))
+            .filter(|object| object.type_().is_some_and(|type_| type_.is_dynamic_field()))
+            .collect::<Vec<_>>();
+        fields.sort_by_key(|object| object.id());
+
+        let has_next_page = fields.len() > limit;
+        fields.truncate(limit);
+        let next_cursor = fields.last().map_or(cursor, |object| Some(object.id()));
+
+        let mut data = Vec::with_capacity(fields.len());
+        for object in fields {
+            if let Some(info) = dynamic_field_info(store, &object, resolver.as_mut())? {
+                data.push(info);
+            }
+        }
+
+        Ok(DynamicFieldPage {
+            data,
+            next_cursor,
+            has_next_page,
+        })
+    }
+
+    async fn get_dynamic_field_object(
+        &self,
+        parent_object_id: ObjectID,
+        name: DynamicFieldName,
+    ) -> RpcResult<IotaObjectResponse> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+
+        let DynamicFieldName {
+            type_: name_type,
+            value,
+        } = name;
+        let layout = TypeLayoutBuilder::build_with_types(&name_type, &ModuleResolver(store))
+            .map_err(IotaRpcInputError::from)?;
+        let name_bcs_value = IotaJsonValue::new(value)
+            .and_then(|value| value.to_bcs_bytes(&layout))
+            .map_err(IotaRpcInputError::from)?;
+
+        let field_id = derive_dynamic_field_id(parent_object_id, &name_type, &name_bcs_value)
+            .map_err(|e| Error::Unexpected(format!("unable to derive dynamic field id: {e}")))?;
+        let object = match store.get_object(&field_id) {
+            Some(object) => Some(object),
+            // Dynamic object fields are keyed by a wrapper of their name and
+            // point to the actual object
+            None => {
+                let wrapper_type = TypeTag::Struct(Box::new(
+                    DynamicFieldInfo::dynamic_object_field_wrapper(name_type),
+                ));
+                let wrapper_id =
+                    derive_dynamic_field_id(parent_object_id, &wrapper_type, &name_bcs_value)
+                        .map_err(|e| {
+                            Error::Unexpected(format!("unable to derive dynamic field id: {e}"))
+                        })?;
+                let mut resolver = simulacrum.type_layout_resolver(Box::new(store));
+                match store.get_object(&wrapper_id) {
+                    Some(wrapper) => dynamic_field_info(store, &wrapper, resolver.as_mut())?
+                        .and_then(|info| store.get_object(&info.object_id)),
+                    None => None,
+                }
+            }
+        };
+
+        Ok(match object {
+            Some(object) => IotaObjectResponse::new_with_data(to_iota_object_data(
+                store,
+                object,
+                IotaObjectDataOptions::full_content(),
+            )?),
+            None => {
+                IotaObjectResponse::new_with_error(IotaObjectResponseError::DynamicFieldNotFound {
+                    parent_object_id,
+                })
+            }
+        })
+    }
+}
+
+impl IotaRpcModule for IndexerApi {
+    fn rpc(self) -> RpcModule<Self> {
+        let mut module = self.into_rpc();
+        for method in UNSUPPORTED_METHODS {
+            module.remove_method(method);
+        }
+        module
+    }
+
+    fn rpc_doc_module() -> Module {
+        let mut module = IndexerApiOpenRpc::module_doc();
+        for method in UNSUPPORTED_METHODS {
+            module.remove_method(method);
+        }
+        module
+    }
+}
+
+/// The subscriptions of the indexer API, which need a WebSocket connection
+/// while the server only serves HTTP.
+const UNSUPPORTED_METHODS: [&str; 4] = [
+    "iotax_subscribeEvent",
+    "iotax_unsubscribeEvent",
+    "iotax_subscribeTransaction",
+    "iotax_unsubscribeTransaction",
+];
+
+/// Whether a transaction matches `filter`, following the semantics of the
+/// transaction indexes of a full node.
+fn transaction_matches(
+    filter: &TransactionFilter,
+    checkpoint: Option<CheckpointSequenceNumber>,
+    transaction: &TransactionData,
+    effects: &TransactionEffects,
+) -> bool {
+    let is_recipient = |address: IotaAddress| {
+        effects
+            .all_changed_objects()
+            .iter()
+            .any(|(_, owner, _)| matches!(owner, Owner::AddressOwner(owner) if *owner == address))
+    };
+    match filter {
+        TransactionFilter::Checkpoint(sequence_number) => checkpoint == Some(*sequence_number),
+        TransactionFilter::MoveFunction {
+            package,
+            module,
+            function,
+        } => transaction.move_calls().into_iter().any(
+            |(call_package, call_module, call_function)| {
+                call_package == package
+                    && module
+                        .as_ref()
+                        .map_or(true, |module| call_module.as_str() == module)
+                    && function
+                        .as_ref()
+                        .map_or(true, |function| call_function.as_str() == function)
+            },
+        ),
+        TransactionFilter::InputObject(id) => transaction
+            .input_objects()
+            .is_ok_and(|objects| objects.iter().any(|object| object.object_id() == *id)),
+        TransactionFilter::ChangedObject(id) => effects
+            .all_changed_objects()
+            .iter()
+            .any(|((object_id, _, _), _, _)| object_id == id),
+        TransactionFilter::FromAddress(address) => transaction.sender() == *address,
+        TransactionFilter::ToAddress(address) => is_recipient(*address),
+        TransactionFilter::FromAndToAddress { from, to } => {
+            transaction.sender() == *from && is_recipient(*to)
+        }
+        TransactionFilter::FromOrToAddress { addr } => {
+            transaction.sender() == *addr || is_recipient(*addr)
+        }
+        TransactionFilter::TransactionKind(kind) => transaction.kind().to_string() == *kind,
+        TransactionFilter::TransactionKindIn(kinds) => {
+            kinds.contains(&transaction.kind().to_string())
+        }
+    }
+}
+
+/// Returns the JSON-RPC representation of `object` if it is a dynamic field.
+fn dynamic_field_info(
+    store: &dyn SimulatorStore,
+    object: &Object,
+    resolver: &mut dyn LayoutResolver,
+) -> Result<Option<DynamicFieldInfo>, Error> {
+    let Some(move_object) = object.data.try_as_move() else {
+        return Ok(None);
+    };
+    if !move_object.type_().is_dynamic_field() {
+        return Ok(None);
+    }
+
+    let layout =
+        into_struct_layout(resolver.get_annotated_layout(&move_object.type_().clone().into())?)?;
+    let move_struct = move_object.to_move_struct(&layout)?;
+    let (name_value, type_, object_id) = DynamicFieldInfo::parse_move_object(&move_struct)?;
+    let name_type = move_object.type_().try_extract_field_name(&type_)?;
+    let bcs_name = bcs::to_bytes(&name_value.clone().undecorate())?;
+    let name = DynamicFieldName {
+        type_: name_type,
+        value: IotaMoveValue::from(name_value).to_json_value(),
+    };
+
+    Ok(Some(match type_ {
+        DynamicFieldType::DynamicObject => {
+            let object = store
+                .get_object(&object_id)
+                .ok_or(UserInputError::ObjectNotFound {
+                    object_id,
+                    version: None,
+                })?;
+            DynamicFieldInfo {
+                name,
+                bcs_name,
+                type_,
+                object_type: object
+                    .type_()
+                    .map(|type_| type_.to_string())
+                    .unwrap_or_default(),
+                object_id,
+                version: object.version(),
+                digest: object.digest(),
+            }
+        }
+        DynamicFieldType::DynamicField => DynamicFieldInfo {
+            name,
+            bcs_name,
+            type_,
+            object_type: move_object.clone().into_type().into_type_params()[1].to_string(),
+            object_id: object.id(),
+            version: object.version(),
+            digest: object.digest(),
+        },
+    }))
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+pub(crate) use coin_api::CoinReadApi;
+pub(crate) use governance_api::GovernanceReadApi;
+pub(crate) use indexer_api::IndexerApi;
+pub(crate) use read_api::ReadApi;
+pub(crate) use simulacrum_api::SimulacrumApi;
+pub use simulacrum_api::{SimulacrumApiClient, SimulacrumApiOpenRpc, SimulacrumApiServer};
+pub(crate) use write_api::WriteApi;
+
+mod coin_api;
+mod governance_api;
+mod indexer_api;
+mod read_api;
+mod simulacrum_api;
+mod write_api;
+
+use iota_json_rpc::{
+    ObjectProviderCache, error::Error, get_balance_changes_from_effect, get_object_changes,
+};
+use iota_json_rpc_types::{
+    IotaObjectData, IotaObjectDataOptions, IotaTransactionBlock, IotaTransactionBlockEvents,
+    IotaTransactionBlockResponse, IotaTransactionBlockResponseOptions,
+};
+use iota_types::{
+    digests::TransactionDigest, effects::TransactionEffectsAPI, error::UserInputError,
+    object::Object, storage::RestStateReader, transaction::TransactionDataAPI,
+};
+use simulacrum::SimulatorStore;
+
+use crate::state::{ModuleResolver, SimulacrumState};
+
+/// Converts `object` into its JSON-RPC representation, resolving its Move
+/// layout from the packages in `store` when the content was requested.
+pub(crate) fn to_iota_object_data(
+    store: &dyn SimulatorStore,
+    object: Object,
+    options: IotaObjectDataOptions,
+) -> Result<IotaObjectData, Error> {
+    let layout = if options.show_content {
+        object.get_layout(&ModuleResolver(store))?
+    } else {
+        None
+    };
+    Ok(IotaObjectData::new(
+        object.compute_object_reference(),
+        object,
+        layout,
+        options,
+        None,
+    )?)
+}
+
+/// Builds the JSON-RPC response for an executed transaction.
+pub(crate) async fn to_iota_transaction_block_response(
+    state: &SimulacrumState,
+    digest: TransactionDigest,
+    options: &IotaTransactionBlockResponseOptions,
+) -> Result<IotaTransactionBlockResponse, Error> {
+    let mut response = IotaTransactionBlockResponse::new(digest);
+
+    // The store lock must not be held across the await points below.
+    let (transaction, effects) = {
+        let simulacrum = state.read();
+        let store = simulacrum.store();
+        let transaction = store
+            .get_transaction(&digest)
+            .ok_or(UserInputError::TransactionNotFound { digest })?
+            .into_inner();
+        let effects = store
+            .get_transaction_effects(&digest)
+            .ok_or(UserInputError::TransactionNotFound { digest })?;
+
+        response.checkpoint = simulacrum
+            .get_transaction_checkpoint(&digest)
+            .map_err(|e| Error::Internal(e.into()))?;
+        response.timestamp_ms = response
+            .checkpoint
+            .and_then(|checkpoint| store.get_checkpoint_by_sequence_number(checkpoint))
+            .map(|checkpoint| checkpoint.timestamp_ms);
+
+        let module_resolver = ModuleResolver(store);
+        if options.show_input {
+            response.transaction = Some(IotaTransactionBlock::try_from(
+                transaction.data().clone(),
+                &module_resolver,
+                digest,
+            )?);
+        }
+        if options.show_raw_input {
+            response.raw_transaction = bcs::to_bytes(transaction.data())?;
+        }
+        if options.show_events {
+            let events = store
+                .get_transaction_events_by_tx_digest(&digest)
+                .unwrap_or_default();
+            response.events = Some(IotaTransactionBlockEvents::try_from_using_module_resolver(
+                events,
+                digest,
+                response.timestamp_ms,
+                &module_resolver,
+            )?);
+        }
+        if options.show_effects {
+            response.effects = Some(effects.clone().try_into()?);
+        }
+        if options.show_raw_effects {
+            response.raw_effects = bcs::to_bytes(&effects)?;
+        }
+        (transaction, effects)
+    };
+
+    let object_cache = ObjectProviderCache::new(state.clone());
+    if options.show_balance_changes {
+        let input_objects = transaction
+            .data()
+            .transaction_data()
+            .input_objects()
+            .unwrap_or_default();
+        response.balance_changes = Some(
+            get_balance_changes_from_effect(&object_cache, &effects, input_objects, None).await?,
+        );
+    }
+    if options.show_object_changes {
+        response.object_changes = Some(
+            get_object_changes(
+                &object_cache,
+                transaction.data().transaction_data().sender(),
+                effects.modified_at_versions(),
+                effects.all_changed_objects(),
+                effects.all_removed_objects(),
+            )
+            .await?,
+        );
+    }
+
+    Ok(response)
+}
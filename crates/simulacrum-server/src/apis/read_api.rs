@@ -0,0 +1,346 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use iota_json_rpc::{
+    IotaRpcModule,
+    error::{Error, IotaRpcInputError},
+};
+use iota_json_rpc_api::{
+    QUERY_MAX_RESULT_LIMIT, QUERY_MAX_RESULT_LIMIT_CHECKPOINTS, ReadApiOpenRpc, ReadApiServer,
+    validate_limit,
+};
+use iota_json_rpc_types::{
+    Checkpoint, CheckpointId, CheckpointPage, IotaEvent, IotaGetPastObjectRequest,
+    IotaObjectDataOptions, IotaObjectResponse, IotaPastObjectResponse, IotaTransactionBlockEvents,
+    IotaTransactionBlockResponse, IotaTransactionBlockResponseOptions, ProtocolConfigResponse,
+};
+use iota_open_rpc::Module;
+use iota_protocol_config::{Chain, ProtocolConfig, ProtocolVersion};
+use iota_types::{
+    base_types::{ObjectID, SequenceNumber, TransactionDigest},
+    error::{IotaObjectResponseError, UserInputError},
+    iota_serde::BigInt,
+    iota_system_state::epoch_start_iota_system_state::EpochStartSystemStateTrait,
+    messages_checkpoint::{CheckpointSequenceNumber, VerifiedCheckpoint},
+    storage::{ReadStore, RestStateReader},
+};
+use jsonrpsee::{RpcModule, core::RpcResult};
+use simulacrum::SimulatorStore;
+
+use crate::{
+    apis::{to_iota_object_data, to_iota_transaction_block_response},
+    state::{ModuleResolver, SimulacrumState},
+};
+
+pub(crate) struct ReadApi {
+    state: SimulacrumState,
+}
+
+impl ReadApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+
+    fn get_object_internal(
+        &self,
+        object_id: ObjectID,
+        options: IotaObjectDataOptions,
+    ) -> Result<IotaObjectResponse, Error> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        match SimulatorStore::get_object(store, &object_id) {
+            Some(object) => Ok(IotaObjectResponse::new_with_data(to_iota_object_data(
+                store, object, options,
+            )?)),
+            None => Ok(IotaObjectResponse::new_with_error(
+                IotaObjectResponseError::NotExists { object_id },
+            )),
+        }
+    }
+
+    fn get_past_object_internal(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+        options: IotaObjectDataOptions,
+    ) -> Result<IotaPastObjectResponse, Error> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        if let Some(object) = store.get_object_at_version(&object_id, version) {
+            return Ok(IotaPastObjectResponse::VersionFound(to_iota_object_data(
+                store, object, options,
+            )?));
+        }
+        Ok(match SimulatorStore::get_object(store, &object_id) {
+            Some(latest) if latest.version() < version => IotaPastObjectResponse::VersionTooHigh {
+                object_id,
+                asked_version: version,
+                latest_version: latest.version(),
+            },
+            Some(_) => IotaPastObjectResponse::VersionNotFound(object_id, version),
+            None => IotaPastObjectResponse::ObjectNotExists(object_id),
+        })
+    }
+
+    fn get_checkpoint_internal(&self, id: CheckpointId) -> Result<Checkpoint, Error> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let checkpoint = match id {
+            CheckpointId::SequenceNumber(sequence_number) => store
+                .get_checkpoint_by_sequence_number(sequence_number)
+                .ok_or(UserInputError::VerifiedCheckpointNotFound(sequence_number))?,
+            CheckpointId::Digest(digest) => {
+                store.get_checkpoint_by_digest(&digest).ok_or_else(|| {
+                    UserInputError::VerifiedCheckpointDigestNotFound(digest.to_string())
+                })?
+            }
+        };
+        to_checkpoint(store, checkpoint)
+    }
+}
+
+pub(super) fn to_checkpoint(
+    store: &dyn SimulatorStore,
+    checkpoint: VerifiedCheckpoint,
+) -> Result<Checkpoint, Error> {
+    let contents = store
+        .get_checkpoint_contents(&checkpoint.content_digest)
+        .ok_or_else(|| {
+            Error::Unexpected(format!(
+                "missing contents for checkpoint {}",
+                checkpoint.sequence_number
+            ))
+        })?;
+    let signature = checkpoint.auth_sig().signature.clone();
+    let summary = checkpoint.into_summary_and_sequence().1;
+    Ok(Checkpoint::from((summary, contents, signature)))
+}
+
+#[async_trait]
+impl ReadApiServer for ReadApi {
+    async fn get_transaction_block(
+        &self,
+        digest: TransactionDigest,
+        options: Option<IotaTransactionBlockResponseOptions>,
+    ) -> RpcResult<IotaTransactionBlockResponse> {
+        let options = options.unwrap_or_default();
+        Ok(to_iota_transaction_block_response(&self.state, digest, &options).await?)
+    }
+
+    async fn multi_get_transaction_blocks(
+        &self,
+        digests: Vec<TransactionDigest>,
+        options: Option<IotaTransactionBlockResponseOptions>,
+    ) -> RpcResult<Vec<IotaTransactionBlockResponse>> {
+        if digests.len() > *QUERY_MAX_RESULT_LIMIT {
+            return Err(
+                IotaRpcInputError::SizeLimitExceeded(QUERY_MAX_RESULT_LIMIT.to_string()).into(),
+            );
+        }
+        let options = options.unwrap_or_default();
+        let mut responses = Vec::with_capacity(digests.len());
+        for digest in digests {
+            responses
+                .push(to_iota_transaction_block_response(&self.state, digest, &options).await?);
+        }
+        Ok(responses)
+    }
+
+    async fn get_object(
+        &self,
+        object_id: ObjectID,
+        options: Option<IotaObjectDataOptions>,
+    ) -> RpcResult<IotaObjectResponse> {
+        Ok(self.get_object_internal(object_id, options.unwrap_or_default())?)
+    }
+
+    async fn multi_get_objects(
+        &self,
+        object_ids: Vec<ObjectID>,
+        options: Option<IotaObjectDataOptions>,
+    ) -> RpcResult<Vec<IotaObjectResponse>> {
+        if object_ids.len() > *QUERY_MAX_RESULT_LIMIT {
+            return Err(
+                IotaRpcInputError::SizeLimitExceeded(QUERY_MAX_RESULT_LIMIT.to_string()).into(),
+            );
+        }
+        let options = options.unwrap_or_default();
+        Ok(object_ids
+            .into_iter()
+            .map(|object_id| self.get_object_internal(object_id, options.clone()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn try_get_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+        options: Option<IotaObjectDataOptions>,
+    ) -> RpcResult<IotaPastObjectResponse> {
+        Ok(self.get_past_object_internal(object_id, version, options.unwrap_or_default())?)
+    }
+
+    async fn try_get_object_before_version(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+    ) -> RpcResult<IotaPastObjectResponse> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        Ok(
+            match store.get_object_lt_or_eq_version(&object_id, version) {
+                Some(object) => IotaPastObjectResponse::VersionFound(to_iota_object_data(
+                    store,
+                    object,
+                    IotaObjectDataOptions::bcs_lossless(),
+                )?),
+                None => IotaPastObjectResponse::ObjectNotExists(object_id),
+            },
+        )
+    }
+
+    async fn try_multi_get_past_objects(
+        &self,
+        past_objects: Vec<IotaGetPastObjectRequest>,
+        options: Option<IotaObjectDataOptions>,
+    ) -> RpcResult<Vec<IotaPastObjectResponse>> {
+        if past_objects.len() > *QUERY_MAX_RESULT_LIMIT {
+            return Err(
+                IotaRpcInputError::SizeLimitExceeded(QUERY_MAX_RESULT_LIMIT.to_string()).into(),
+            );
+        }
+        let options = options.unwrap_or_default();
+        Ok(past_objects
+            .into_iter()
+            .map(|request| {
+                self.get_past_object_internal(request.object_id, request.version, options.clone())
+            })
+            .collect::<Result<_, _>>()?)
+    }
+
+    async fn get_checkpoint(&self, id: CheckpointId) -> RpcResult<Checkpoint> {
+        Ok(self.get_checkpoint_internal(id)?)
+    }
+
+    async fn get_checkpoints(
+        &self,
+        cursor: Option<BigInt<u64>>,
+        limit: Option<usize>,
+        descending_order: bool,
+    ) -> RpcResult<CheckpointPage> {
+        let limit = validate_limit(limit, QUERY_MAX_RESULT_LIMIT_CHECKPOINTS)
+            .map_err(IotaRpcInputError::from)?;
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let latest = store
+            .get_highest_checkpoint()
+            .map(|checkpoint| checkpoint.sequence_number)
+            .unwrap_or_default();
+
+        // The cursor itself is excluded from the page
+        let sequence_numbers: Box<dyn Iterator<Item = CheckpointSequenceNumber>> =
+            match (cursor.map(|cursor| *cursor), descending_order) {
+                (Some(cursor), true) => Box::new((0..cursor.min(latest + 1)).rev()),
+                (None, true) => Box::new((0..=latest).rev()),
+                (Some(cursor), false) => Box::new(cursor.saturating_add(1)..=latest),
+                (None, false) => Box::new(0..=latest),
+            };
+
+        let mut data = sequence_numbers
+            .take(limit + 1)
+            .filter_map(|sequence_number| store.get_checkpoint_by_sequence_number(sequence_number))
+            .map(|checkpoint| to_checkpoint(store, checkpoint))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let has_next_page = data.len() > limit;
+        data.truncate(limit);
+        let next_cursor = has_next_page
+            .then(|| {
+                data.last()
+                    .map(|checkpoint| checkpoint.sequence_number.into())
+            })
+            .flatten();
+
+        Ok(CheckpointPage {
+            data,
+            next_cursor,
+            has_next_page,
+        })
+    }
+
+    async fn get_events(&self, transaction_digest: TransactionDigest) -> RpcResult<Vec<IotaEvent>> {
+        let simulacrum = self.state.read();
+        let store = simulacrum.store();
+        let events = store
+            .get_transaction_events_by_tx_digest(&transaction_digest)
+            .unwrap_or_default();
+        let timestamp_ms = simulacrum
+            .get_transaction_checkpoint(&transaction_digest)
+            .map_err(|e| Error::Internal(e.into()))?
+            .and_then(|checkpoint| store.get_checkpoint_by_sequence_number(checkpoint))
+            .map(|checkpoint| checkpoint.timestamp_ms);
+        let events = IotaTransactionBlockEvents::try_from_using_module_resolver(
+            events,
+            transaction_digest,
+            timestamp_ms,
+            &ModuleResolver(store),
+        )
+        .map_err(Error::from)?;
+        Ok(events.data)
+    }
+
+    async fn get_total_transaction_blocks(&self) -> RpcResult<BigInt<u64>> {
+        let checkpoint = self
+            .state
+            .read()
+            .get_latest_checkpoint()
+            .map_err(|e| Error::Internal(e.into()))?;
+        Ok(checkpoint.network_total_transactions.into())
+    }
+
+    async fn get_latest_checkpoint_sequence_number(&self) -> RpcResult<BigInt<u64>> {
+        let sequence_number = self
+            .state
+            .read()
+            .get_latest_checkpoint_sequence_number()
+            .map_err(|e| Error::Internal(e.into()))?;
+        Ok(sequence_number.into())
+    }
+
+    async fn get_protocol_config(
+        &self,
+        version: Option<BigInt<u64>>,
+    ) -> RpcResult<ProtocolConfigResponse> {
+        let version = version.map_or_else(
+            || self.state.read().epoch_start_state().protocol_version(),
+            |version| ProtocolVersion::new(*version),
+        );
+        let config = ProtocolConfig::get_for_version_if_supported(version, Chain::Unknown).ok_or(
+            IotaRpcInputError::ProtocolVersionUnsupported(
+                ProtocolVersion::MIN.as_u64(),
+                ProtocolVersion::MAX.as_u64(),
+            ),
+        )?;
+        Ok(ProtocolConfigResponse::from(config))
+    }
+
+    async fn get_chain_identifier(&self) -> RpcResult<String> {
+        let chain_identifier = self
+            .state
+            .read()
+            .get_chain_identifier()
+            .map_err(|e| Error::Internal(e.into()))?;
+        Ok(chain_identifier.to_string())
+    }
+}
+
+impl IotaRpcModule for ReadApi {
+    fn rpc(self) -> RpcModule<Self> {
+        self.into_rpc()
+    }
+
+    fn rpc_doc_module() -> Module {
+        ReadApiOpenRpc::module_doc()
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use iota_json_rpc::{IotaRpcModule, error::Error};
+use iota_json_rpc_types::Checkpoint;
+use iota_open_rpc::Module;
+use iota_open_rpc_macros::open_rpc;
+use iota_types::{base_types::IotaAddress, digests::TransactionDigest, iota_serde::BigInt};
+use jsonrpsee::{RpcModule, core::RpcResult, proc_macros::rpc};
+
+use crate::{apis::read_api::to_checkpoint, state::SimulacrumState};
+
+/// Provides manual control over the chain served by the simulacrum server,
+/// such as advancing time, closing checkpoints and epochs, and taking and
+/// reverting to snapshots of the whole chain state.
+#[open_rpc(namespace = "simulacrum", tag = "Simulacrum API")]
+#[rpc(server, client, namespace = "simulacrum")]
+pub trait SimulacrumApi {
+    /// Advance the chain clock by the given number of milliseconds and return
+    /// the digest of the transaction that updated the clock.
+    #[method(name = "advanceClock")]
+    async fn advance_clock(&self, duration_ms: BigInt<u64>) -> RpcResult<TransactionDigest>;
+
+    /// Advance the chain to the next epoch. The final checkpoint of the
+    /// current epoch is created as part of this.
+    #[method(name = "advanceEpoch")]
+    async fn advance_epoch(&self) -> RpcResult<()>;
+
+    /// Create a checkpoint containing all transactions executed since the
+    /// last checkpoint and return it.
+    #[method(name = "createCheckpoint")]
+    async fn create_checkpoint(&self) -> RpcResult<Checkpoint>;
+
+    /// Transfer the given amount of NANOS from the faucet account to
+    /// `address` and return the digest of the transfer.
+    #[method(name = "requestGas")]
+    async fn request_gas(
+        &self,
+        address: IotaAddress,
+        amount: BigInt<u64>,
+    ) -> RpcResult<TransactionDigest>;
+
+    /// Take a snapshot of the whole chain state and return its id.
+    #[method(name = "snapshot")]
+    async fn snapshot(&self) -> RpcResult<BigInt<u64>>;
+
+    /// Revert the chain state to the snapshot with the given id. The snapshot
+    /// and all snapshots taken after it are discarded. Return false if no
+    /// such snapshot exists.
+    #[method(name = "revert")]
+    async fn revert(&self, id: BigInt<u64>) -> RpcResult<bool>;
+}
+
+pub(crate) struct SimulacrumApi {
+    state: SimulacrumState,
+}
+
+impl SimulacrumApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl SimulacrumApiServer for SimulacrumApi {
+    async fn advance_clock(&self, duration_ms: BigInt<u64>) -> RpcResult<TransactionDigest> {
+        Ok(self
+            .state
+            .advance_clock(Duration::from_millis(*duration_ms))
+            .await)
+    }
+
+    async fn advance_epoch(&self) -> RpcResult<()> {
+        self.state
+            .spawn_blocking(|state| state.write().advance_epoch())
+            .await;
+        Ok(())
+    }
+
+    async fn create_checkpoint(&self) -> RpcResult<Checkpoint> {
+        Ok(self
+            .state
+            .spawn_blocking(|state| {
+                let mut simulacrum = state.write();
+                let checkpoint = simulacrum.create_checkpoint();
+                to_checkpoint(simulacrum.store(), checkpoint)
+            })
+            .await?)
+    }
+
+    async fn request_gas(
+        &self,
+        address: IotaAddress,
+        amount: BigInt<u64>,
+    ) -> RpcResult<TransactionDigest> {
+        Ok(self
+            .state
+            .request_gas(address, *amount)
+            .await
+            .map_err(Error::from)?)
+    }
+
+    async fn snapshot(&self) -> RpcResult<BigInt<u64>> {
+        Ok(self.state.take_snapshot().await.into())
+    }
+
+    async fn revert(&self, id: BigInt<u64>) -> RpcResult<bool> {
+        Ok(self.state.revert_to_snapshot(*id).await)
+    }
+}
+
+impl IotaRpcModule for SimulacrumApi {
+    fn rpc(self) -> RpcModule<Self> {
+        self.into_rpc()
+    }
+
+    fn rpc_doc_module() -> Module {
+        SimulacrumApiOpenRpc::module_doc()
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use async_trait::async_trait;
+use fastcrypto::{encoding::Base64, traits::ToFromBytes};
+use iota_json_rpc::{
+    IotaRpcModule, ObjectProviderCache,
+    error::{Error, IotaRpcInputError},
+    get_balance_changes_from_effect, get_object_changes,
+};
+use iota_json_rpc_api::{WriteApiOpenRpc, WriteApiServer};
+use iota_json_rpc_types::{
+    DevInspectArgs, DevInspectResults, DryRunTransactionBlockResponse, IotaTransactionBlockData,
+    IotaTransactionBlockEvents, IotaTransactionBlockResponse, IotaTransactionBlockResponseOptions,
+};
+use iota_open_rpc::Module;
+use iota_types::{
+    base_types::IotaAddress,
+    effects::TransactionEffectsAPI,
+    error::IotaError,
+    iota_serde::BigInt,
+    quorum_driver_types::ExecuteTransactionRequestType,
+    signature::GenericSignature,
+    transaction::{
+        GasData, Transaction, TransactionData, TransactionDataAPI, TransactionDataV1,
+        TransactionExpiration, TransactionKind,
+    },
+};
+use jsonrpsee::{RpcModule, core::RpcResult};
+
+use crate::{
+    apis::to_iota_transaction_block_response,
+    state::{ModuleResolver, SimulacrumState},
+};
+
+pub(crate) struct WriteApi {
+    state: SimulacrumState,
+}
+
+impl WriteApi {
+    pub fn new(state: SimulacrumState) -> Self {
+        Self { state }
+    }
+
+    fn convert_bytes<T: serde::de::DeserializeOwned>(
+        &self,
+        tx_bytes: Base64,
+    ) -> Result<T, IotaRpcInputError> {
+        let data: T = bcs::from_bytes(&tx_bytes.to_vec()?)?;
+        Ok(data)
+    }
+}
+
+#[async_trait]
+impl WriteApiServer for WriteApi {
+    async fn execute_transaction_block(
+        &self,
+        tx_bytes: Base64,
+        signatures: Vec<Base64>,
+        options: Option<IotaTransactionBlockResponseOptions>,
+        // Transactions are executed synchronously, so they are always executed
+        // locally by the time the response is sent.
+        _request_type: Option<ExecuteTransactionRequestType>,
+    ) -> RpcResult<IotaTransactionBlockResponse> {
+        let tx_data: TransactionData = self.convert_bytes(tx_bytes)?;
+        let mut sigs = Vec::with_capacity(signatures.len());
+        for sig in signatures {
+            sigs.push(
+                GenericSignature::from_bytes(&sig.to_vec().map_err(IotaRpcInputError::from)?)
+                    .map_err(IotaRpcInputError::from)?,
+            );
+        }
+        let transaction = Transaction::from_generic_sig_data(tx_data, sigs);
+        let digest = *transaction.digest();
+
+        self.state
+            .execute_transaction(transaction)
+            .await
+            .map_err(Error::from)?;
+
+        let options = options.unwrap_or_default();
+        let mut response =
+            to_iota_transaction_block_response(&self.state, digest, &options).await?;
+        response.confirmed_local_execution = Some(true);
+        Ok(response)
+    }
+
+    async fn dev_inspect_transaction_block(
+        &self,
+        sender_address: IotaAddress,
+        tx_bytes: Base64,
+        gas_price: Option<BigInt<u64>>,
+        // The simulacrum only executes transactions in its current epoch
+        _epoch: Option<BigInt<u64>>,
+        additional_args: Option<DevInspectArgs>,
+    ) -> RpcResult<DevInspectResults> {
+        let DevInspectArgs {
+            gas_sponsor,
+            gas_budget,
+            gas_objects,
+            show_raw_txn_data_and_effects,
+            skip_checks,
+        } = additional_args.unwrap_or_default();
+        let kind: TransactionKind = self.convert_bytes(tx_bytes)?;
+        if kind.is_system_tx() {
+            return Err(Error::from(IotaError::UnsupportedFeature {
+                error: "system transactions are not supported".to_string(),
+            })
+            .into());
+        }
+
+        let (reference_gas_price, max_tx_gas) = {
+            let simulacrum = self.state.read();
+            (
+                simulacrum.reference_gas_price(),
+                simulacrum.protocol_config().max_tx_gas(),
+            )
+        };
+        // The payment may be empty, in which case a dummy gas coin is used
+        let transaction = TransactionData::V1(TransactionDataV1 {
+            kind,
+            sender: sender_address,
+            gas_data: GasData {
+                payment: gas_objects.unwrap_or_default(),
+                owner: gas_sponsor.unwrap_or(sender_address),
+                price: gas_price.map_or(reference_gas_price, |price| *price),
+                budget: gas_budget.map_or(max_tx_gas, |budget| *budget),
+            },
+            expiration: TransactionExpiration::None,
+        });
+
+        Ok(self
+            .state
+            .dev_inspect_transaction(
+                transaction,
+                skip_checks.unwrap_or(true),
+                show_raw_txn_data_and_effects.unwrap_or(false),
+            )
+            .await
+            .map_err(Error::from)?)
+    }
+
+    async fn dry_run_transaction_block(
+        &self,
+        tx_bytes: Base64,
+    ) -> RpcResult<DryRunTransactionBlockResponse> {
+        let tx_data: TransactionData = self.convert_bytes(tx_bytes)?;
+        let sender = tx_data.sender();
+        let input_objects = tx_data.input_objects().map_err(Error::from)?;
+
+        let (inner_temporary_store, effects) = self
+            .state
+            .dry_run_transaction(tx_data.clone())
+            .await
+            .map_err(Error::from)?;
+        let digest = *effects.transaction_digest();
+
+        let (input, events) = {
+            let simulacrum = self.state.read();
+            let module_resolver = ModuleResolver(simulacrum.store());
+            let input = IotaTransactionBlockData::try_from(tx_data, &module_resolver, digest)
+                .map_err(Error::from)?;
+            let events = IotaTransactionBlockEvents::try_from_using_module_resolver(
+                inner_temporary_store.events,
+                digest,
+                None,
+                &module_resolver,
+            )
+            .map_err(Error::from)?;
+            (input, events)
+        };
+
+        // Objects written by the transaction are only known to the temporary store
+        let object_cache = ObjectProviderCache::new_with_output_objects(
+            self.state.clone(),
+            inner_temporary_store.written.into_values().collect(),
+        );
+        let balance_changes =
+            get_balance_changes_from_effect(&object_cache, &effects, input_objects, None)
+                .await
+                .map_err(Error::from)?;
+        let object_changes = get_object_changes(
+            &object_cache,
+            sender,
+            effects.modified_at_versions(),
+            effects.all_changed_objects(),
+            effects.all_removed_objects(),
+        )
+        .await
+        .map_err(Error::from)?;
+
+        Ok(DryRunTransactionBlockResponse {
+            effects: effects.try_into().map_err(Error::from)?,
+            events,
+            object_changes,
+            balance_changes,
+            input,
+        })
+    }
+}
+
+impl IotaRpcModule for WriteApi {
+    fn rpc(self) -> RpcModule<Self> {
+        self.into_rpc()
+    }
+
+    fn rpc_doc_module() -> Module {
+        WriteApiOpenRpc::module_doc()
+    }
+}
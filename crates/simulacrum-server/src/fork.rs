@@ -0,0 +1,366 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use anyhow::{Context, Result, bail};
+use iota_json_rpc_types::{
+    IotaObjectDataOptions, IotaTransactionBlockEffectsAPI, IotaTransactionBlockResponseOptions,
+    IotaTransactionBlockResponseQuery, TransactionFilter,
+};
+use iota_replay::{DataFetcher, RemoteFetcher, types::ReplayEngineError};
+use iota_sdk::IotaClient;
+use iota_types::{
+    base_types::{ObjectID, SequenceNumber},
+    digests::TransactionDigest,
+    error::IotaObjectResponseError,
+    messages_checkpoint::CheckpointSequenceNumber,
+    object::Object,
+};
+use parking_lot::Mutex;
+use simulacrum::ObjectFetcher;
+use tokio::runtime::Handle;
+
+/// An [`ObjectFetcher`] which reads objects from a full node through its
+/// JSON-RPC API, as of a pinned checkpoint.
+///
+/// The JSON-RPC API only serves the latest state, so the version of an object
+/// which changed after the pinned checkpoint is found through the last
+/// transaction which wrote it in or before that checkpoint. Objects are then
+/// read in batches through a [`RemoteFetcher`], which caches them.
+///
+/// Requests run on a runtime owned by the fetcher, so that objects can be
+/// fetched synchronously from within the store on any thread.
+pub struct RpcObjectFetcher {
+    inner: Arc<Inner>,
+    runtime: Handle,
+}
+
+struct Inner {
+    fetcher: RemoteFetcher,
+    checkpoint: CheckpointSequenceNumber,
+    /// Checkpoints of the transactions which wrote the latest version of
+    /// objects.
+    transaction_checkpoints: Mutex<HashMap<TransactionDigest, Option<CheckpointSequenceNumber>>>,
+}
+
+impl RpcObjectFetcher {
+    /// Creates a fetcher pinned to `checkpoint`, or to the latest checkpoint
+    /// of the remote network if none is given.
+    pub async fn new(
+        client: IotaClient,
+        checkpoint: Option<CheckpointSequenceNumber>,
+    ) -> Result<Self> {
+        let checkpoint = match checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => {
+                client
+                    .read_api()
+                    .get_latest_checkpoint_sequence_number()
+                    .await?
+            }
+        };
+        Ok(Self {
+            inner: Arc::new(Inner {
+                fetcher: RemoteFetcher::new(client),
+                checkpoint,
+                transaction_checkpoints: Default::default(),
+            }),
+            runtime: spawn_runtime()?,
+        })
+    }
+
+    /// The checkpoint the remote network is forked at.
+    pub fn checkpoint(&self) -> CheckpointSequenceNumber {
+        self.inner.checkpoint
+    }
+
+    /// Returns the timestamp of the checkpoint the remote network is forked
+    /// at.
+    pub async fn checkpoint_timestamp_ms(&self) -> Result<u64> {
+        Ok(self
+            .inner
+            .client()
+            .read_api()
+            .get_checkpoint(self.inner.checkpoint.into())
+            .await?
+            .timestamp_ms)
+    }
+
+    /// Runs `future` on the fetcher's runtime and waits for its output.
+    fn block_on<T, F>(&self, future: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.runtime.spawn(async move {
+            // the receiver only goes away if the caller panicked
+            let _ = sender.send(future.await);
+        });
+        receiver
+            .recv()
+            .context("the request to the forked network was cancelled")?
+    }
+}
+
+impl Inner {
+    fn client(&self) -> &IotaClient {
+        &self.fetcher.rpc_client
+    }
+
+    /// Whether `digest` was executed in or before the pinned checkpoint.
+    async fn is_before_fork(&self, digest: TransactionDigest) -> Result<bool> {
+        let cached = self.transaction_checkpoints.lock().get(&digest).copied();
+        let checkpoint = match cached {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let checkpoint = self
+                    .client()
+                    .read_api()
+                    .get_transaction_with_options(
+                        digest,
+                        IotaTransactionBlockResponseOptions::new(),
+                    )
+                    .await?
+                    .checkpoint;
+                self.transaction_checkpoints
+                    .lock()
+                    .insert(digest, checkpoint);
+                checkpoint
+            }
+        };
+        Ok(self.includes(checkpoint))
+    }
+
+    /// Whether a transaction in `checkpoint` was executed in or before the
+    /// pinned checkpoint. Transactions which are not yet part of a checkpoint
+    /// are newer than any checkpoint.
+    fn includes(&self, checkpoint: Option<CheckpointSequenceNumber>) -> bool {
+        checkpoint.is_some_and(|checkpoint| checkpoint <= self.checkpoint)
+    }
+
+    async fn object_at_fork(&self, id: ObjectID) -> Result<Option<Object>> {
+        let response = self
+            .client()
+            .read_api()
+            .get_object_with_options(id, IotaObjectDataOptions::bcs_lossless())
+            .await?;
+        match (response.data, response.error) {
+            (Some(data), _) => {
+                let object: Object = data.try_into()?;
+                if self.is_before_fork(object.previous_transaction).await? {
+                    return Ok(Some(object));
+                }
+            }
+            (None, Some(IotaObjectResponseError::NotExists { .. })) => return Ok(None),
+            (None, Some(IotaObjectResponseError::Deleted { .. })) => {
+                // The object is only gone at the fork point if the transaction
+                // that deleted it, which took it as input, was executed before.
+                let deleted_by = self
+                    .client()
+                    .read_api()
+                    .query_transaction_blocks(
+                        IotaTransactionBlockResponseQuery::new_with_filter(
+                            TransactionFilter::InputObject(id),
+                        ),
+                        None,
+                        1,
+                        true,
+                    )
+                    .await?
+                    .data
+                    .pop();
+                if deleted_by.is_some_and(|response| self.includes(response.checkpoint)) {
+                    return Ok(None);
+                }
+            }
+            (None, error) => bail!("unexpected response when fetching object {id}: {error:?}"),
+        }
+        self.object_written_before_fork(id).await
+    }
+
+    /// Returns the version of the object written by the last transaction
+    /// which changed it in or before the pinned checkpoint.
+    async fn object_written_before_fork(&self, id: ObjectID) -> Result<Option<Object>> {
+        let query = IotaTransactionBlockResponseQuery::new(
+            Some(TransactionFilter::ChangedObject(id)),
+            Some(IotaTransactionBlockResponseOptions::new().with_effects()),
+        );
+        let mut cursor: Option<TransactionDigest> = None;
+        loop {
+            let page = self
+                .client()
+                .read_api()
+                .query_transaction_blocks(query.clone(), cursor, None, true)
+                .await?;
+            if let Some(response) = page
+                .data
+                .into_iter()
+                .find(|response| self.includes(response.checkpoint))
+            {
+                let effects = response.effects.with_context(|| {
+                    format!("missing effects of transaction {}", response.digest)
+                })?;
+                let version = effects
+                    .all_changed_objects()
+                    .into_iter()
+                    .find_map(|(object, _)| (object.object_id() == id).then_some(object.version()))
+                    .with_context(|| {
+                        format!(
+                            "transaction {} does not change object {id}",
+                            response.digest
+                        )
+                    })?;
+                return self.object_at_version(id, version).await;
+            }
+            if !page.has_next_page {
+                return Ok(None);
+            }
+            cursor = page.next_cursor;
+        }
+    }
+
+    async fn object_at_version(
+        &self,
+        id: ObjectID,
+        version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        Ok(self
+            .objects_at_versions(vec![(id, version)])
+            .await?
+            .pop()
+            .flatten())
+    }
+
+    async fn objects_at_versions(
+        &self,
+        keys: Vec<(ObjectID, SequenceNumber)>,
+    ) -> Result<Vec<Option<Object>>> {
+        let objects = match self.fetcher.multi_get_versioned(&keys).await {
+            Ok(objects) => objects,
+            // A single missing object fails the whole batch, so look up each
+            // object on its own to tell which ones are missing.
+            Err(error) if is_not_found(&error) => {
+                let mut objects = Vec::with_capacity(keys.len());
+                for key in &keys {
+                    objects.push(
+                        match self
+                            .fetcher
+                            .multi_get_versioned(std::slice::from_ref(key))
+                            .await
+                        {
+                            Ok(mut found) => found.pop(),
+                            Err(error) if is_not_found(&error) => None,
+                            Err(error) => return Err(error.into()),
+                        },
+                    );
+                }
+                return Ok(objects);
+            }
+            Err(error) => return Err(error.into()),
+        };
+        // The fetcher returns cached objects after the fetched ones, so match
+        // them back to the requested keys.
+        let mut objects: HashMap<_, _> = objects
+            .into_iter()
+            .map(|object| ((object.id(), object.version()), object))
+            .collect();
+        Ok(keys.iter().map(|key| objects.remove(key)).collect())
+    }
+
+    async fn object_before_version(
+        &self,
+        id: ObjectID,
+        version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        match self.fetcher.get_child_object(&id, version).await {
+            Ok(object) => Ok(Some(object)),
+            Err(error) if is_not_found(&error) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+impl ObjectFetcher for RpcObjectFetcher {
+    fn get_object(&self, id: &ObjectID) -> Result<Option<Object>> {
+        let (inner, id) = (self.inner.clone(), *id);
+        self.block_on(async move { inner.object_at_fork(id).await })
+    }
+
+    fn get_object_at_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        let (inner, id) = (self.inner.clone(), *id);
+        self.block_on(async move { inner.object_at_version(id, version).await })
+    }
+
+    fn get_objects_at_versions(
+        &self,
+        keys: &[(ObjectID, SequenceNumber)],
+    ) -> Result<Vec<Option<Object>>> {
+        let (inner, keys) = (self.inner.clone(), keys.to_vec());
+        self.block_on(async move { inner.objects_at_versions(keys).await })
+    }
+
+    fn get_object_before_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        let (inner, id) = (self.inner.clone(), *id);
+        self.block_on(async move { inner.object_before_version(id, version).await })
+    }
+}
+
+/// Starts a runtime on a dedicated thread, which keeps running for the rest
+/// of the process.
+fn spawn_runtime() -> Result<Handle> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let handle = runtime.handle().clone();
+    std::thread::Builder::new()
+        .name("rpc-object-fetcher".to_owned())
+        .spawn(move || runtime.block_on(std::future::pending::<()>()))?;
+    Ok(handle)
+}
+
+/// Whether the remote network reported the requested object or version as
+/// missing, rather than failing to answer.
+fn is_not_found(error: &ReplayEngineError) -> bool {
+    matches!(
+        error,
+        ReplayEngineError::ObjectNotExist { .. }
+            | ReplayEngineError::ObjectVersionNotFound { .. }
+            | ReplayEngineError::ObjectVersionTooHigh { .. }
+            | ReplayEngineError::ObjectDeleted { .. }
+    )
+}
+
+/// An [`ObjectFetcher`] for chains which do not fork a remote network.
+pub struct NoRemote;
+
+impl ObjectFetcher for NoRemote {
+    fn get_object(&self, _id: &ObjectID) -> Result<Option<Object>> {
+        Ok(None)
+    }
+
+    fn get_object_at_version(
+        &self,
+        _id: &ObjectID,
+        _version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        Ok(None)
+    }
+
+    fn get_object_before_version(
+        &self,
+        _id: &ObjectID,
+        _version: SequenceNumber,
+    ) -> Result<Option<Object>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! A JSON-RPC and REST server backed by a [`Simulacrum`].
+//!
+//! The server exposes the standard read and write APIs of a full node on top
+//! of a deterministic, single process chain, together with the `simulacrum`
+//! namespace which allows clients to manually advance the clock and the
+//! epoch, create checkpoints and snapshot and revert the whole chain state.
+//!
+//! Compared to a full node, the served APIs have the following limitations:
+//!
+//! - The event and transaction subscriptions are not served, as the server only
+//!   accepts HTTP connections.
+//! - Transaction and event queries only cover the transactions executed by the
+//!   simulacrum itself, including the ones pending in the next checkpoint.
+//! - When forking a remote network, dynamic fields, coin metadata and total
+//!   supplies are only resolved from the objects and packages known locally.
+//!
+//! [`Simulacrum`]: simulacrum::Simulacrum
+
+use std::{net::SocketAddr, sync::Arc};
+
+use iota_json_rpc::{JsonRpcServerBuilder, ServerType};
+use prometheus::Registry;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::apis::{CoinReadApi, GovernanceReadApi, IndexerApi, ReadApi, SimulacrumApi, WriteApi};
+
+pub mod apis;
+mod fork;
+mod state;
+
+pub use fork::{NoRemote, RpcObjectFetcher};
+pub use state::{ExecutedTransaction, ServerSimulacrum, SimulacrumState};
+
+/// Builds the router serving the JSON-RPC and REST APIs from `state`.
+pub async fn build_router(
+    state: SimulacrumState,
+    prometheus_registry: &Registry,
+) -> anyhow::Result<axum::Router> {
+    let mut server =
+        JsonRpcServerBuilder::new(env!("CARGO_PKG_VERSION"), prometheus_registry, None, None);
+    server.register_module(ReadApi::new(state.clone()))?;
+    server.register_module(CoinReadApi::new(state.clone()))?;
+    server.register_module(GovernanceReadApi::new(state.clone()))?;
+    server.register_module(IndexerApi::new(state.clone()))?;
+    server.register_module(WriteApi::new(state.clone()))?;
+    server.register_module(SimulacrumApi::new(state.clone()))?;
+    let json_rpc_router = server.to_router(ServerType::Http).await?;
+
+    let mut rest_service =
+        iota_rest_api::RestService::new(Arc::new(state.clone()), env!("CARGO_PKG_VERSION"));
+    rest_service.with_executor(Arc::new(state));
+
+    Ok(json_rpc_router.merge(rest_service.into_router()))
+}
+
+/// Starts serving the APIs from `state` on `listen_address`.
+///
+/// Returns the address the server is bound to, which is useful when binding
+/// to port 0, together with the handle of the serving task.
+pub async fn start_server(
+    state: SimulacrumState,
+    listen_address: SocketAddr,
+    prometheus_registry: &Registry,
+) -> anyhow::Result<(SocketAddr, JoinHandle<()>)> {
+    let router = build_router(state, prometheus_registry).await?;
+    let listener = tokio::net::TcpListener::bind(listen_address).await?;
+    let local_addr = listener.local_addr()?;
+
+    let handle = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap()
+    });
+
+    info!(local_addr =? local_addr, "Simulacrum server listening on {local_addr}");
+
+    Ok((local_addr, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use iota_json_rpc_api::{CoinReadApiClient, ReadApiClient};
+    use iota_types::{base_types::IotaAddress, gas_coin::NANOS_PER_IOTA, iota_serde::BigInt};
+    use jsonrpsee::http_client::HttpClientBuilder;
+    use rand::{SeedableRng, rngs::StdRng};
+    use simulacrum::Simulacrum;
+
+    use super::*;
+    use crate::apis::SimulacrumApiClient;
+
+    #[tokio::test]
+    async fn manual_controls_and_snapshots() {
+        let state = SimulacrumState::new(
+            Simulacrum::new_forked(StdRng::from_seed([9; 32]), 1, Arc::new(NoRemote)),
+            true,
+        );
+        let (addr, _handle) = start_server(state, "127.0.0.1:0".parse().unwrap(), &Registry::new())
+            .await
+            .unwrap();
+        let client = HttpClientBuilder::default()
+            .build(format!("http://{addr}"))
+            .unwrap();
+
+        let start = *client
+            .get_latest_checkpoint_sequence_number()
+            .await
+            .unwrap();
+        let snapshot = client.snapshot().await.unwrap();
+
+        let recipient = IotaAddress::random_for_testing_only();
+        client
+            .request_gas(recipient, NANOS_PER_IOTA.into())
+            .await
+            .unwrap();
+        client.advance_clock(BigInt::from(1_000)).await.unwrap();
+        let checkpoint = client.create_checkpoint().await.unwrap();
+        assert_eq!(checkpoint.sequence_number, start + 3);
+
+        let balance = client.get_balance(recipient, None).await.unwrap();
+        assert_eq!(balance.total_balance, NANOS_PER_IOTA as u128);
+
+        assert!(client.revert(snapshot).await.unwrap());
+        assert!(!client.revert(snapshot).await.unwrap());
+
+        let balance = client.get_balance(recipient, None).await.unwrap();
+        assert_eq!(balance.total_balance, 0);
+        assert_eq!(
+            *client
+                .get_latest_checkpoint_sequence_number()
+                .await
+                .unwrap(),
+            start
+        );
+    }
+}
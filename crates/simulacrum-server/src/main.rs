@@ -0,0 +1,102 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use clap::Parser;
+use iota_sdk::IotaClientBuilder;
+use prometheus::Registry;
+use rand::{SeedableRng, rngs::StdRng};
+use simulacrum::{ObjectFetcher, Simulacrum};
+use simulacrum_server::{NoRemote, RpcObjectFetcher, SimulacrumState, start_server};
+use tracing::info;
+
+/// Serve the JSON-RPC and REST APIs from a deterministic, in-memory chain.
+#[derive(Parser)]
+#[command(name = "simulacrum-server")]
+struct Args {
+    /// The address the APIs are served on.
+    #[arg(long, default_value = "127.0.0.1:9000")]
+    listen_address: SocketAddr,
+    /// Seed used to generate the genesis, the validator and account keys.
+    /// The same seed always produces the same chain.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Directory checkpoints are written to so that indexers can ingest
+    /// them.
+    #[arg(long)]
+    data_ingestion_path: Option<PathBuf>,
+    /// Don't create a checkpoint after each executed transaction. Checkpoints
+    /// then have to be created with `simulacrum_createCheckpoint`.
+    #[arg(long)]
+    no_auto_checkpoint: bool,
+    /// JSON-RPC URL of a full node whose object state is forked. Objects are
+    /// fetched lazily the first time a transaction reads them.
+    #[arg(long)]
+    fork_url: Option<String>,
+    /// The checkpoint the remote network is forked at. Defaults to the latest
+    /// checkpoint of the full node.
+    #[arg(long, requires = "fork_url")]
+    fork_checkpoint: Option<u64>,
+    /// Restore the chain state saved with `--dump-state` instead of starting
+    /// from a new genesis. A forked state must be loaded with the same
+    /// `--fork-url` and `--fork-checkpoint` it was created with.
+    #[arg(long)]
+    load_state: Option<PathBuf>,
+    /// Save the chain state to this file when the server is stopped.
+    #[arg(long)]
+    dump_state: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _guard = telemetry_subscribers::TelemetryConfig::new()
+        .with_env()
+        .init();
+
+    let args = Args::parse();
+
+    let (fetcher, chain_start_timestamp_ms): (Arc<dyn ObjectFetcher>, u64) = match &args.fork_url {
+        Some(fork_url) => {
+            let client = IotaClientBuilder::default().build(fork_url).await?;
+            let fetcher = RpcObjectFetcher::new(client, args.fork_checkpoint).await?;
+            info!("Forking {fork_url} at checkpoint {}", fetcher.checkpoint());
+            let timestamp_ms = fetcher.checkpoint_timestamp_ms().await?;
+            (Arc::new(fetcher), timestamp_ms)
+        }
+        None => (Arc::new(NoRemote), 1),
+    };
+
+    let rng = StdRng::seed_from_u64(args.seed);
+    let mut simulacrum = match &args.load_state {
+        Some(path) => {
+            info!("Loading chain state from {}", path.display());
+            Simulacrum::load_forked(path, rng, fetcher)?
+        }
+        None => Simulacrum::new_forked(rng, chain_start_timestamp_ms, fetcher),
+    };
+    if let Some(data_ingestion_path) = args.data_ingestion_path {
+        simulacrum.set_data_ingestion_path(data_ingestion_path);
+    }
+    for (address, _) in simulacrum.keystore().accounts() {
+        info!("Funded account: {address}");
+    }
+
+    let state = SimulacrumState::new(simulacrum, !args.no_auto_checkpoint);
+    let (_, handle) = start_server(state.clone(), args.listen_address, &Registry::new()).await?;
+    tokio::select! {
+        result = handle => result?,
+        _ = tokio::signal::ctrl_c() => info!("Shutting down"),
+    }
+
+    if let Some(path) = args.dump_state {
+        let mut simulacrum = state.write();
+        if simulacrum.has_pending_transactions() {
+            simulacrum.create_checkpoint();
+        }
+        simulacrum.save(&path)?;
+        info!("Saved chain state to {}", path.display());
+    }
+    Ok(())
+}
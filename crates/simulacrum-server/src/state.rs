@@ -0,0 +1,610 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use iota_json_rpc::ObjectProvider;
+use iota_json_rpc_types::DevInspectResults;
+use iota_types::{
+    base_types::{IotaAddress, ObjectID, SequenceNumber},
+    committee::{Committee, EpochId},
+    digests::{ChainIdentifier, TransactionDigest, TransactionEventsDigest},
+    effects::{TransactionEffects, TransactionEffectsAPI, TransactionEvents},
+    error::{IotaError, IotaResult, UserInputError},
+    inner_temporary_store::{InnerTemporaryStore, PackageStoreWithFallback},
+    messages_checkpoint::{
+        CheckpointContents, CheckpointContentsDigest, CheckpointDigest, CheckpointSequenceNumber,
+        FullCheckpointContents, VerifiedCheckpoint,
+    },
+    object::Object,
+    quorum_driver_types::{
+        EffectsFinalityInfo, ExecuteTransactionRequestV1, ExecuteTransactionResponseV1,
+        FinalizedEffects, QuorumDriverError,
+    },
+    storage::{
+        AccountOwnedObjectInfo, CoinInfo, DynamicFieldIndexInfo, DynamicFieldKey, ObjectStore,
+        ReadStore, RestStateReader, error::Result as StorageResult, get_module,
+    },
+    transaction::{Transaction, TransactionData, TransactionDataAPI, VerifiedTransaction},
+    transaction_executor::TransactionExecutor,
+};
+use move_binary_format::CompiledModule;
+use move_bytecode_utils::module_cache::GetModule;
+use move_core_types::language_storage::{ModuleId, StructTag};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rand::rngs::StdRng;
+use simulacrum::{ForkingStore, Simulacrum, SimulatorStore, Snapshot};
+
+/// The `Simulacrum` flavour served by the RPC server.
+pub type ServerSimulacrum = Simulacrum<StdRng, ForkingStore>;
+
+/// Shared handle to the `Simulacrum` backing the RPC server.
+///
+/// All API modules, the REST service and the transaction executor operate on
+/// clones of the same handle, so every request observes the effects of the
+/// previously executed ones.
+///
+/// Executing transactions holds the write lock of the `Simulacrum`, so it is
+/// done on blocking threads through the async methods of the handle.
+#[derive(Clone)]
+pub struct SimulacrumState {
+    simulacrum: Arc<RwLock<ServerSimulacrum>>,
+    snapshots: Arc<Mutex<Vec<Snapshot<ForkingStore>>>>,
+    transaction_index: Arc<Mutex<TransactionIndex>>,
+    auto_checkpoint: bool,
+}
+
+/// The checkpointed transactions in execution order, extended as checkpoints
+/// are created so that queries don't go over every checkpoint.
+#[derive(Default)]
+struct TransactionIndex {
+    transactions: Vec<(TransactionDigest, Option<CheckpointSequenceNumber>)>,
+    /// The next checkpoint to index.
+    next_checkpoint: CheckpointSequenceNumber,
+}
+
+impl TransactionIndex {
+    fn update(&mut self, simulacrum: &ServerSimulacrum) {
+        let store = simulacrum.store();
+        let Some(latest) = store.get_highest_checkpoint() else {
+            return;
+        };
+        for sequence_number in self.next_checkpoint..=latest.sequence_number {
+            let Some(contents) = store
+                .get_checkpoint_by_sequence_number(sequence_number)
+                .and_then(|checkpoint| store.get_checkpoint_contents(&checkpoint.content_digest))
+            else {
+                continue;
+            };
+            self.transactions.extend(
+                contents
+                    .iter()
+                    .map(|digests| (digests.transaction, Some(sequence_number))),
+            );
+        }
+        self.next_checkpoint = latest.sequence_number + 1;
+    }
+}
+
+impl SimulacrumState {
+    /// Wraps `simulacrum` into a shared handle.
+    ///
+    /// When `auto_checkpoint` is set, a checkpoint is created right after each
+    /// transaction submitted through the write APIs is executed.
+    pub fn new(simulacrum: ServerSimulacrum, auto_checkpoint: bool) -> Self {
+        Self {
+            simulacrum: Arc::new(RwLock::new(simulacrum)),
+            snapshots: Default::default(),
+            transaction_index: Default::default(),
+            auto_checkpoint,
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, ServerSimulacrum> {
+        self.simulacrum.read()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, ServerSimulacrum> {
+        self.simulacrum.write()
+    }
+
+    /// Runs `f` on a blocking thread, so that the async runtime isn't blocked
+    /// while `f` executes transactions or waits for the lock of the
+    /// `Simulacrum`.
+    pub async fn spawn_blocking<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Self) -> R + Send + 'static,
+    ) -> R {
+        let state = self.clone();
+        match tokio::task::spawn_blocking(move || f(&state)).await {
+            Ok(result) => result,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// Returns the digests of all executed transactions in execution order,
+    /// together with the checkpoint including them. Transactions executed
+    /// since the last checkpoint come last.
+    pub fn executed_transactions(
+        &self,
+        simulacrum: &ServerSimulacrum,
+    ) -> Vec<(TransactionDigest, Option<CheckpointSequenceNumber>)> {
+        let mut index = self.transaction_index.lock();
+        index.update(simulacrum);
+        index
+            .transactions
+            .iter()
+            .copied()
+            .chain(
+                simulacrum
+                    .pending_transactions()
+                    .map(|digest| (*digest, None)),
+            )
+            .collect()
+    }
+
+    /// Executes a signed transaction, returning its effects together with
+    /// its events and the checkpoint it was included in, if any.
+    pub async fn execute_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> IotaResult<ExecutedTransaction> {
+        let create_checkpoint = self.auto_checkpoint;
+        self.spawn_blocking(move |state| {
+            state.execute_transaction_impl(transaction, create_checkpoint)
+        })
+        .await
+    }
+
+    fn execute_transaction_impl(
+        &self,
+        transaction: Transaction,
+        create_checkpoint: bool,
+    ) -> IotaResult<ExecutedTransaction> {
+        let digest = *transaction.digest();
+        let mut simulacrum = self.write();
+        let (effects, _) = simulacrum
+            .execute_transaction(transaction)
+            .map_err(into_iota_error)?;
+        let events = simulacrum
+            .store()
+            .get_transaction_events_by_tx_digest(&digest)
+            .unwrap_or_default();
+        let checkpoint = create_checkpoint.then(|| simulacrum.create_checkpoint().sequence_number);
+        Ok(ExecutedTransaction {
+            effects,
+            events,
+            checkpoint,
+        })
+    }
+
+    /// Executes `transaction` without signature checks on top of the current
+    /// state and discards the results.
+    pub async fn dry_run_transaction(
+        &self,
+        transaction: TransactionData,
+    ) -> IotaResult<(InnerTemporaryStore, TransactionEffects)> {
+        self.spawn_blocking(move |state| {
+            let (inner_temporary_store, effects, _) = state
+                .read()
+                .dry_run_transaction(transaction)
+                .map_err(into_iota_error)?;
+            Ok((inner_temporary_store, effects))
+        })
+        .await
+    }
+
+    /// Executes `transaction` in dev-inspect mode on top of the current state
+    /// and discards the results.
+    pub async fn dev_inspect_transaction(
+        &self,
+        transaction: TransactionData,
+        skip_checks: bool,
+        show_raw_txn_data_and_effects: bool,
+    ) -> IotaResult<DevInspectResults> {
+        self.spawn_blocking(move |state| {
+            state.dev_inspect_transaction_impl(
+                transaction,
+                skip_checks,
+                show_raw_txn_data_and_effects,
+            )
+        })
+        .await
+    }
+
+    fn dev_inspect_transaction_impl(
+        &self,
+        transaction: TransactionData,
+        skip_checks: bool,
+        show_raw_txn_data_and_effects: bool,
+    ) -> IotaResult<DevInspectResults> {
+        let raw_txn_data = if show_raw_txn_data_and_effects {
+            bcs::to_bytes(&transaction).map_err(|_| IotaError::TransactionSerialization {
+                error: "Failed to serialize transaction during dev inspect".to_string(),
+            })?
+        } else {
+            vec![]
+        };
+
+        let simulacrum = self.read();
+        let (inner_temporary_store, effects, execution_result) = simulacrum
+            .dev_inspect_transaction(transaction, skip_checks)
+            .map_err(into_iota_error)?;
+
+        let raw_effects = if show_raw_txn_data_and_effects {
+            bcs::to_bytes(&effects).map_err(|_| IotaError::TransactionSerialization {
+                error: "Failed to serialize transaction effects during dev inspect".to_string(),
+            })?
+        } else {
+            vec![]
+        };
+
+        // Packages published by the transaction are only known to the
+        // temporary store
+        let mut layout_resolver = simulacrum.type_layout_resolver(Box::new(
+            PackageStoreWithFallback::new(&inner_temporary_store, simulacrum.store()),
+        ));
+        DevInspectResults::new(
+            effects,
+            inner_temporary_store.events.clone(),
+            execution_result,
+            raw_txn_data,
+            raw_effects,
+            layout_resolver.as_mut(),
+        )
+    }
+
+    /// Advances the chain clock by `duration` and returns the digest of the
+    /// executed ConsensusCommitPrologue transaction.
+    pub async fn advance_clock(&self, duration: Duration) -> TransactionDigest {
+        self.spawn_blocking(move |state| {
+            let mut simulacrum = state.write();
+            let effects = simulacrum.advance_clock(duration);
+            if state.auto_checkpoint {
+                simulacrum.create_checkpoint();
+            }
+            *effects.transaction_digest()
+        })
+        .await
+    }
+
+    /// Transfers `amount` NANOS from the faucet account to `address` and
+    /// returns the digest of the transfer.
+    pub async fn request_gas(
+        &self,
+        address: IotaAddress,
+        amount: u64,
+    ) -> IotaResult<TransactionDigest> {
+        self.spawn_blocking(move |state| {
+            let mut simulacrum = state.write();
+            let effects = simulacrum
+                .request_gas(address, amount)
+                .map_err(into_iota_error)?;
+            if state.auto_checkpoint {
+                simulacrum.create_checkpoint();
+            }
+            Ok(*effects.transaction_digest())
+        })
+        .await
+    }
+
+    /// Takes a snapshot of the whole chain state and returns its id.
+    pub async fn take_snapshot(&self) -> u64 {
+        self.spawn_blocking(|state| {
+            let snapshot = state.read().snapshot();
+            let mut snapshots = state.snapshots.lock();
+            snapshots.push(snapshot);
+            (snapshots.len() - 1) as u64
+        })
+        .await
+    }
+
+    /// Reverts the chain state to the snapshot with the given id.
+    ///
+    /// The snapshot as well as all snapshots taken after it are discarded.
+    /// Returns `false` if no such snapshot exists.
+    pub async fn revert_to_snapshot(&self, id: u64) -> bool {
+        self.spawn_blocking(move |state| {
+            let mut snapshots = state.snapshots.lock();
+            if id as usize >= snapshots.len() {
+                return false;
+            }
+            let snapshot = snapshots.drain(id as usize..).next().unwrap();
+            let mut simulacrum = state.write();
+            simulacrum.revert_to_snapshot(snapshot);
+            // Checkpoints after the snapshot are dropped and their sequence
+            // numbers reused
+            *state.transaction_index.lock() = TransactionIndex::default();
+            true
+        })
+        .await
+    }
+}
+
+/// The outcome of a transaction executed through [`SimulacrumState`].
+pub struct ExecutedTransaction {
+    pub effects: TransactionEffects,
+    pub events: TransactionEvents,
+    pub checkpoint: Option<CheckpointSequenceNumber>,
+}
+
+/// Transaction execution errors from the `Simulacrum` are `anyhow` errors
+/// wrapping the original `IotaError`, unwrap it so that clients receive the
+/// same errors a full node would return.
+fn into_iota_error(e: anyhow::Error) -> IotaError {
+    e.downcast::<IotaError>()
+        .unwrap_or_else(|e| IotaError::Unknown(e.to_string()))
+}
+
+/// Resolves Move modules from the packages held by a `SimulatorStore`.
+pub struct ModuleResolver<'a>(pub &'a dyn SimulatorStore);
+
+impl GetModule for ModuleResolver<'_> {
+    type Error = IotaError;
+    type Item = CompiledModule;
+
+    fn get_module_by_id(&self, id: &ModuleId) -> Result<Option<Self::Item>, Self::Error> {
+        get_module(self.0, id)?
+            .map(|bytes| {
+                CompiledModule::deserialize_with_defaults(&bytes).map_err(|e| {
+                    IotaError::ModuleDeserializationFailure {
+                        error: e.to_string(),
+                    }
+                })
+            })
+            .transpose()
+    }
+}
+
+#[async_trait]
+impl ObjectProvider for SimulacrumState {
+    type Error = IotaError;
+
+    async fn get_object(
+        &self,
+        id: &ObjectID,
+        version: &SequenceNumber,
+    ) -> Result<Object, Self::Error> {
+        self.read()
+            .store()
+            .get_object_at_version(id, *version)
+            .ok_or_else(|| {
+                UserInputError::ObjectNotFound {
+                    object_id: *id,
+                    version: Some(*version),
+                }
+                .into()
+            })
+    }
+
+    async fn find_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: &SequenceNumber,
+    ) -> Result<Option<Object>, Self::Error> {
+        Ok(self
+            .read()
+            .store()
+            .get_object_lt_or_eq_version(id, *version))
+    }
+}
+
+#[async_trait]
+impl TransactionExecutor for SimulacrumState {
+    async fn execute_transaction(
+        &self,
+        request: ExecuteTransactionRequestV1,
+        _client_addr: Option<std::net::SocketAddr>,
+    ) -> Result<ExecuteTransactionResponseV1, QuorumDriverError> {
+        let ExecuteTransactionRequestV1 {
+            transaction,
+            include_events,
+            include_input_objects,
+            include_output_objects,
+            include_auxiliary_data: _,
+        } = request;
+
+        let input_objects = include_input_objects.then(|| {
+            let simulacrum = self.read();
+            transaction
+                .data()
+                .transaction_data()
+                .input_objects()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|kind| {
+                    SimulatorStore::get_object(simulacrum.store(), &kind.object_id())
+                })
+                .collect()
+        });
+
+        // The REST API reports finality through the checkpoint including the
+        // transaction, so it is always checkpointed right away.
+        let ExecutedTransaction {
+            effects,
+            events,
+            checkpoint,
+        } = self
+            .spawn_blocking(move |state| state.execute_transaction_impl(transaction, true))
+            .await
+            .map_err(QuorumDriverError::QuorumDriverInternal)?;
+        let checkpoint = checkpoint.expect("checkpoint was requested");
+
+        let output_objects = include_output_objects.then(|| {
+            let simulacrum = self.read();
+            effects
+                .all_changed_objects()
+                .into_iter()
+                .filter_map(|((id, version, _), _, _)| {
+                    simulacrum.store().get_object_at_version(&id, version)
+                })
+                .collect()
+        });
+
+        Ok(ExecuteTransactionResponseV1 {
+            effects: FinalizedEffects {
+                finality_info: EffectsFinalityInfo::Checkpointed(
+                    effects.executed_epoch(),
+                    checkpoint,
+                ),
+                effects,
+            },
+            events: include_events.then_some(events),
+            input_objects,
+            output_objects,
+            auxiliary_data: None,
+        })
+    }
+}
+
+// The REST service reads through the trait objects below while the JSON-RPC
+// modules may concurrently execute transactions, so every call takes the read
+// lock and iterators are collected before the lock is released.
+
+impl ObjectStore for SimulacrumState {
+    fn get_object(&self, object_id: &ObjectID) -> StorageResult<Option<Object>> {
+        ObjectStore::get_object(&*self.read(), object_id)
+    }
+
+    fn get_object_by_key(
+        &self,
+        object_id: &ObjectID,
+        version: SequenceNumber,
+    ) -> StorageResult<Option<Object>> {
+        ObjectStore::get_object_by_key(&*self.read(), object_id, version)
+    }
+}
+
+impl ReadStore for SimulacrumState {
+    fn get_committee(&self, epoch: EpochId) -> StorageResult<Option<Arc<Committee>>> {
+        self.read().get_committee(epoch)
+    }
+
+    fn get_latest_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.read().get_latest_checkpoint()
+    }
+
+    fn get_highest_verified_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.read().get_highest_verified_checkpoint()
+    }
+
+    fn get_highest_synced_checkpoint(&self) -> StorageResult<VerifiedCheckpoint> {
+        self.read().get_highest_synced_checkpoint()
+    }
+
+    fn get_lowest_available_checkpoint(&self) -> StorageResult<CheckpointSequenceNumber> {
+        self.read().get_lowest_available_checkpoint()
+    }
+
+    fn get_checkpoint_by_digest(
+        &self,
+        digest: &CheckpointDigest,
+    ) -> StorageResult<Option<VerifiedCheckpoint>> {
+        self.read().get_checkpoint_by_digest(digest)
+    }
+
+    fn get_checkpoint_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> StorageResult<Option<VerifiedCheckpoint>> {
+        ReadStore::get_checkpoint_by_sequence_number(&*self.read(), sequence_number)
+    }
+
+    fn get_checkpoint_contents_by_digest(
+        &self,
+        digest: &CheckpointContentsDigest,
+    ) -> StorageResult<Option<CheckpointContents>> {
+        self.read().get_checkpoint_contents_by_digest(digest)
+    }
+
+    fn get_checkpoint_contents_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> StorageResult<Option<CheckpointContents>> {
+        self.read()
+            .get_checkpoint_contents_by_sequence_number(sequence_number)
+    }
+
+    fn get_transaction(
+        &self,
+        tx_digest: &TransactionDigest,
+    ) -> StorageResult<Option<Arc<VerifiedTransaction>>> {
+        ReadStore::get_transaction(&*self.read(), tx_digest)
+    }
+
+    fn get_transaction_effects(
+        &self,
+        tx_digest: &TransactionDigest,
+    ) -> StorageResult<Option<TransactionEffects>> {
+        ReadStore::get_transaction_effects(&*self.read(), tx_digest)
+    }
+
+    fn get_events(
+        &self,
+        event_digest: &TransactionEventsDigest,
+    ) -> StorageResult<Option<TransactionEvents>> {
+        self.read().get_events(event_digest)
+    }
+
+    fn get_full_checkpoint_contents_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> StorageResult<Option<FullCheckpointContents>> {
+        self.read()
+            .get_full_checkpoint_contents_by_sequence_number(sequence_number)
+    }
+
+    fn get_full_checkpoint_contents(
+        &self,
+        digest: &CheckpointContentsDigest,
+    ) -> StorageResult<Option<FullCheckpointContents>> {
+        self.read().get_full_checkpoint_contents(digest)
+    }
+}
+
+impl RestStateReader for SimulacrumState {
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> StorageResult<Option<CheckpointSequenceNumber>> {
+        self.read().get_transaction_checkpoint(digest)
+    }
+
+    fn get_lowest_available_checkpoint_objects(&self) -> StorageResult<CheckpointSequenceNumber> {
+        self.read().get_lowest_available_checkpoint_objects()
+    }
+
+    fn get_chain_identifier(&self) -> StorageResult<ChainIdentifier> {
+        self.read().get_chain_identifier()
+    }
+
+    fn account_owned_objects_info_iter(
+        &self,
+        owner: IotaAddress,
+        cursor: Option<ObjectID>,
+    ) -> StorageResult<Box<dyn Iterator<Item = AccountOwnedObjectInfo> + '_>> {
+        let objects = self
+            .read()
+            .account_owned_objects_info_iter(owner, cursor)?
+            .collect::<Vec<_>>();
+        Ok(Box::new(objects.into_iter()))
+    }
+
+    fn dynamic_field_iter(
+        &self,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
+    ) -> StorageResult<Box<dyn Iterator<Item = (DynamicFieldKey, DynamicFieldIndexInfo)> + '_>>
+    {
+        let fields = self
+            .read()
+            .dynamic_field_iter(parent, cursor)?
+            .collect::<Vec<_>>();
+        Ok(Box::new(fields.into_iter()))
+    }
+
+    fn get_coin_info(&self, coin_type: &StructTag) -> StorageResult<Option<CoinInfo>> {
+        self.read().get_coin_info(coin_type)
+    }
+}
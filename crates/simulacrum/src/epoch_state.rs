@@ -11,18 +11,25 @@ use iota_protocol_config::{Chain, ProtocolConfig, ProtocolVersion};
 use iota_types::{
     committee::{Committee, EpochId},
     effects::TransactionEffects,
+    error::ExecutionError,
+    execution::{ExecutionResult, TypeLayoutStore},
     gas::IotaGasStatus,
     inner_temporary_store::InnerTemporaryStore,
     iota_system_state::{
         IotaSystemState, IotaSystemStateTrait,
         epoch_start_iota_system_state::{EpochStartSystemState, EpochStartSystemStateTrait},
     },
+    layout_resolver::LayoutResolver,
     metrics::{BytecodeVerifierMetrics, LimitsMetrics},
-    transaction::{TransactionDataAPI, VerifiedTransaction},
+    object::Object,
+    transaction::{
+        InputObjectKind, ObjectReadResult, TransactionData, TransactionDataAPI, VerifiedTransaction,
+    },
 };
 
 use crate::SimulatorStore;
 
+#[derive(Clone)]
 pub struct EpochState {
     epoch_start_state: EpochStartSystemState,
     committee: Committee,
@@ -72,6 +79,15 @@ impl EpochState {
         round
     }
 
+    /// Returns the round the next clock update will use without consuming it.
+    pub fn peek_next_consensus_round(&self) -> u64 {
+        self.next_consensus_round
+    }
+
+    pub fn set_next_consensus_round(&mut self, round: u64) {
+        self.next_consensus_round = round;
+    }
+
     pub fn committee(&self) -> &Committee {
         &self.committee
     }
@@ -148,4 +164,128 @@ impl EpochState {
             tx_digest,
         ))
     }
+
+    /// Executes `transaction` in dev-inspect mode, which allows any Move
+    /// function to be called and returns the values produced by each command.
+    ///
+    /// If the transaction has no gas payment, a dummy gas coin owned by the
+    /// gas owner is used instead. Unless `skip_checks` is false, only the
+    /// lightweight dev-inspect input checks are run.
+    pub fn dev_inspect_transaction(
+        &self,
+        store: &dyn SimulatorStore,
+        deny_config: &TransactionDenyConfig,
+        transaction: TransactionData,
+        skip_checks: bool,
+    ) -> Result<(
+        InnerTemporaryStore,
+        TransactionEffects,
+        Result<Vec<ExecutionResult>, ExecutionError>,
+    )> {
+        transaction.validity_check_no_gas_check(&self.protocol_config)?;
+
+        let tx_digest = transaction.digest();
+        let input_object_kinds = transaction.input_objects()?;
+        let receiving_object_refs = transaction.receiving_objects();
+
+        iota_transaction_checks::deny::check_transaction_for_signing(
+            &transaction,
+            &[],
+            &input_object_kinds,
+            &receiving_object_refs,
+            deny_config,
+            &store,
+        )?;
+
+        let (mut input_objects, receiving_objects) = store.read_objects_for_synchronous_execution(
+            &tx_digest,
+            &input_object_kinds,
+            &receiving_object_refs,
+        )?;
+
+        let dummy_gas_object = Object::new_gas_with_balance_and_owner_for_testing(
+            DEV_INSPECT_GAS_COIN_VALUE,
+            transaction.gas_owner(),
+        );
+        let gas_objects = if transaction.gas().is_empty() {
+            vec![dummy_gas_object.compute_object_reference()]
+        } else {
+            transaction.gas().to_vec()
+        };
+
+        let reference_gas_price = self.epoch_start_state.reference_gas_price();
+        let (gas_status, checked_input_objects) = if skip_checks {
+            if transaction.gas().is_empty() {
+                input_objects.push(ObjectReadResult::new(
+                    InputObjectKind::ImmOrOwnedMoveObject(gas_objects[0]),
+                    dummy_gas_object.into(),
+                ));
+            }
+            let checked_input_objects = iota_transaction_checks::check_dev_inspect_input(
+                &self.protocol_config,
+                transaction.kind(),
+                input_objects,
+                receiving_objects,
+            )?;
+            let gas_status = IotaGasStatus::new(
+                self.protocol_config.max_tx_gas(),
+                transaction.gas_price(),
+                reference_gas_price,
+                &self.protocol_config,
+            )?;
+            (gas_status, checked_input_objects)
+        } else if transaction.gas().is_empty() {
+            iota_transaction_checks::check_transaction_input_with_given_gas(
+                &self.protocol_config,
+                reference_gas_price,
+                &transaction,
+                input_objects,
+                receiving_objects,
+                dummy_gas_object,
+                &self.bytecode_verifier_metrics,
+            )?
+        } else {
+            iota_transaction_checks::check_transaction_input(
+                &self.protocol_config,
+                reference_gas_price,
+                &transaction,
+                input_objects,
+                &receiving_objects,
+                &self.bytecode_verifier_metrics,
+            )?
+        };
+
+        let (kind, signer, _) = transaction.execution_parts();
+        let (inner_temporary_store, _, effects, execution_result) =
+            self.executor.dev_inspect_transaction(
+                store.backing_store(),
+                &self.protocol_config,
+                self.limits_metrics.clone(),
+                false,           // enable_expensive_checks
+                &HashSet::new(), // certificate_deny_set
+                &self.epoch_start_state.epoch(),
+                self.epoch_start_state.epoch_start_timestamp_ms(),
+                checked_input_objects,
+                gas_objects,
+                gas_status,
+                kind,
+                signer,
+                tx_digest,
+                skip_checks,
+            );
+        Ok((inner_temporary_store, effects, execution_result))
+    }
+
+    /// Returns a resolver of the layouts of types defined in the packages of
+    /// `store`.
+    pub fn type_layout_resolver<'a>(
+        &'a self,
+        store: Box<dyn TypeLayoutStore + 'a>,
+    ) -> Box<dyn LayoutResolver + 'a> {
+        self.executor.type_layout_resolver(store)
+    }
 }
+
+/// The balance of the gas coin used by dev-inspect transactions which do not
+/// provide a gas payment.
+const DEV_INSPECT_GAS_COIN_VALUE: u64 = 1_000_000_000_000;
@@ -15,12 +15,18 @@
 mod epoch_state;
 pub mod store;
 
-use std::{num::NonZeroUsize, path::PathBuf, sync::Arc};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow, ensure};
 use fastcrypto::traits::Signer;
 use iota_config::{genesis, transaction_deny_config::TransactionDenyConfig};
-use iota_protocol_config::ProtocolVersion;
+use iota_protocol_config::{ProtocolConfig, ProtocolVersion};
 use iota_storage::blob::{Blob, BlobEncoding};
 use iota_swarm_config::{
     genesis_config::AccountConfig, network_config::NetworkConfig,
@@ -28,16 +34,22 @@ use iota_swarm_config::{
 };
 use iota_types::{
     base_types::{AuthorityName, IotaAddress, ObjectID, VersionNumber},
+    coin::{CoinMetadata, TreasuryCap},
     committee::Committee,
     crypto::AuthoritySignature,
-    digests::ConsensusCommitDigest,
-    effects::TransactionEffects,
+    digests::{ConsensusCommitDigest, TransactionDigest},
+    dynamic_field::{DynamicFieldInfo, DynamicFieldType},
+    effects::{TransactionEffects, TransactionEffectsAPI},
     error::ExecutionError,
+    execution::{ExecutionResult, TypeLayoutStore},
     gas_coin::{GasCoin, NANOS_PER_IOTA},
     inner_temporary_store::InnerTemporaryStore,
     iota_system_state::epoch_start_iota_system_state::EpochStartSystemState,
+    is_system_package,
+    layout_resolver::{LayoutResolver, into_struct_layout},
     messages_checkpoint::{
-        CheckpointContents, CheckpointSequenceNumber, EndOfEpochData, VerifiedCheckpoint,
+        CheckpointContents, CheckpointSequenceNumber, EndOfEpochData, FullCheckpointContents,
+        VerifiedCheckpoint,
     },
     mock_checkpoint_builder::{MockCheckpointBuilder, ValidatorKeypairProvider},
     object::Object,
@@ -45,14 +57,19 @@ use iota_types::{
     signature::VerifyParams,
     storage::{ObjectStore, ReadStore, RestStateReader},
     transaction::{
-        EndOfEpochTransactionKind, GasData, Transaction, TransactionData, TransactionKind,
-        VerifiedTransaction,
+        EndOfEpochTransactionKind, GasData, SenderSignedData, Transaction, TransactionData,
+        TransactionKind, VerifiedTransaction,
     },
 };
 use move_core_types::language_storage::StructTag;
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
 
-pub use self::store::{SimulatorStore, in_mem_store::InMemoryStore};
+pub use self::store::{
+    SimulatorStore,
+    forking_store::{ForkingStore, ObjectFetcher},
+    in_mem_store::InMemoryStore,
+};
 use self::{epoch_state::EpochState, store::in_mem_store::KeyStore};
 
 /// A `Simulacrum` of Iota.
@@ -67,7 +84,6 @@ use self::{epoch_state::EpochState, store::in_mem_store::KeyStore};
 pub struct Simulacrum<R = OsRng, Store: SimulatorStore = InMemoryStore> {
     rng: R,
     keystore: KeyStore,
-    #[allow(unused)]
     genesis: genesis::Genesis,
     store: Store,
     checkpoint_builder: MockCheckpointBuilder,
@@ -136,6 +152,123 @@ where
         let store = InMemoryStore::new(&config.genesis);
         Self::new_with_network_config_store(config, rng, store)
     }
+
+    /// Loads a Simulacrum previously saved with [`Simulacrum::save`].
+    pub fn load(path: impl AsRef<Path>, rng: R) -> Result<Self> {
+        let persisted = PersistedSimulacrum::<InMemoryStore>::load(path.as_ref())?;
+        Ok(Self::from_persisted(persisted, rng, |store| store))
+    }
+}
+
+impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
+    fn from_persisted<T>(
+        persisted: PersistedSimulacrum<T>,
+        rng: R,
+        into_store: impl FnOnce(T) -> S,
+    ) -> Self {
+        let PersistedSimulacrum {
+            genesis,
+            keystore,
+            store,
+            next_consensus_round,
+        } = persisted;
+        let store = into_store(store);
+        let checkpoint_builder = MockCheckpointBuilder::new(
+            store
+                .get_highest_checkpoint()
+                .expect("a persisted store contains at least the genesis checkpoint"),
+        );
+        let mut epoch_state = EpochState::new(store.get_system_state());
+        epoch_state.set_next_consensus_round(next_consensus_round);
+
+        Self {
+            rng,
+            keystore,
+            genesis,
+            store,
+            checkpoint_builder,
+            epoch_state,
+            deny_config: TransactionDenyConfig::default(),
+            data_ingestion_path: None,
+        }
+    }
+
+    fn save_with_store<T: Serialize>(&self, path: &Path, store: &T) -> Result<()> {
+        ensure!(
+            !self.has_pending_transactions(),
+            "transactions which are not yet part of a checkpoint cannot be saved, \
+             create a checkpoint first"
+        );
+        let persisted = PersistedSimulacrumRef {
+            genesis: &self.genesis,
+            keystore: &self.keystore,
+            store,
+            next_consensus_round: self.epoch_state.peek_next_consensus_round(),
+        };
+        let mut write = BufWriter::new(File::create(path)?);
+        bcs::serialize_into(&mut write, &persisted)
+            .with_context(|| format!("unable to save Simulacrum to {}", path.display()))
+    }
+}
+
+impl<R> Simulacrum<R, InMemoryStore> {
+    /// Saves the whole chain state, including the genesis and all keys, to
+    /// `path` so that it can be restored with [`Simulacrum::load`].
+    ///
+    /// All executed transactions must be part of a checkpoint.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.save_with_store(path.as_ref(), &self.store)
+    }
+}
+
+impl<R> Simulacrum<R, ForkingStore>
+where
+    R: rand::RngCore + rand::CryptoRng,
+{
+    /// Create a new Simulacrum which forks the object state of a remote
+    /// network, see [`ForkingStore`].
+    ///
+    /// The chain starts from a fresh local genesis with the clock set to
+    /// `chain_start_timestamp_ms`, which usually is the timestamp of the
+    /// checkpoint the remote network is forked at.
+    pub fn new_forked(
+        mut rng: R,
+        chain_start_timestamp_ms: u64,
+        fetcher: Arc<dyn ObjectFetcher>,
+    ) -> Self {
+        let config = ConfigBuilder::new_with_temp_dir()
+            .rng(&mut rng)
+            .with_chain_start_timestamp_ms(chain_start_timestamp_ms)
+            .deterministic_committee_size(NonZeroUsize::new(1).unwrap())
+            .build();
+        let store = ForkingStore::new(&config.genesis, fetcher);
+        Self::new_with_network_config_store(&config, rng, store)
+    }
+
+    /// Loads a forked Simulacrum previously saved with
+    /// [`Simulacrum::save`], fetching objects which were not yet fetched
+    /// at the time it was saved through `fetcher`.
+    pub fn load_forked(
+        path: impl AsRef<Path>,
+        rng: R,
+        fetcher: Arc<dyn ObjectFetcher>,
+    ) -> Result<Self> {
+        let persisted = PersistedSimulacrum::load(path.as_ref())?;
+        Ok(Self::from_persisted(persisted, rng, |state| {
+            ForkingStore::from_state(state, fetcher)
+        }))
+    }
+}
+
+impl<R> Simulacrum<R, ForkingStore> {
+    /// Saves the whole chain state, including the genesis, all keys and the
+    /// objects fetched from the forked network, to `path` so that it can be
+    /// restored with [`Simulacrum::load_forked`].
+    ///
+    /// All executed transactions must be part of a checkpoint.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.save_with_store(path.as_ref(), &self.store.to_state())
+    }
 }
 
 impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
@@ -199,6 +332,18 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         Ok((effects, execution_error_opt.err()))
     }
 
+    /// Returns true if transactions were executed since the last checkpoint
+    /// was created.
+    pub fn has_pending_transactions(&self) -> bool {
+        self.checkpoint_builder.size() != 0
+    }
+
+    /// Returns the digests of the transactions executed since the last
+    /// checkpoint was created, in execution order.
+    pub fn pending_transactions(&self) -> impl Iterator<Item = &TransactionDigest> {
+        self.checkpoint_builder.transaction_digests()
+    }
+
     /// Creates the next Checkpoint using the Transactions enqueued since the
     /// last checkpoint was created.
     pub fn create_checkpoint(&mut self) -> VerifiedCheckpoint {
@@ -289,6 +434,64 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         self.epoch_state = new_epoch_state;
     }
 
+    /// Executes the provided TransactionData without committing its results.
+    ///
+    /// The transaction undergoes the same input checks as
+    /// `execute_transaction` with the exception of signature verification,
+    /// which allows clients to preview the effects of a transaction before
+    /// signing it. Neither the store nor the checkpoint builder are modified.
+    pub fn dry_run_transaction(
+        &self,
+        transaction: TransactionData,
+    ) -> anyhow::Result<(
+        InnerTemporaryStore,
+        TransactionEffects,
+        Option<ExecutionError>,
+    )> {
+        let transaction = VerifiedTransaction::new_unchecked(Transaction::new(
+            SenderSignedData::new(transaction, vec![]),
+        ));
+
+        let (inner_temporary_store, _, effects, execution_error_opt) = self
+            .epoch_state
+            .execute_transaction(&self.store, &self.deny_config, &transaction)?;
+        Ok((inner_temporary_store, effects, execution_error_opt.err()))
+    }
+
+    /// Executes the provided TransactionData in dev-inspect mode without
+    /// committing its results.
+    ///
+    /// Any Move function can be called and the values produced by each
+    /// command are returned. A dummy gas coin is used if the transaction has
+    /// no gas payment, and when `skip_checks` is set only lightweight input
+    /// checks are run. Neither the store nor the checkpoint builder are
+    /// modified.
+    pub fn dev_inspect_transaction(
+        &self,
+        transaction: TransactionData,
+        skip_checks: bool,
+    ) -> anyhow::Result<(
+        InnerTemporaryStore,
+        TransactionEffects,
+        Result<Vec<ExecutionResult>, ExecutionError>,
+    )> {
+        self.epoch_state.dev_inspect_transaction(
+            &self.store,
+            &self.deny_config,
+            transaction,
+            skip_checks,
+        )
+    }
+
+    /// Returns a resolver of the layouts of types defined in the packages of
+    /// `store`, such as [`Simulacrum::store`].
+    pub fn type_layout_resolver<'a>(
+        &'a self,
+        store: Box<dyn TypeLayoutStore + 'a>,
+    ) -> Box<dyn LayoutResolver + 'a> {
+        self.epoch_state.type_layout_resolver(store)
+    }
+
     pub fn store(&self) -> &dyn SimulatorStore {
         &self.store
     }
@@ -316,6 +519,35 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
         self.epoch_state.reference_gas_price()
     }
 
+    /// Return the protocol config for the current epoch
+    pub fn protocol_config(&self) -> &ProtocolConfig {
+        self.epoch_state.protocol_config()
+    }
+
+    /// Returns the live object of type `object_type` created by the
+    /// transaction which published the package `package_id`, such as the
+    /// `CoinMetadata` of a coin defined in that package.
+    ///
+    /// Only packages published locally are searched, as the transactions of
+    /// a forked network are not available.
+    pub fn find_package_object(
+        &self,
+        package_id: ObjectID,
+        object_type: &StructTag,
+    ) -> Option<Object> {
+        let publish_digest = if is_system_package(package_id) {
+            *self.genesis.transaction().digest()
+        } else {
+            self.store().get_object(&package_id)?.previous_transaction
+        };
+        self.store()
+            .get_transaction_effects(&publish_digest)?
+            .created()
+            .into_iter()
+            .filter_map(|((id, _, _), _)| self.store().get_object(&id))
+            .find(|object| object.type_().is_some_and(|type_| type_.is(object_type)))
+    }
+
     /// Request that `amount` Nanos be sent to `address` from a faucet account.
     ///
     /// ```
@@ -394,6 +626,71 @@ impl<R, S: store::SimulatorStore> Simulacrum<R, S> {
     }
 }
 
+impl<R, S: store::SimulatorStore + Clone> Simulacrum<R, S> {
+    /// Takes a snapshot of the current chain state.
+    ///
+    /// The snapshot captures the store, the transactions enqueued for the next
+    /// checkpoint and the epoch state, which allows the chain to be rewound
+    /// with [`Simulacrum::revert_to_snapshot`].
+    pub fn snapshot(&self) -> Snapshot<S> {
+        Snapshot {
+            store: self.store.clone(),
+            checkpoint_builder: self.checkpoint_builder.clone(),
+            epoch_state: self.epoch_state.clone(),
+        }
+    }
+
+    /// Reverts the chain state to a previously taken [`Snapshot`].
+    ///
+    /// NOTE: Checkpoint files which were already written to the data ingestion
+    /// path are not removed.
+    pub fn revert_to_snapshot(&mut self, snapshot: Snapshot<S>) {
+        let Snapshot {
+            store,
+            checkpoint_builder,
+            epoch_state,
+        } = snapshot;
+        self.store = store;
+        self.checkpoint_builder = checkpoint_builder;
+        self.epoch_state = epoch_state;
+    }
+}
+
+/// A point-in-time copy of the chain state of a [`Simulacrum`].
+#[derive(Clone)]
+pub struct Snapshot<Store = InMemoryStore> {
+    store: Store,
+    checkpoint_builder: MockCheckpointBuilder,
+    epoch_state: EpochState,
+}
+
+/// The on-disk representation of a [`Simulacrum`].
+#[derive(Deserialize)]
+struct PersistedSimulacrum<Store> {
+    genesis: genesis::Genesis,
+    keystore: KeyStore,
+    store: Store,
+    next_consensus_round: u64,
+}
+
+/// Borrowed counterpart of [`PersistedSimulacrum`] used when saving.
+#[derive(Serialize)]
+struct PersistedSimulacrumRef<'a, Store> {
+    genesis: &'a genesis::Genesis,
+    keystore: &'a KeyStore,
+    store: &'a Store,
+    next_consensus_round: u64,
+}
+
+impl<Store: serde::de::DeserializeOwned> PersistedSimulacrum<Store> {
+    fn load(path: &Path) -> Result<Self> {
+        let read = File::open(path)
+            .with_context(|| format!("unable to load Simulacrum from {}", path.display()))?;
+        bcs::from_reader(BufReader::new(read))
+            .with_context(|| format!("unable to parse Simulacrum from {}", path.display()))
+    }
+}
+
 pub struct CommitteeWithKeys<'a> {
     keystore: &'a KeyStore,
     committee: &'a Committee,
@@ -442,9 +739,9 @@ impl<T, V: store::SimulatorStore> ObjectStore for Simulacrum<T, V> {
 impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
     fn get_committee(
         &self,
-        _epoch: iota_types::committee::EpochId,
+        epoch: iota_types::committee::EpochId,
     ) -> iota_types::storage::error::Result<Option<std::sync::Arc<Committee>>> {
-        todo!()
+        Ok(self.store().get_committee_by_epoch(epoch).map(Arc::new))
     }
 
     fn get_latest_checkpoint(&self) -> iota_types::storage::error::Result<VerifiedCheckpoint> {
@@ -454,13 +751,14 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
     fn get_highest_verified_checkpoint(
         &self,
     ) -> iota_types::storage::error::Result<VerifiedCheckpoint> {
-        todo!()
+        // Every checkpoint created by the simulacrum is both verified and synced
+        self.get_latest_checkpoint()
     }
 
     fn get_highest_synced_checkpoint(
         &self,
     ) -> iota_types::storage::error::Result<VerifiedCheckpoint> {
-        todo!()
+        self.get_latest_checkpoint()
     }
 
     fn get_lowest_available_checkpoint(
@@ -499,11 +797,17 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
 
     fn get_checkpoint_contents_by_sequence_number(
         &self,
-        _sequence_number: iota_types::messages_checkpoint::CheckpointSequenceNumber,
+        sequence_number: iota_types::messages_checkpoint::CheckpointSequenceNumber,
     ) -> iota_types::storage::error::Result<
         Option<iota_types::messages_checkpoint::CheckpointContents>,
     > {
-        todo!()
+        Ok(self
+            .store()
+            .get_checkpoint_by_sequence_number(sequence_number)
+            .and_then(|checkpoint| {
+                self.store()
+                    .get_checkpoint_contents(&checkpoint.content_digest)
+            }))
     }
 
     fn get_transaction(
@@ -529,31 +833,37 @@ impl<T, V: store::SimulatorStore> ReadStore for Simulacrum<T, V> {
 
     fn get_full_checkpoint_contents_by_sequence_number(
         &self,
-        _sequence_number: iota_types::messages_checkpoint::CheckpointSequenceNumber,
+        sequence_number: iota_types::messages_checkpoint::CheckpointSequenceNumber,
     ) -> iota_types::storage::error::Result<
         Option<iota_types::messages_checkpoint::FullCheckpointContents>,
     > {
-        todo!()
+        match self.get_checkpoint_contents_by_sequence_number(sequence_number)? {
+            Some(contents) => FullCheckpointContents::from_checkpoint_contents(self, contents),
+            None => Ok(None),
+        }
     }
 
     fn get_full_checkpoint_contents(
         &self,
-        _digest: &iota_types::messages_checkpoint::CheckpointContentsDigest,
+        digest: &iota_types::messages_checkpoint::CheckpointContentsDigest,
     ) -> iota_types::storage::error::Result<
         Option<iota_types::messages_checkpoint::FullCheckpointContents>,
     > {
-        todo!()
+        match self.store().get_checkpoint_contents(digest) {
+            Some(contents) => FullCheckpointContents::from_checkpoint_contents(self, contents),
+            None => Ok(None),
+        }
     }
 }
 
 impl<T: Send + Sync, V: store::SimulatorStore + Send + Sync> RestStateReader for Simulacrum<T, V> {
     fn get_transaction_checkpoint(
         &self,
-        _digest: &iota_types::digests::TransactionDigest,
+        digest: &iota_types::digests::TransactionDigest,
     ) -> iota_types::storage::error::Result<
         Option<iota_types::messages_checkpoint::CheckpointSequenceNumber>,
     > {
-        todo!()
+        Ok(self.store().get_transaction_checkpoint(digest))
     }
 
     fn get_lowest_available_checkpoint_objects(
@@ -576,18 +886,33 @@ impl<T: Send + Sync, V: store::SimulatorStore + Send + Sync> RestStateReader for
 
     fn account_owned_objects_info_iter(
         &self,
-        _owner: IotaAddress,
-        _cursor: Option<ObjectID>,
+        owner: IotaAddress,
+        cursor: Option<ObjectID>,
     ) -> iota_types::storage::error::Result<
         Box<dyn Iterator<Item = iota_types::storage::AccountOwnedObjectInfo> + '_>,
     > {
-        todo!()
+        let mut objects = self
+            .store()
+            .owned_objects(owner)
+            .filter(|object| cursor.map_or(true, |cursor| object.id() >= cursor))
+            .filter_map(|object| {
+                Some(iota_types::storage::AccountOwnedObjectInfo {
+                    owner,
+                    object_id: object.id(),
+                    version: object.version(),
+                    type_: object.type_()?.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        // Paging relies on a stable ordering of the owned objects
+        objects.sort_by_key(|info| info.object_id);
+        Ok(Box::new(objects.into_iter()))
     }
 
     fn dynamic_field_iter(
         &self,
-        _parent: ObjectID,
-        _cursor: Option<ObjectID>,
+        parent: ObjectID,
+        cursor: Option<ObjectID>,
     ) -> iota_types::storage::error::Result<
         Box<
             dyn Iterator<
@@ -598,17 +923,97 @@ impl<T: Send + Sync, V: store::SimulatorStore + Send + Sync> RestStateReader for
                 > + '_,
         >,
     > {
-        todo!()
+        let mut resolver = self.type_layout_resolver(Box::new(self.store()));
+        let mut fields = self
+            .store()
+            .child_objects(parent)
+            .filter(|object| cursor.map_or(true, |cursor| object.id() >= cursor))
+            .map(|object| {
+                Ok(
+                    dynamic_field_index_info(&object, resolver.as_mut())?.map(|info| {
+                        (
+                            iota_types::storage::DynamicFieldKey::new(parent, object.id()),
+                            info,
+                        )
+                    }),
+                )
+            })
+            .filter_map(Result::transpose)
+            .collect::<iota_types::storage::error::Result<Vec<_>>>()?;
+        // Paging relies on a stable ordering of the fields
+        fields.sort_by_key(|(key, _)| *key);
+        Ok(Box::new(fields.into_iter()))
     }
 
     fn get_coin_info(
         &self,
-        _coin_type: &StructTag,
+        coin_type: &StructTag,
     ) -> iota_types::storage::error::Result<Option<iota_types::storage::CoinInfo>> {
-        todo!()
+        let package_id = ObjectID::from(coin_type.address);
+        let coin_metadata_object_id = self
+            .find_package_object(package_id, &CoinMetadata::type_(coin_type.clone()))
+            .map(|object| object.id());
+        let treasury_object_id = self
+            .find_package_object(package_id, &TreasuryCap::type_(coin_type.clone()))
+            .map(|object| object.id());
+        Ok(
+            (coin_metadata_object_id.is_some() || treasury_object_id.is_some()).then_some(
+                iota_types::storage::CoinInfo {
+                    coin_metadata_object_id,
+                    treasury_object_id,
+                },
+            ),
+        )
     }
 }
 
+/// Returns the index entry of `object` if it is a dynamic field.
+fn dynamic_field_index_info(
+    object: &Object,
+    resolver: &mut dyn LayoutResolver,
+) -> iota_types::storage::error::Result<Option<iota_types::storage::DynamicFieldIndexInfo>> {
+    use iota_types::storage::error::Error as StorageError;
+
+    let Some(move_object) = object.data.try_as_move() else {
+        return Ok(None);
+    };
+    if !move_object.type_().is_dynamic_field() {
+        return Ok(None);
+    }
+
+    let layout = into_struct_layout(
+        resolver
+            .get_annotated_layout(&move_object.type_().clone().into())
+            .map_err(StorageError::custom)?,
+    )
+    .map_err(StorageError::custom)?;
+    let move_struct = move_object
+        .to_move_struct(&layout)
+        .map_err(StorageError::serialization)?;
+    let (name_value, dynamic_field_type, object_id) =
+        DynamicFieldInfo::parse_move_object(&move_struct).map_err(StorageError::custom)?;
+
+    let name_type = move_object
+        .type_()
+        .try_extract_field_name(&dynamic_field_type)
+        .map_err(StorageError::custom)?;
+    let name_value = name_value
+        .undecorate()
+        .simple_serialize()
+        .ok_or_else(|| StorageError::custom("unable to serialize the dynamic field name"))?;
+    let dynamic_object_id = match dynamic_field_type {
+        DynamicFieldType::DynamicObject => Some(object_id),
+        DynamicFieldType::DynamicField => None,
+    };
+
+    Ok(Some(iota_types::storage::DynamicFieldIndexInfo {
+        dynamic_field_type,
+        name_type,
+        name_value,
+        dynamic_object_id,
+    }))
+}
+
 impl Simulacrum {
     /// Generate a random transfer transaction.
     /// TODO: This is here today to make it easier to write tests. But we should
@@ -652,8 +1057,8 @@ mod tests {
     use std::time::Duration;
 
     use iota_types::{
-        base_types::IotaAddress, effects::TransactionEffectsAPI, gas_coin::GasCoin,
-        transaction::TransactionDataAPI,
+        IOTA_CLOCK_OBJECT_ID, base_types::IotaAddress, effects::TransactionEffectsAPI,
+        gas_coin::GasCoin, storage::ObjectKey, transaction::TransactionDataAPI,
     };
     use rand::{SeedableRng, rngs::StdRng};
 
@@ -759,4 +1164,176 @@ mod tests {
         assert_eq!(&checkpoint.epoch_rolling_gas_cost_summary, gas_summary);
         assert_eq!(checkpoint.network_total_transactions, 2); // genesis + 1 txn
     }
+
+    #[test]
+    fn dry_run() {
+        let mut sim = Simulacrum::new();
+        let recipient = IotaAddress::random_for_testing_only();
+        let (tx, _) = sim.transfer_txn(recipient);
+
+        let (_, effects, error) = sim
+            .dry_run_transaction(tx.data().transaction_data().clone())
+            .unwrap();
+        assert!(error.is_none());
+        assert!(sim.store().owned_objects(recipient).next().is_none());
+
+        let executed_effects = sim.execute_transaction(tx).unwrap().0;
+        assert_eq!(effects, executed_effects);
+    }
+
+    #[test]
+    fn snapshot_and_revert() {
+        let mut sim = Simulacrum::new();
+        sim.advance_clock(Duration::from_millis(1));
+        sim.create_checkpoint();
+
+        let snapshot = sim.snapshot();
+        let checkpoint = sim.store().get_highest_checkpoint().unwrap();
+        let clock = sim.store().get_clock();
+
+        let recipient = IotaAddress::random_for_testing_only();
+        let (tx, _) = sim.transfer_txn(recipient);
+        sim.execute_transaction(tx).unwrap();
+        sim.advance_epoch();
+        assert!(sim.store().owned_objects(recipient).next().is_some());
+
+        sim.revert_to_snapshot(snapshot);
+        assert!(sim.store().owned_objects(recipient).next().is_none());
+        assert_eq!(
+            sim.store().get_highest_checkpoint().unwrap().digest(),
+            checkpoint.digest()
+        );
+        assert_eq!(sim.store().get_clock().timestamp_ms(), clock.timestamp_ms());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("simulacrum.bcs");
+
+        let mut sim = Simulacrum::new_with_rng(StdRng::from_seed([9; 32]));
+        let recipient = IotaAddress::random_for_testing_only();
+        sim.request_gas(recipient, NANOS_PER_IOTA).unwrap();
+        assert!(sim.save(&path).is_err());
+
+        sim.advance_epoch();
+        sim.save(&path).unwrap();
+
+        let mut loaded = Simulacrum::load(&path, StdRng::from_seed([0; 32])).unwrap();
+        assert_eq!(
+            loaded.store().get_highest_checkpoint().unwrap().digest(),
+            sim.store().get_highest_checkpoint().unwrap().digest()
+        );
+        assert!(loaded.store().owned_objects(recipient).next().is_some());
+
+        // The loaded chain keeps producing the same checkpoints.
+        sim.advance_clock(Duration::from_millis(1));
+        loaded.advance_clock(Duration::from_millis(1));
+        assert_eq!(
+            sim.create_checkpoint().digest(),
+            loaded.create_checkpoint().digest()
+        );
+    }
+
+    /// Serves the objects of another chain, as of the time it was created.
+    struct StoreFetcher(InMemoryStore);
+
+    impl ObjectFetcher for StoreFetcher {
+        fn get_object(&self, id: &ObjectID) -> Result<Option<Object>> {
+            Ok(self.0.get_object(id).cloned())
+        }
+
+        fn get_object_at_version(
+            &self,
+            id: &ObjectID,
+            version: VersionNumber,
+        ) -> Result<Option<Object>> {
+            Ok(self.0.get_object_at_version(id, version).cloned())
+        }
+
+        fn get_object_before_version(
+            &self,
+            id: &ObjectID,
+            version: VersionNumber,
+        ) -> Result<Option<Object>> {
+            Ok(self
+                .0
+                .get_object_lt_or_eq_version(id, version.one_before().unwrap())
+                .cloned())
+        }
+    }
+
+    #[test]
+    fn fork() {
+        let mut remote = Simulacrum::new();
+        let recipient = IotaAddress::random_for_testing_only();
+        let (tx, _) = remote.transfer_txn(recipient);
+        remote.execute_transaction(tx).unwrap();
+        let coin = remote.store().owned_objects(recipient).next().unwrap();
+
+        let mut sim = Simulacrum::new_forked(
+            OsRng,
+            remote.store().get_clock().timestamp_ms(),
+            Arc::new(StoreFetcher(remote.store.clone())),
+        );
+        assert_eq!(
+            store::SimulatorStore::get_object(sim.store(), &coin.id()),
+            Some(coin.clone())
+        );
+        // Remote objects cannot be enumerated.
+        assert!(sim.store().owned_objects(recipient).next().is_none());
+
+        // Local transactions work on top of the forked state.
+        sim.request_gas(recipient, NANOS_PER_IOTA).unwrap();
+        sim.create_checkpoint();
+        assert_eq!(sim.store().owned_objects(recipient).count(), 1);
+    }
+
+    /// Fails every request, as if the remote network were unreachable.
+    struct UnreachableFetcher;
+
+    impl ObjectFetcher for UnreachableFetcher {
+        fn get_object(&self, _id: &ObjectID) -> Result<Option<Object>> {
+            Err(anyhow!("unreachable"))
+        }
+
+        fn get_object_at_version(
+            &self,
+            _id: &ObjectID,
+            _version: VersionNumber,
+        ) -> Result<Option<Object>> {
+            Err(anyhow!("unreachable"))
+        }
+
+        fn get_object_before_version(
+            &self,
+            _id: &ObjectID,
+            _version: VersionNumber,
+        ) -> Result<Option<Object>> {
+            Err(anyhow!("unreachable"))
+        }
+    }
+
+    #[test]
+    fn fork_propagates_fetch_errors() {
+        let sim = Simulacrum::new_forked(OsRng, 1, Arc::new(UnreachableFetcher));
+        let id = ObjectID::random();
+        assert!(ObjectStore::get_object(sim.store(), &id).is_err());
+        assert!(
+            sim.store()
+                .multi_get_objects_by_key(&[ObjectKey(id, VersionNumber::from(1))])
+                .is_err()
+        );
+
+        // Objects known locally are read without the remote network.
+        let clock = ObjectStore::get_object(sim.store(), &IOTA_CLOCK_OBJECT_ID)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            sim.store()
+                .multi_get_objects_by_key(&[ObjectKey(clock.id(), clock.version())])
+                .unwrap(),
+            vec![Some(clock)]
+        );
+    }
 }
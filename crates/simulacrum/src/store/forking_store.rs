@@ -0,0 +1,539 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use iota_config::genesis;
+use iota_types::{
+    base_types::{IotaAddress, ObjectID, SequenceNumber, VersionNumber},
+    committee::{Committee, EpochId},
+    digests::{ObjectDigest, TransactionDigest, TransactionEventsDigest},
+    effects::{TransactionEffects, TransactionEffectsAPI, TransactionEvents},
+    error::IotaError,
+    messages_checkpoint::{
+        CheckpointContents, CheckpointContentsDigest, CheckpointDigest, CheckpointSequenceNumber,
+        VerifiedCheckpoint,
+    },
+    object::{Object, Owner},
+    storage::{
+        BackingPackageStore, ChildObjectResolver, ObjectKey, ObjectStore, PackageObject,
+        load_package_object_from_object_store,
+    },
+    transaction::VerifiedTransaction,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::{SimulatorStore, in_mem_store::InMemoryStore};
+
+/// Source of the objects of a remote network forked by a [`ForkingStore`].
+///
+/// Implementations must return the state of the remote network as of a fixed
+/// point, e.g. a pinned checkpoint, so that repeated reads are consistent.
+pub trait ObjectFetcher: Send + Sync {
+    /// Fetches the latest version of the object as of the fork point, `None`
+    /// if the object did not exist or was deleted at that point.
+    fn get_object(&self, id: &ObjectID) -> anyhow::Result<Option<Object>>;
+
+    /// Fetches the object at exactly `version`.
+    fn get_object_at_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>>;
+
+    /// Fetches the objects at exactly the given versions, in the order of
+    /// `keys`.
+    fn get_objects_at_versions(
+        &self,
+        keys: &[(ObjectID, SequenceNumber)],
+    ) -> anyhow::Result<Vec<Option<Object>>> {
+        keys.iter()
+            .map(|(id, version)| self.get_object_at_version(id, *version))
+            .collect()
+    }
+
+    /// Fetches the highest version of the object that is strictly less than
+    /// `version`.
+    fn get_object_before_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>>;
+}
+
+/// Objects fetched from the remote network.
+///
+/// As the remote state is pinned, the cache stays valid across snapshots of
+/// the store and is persisted together with it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct RemoteObjects {
+    latest: HashMap<ObjectID, Option<Object>>,
+    versioned: HashMap<(ObjectID, SequenceNumber), Object>,
+}
+
+/// A [`SimulatorStore`] which forks the object state of a remote network.
+///
+/// All chain data produced locally is kept in an [`InMemoryStore`] that is
+/// initialized from a local genesis, so system objects, the committee and the
+/// framework packages are the local ones. Any other object which is not known
+/// locally is lazily fetched from the remote network through an
+/// [`ObjectFetcher`] the first time it is read, allowing transactions to be
+/// executed against existing packages and shared objects of that network.
+///
+/// Only locally known objects are returned by
+/// [`owned_objects`](SimulatorStore::owned_objects) and
+/// [`child_objects`](SimulatorStore::child_objects), as remote objects cannot
+/// be enumerated.
+#[derive(Clone)]
+pub struct ForkingStore {
+    local: InMemoryStore,
+    /// Remote objects which have been deleted by local transactions.
+    deleted: BTreeSet<ObjectID>,
+    remote: Arc<RwLock<RemoteObjects>>,
+    fetcher: Arc<dyn ObjectFetcher>,
+}
+
+/// The persisted form of a [`ForkingStore`], see
+/// [`ForkingStore::to_state`].
+#[derive(Serialize, Deserialize)]
+pub struct ForkingStoreState {
+    local: InMemoryStore,
+    deleted: BTreeSet<ObjectID>,
+    remote: RemoteObjects,
+}
+
+impl ForkingStore {
+    pub fn new(genesis: &genesis::Genesis, fetcher: Arc<dyn ObjectFetcher>) -> Self {
+        Self {
+            local: InMemoryStore::new(genesis),
+            deleted: BTreeSet::new(),
+            remote: Default::default(),
+            fetcher,
+        }
+    }
+
+    /// Restores a store from its persisted state, fetching any object not
+    /// contained in it through `fetcher`.
+    pub fn from_state(state: ForkingStoreState, fetcher: Arc<dyn ObjectFetcher>) -> Self {
+        Self {
+            local: state.local,
+            deleted: state.deleted,
+            remote: Arc::new(RwLock::new(state.remote)),
+            fetcher,
+        }
+    }
+
+    /// Returns the persisted form of the store, which includes the remote
+    /// objects fetched so far.
+    pub fn to_state(&self) -> ForkingStoreState {
+        ForkingStoreState {
+            local: self.local.clone(),
+            deleted: self.deleted.clone(),
+            remote: self.remote.read().unwrap().clone(),
+        }
+    }
+
+    /// Returns the chain data produced locally.
+    pub fn local(&self) -> &InMemoryStore {
+        &self.local
+    }
+
+    /// Whether the object has been created, modified or deleted locally, in
+    /// which case the remote network must not be consulted for it.
+    fn is_local(&self, id: &ObjectID) -> bool {
+        self.deleted.contains(id)
+            || self
+                .local
+                .get_object_lt_or_eq_version(id, SequenceNumber::MAX)
+                .is_some()
+    }
+
+    fn remote_object(&self, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = self.remote.read().unwrap().latest.get(id) {
+            return Ok(object.clone());
+        }
+        let object = self.fetcher.get_object(id)?;
+        let mut remote = self.remote.write().unwrap();
+        if let Some(object) = &object {
+            remote
+                .versioned
+                .insert((*id, object.version()), object.clone());
+        }
+        remote.latest.insert(*id, object.clone());
+        Ok(object)
+    }
+
+    fn remote_object_at_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = self.remote.read().unwrap().versioned.get(&(*id, version)) {
+            return Ok(Some(object.clone()));
+        }
+        let object = self.fetcher.get_object_at_version(id, version)?;
+        if let Some(object) = &object {
+            self.remote
+                .write()
+                .unwrap()
+                .versioned
+                .insert((*id, version), object.clone());
+        }
+        Ok(object)
+    }
+
+    fn remote_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = self.remote_object_at_version(id, version)? {
+            return Ok(Some(object));
+        }
+        let object = self.fetcher.get_object_before_version(id, version)?;
+        if let Some(object) = &object {
+            self.remote
+                .write()
+                .unwrap()
+                .versioned
+                .insert((*id, object.version()), object.clone());
+        }
+        Ok(object)
+    }
+
+    fn remote_objects_at_versions(
+        &self,
+        keys: &[ObjectKey],
+    ) -> anyhow::Result<Vec<Option<Object>>> {
+        let mut objects: Vec<_> = {
+            let remote = self.remote.read().unwrap();
+            keys.iter()
+                .map(|ObjectKey(id, version)| remote.versioned.get(&(*id, *version)).cloned())
+                .collect()
+        };
+        let missing: Vec<_> = keys
+            .iter()
+            .zip(&objects)
+            .filter(|(_, object)| object.is_none())
+            .map(|(ObjectKey(id, version), _)| (*id, *version))
+            .collect();
+        if missing.is_empty() {
+            return Ok(objects);
+        }
+        let mut fetched = self.fetcher.get_objects_at_versions(&missing)?.into_iter();
+        let mut remote = self.remote.write().unwrap();
+        for object in objects.iter_mut().filter(|object| object.is_none()) {
+            *object = fetched.next().flatten();
+            if let Some(object) = object {
+                remote
+                    .versioned
+                    .insert((object.id(), object.version()), object.clone());
+            }
+        }
+        Ok(objects)
+    }
+
+    /// Returns the latest version of the object, reporting failures to reach
+    /// the remote network as errors rather than as a missing object.
+    pub fn try_get_object(&self, id: &ObjectID) -> anyhow::Result<Option<Object>> {
+        if self.is_local(id) {
+            return Ok(self.local.get_object(id).cloned());
+        }
+        self.remote_object(id)
+    }
+
+    /// Returns the object at exactly `version`, reporting failures to reach
+    /// the remote network as errors rather than as a missing object.
+    pub fn try_get_object_at_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = self.local.get_object_at_version(id, version) {
+            return Ok(Some(object.clone()));
+        }
+        self.remote_object_at_version(id, version)
+    }
+
+    /// Returns the highest version of the object that is less than or equal
+    /// to `version`, reporting failures to reach the remote network as errors
+    /// rather than as a missing object.
+    pub fn try_get_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> anyhow::Result<Option<Object>> {
+        if let Some(object) = self.local.get_object_lt_or_eq_version(id, version) {
+            return Ok(Some(object.clone()));
+        }
+        self.remote_object_lt_or_eq_version(id, version)
+    }
+
+    /// The [`SimulatorStore`] getters can't return errors, so failures to
+    /// reach the remote network are logged there and the object is reported
+    /// as missing. Execution reads objects through the fallible getters.
+    fn or_warn(id: &ObjectID, result: anyhow::Result<Option<Object>>) -> Option<Object> {
+        result.unwrap_or_else(|e| {
+            warn!("failed to fetch object {id} from the forked network: {e}");
+            None
+        })
+    }
+}
+
+impl BackingPackageStore for ForkingStore {
+    fn get_package_object(
+        &self,
+        package_id: &ObjectID,
+    ) -> iota_types::error::IotaResult<Option<PackageObject>> {
+        load_package_object_from_object_store(self, package_id)
+    }
+}
+
+impl ChildObjectResolver for ForkingStore {
+    fn read_child_object(
+        &self,
+        parent: &ObjectID,
+        child: &ObjectID,
+        child_version_upper_bound: SequenceNumber,
+    ) -> iota_types::error::IotaResult<Option<Object>> {
+        // Children may have been modified after the parent was, so look up
+        // the version that was current at the bound.
+        let Some(child_object) = self
+            .try_get_object_lt_or_eq_version(child, child_version_upper_bound)
+            .map_err(|e| IotaError::Storage(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        let parent = *parent;
+        if child_object.owner != Owner::ObjectOwner(parent.into()) {
+            return Err(IotaError::InvalidChildObjectAccess {
+                object: *child,
+                given_parent: parent,
+                actual_owner: child_object.owner,
+            });
+        }
+
+        Ok(Some(child_object))
+    }
+
+    fn get_object_received_at_version(
+        &self,
+        owner: &ObjectID,
+        receiving_object_id: &ObjectID,
+        receive_object_at_version: SequenceNumber,
+        _epoch_id: EpochId,
+    ) -> iota_types::error::IotaResult<Option<Object>> {
+        let recv_object = match SimulatorStore::get_object(self, receiving_object_id) {
+            None => return Ok(None),
+            Some(obj) => obj,
+        };
+        if recv_object.owner != Owner::AddressOwner((*owner).into()) {
+            return Ok(None);
+        }
+
+        if recv_object.version() != receive_object_at_version {
+            return Ok(None);
+        }
+        Ok(Some(recv_object))
+    }
+}
+
+impl ObjectStore for ForkingStore {
+    fn get_object(
+        &self,
+        object_id: &ObjectID,
+    ) -> Result<Option<Object>, iota_types::storage::error::Error> {
+        self.try_get_object(object_id)
+            .map_err(iota_types::storage::error::Error::custom)
+    }
+
+    fn get_object_by_key(
+        &self,
+        object_id: &ObjectID,
+        version: VersionNumber,
+    ) -> Result<Option<Object>, iota_types::storage::error::Error> {
+        self.try_get_object_at_version(object_id, version)
+            .map_err(iota_types::storage::error::Error::custom)
+    }
+
+    fn multi_get_objects_by_key(
+        &self,
+        object_keys: &[ObjectKey],
+    ) -> Result<Vec<Option<Object>>, iota_types::storage::error::Error> {
+        let mut objects: Vec<_> = object_keys
+            .iter()
+            .map(|ObjectKey(id, version)| self.local.get_object_at_version(id, *version).cloned())
+            .collect();
+        let missing: Vec<_> = object_keys
+            .iter()
+            .zip(&objects)
+            .filter(|(_, object)| object.is_none())
+            .map(|(key, _)| *key)
+            .collect();
+        if missing.is_empty() {
+            return Ok(objects);
+        }
+        // Objects which are not known locally are fetched in a single batch
+        let mut fetched = self
+            .remote_objects_at_versions(&missing)
+            .map_err(iota_types::storage::error::Error::custom)?
+            .into_iter();
+        for object in objects.iter_mut().filter(|object| object.is_none()) {
+            *object = fetched.next().flatten();
+        }
+        Ok(objects)
+    }
+}
+
+impl SimulatorStore for ForkingStore {
+    fn get_checkpoint_by_sequence_number(
+        &self,
+        sequence_number: CheckpointSequenceNumber,
+    ) -> Option<VerifiedCheckpoint> {
+        self.local
+            .get_checkpoint_by_sequence_number(sequence_number)
+            .cloned()
+    }
+
+    fn get_checkpoint_by_digest(&self, digest: &CheckpointDigest) -> Option<VerifiedCheckpoint> {
+        self.local.get_checkpoint_by_digest(digest).cloned()
+    }
+
+    fn get_highest_checkpoint(&self) -> Option<VerifiedCheckpoint> {
+        self.local.get_highest_checkpoint().cloned()
+    }
+
+    fn get_checkpoint_contents(
+        &self,
+        digest: &CheckpointContentsDigest,
+    ) -> Option<CheckpointContents> {
+        self.local.get_checkpoint_contents(digest).cloned()
+    }
+
+    fn get_committee_by_epoch(&self, epoch: EpochId) -> Option<Committee> {
+        self.local.get_committee_by_epoch(epoch).cloned()
+    }
+
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.local.get_transaction_checkpoint(digest)
+    }
+
+    fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction> {
+        self.local.get_transaction(digest).cloned()
+    }
+
+    fn get_transaction_effects(&self, digest: &TransactionDigest) -> Option<TransactionEffects> {
+        self.local.get_transaction_effects(digest).cloned()
+    }
+
+    fn get_transaction_events(
+        &self,
+        digest: &TransactionEventsDigest,
+    ) -> Option<TransactionEvents> {
+        self.local.get_transaction_events(digest).cloned()
+    }
+
+    fn get_transaction_events_by_tx_digest(
+        &self,
+        tx_digest: &TransactionDigest,
+    ) -> Option<TransactionEvents> {
+        SimulatorStore::get_transaction_events_by_tx_digest(&self.local, tx_digest)
+    }
+
+    fn get_object(&self, id: &ObjectID) -> Option<Object> {
+        Self::or_warn(id, self.try_get_object(id))
+    }
+
+    fn get_object_at_version(&self, id: &ObjectID, version: SequenceNumber) -> Option<Object> {
+        Self::or_warn(id, self.try_get_object_at_version(id, version))
+    }
+
+    fn get_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Option<Object> {
+        Self::or_warn(id, self.try_get_object_lt_or_eq_version(id, version))
+    }
+
+    fn get_system_state(&self) -> iota_types::iota_system_state::IotaSystemState {
+        self.local.get_system_state()
+    }
+
+    fn get_clock(&self) -> iota_types::clock::Clock {
+        self.local.get_clock()
+    }
+
+    fn owned_objects(&self, owner: IotaAddress) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(self.local.owned_objects(owner).cloned())
+    }
+
+    fn child_objects(&self, parent: ObjectID) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(self.local.child_objects(parent).cloned())
+    }
+
+    fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
+        self.local.insert_checkpoint(checkpoint)
+    }
+
+    fn insert_checkpoint_contents(&mut self, contents: CheckpointContents) {
+        self.local.insert_checkpoint_contents(contents)
+    }
+
+    fn insert_committee(&mut self, committee: Committee) {
+        self.local.insert_committee(committee)
+    }
+
+    fn insert_executed_transaction(
+        &mut self,
+        transaction: VerifiedTransaction,
+        effects: TransactionEffects,
+        events: TransactionEvents,
+        written_objects: BTreeMap<ObjectID, Object>,
+    ) {
+        let deleted_objects = effects.deleted();
+        let tx_digest = *effects.transaction_digest();
+        self.insert_transaction(transaction);
+        self.insert_transaction_effects(effects);
+        self.insert_events(&tx_digest, events);
+        self.update_objects(written_objects, deleted_objects);
+    }
+
+    fn insert_transaction(&mut self, transaction: VerifiedTransaction) {
+        self.local.insert_transaction(transaction)
+    }
+
+    fn insert_transaction_effects(&mut self, effects: TransactionEffects) {
+        self.local.insert_transaction_effects(effects)
+    }
+
+    fn insert_events(&mut self, tx_digest: &TransactionDigest, events: TransactionEvents) {
+        self.local.insert_events(tx_digest, events)
+    }
+
+    fn update_objects(
+        &mut self,
+        written_objects: BTreeMap<ObjectID, Object>,
+        deleted_objects: Vec<(ObjectID, SequenceNumber, ObjectDigest)>,
+    ) {
+        for (object_id, _, _) in &deleted_objects {
+            self.deleted.insert(*object_id);
+        }
+        for object_id in written_objects.keys() {
+            self.deleted.remove(object_id);
+        }
+        self.local.update_objects(written_objects, deleted_objects)
+    }
+
+    fn backing_store(&self) -> &dyn iota_types::storage::BackingStore {
+        self
+    }
+}
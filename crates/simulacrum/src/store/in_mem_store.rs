@@ -4,6 +4,7 @@
 
 use std::collections::{BTreeMap, HashMap};
 
+use fastcrypto::traits::{EncodeDecodeBase64, KeyPair};
 use iota_config::genesis;
 use iota_types::{
     base_types::{AuthorityName, IotaAddress, ObjectID, SequenceNumber},
@@ -14,29 +15,34 @@ use iota_types::{
     error::IotaError,
     messages_checkpoint::{
         CheckpointContents, CheckpointContentsDigest, CheckpointDigest, CheckpointSequenceNumber,
-        VerifiedCheckpoint,
+        TrustedCheckpoint, VerifiedCheckpoint,
     },
     object::{Object, Owner},
     storage::{
         BackingPackageStore, ChildObjectResolver, ObjectStore, PackageObject, get_module,
         load_package_object_from_object_store,
     },
-    transaction::VerifiedTransaction,
+    transaction::{TrustedTransaction, VerifiedTransaction},
 };
 use move_binary_format::CompiledModule;
 use move_bytecode_utils::module_cache::GetModule;
 use move_core_types::{language_storage::ModuleId, resolver::ModuleResolver};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
 
 use super::SimulatorStore;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct InMemoryStore {
     // Checkpoint data
+    #[serde(with = "verified_checkpoints")]
     checkpoints: BTreeMap<CheckpointSequenceNumber, VerifiedCheckpoint>,
     checkpoint_digest_to_sequence_number: HashMap<CheckpointDigest, CheckpointSequenceNumber>,
     checkpoint_contents: HashMap<CheckpointContentsDigest, CheckpointContents>,
+    // Map from transaction digest to the checkpoint which includes it
+    transaction_checkpoints: HashMap<TransactionDigest, CheckpointSequenceNumber>,
 
     // Transaction data
+    #[serde(with = "verified_transactions")]
     transactions: HashMap<TransactionDigest, VerifiedTransaction>,
     effects: HashMap<TransactionDigest, TransactionEffects>,
     events: HashMap<TransactionEventsDigest, TransactionEvents>,
@@ -90,6 +96,14 @@ impl InMemoryStore {
     pub fn get_committee_by_epoch(&self, epoch: EpochId) -> Option<&Committee> {
         self.epoch_to_committee.get(epoch as usize)
     }
+
+    pub fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.transaction_checkpoints.get(digest).copied()
+    }
+
     pub fn get_transaction(&self, digest: &TransactionDigest) -> Option<&VerifiedTransaction> {
         self.transactions.get(digest)
     }
@@ -119,6 +133,17 @@ impl InMemoryStore {
             .and_then(|versions| versions.get(&version))
     }
 
+    pub fn get_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Option<&Object> {
+        self.objects
+            .get(id)
+            .and_then(|versions| versions.range(..=version).next_back())
+            .map(|(_, object)| object)
+    }
+
     pub fn get_system_state(&self) -> iota_types::iota_system_state::IotaSystemState {
         iota_types::iota_system_state::get_iota_system_state(self).expect("system state must exist")
     }
@@ -138,6 +163,16 @@ impl InMemoryStore {
                 move |object| matches!(object.owner, Owner::AddressOwner(addr) if addr == owner),
             )
     }
+
+    pub fn child_objects(&self, parent: ObjectID) -> impl Iterator<Item = &Object> {
+        let parent = IotaAddress::from(parent);
+        self.live_objects
+            .iter()
+            .flat_map(|(id, version)| self.get_object_at_version(id, *version))
+            .filter(
+                move |object| matches!(object.owner, Owner::ObjectOwner(addr) if addr == parent),
+            )
+    }
 }
 
 impl InMemoryStore {
@@ -155,15 +190,47 @@ impl InMemoryStore {
 
         self.checkpoint_digest_to_sequence_number
             .insert(*checkpoint.digest(), *checkpoint.sequence_number());
+        if let Some(contents) = self.checkpoint_contents.get(&checkpoint.content_digest) {
+            Self::index_checkpoint_transactions(
+                &mut self.transaction_checkpoints,
+                *checkpoint.sequence_number(),
+                contents,
+            );
+        }
         self.checkpoints
             .insert(*checkpoint.sequence_number(), checkpoint);
     }
 
     pub fn insert_checkpoint_contents(&mut self, contents: CheckpointContents) {
+        // Contents are usually inserted right after their checkpoint
+        if let Some(checkpoint) = self
+            .checkpoints
+            .values()
+            .rev()
+            .find(|checkpoint| checkpoint.content_digest == *contents.digest())
+        {
+            Self::index_checkpoint_transactions(
+                &mut self.transaction_checkpoints,
+                *checkpoint.sequence_number(),
+                &contents,
+            );
+        }
         self.checkpoint_contents
             .insert(*contents.digest(), contents);
     }
 
+    fn index_checkpoint_transactions(
+        transaction_checkpoints: &mut HashMap<TransactionDigest, CheckpointSequenceNumber>,
+        sequence_number: CheckpointSequenceNumber,
+        contents: &CheckpointContents,
+    ) {
+        transaction_checkpoints.extend(
+            contents
+                .iter()
+                .map(|digests| (digests.transaction, sequence_number)),
+        );
+    }
+
     pub fn insert_committee(&mut self, committee: Committee) {
         let epoch = committee.epoch as usize;
 
@@ -337,8 +404,6 @@ impl KeyStore {
     pub fn from_network_config(
         network_config: &iota_swarm_config::network_config::NetworkConfig,
     ) -> Self {
-        use fastcrypto::traits::KeyPair;
-
         let validator_keys = network_config
             .validator_configs()
             .iter()
@@ -370,6 +435,62 @@ impl KeyStore {
     }
 }
 
+impl Serialize for KeyStore {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct RawKeyStore<'a> {
+            validator_keys: Vec<String>,
+            account_keys: Vec<&'a AccountKeyPair>,
+        }
+
+        RawKeyStore {
+            validator_keys: self
+                .validator_keys
+                .values()
+                .map(|key| key.encode_base64())
+                .collect(),
+            account_keys: self.account_keys.values().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyStore {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawKeyStore {
+            validator_keys: Vec<String>,
+            account_keys: Vec<AccountKeyPair>,
+        }
+
+        let raw_key_store = RawKeyStore::deserialize(deserializer)?;
+        let validator_keys = raw_key_store
+            .validator_keys
+            .iter()
+            .map(|key| {
+                let key = AuthorityKeyPair::decode_base64(key).map_err(D::Error::custom)?;
+                Ok((key.public().into(), key))
+            })
+            .collect::<Result<_, D::Error>>()?;
+        let account_keys = raw_key_store
+            .account_keys
+            .into_iter()
+            .map(|key| (key.public().into(), key))
+            .collect();
+
+        Ok(Self {
+            validator_keys,
+            account_keys,
+        })
+    }
+}
+
 impl SimulatorStore for InMemoryStore {
     fn get_checkpoint_by_sequence_number(
         &self,
@@ -398,6 +519,13 @@ impl SimulatorStore for InMemoryStore {
         self.get_committee_by_epoch(epoch).cloned()
     }
 
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber> {
+        self.get_transaction_checkpoint(digest)
+    }
+
     fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction> {
         self.get_transaction(digest).cloned()
     }
@@ -431,6 +559,14 @@ impl SimulatorStore for InMemoryStore {
         self.get_object_at_version(id, version).cloned()
     }
 
+    fn get_object_lt_or_eq_version(
+        &self,
+        id: &ObjectID,
+        version: SequenceNumber,
+    ) -> Option<Object> {
+        self.get_object_lt_or_eq_version(id, version).cloned()
+    }
+
     fn get_system_state(&self) -> iota_types::iota_system_state::IotaSystemState {
         self.get_system_state()
     }
@@ -443,6 +579,10 @@ impl SimulatorStore for InMemoryStore {
         Box::new(self.owned_objects(owner).cloned())
     }
 
+    fn child_objects(&self, parent: ObjectID) -> Box<dyn Iterator<Item = Object> + '_> {
+        Box::new(self.child_objects(parent).cloned())
+    }
+
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint) {
         self.insert_checkpoint(checkpoint)
     }
@@ -489,3 +629,71 @@ impl SimulatorStore for InMemoryStore {
         self
     }
 }
+
+/// Verified checkpoints are persisted in their trusted form.
+mod verified_checkpoints {
+    use super::*;
+
+    pub fn serialize<S>(
+        checkpoints: &BTreeMap<CheckpointSequenceNumber, VerifiedCheckpoint>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(
+            checkpoints
+                .values()
+                .map(VerifiedCheckpoint::serializable_ref),
+        )
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<CheckpointSequenceNumber, VerifiedCheckpoint>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<TrustedCheckpoint>::deserialize(deserializer)?
+            .into_iter()
+            .map(|checkpoint| {
+                let checkpoint = VerifiedCheckpoint::from(checkpoint);
+                (*checkpoint.sequence_number(), checkpoint)
+            })
+            .collect())
+    }
+}
+
+/// Verified transactions are persisted in their trusted form.
+mod verified_transactions {
+    use super::*;
+
+    pub fn serialize<S>(
+        transactions: &HashMap<TransactionDigest, VerifiedTransaction>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(
+            transactions
+                .values()
+                .map(VerifiedTransaction::serializable_ref),
+        )
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<TransactionDigest, VerifiedTransaction>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<TrustedTransaction>::deserialize(deserializer)?
+            .into_iter()
+            .map(|transaction| {
+                let transaction = VerifiedTransaction::from(transaction);
+                (*transaction.digest(), transaction)
+            })
+            .collect())
+    }
+}
@@ -22,6 +22,7 @@ use iota_types::{
         ReceivingObjects, VerifiedTransaction,
     },
 };
+pub mod forking_store;
 pub mod in_mem_store;
 
 pub trait SimulatorStore:
@@ -66,6 +67,13 @@ pub trait SimulatorStore:
 
     fn get_committee_by_epoch(&self, epoch: EpochId) -> Option<Committee>;
 
+    /// Returns the sequence number of the checkpoint which includes the
+    /// transaction.
+    fn get_transaction_checkpoint(
+        &self,
+        digest: &TransactionDigest,
+    ) -> Option<CheckpointSequenceNumber>;
+
     fn get_transaction(&self, digest: &TransactionDigest) -> Option<VerifiedTransaction>;
 
     fn get_transaction_effects(&self, digest: &TransactionDigest) -> Option<TransactionEffects>;
@@ -82,12 +90,21 @@ pub trait SimulatorStore:
 
     fn get_object_at_version(&self, id: &ObjectID, version: SequenceNumber) -> Option<Object>;
 
+    /// Returns the highest version of the object that is less than or equal
+    /// to `version`.
+    fn get_object_lt_or_eq_version(&self, id: &ObjectID, version: SequenceNumber)
+    -> Option<Object>;
+
     fn get_system_state(&self) -> iota_types::iota_system_state::IotaSystemState;
 
     fn get_clock(&self) -> iota_types::clock::Clock;
 
     fn owned_objects(&self, owner: IotaAddress) -> Box<dyn Iterator<Item = Object> + '_>;
 
+    /// Returns the live objects owned by the object `parent`, such as its
+    /// dynamic fields.
+    fn child_objects(&self, parent: ObjectID) -> Box<dyn Iterator<Item = Object> + '_>;
+
     fn insert_checkpoint(&mut self, checkpoint: VerifiedCheckpoint);
 
     fn insert_checkpoint_contents(&mut self, contents: CheckpointContents);
@@ -131,14 +148,14 @@ pub trait SimulatorStore:
         for kind in input_object_kinds {
             let obj = match kind {
                 InputObjectKind::MovePackage(id) => {
-                    crate::store::SimulatorStore::get_object(self, id)
+                    iota_types::storage::ObjectStore::get_object(self, id)?
                 }
                 InputObjectKind::ImmOrOwnedMoveObject(objref) => {
                     self.get_object_by_key(&objref.0, objref.1)?
                 }
 
                 InputObjectKind::SharedMoveObject { id, .. } => {
-                    crate::store::SimulatorStore::get_object(self, id)
+                    iota_types::storage::ObjectStore::get_object(self, id)?
                 }
             };
 
@@ -151,7 +168,7 @@ pub trait SimulatorStore:
         let mut receiving_objects = Vec::new();
         for objref in receiving_object_refs {
             // no need for marker table check in simulacrum
-            let Some(obj) = crate::store::SimulatorStore::get_object(self, &objref.0) else {
+            let Some(obj) = iota_types::storage::ObjectStore::get_object(self, &objref.0)? else {
                 return Err(UserInputError::ObjectNotFound {
                     object_id: objref.0,
                     version: Some(objref.1),
@@ -17,6 +17,7 @@ pub use rocksdb;
 pub mod traits;
 pub use traits::Map;
 pub mod metrics;
+pub mod migration;
 pub mod rocks;
 pub use typed_store_error::TypedStoreError;
 pub mod sally;
@@ -20,6 +20,8 @@ use rocksdb::{PerfContext, PerfMetric, PerfStatsLevel, perf::set_perf_stats};
 use tap::TapFallible;
 use tracing::warn;
 
+use crate::rocks::introspection::HotKeyTracker;
+
 thread_local! {
     static PER_THREAD_ROCKS_PERF_CONTEXT: std::cell::RefCell<rocksdb::PerfContext>  = RefCell::new(PerfContext::default());
 }
@@ -952,6 +954,7 @@ pub struct DBMetrics {
     pub cf_metrics: ColumnFamilyMetrics,
     pub read_perf_ctx_metrics: ReadPerfContextMetrics,
     pub write_perf_ctx_metrics: WritePerfContextMetrics,
+    pub hot_keys: HotKeyTracker,
 }
 
 static ONCE: OnceCell<Arc<DBMetrics>> = OnceCell::new();
@@ -963,6 +966,7 @@ impl DBMetrics {
             cf_metrics: ColumnFamilyMetrics::new(registry),
             read_perf_ctx_metrics: ReadPerfContextMetrics::new(registry),
             write_perf_ctx_metrics: WritePerfContextMetrics::new(registry),
+            hot_keys: HotKeyTracker::default(),
         }
     }
     pub fn init(registry: &Registry) -> &'static Arc<DBMetrics> {
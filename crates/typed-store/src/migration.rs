@@ -0,0 +1,674 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Online schema migrations for typed tables.
+//!
+//! Every table which is migrated has a version, persisted in a table of
+//! [`TableVersion`] entries keyed by table name. A [`TableMigration`] declares
+//! how rows of one version of a table are re-encoded into the next version.
+//! The [`TableMigrator`] applies a migration in batches of rows while the
+//! table is in use, and persists a cursor after each batch so that an
+//! interrupted migration resumes where it stopped.
+//!
+//! During the migration window the table holds rows of both encodings.
+//! Writers must only write rows of the new encoding, through
+//! [`TableMigrator::insert`] or while holding [`TableMigrator::write_guard`],
+//! and readers use [`TableMigrator::get`] which decodes either encoding into
+//! the new value type. Once the migration has completed the table can be read
+//! and written directly.
+//!
+//! Migrations run on [`DBMap`] tables, and on [`TestDB`] tables in tests.
+
+use std::{
+    marker::PhantomData,
+    ops::Bound,
+    sync::{RwLock, RwLockReadGuard},
+    time::Duration,
+};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tracing::info;
+
+use crate::{
+    Map, TypedStoreError,
+    rocks::{
+        DBMap, be_fix_int_ser,
+        errors::{typed_store_err_from_bcs_err, typed_store_err_from_bincode_err},
+    },
+    test_db::TestDB,
+};
+
+/// Version of a table which has never been migrated.
+pub const INITIAL_TABLE_VERSION: u32 = 0;
+const DEFAULT_MIGRATION_BATCH_SIZE: usize = 1000;
+
+/// The persisted version of a table, and the progress of its ongoing
+/// migration, if any.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableVersion {
+    pub version: u32,
+    pub migration: Option<MigrationProgress>,
+    /// The number of rows re-encoded by the migration to `version`.
+    pub migrated_rows: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationProgress {
+    /// The serialized key of the last row which was re-encoded.
+    pub cursor: Option<Vec<u8>>,
+    pub migrated_rows: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStatus {
+    /// The table is at a version the migration doesn't apply to.
+    NotApplicable {
+        version: u32,
+    },
+    InProgress {
+        migrated_rows: u64,
+    },
+    Completed {
+        migrated_rows: u64,
+    },
+}
+
+/// Declares how the rows of a table are re-encoded from `FROM_VERSION` to
+/// `FROM_VERSION + 1`.
+///
+/// The old encoding must not be able to decode rows of the new encoding, as
+/// the migrator relies on it to skip rows which were already re-encoded or
+/// written by the new code. Adding a variant to a versioned enum value
+/// guarantees this for rows of the new variant.
+pub trait TableMigration {
+    type Key: Serialize + DeserializeOwned;
+    type OldValue: DeserializeOwned;
+    type NewValue: Serialize + DeserializeOwned;
+
+    /// The name of the migrated table, under which its version is persisted.
+    const TABLE_NAME: &'static str;
+    const FROM_VERSION: u32;
+
+    fn migrate(key: &Self::Key, value: Self::OldValue) -> Self::NewValue;
+}
+
+/// A table which can be migrated, along with the table of versions of its
+/// database.
+pub trait MigratedTable<K, V> {
+    type Versions;
+
+    /// Returns the persisted version of the table named `table_name`.
+    fn read_table_version(
+        versions: &Self::Versions,
+        table_name: &str,
+    ) -> Result<Option<TableVersion>, TypedStoreError>;
+
+    /// Returns up to `limit` keys and their serialized form, starting after
+    /// the serialized key `cursor` or at the start of the table.
+    fn keys_after(
+        &self,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(K, Vec<u8>)>, TypedStoreError>;
+
+    fn get_raw_value(&self, key: &K) -> Result<Option<Vec<u8>>, TypedStoreError>;
+
+    fn multi_get_raw_values(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, TypedStoreError>;
+
+    fn insert_row(&self, key: &K, value: &V) -> Result<(), TypedStoreError>;
+
+    /// Writes `rows` along with the version of the table named `table_name`
+    /// in one atomic batch.
+    fn write_rows_with_version(
+        &self,
+        rows: Vec<(K, V)>,
+        versions: &Self::Versions,
+        table_name: &str,
+        table_version: &TableVersion,
+    ) -> Result<(), TypedStoreError>;
+}
+
+impl<K, V> MigratedTable<K, V> for DBMap<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    type Versions = DBMap<String, TableVersion>;
+
+    fn read_table_version(
+        versions: &Self::Versions,
+        table_name: &str,
+    ) -> Result<Option<TableVersion>, TypedStoreError> {
+        versions.get(&table_name.to_string())
+    }
+
+    fn keys_after(
+        &self,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(K, Vec<u8>)>, TypedStoreError> {
+        let mut keys_iter = self.keys();
+        if let Some(cursor) = cursor {
+            keys_iter = keys_iter.skip_to(&deserialize_key::<K>(cursor)?)?;
+        }
+        let mut keys = Vec::with_capacity(limit);
+        for key in keys_iter {
+            let key = key?;
+            let raw_key = be_fix_int_ser(&key)?;
+            // The iterator starts at the cursor if its row still exists
+            if cursor == Some(raw_key.as_slice()) {
+                continue;
+            }
+            keys.push((key, raw_key));
+            if keys.len() == limit {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    fn get_raw_value(&self, key: &K) -> Result<Option<Vec<u8>>, TypedStoreError> {
+        self.get_raw_bytes(key)
+    }
+
+    fn multi_get_raw_values(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, TypedStoreError> {
+        self.multi_get_raw_bytes(keys)
+    }
+
+    fn insert_row(&self, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        self.insert(key, value)
+    }
+
+    fn write_rows_with_version(
+        &self,
+        rows: Vec<(K, V)>,
+        versions: &Self::Versions,
+        table_name: &str,
+        table_version: &TableVersion,
+    ) -> Result<(), TypedStoreError> {
+        let mut batch = self.batch();
+        batch.insert_batch(self, rows)?;
+        batch.insert_batch(versions, [(table_name.to_string(), table_version)])?;
+        batch.write()
+    }
+}
+
+impl<K, V> MigratedTable<K, V> for TestDB<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    type Versions = TestDB<String, TableVersion>;
+
+    fn read_table_version(
+        versions: &Self::Versions,
+        table_name: &str,
+    ) -> Result<Option<TableVersion>, TypedStoreError> {
+        versions.get(&table_name.to_string())
+    }
+
+    fn keys_after(
+        &self,
+        cursor: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<(K, Vec<u8>)>, TypedStoreError> {
+        let lower_bound =
+            cursor.map_or(Bound::Unbounded, |cursor| Bound::Excluded(cursor.to_vec()));
+        self.rows
+            .read()
+            .unwrap()
+            .range((lower_bound, Bound::Unbounded))
+            .take(limit)
+            .map(|(raw_key, _)| Ok((deserialize_key(raw_key)?, raw_key.clone())))
+            .collect()
+    }
+
+    fn get_raw_value(&self, key: &K) -> Result<Option<Vec<u8>>, TypedStoreError> {
+        self.get_raw_bytes(key)
+    }
+
+    fn multi_get_raw_values(&self, keys: &[K]) -> Result<Vec<Option<Vec<u8>>>, TypedStoreError> {
+        self.multi_get_raw_bytes(keys)
+    }
+
+    fn insert_row(&self, key: &K, value: &V) -> Result<(), TypedStoreError> {
+        self.insert(key, value)
+    }
+
+    fn write_rows_with_version(
+        &self,
+        rows: Vec<(K, V)>,
+        versions: &Self::Versions,
+        table_name: &str,
+        table_version: &TableVersion,
+    ) -> Result<(), TypedStoreError> {
+        let mut batch = self.batch();
+        batch.insert_batch(self, rows)?;
+        batch.insert_batch(versions, [(table_name.to_string(), table_version)])?;
+        batch.write()
+    }
+}
+
+/// Applies a [`TableMigration`] to a table, tracking its progress in a table
+/// of versions of the same database.
+///
+/// The migrator caches the version of the table, so there must be only one
+/// migrator per table at a time.
+pub struct TableMigrator<'a, M: TableMigration, T: MigratedTable<M::Key, M::NewValue>> {
+    table: &'a T,
+    versions: &'a T::Versions,
+    batch_size: usize,
+    /// The persisted version of the table, loaded on first use.
+    table_version: RwLock<Option<TableVersion>>,
+    /// Held exclusively while a batch of rows is read and re-encoded, and
+    /// shared by writers, so that rows written concurrently are not
+    /// overwritten with re-encoded stale values.
+    write_lock: RwLock<()>,
+    _phantom: PhantomData<M>,
+}
+
+impl<'a, M: TableMigration, T: MigratedTable<M::Key, M::NewValue>> TableMigrator<'a, M, T> {
+    pub fn new(table: &'a T, versions: &'a T::Versions) -> Self {
+        Self {
+            table,
+            versions,
+            batch_size: DEFAULT_MIGRATION_BATCH_SIZE,
+            table_version: RwLock::new(None),
+            write_lock: RwLock::new(()),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Returns the persisted version of the table.
+    pub fn table_version(&self) -> Result<TableVersion, TypedStoreError> {
+        self.with_table_version(TableVersion::clone)
+    }
+
+    fn with_table_version<R>(
+        &self,
+        f: impl FnOnce(&TableVersion) -> R,
+    ) -> Result<R, TypedStoreError> {
+        if let Some(table_version) = &*self.table_version.read().unwrap() {
+            return Ok(f(table_version));
+        }
+        let mut cached = self.table_version.write().unwrap();
+        if cached.is_none() {
+            *cached =
+                Some(T::read_table_version(self.versions, M::TABLE_NAME)?.unwrap_or_default());
+        }
+        Ok(f(cached.as_ref().unwrap()))
+    }
+
+    /// Persists the version of the table along with `rows`.
+    fn write_table_version(
+        &self,
+        rows: Vec<(M::Key, M::NewValue)>,
+        table_version: TableVersion,
+    ) -> Result<(), TypedStoreError> {
+        self.table
+            .write_rows_with_version(rows, self.versions, M::TABLE_NAME, &table_version)?;
+        *self.table_version.write().unwrap() = Some(table_version);
+        Ok(())
+    }
+
+    pub fn status(&self) -> Result<MigrationStatus, TypedStoreError> {
+        self.with_table_version(|table_version| match table_version {
+            TableVersion {
+                version,
+                migration: Some(progress),
+                ..
+            } if *version == M::FROM_VERSION => MigrationStatus::InProgress {
+                migrated_rows: progress.migrated_rows,
+            },
+            TableVersion {
+                version,
+                migrated_rows,
+                ..
+            } if *version == M::FROM_VERSION + 1 => MigrationStatus::Completed {
+                migrated_rows: *migrated_rows,
+            },
+            TableVersion { version, .. } => MigrationStatus::NotApplicable { version: *version },
+        })
+    }
+
+    /// Marks the migration as started if the table is at `FROM_VERSION`. From
+    /// then on readers must go through [`Self::get`], and writers through
+    /// [`Self::insert`] or [`Self::write_guard`].
+    pub fn start(&self) -> Result<MigrationStatus, TypedStoreError> {
+        let mut table_version = self.table_version()?;
+        if table_version.version == M::FROM_VERSION && table_version.migration.is_none() {
+            info!(
+                "Starting migration of table {} from version {}",
+                M::TABLE_NAME,
+                M::FROM_VERSION
+            );
+            table_version.migration = Some(MigrationProgress::default());
+            self.write_table_version(vec![], table_version)?;
+        }
+        self.status()
+    }
+
+    /// Re-encodes the next batch of rows and persists the progress along with
+    /// them. Completes the migration once all rows have been re-encoded.
+    pub fn step(&self) -> Result<MigrationStatus, TypedStoreError> {
+        let _write_guard = self.write_lock.write().unwrap();
+        let mut table_version = self.table_version()?;
+        let mut progress = match table_version.migration.take() {
+            Some(progress) if table_version.version == M::FROM_VERSION => progress,
+            _ => return self.status(),
+        };
+
+        // Rows up to the cursor were re-encoded by the previous batches
+        let keys = self
+            .table
+            .keys_after(progress.cursor.as_deref(), self.batch_size)?;
+
+        let Some((_, last_raw_key)) = keys.last() else {
+            info!(
+                "Completed migration of table {} to version {} after {} rows",
+                M::TABLE_NAME,
+                M::FROM_VERSION + 1,
+                progress.migrated_rows
+            );
+            self.write_table_version(vec![], TableVersion {
+                version: M::FROM_VERSION + 1,
+                migration: None,
+                migrated_rows: progress.migrated_rows,
+            })?;
+            return Ok(MigrationStatus::Completed {
+                migrated_rows: progress.migrated_rows,
+            });
+        };
+        let cursor = last_raw_key.clone();
+
+        // Writers are blocked until the batch is written, so the rows read
+        // here are still current when they are overwritten
+        let (keys, _): (Vec<_>, Vec<_>) = keys.into_iter().unzip();
+        let raw_values = self.table.multi_get_raw_values(&keys)?;
+        let mut migrated = Vec::with_capacity(keys.len());
+        for (key, raw_value) in keys.into_iter().zip(raw_values) {
+            let Some(raw_value) = raw_value else {
+                continue;
+            };
+            // Rows which don't decode with the old encoding were already
+            // re-encoded, or written by the new code
+            if let Ok(old_value) = bcs::from_bytes::<M::OldValue>(&raw_value) {
+                let new_value = M::migrate(&key, old_value);
+                migrated.push((key, new_value));
+            }
+        }
+        progress.migrated_rows += migrated.len() as u64;
+        progress.cursor = Some(cursor);
+        let migrated_rows = progress.migrated_rows;
+
+        self.write_table_version(migrated, TableVersion {
+            migration: Some(progress),
+            ..table_version
+        })?;
+        Ok(MigrationStatus::InProgress { migrated_rows })
+    }
+
+    /// Starts the migration if needed and runs it to completion, pausing
+    /// between batches to limit its impact on other users of the table.
+    pub async fn run(&self, pause: Duration) -> Result<MigrationStatus, TypedStoreError> {
+        let mut status = self.start()?;
+        while let MigrationStatus::InProgress { .. } = status {
+            status = self.step()?;
+            if !pause.is_zero() {
+                tokio::time::sleep(pause).await;
+            }
+        }
+        Ok(status)
+    }
+
+    /// Returns a guard to hold while writing to the table other than through
+    /// [`Self::insert`], e.g. in a batch, during the migration.
+    pub fn write_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.write_lock.read().unwrap()
+    }
+
+    /// Writes a row of the new encoding.
+    pub fn insert(&self, key: &M::Key, value: &M::NewValue) -> Result<(), TypedStoreError> {
+        let _write_guard = self.write_guard();
+        self.table.insert_row(key, value)
+    }
+
+    /// Reads a row of the table, decoding either the old or the new encoding
+    /// while the migration is in progress.
+    pub fn get(&self, key: &M::Key) -> Result<Option<M::NewValue>, TypedStoreError> {
+        let Some(raw_value) = self.table.get_raw_value(key)? else {
+            return Ok(None);
+        };
+        // Rows up to the cursor were re-encoded, so decode them with the new
+        // encoding first
+        let raw_key = be_fix_int_ser(key)?;
+        let migrated = self.with_table_version(|table_version| {
+            (table_version.version == M::FROM_VERSION).then(|| {
+                table_version
+                    .migration
+                    .as_ref()
+                    .and_then(|progress| progress.cursor.as_ref())
+                    .is_some_and(|cursor| raw_key <= *cursor)
+            })
+        })?;
+        let Some(migrated) = migrated else {
+            return bcs::from_bytes(&raw_value)
+                .map(Some)
+                .map_err(typed_store_err_from_bcs_err);
+        };
+        let decode_new = || bcs::from_bytes::<M::NewValue>(&raw_value).ok();
+        let decode_old = || {
+            bcs::from_bytes::<M::OldValue>(&raw_value)
+                .ok()
+                .map(|old_value| M::migrate(key, old_value))
+        };
+        let value = if migrated {
+            decode_new().or_else(decode_old)
+        } else {
+            decode_old().or_else(decode_new)
+        };
+        value.map(Some).ok_or_else(|| {
+            TypedStoreError::Serialization(format!(
+                "Row of table {} matches neither version {} nor {}",
+                M::TABLE_NAME,
+                M::FROM_VERSION,
+                M::FROM_VERSION + 1
+            ))
+        })
+    }
+}
+
+/// Decodes a key serialized with [`be_fix_int_ser`].
+fn deserialize_key<K: DeserializeOwned>(raw_key: &[u8]) -> Result<K, TypedStoreError> {
+    bincode::DefaultOptions::new()
+        .with_big_endian()
+        .with_fixint_encoding()
+        .deserialize(raw_key)
+        .map_err(typed_store_err_from_bincode_err)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{MigrationStatus, TableMigration, TableMigrator, TableVersion};
+    use crate::{Map, rocks::be_fix_int_ser, test_db::TestDB};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    enum ValueV1 {
+        V1(u32),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum ValueV2 {
+        V1(u32),
+        V2(u64, String),
+    }
+
+    /// Opens a table filled with rows of the old encoding, and a table of
+    /// versions.
+    fn old_tables(rows: u64) -> (TestDB<u64, ValueV2>, TestDB<String, TableVersion>) {
+        let table = TestDB::open();
+        table.rows.write().unwrap().extend((0..rows).map(|i| {
+            (
+                be_fix_int_ser(&i).unwrap(),
+                bcs::to_bytes(&ValueV1::V1(i as u32)).unwrap(),
+            )
+        }));
+        (table, TestDB::open())
+    }
+
+    struct MigrateToV2;
+
+    impl TableMigration for MigrateToV2 {
+        type Key = u64;
+        type OldValue = ValueV1;
+        type NewValue = ValueV2;
+
+        const TABLE_NAME: &'static str = "values";
+        const FROM_VERSION: u32 = 0;
+
+        fn migrate(key: &u64, value: ValueV1) -> ValueV2 {
+            let ValueV1::V1(value) = value;
+            ValueV2::V2(value as u64, key.to_string())
+        }
+    }
+
+    #[test]
+    fn test_migration_in_batches() {
+        let (table, versions) = old_tables(10);
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions).with_batch_size(4);
+
+        assert_eq!(migrator.start().unwrap(), MigrationStatus::InProgress {
+            migrated_rows: 0
+        });
+        assert_eq!(migrator.step().unwrap(), MigrationStatus::InProgress {
+            migrated_rows: 4
+        });
+
+        // Readers see both encodings, and new writes go in the new encoding
+        assert_eq!(
+            migrator.get(&1).unwrap(),
+            Some(ValueV2::V2(1, "1".to_string()))
+        );
+        assert_eq!(
+            migrator.get(&8).unwrap(),
+            Some(ValueV2::V2(8, "8".to_string()))
+        );
+        migrator
+            .insert(&9, &ValueV2::V2(42, "new".to_string()))
+            .unwrap();
+        migrator
+            .insert(&20, &ValueV2::V2(20, "new".to_string()))
+            .unwrap();
+
+        // An interrupted migration resumes from the persisted cursor
+        drop(migrator);
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions).with_batch_size(4);
+        assert_eq!(migrator.step().unwrap(), MigrationStatus::InProgress {
+            migrated_rows: 8
+        });
+        assert_eq!(migrator.step().unwrap(), MigrationStatus::InProgress {
+            migrated_rows: 9
+        });
+        assert_eq!(migrator.step().unwrap(), MigrationStatus::Completed {
+            migrated_rows: 9
+        });
+        assert_eq!(migrator.table_version().unwrap(), TableVersion {
+            version: 1,
+            migration: None,
+            migrated_rows: 9,
+        });
+
+        let rows: Vec<_> = table.safe_iter().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 11);
+        assert_eq!(rows[3], (3, ValueV2::V2(3, "3".to_string())));
+        assert_eq!(rows[9], (9, ValueV2::V2(42, "new".to_string())));
+        assert_eq!(rows[10], (20, ValueV2::V2(20, "new".to_string())));
+        // The number of migrated rows is kept once completed
+        drop(migrator);
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions);
+        assert_eq!(migrator.start().unwrap(), MigrationStatus::Completed {
+            migrated_rows: 9
+        });
+    }
+
+    #[test]
+    fn test_migration_persists_rows_with_cursor() {
+        let (table, versions) = old_tables(10);
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions).with_batch_size(4);
+        migrator.start().unwrap();
+        migrator.step().unwrap();
+
+        // The re-encoded rows and the cursor are written together
+        let progress = versions
+            .get(&"values".to_string())
+            .unwrap()
+            .unwrap()
+            .migration
+            .unwrap();
+        assert_eq!(progress.cursor, Some(be_fix_int_ser(&3u64).unwrap()));
+        assert_eq!(progress.migrated_rows, 4);
+        assert_eq!(
+            table.get(&3).unwrap(),
+            Some(ValueV2::V2(3, "3".to_string()))
+        );
+        assert!(
+            bcs::from_bytes::<ValueV1>(&table.get_raw_bytes(&4).unwrap().unwrap()).is_ok(),
+            "rows after the cursor are not re-encoded yet"
+        );
+
+        // The next batch starts after the cursor, even if its row was removed
+        table.remove(&3).unwrap();
+        assert_eq!(migrator.step().unwrap(), MigrationStatus::InProgress {
+            migrated_rows: 8
+        });
+        assert_eq!(
+            versions
+                .get(&"values".to_string())
+                .unwrap()
+                .unwrap()
+                .migration
+                .unwrap()
+                .cursor,
+            Some(be_fix_int_ser(&7u64).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migration_run() {
+        let (table, versions) = old_tables(25);
+        versions
+            .insert(&"values".to_string(), &TableVersion {
+                version: 1,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // The migration doesn't apply to tables at other versions
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions);
+        assert_eq!(
+            migrator.run(Duration::ZERO).await.unwrap(),
+            MigrationStatus::NotApplicable { version: 1 }
+        );
+
+        versions.unsafe_clear().unwrap();
+        let migrator = TableMigrator::<MigrateToV2, _>::new(&table, &versions);
+        assert_eq!(
+            migrator.run(Duration::ZERO).await.unwrap(),
+            MigrationStatus::Completed { migrated_rows: 25 }
+        );
+        assert_eq!(
+            table.get(&24).unwrap(),
+            Some(ValueV2::V2(24, "24".to_string()))
+        );
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers to inspect the storage usage of a RocksDB instance at runtime,
+//! without having to stop the node and open its database with external tools.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use rocksdb::{AsColumnFamilyRef, ReadOptions, properties};
+
+use super::{ROCKSDB_PROPERTY_TOTAL_BLOB_FILES_SIZE, RocksDB};
+use crate::TypedStoreError;
+
+/// Maximum number of distinct keys tracked by the [`HotKeyTracker`]. Once
+/// reached, a newly sampled key replaces the least read tracked key and
+/// inherits its count, so frequently read keys are never evicted.
+pub(crate) const MAX_TRACKED_HOT_KEYS: usize = 10_000;
+
+/// Number of evenly spaced positions of the key range sampled by
+/// [`largest_key_prefixes`] in tables with more keys than it may scan.
+const KEY_SAMPLE_POSITIONS: usize = 64;
+
+/// Storage usage of a single table (column family).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStorageStats {
+    pub table_name: String,
+    pub total_sst_files_size: u64,
+    pub total_blob_files_size: u64,
+    pub size_all_mem_tables: u64,
+    pub estimated_num_keys: u64,
+    pub estimated_pending_compaction_bytes: u64,
+}
+
+/// Aggregated usage of all keys sharing the same prefix in a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPrefixUsage {
+    pub prefix: Vec<u8>,
+    pub num_keys: u64,
+    pub key_bytes: u64,
+    pub value_bytes: u64,
+}
+
+impl KeyPrefixUsage {
+    pub fn total_bytes(&self) -> u64 {
+        self.key_bytes + self.value_bytes
+    }
+}
+
+fn get_u64_property(
+    rocksdb: &RocksDB,
+    cf: &impl AsColumnFamilyRef,
+    property_name: &'static std::ffi::CStr,
+) -> Result<u64, TypedStoreError> {
+    rocksdb
+        .property_int_value_cf(cf, property_name)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| TypedStoreError::RocksDB(e.into_string()))
+}
+
+/// Returns the storage usage of every table of the database.
+pub fn table_storage_stats(rocksdb: &RocksDB) -> Result<Vec<TableStorageStats>, TypedStoreError> {
+    let table_names = rocksdb::DBWithThreadMode::<rocksdb::MultiThreaded>::list_cf(
+        &rocksdb::Options::default(),
+        rocksdb.path(),
+    )
+    .map_err(|e| TypedStoreError::RocksDB(e.into_string()))?;
+    let mut stats = Vec::with_capacity(table_names.len());
+    for table_name in table_names {
+        // Tables which are not opened by this instance can't be queried
+        let Some(cf) = rocksdb.cf_handle(&table_name) else {
+            continue;
+        };
+        stats.push(TableStorageStats {
+            total_sst_files_size: get_u64_property(rocksdb, &cf, properties::TOTAL_SST_FILES_SIZE)?,
+            total_blob_files_size: get_u64_property(
+                rocksdb,
+                &cf,
+                ROCKSDB_PROPERTY_TOTAL_BLOB_FILES_SIZE,
+            )?,
+            size_all_mem_tables: get_u64_property(rocksdb, &cf, properties::SIZE_ALL_MEM_TABLES)?,
+            estimated_num_keys: get_u64_property(rocksdb, &cf, properties::ESTIMATE_NUM_KEYS)?,
+            estimated_pending_compaction_bytes: get_u64_property(
+                rocksdb,
+                &cf,
+                properties::ESTIMATE_PENDING_COMPACTION_BYTES,
+            )?,
+            table_name,
+        });
+    }
+    Ok(stats)
+}
+
+/// Scans up to `max_scanned_keys` keys of a table, and returns the `limit` key
+/// prefixes of `prefix_len` bytes using the most space, in descending order.
+///
+/// Tables with more keys are sampled at evenly spaced positions of their key
+/// range, and the usage of the sampled keys is extrapolated to the estimated
+/// number of keys of the table.
+pub fn largest_key_prefixes(
+    rocksdb: &RocksDB,
+    table_name: &str,
+    prefix_len: usize,
+    max_scanned_keys: usize,
+    limit: usize,
+) -> Result<Vec<KeyPrefixUsage>, TypedStoreError> {
+    let cf = rocksdb
+        .cf_handle(table_name)
+        .ok_or_else(|| TypedStoreError::UnregisteredColumn(table_name.to_string()))?;
+    let estimated_num_keys = get_u64_property(rocksdb, &cf, properties::ESTIMATE_NUM_KEYS)?;
+    let mut readopts = ReadOptions::default();
+    // Don't evict hot blocks from the cache for a one-off scan
+    readopts.fill_cache(false);
+    let mut iter = rocksdb.raw_iterator_cf(&cf, readopts);
+    iter.seek_to_last();
+    let Some(last_key) = iter.key().map(<[u8]>::to_vec) else {
+        return Ok(vec![]);
+    };
+    iter.seek_to_first();
+    let Some(first_key) = iter.key().map(<[u8]>::to_vec) else {
+        return Ok(vec![]);
+    };
+
+    let positions = if estimated_num_keys > max_scanned_keys as u64 {
+        KEY_SAMPLE_POSITIONS.min(max_scanned_keys).max(1)
+    } else {
+        1
+    };
+    let keys_per_position = max_scanned_keys / positions;
+    let mut usage_by_prefix: HashMap<Vec<u8>, KeyPrefixUsage> = HashMap::new();
+    let mut scanned_keys = 0;
+    for position in 0..positions {
+        let seek_key = interpolate_key(&first_key, &last_key, position, positions);
+        // Positions within the keys already scanned continue from the current key
+        if iter.key().is_some_and(|key| key < seek_key.as_slice()) {
+            iter.seek(&seek_key);
+        }
+        for _ in 0..keys_per_position {
+            let (Some(key), Some(value)) = (iter.key(), iter.value()) else {
+                break;
+            };
+            let prefix = &key[..prefix_len.min(key.len())];
+            let usage = usage_by_prefix
+                .entry(prefix.to_vec())
+                .or_insert_with(|| KeyPrefixUsage {
+                    prefix: prefix.to_vec(),
+                    num_keys: 0,
+                    key_bytes: 0,
+                    value_bytes: 0,
+                });
+            usage.num_keys += 1;
+            usage.key_bytes += key.len() as u64;
+            usage.value_bytes += value.len() as u64;
+            scanned_keys += 1;
+            iter.next();
+        }
+    }
+
+    let mut usage: Vec<_> = usage_by_prefix.into_values().collect();
+    if positions > 1 && scanned_keys > 0 {
+        let extrapolate =
+            |n: u64| (n as u128 * estimated_num_keys as u128 / scanned_keys as u128) as u64;
+        for usage in &mut usage {
+            usage.num_keys = extrapolate(usage.num_keys);
+            usage.key_bytes = extrapolate(usage.key_bytes);
+            usage.value_bytes = extrapolate(usage.value_bytes);
+        }
+    }
+    usage.sort_by(|a, b| {
+        b.total_bytes()
+            .cmp(&a.total_bytes())
+            .then_with(|| a.prefix.cmp(&b.prefix))
+    });
+    usage.truncate(limit);
+    Ok(usage)
+}
+
+/// Returns the key at `position` out of `positions` evenly spaced positions
+/// from `first` to `last`, interpolated on the 8 bytes following their common
+/// prefix.
+fn interpolate_key(first: &[u8], last: &[u8], position: usize, positions: usize) -> Vec<u8> {
+    let common_len = first
+        .iter()
+        .zip(last)
+        .take_while(|(first, last)| first == last)
+        .count();
+    let to_u64 = |key: &[u8]| {
+        let suffix = &key[common_len..];
+        let mut bytes = [0; 8];
+        let len = suffix.len().min(bytes.len());
+        bytes[..len].copy_from_slice(&suffix[..len]);
+        u64::from_be_bytes(bytes)
+    };
+    let (start, end) = (to_u64(first), to_u64(last));
+    let offset = (end.saturating_sub(start) as u128 * position as u128 / positions as u128) as u64;
+    let mut key = first[..common_len].to_vec();
+    key.extend_from_slice(&(start + offset).to_be_bytes());
+    key
+}
+
+/// A key which was read frequently, with the number of its sampled reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotKey {
+    pub table_name: String,
+    pub key: Vec<u8>,
+    pub sampled_reads: u64,
+}
+
+/// Tracks the most frequently read keys by sampling one out of every
+/// `sample_every` point reads. Sampling is disabled by default.
+#[derive(Debug, Default)]
+pub struct HotKeyTracker {
+    sample_every: AtomicU64,
+    reads: AtomicU64,
+    sampled_keys: Mutex<SampledKeys>,
+}
+
+type TableKey = (String, Vec<u8>);
+
+/// Sampled read counts of at most [`MAX_TRACKED_HOT_KEYS`] keys, following
+/// the Space-Saving algorithm.
+#[derive(Debug, Default)]
+struct SampledKeys {
+    counts: HashMap<TableKey, u64>,
+    /// The tracked keys ordered by count, and by key for equal counts.
+    by_count: BTreeSet<(u64, Reverse<TableKey>)>,
+}
+
+impl SampledKeys {
+    fn increment(&mut self, key: TableKey) {
+        let count = match self.counts.get(&key) {
+            Some(&count) => {
+                self.by_count.remove(&(count, Reverse(key.clone())));
+                count + 1
+            }
+            None if self.counts.len() >= MAX_TRACKED_HOT_KEYS => {
+                let (min_count, Reverse(evicted)) = self
+                    .by_count
+                    .pop_first()
+                    .expect("tracked keys are not empty");
+                self.counts.remove(&evicted);
+                min_count + 1
+            }
+            None => 1,
+        };
+        self.counts.insert(key.clone(), count);
+        self.by_count.insert((count, Reverse(key)));
+    }
+
+    fn clear(&mut self) {
+        self.counts.clear();
+        self.by_count.clear();
+    }
+}
+
+impl HotKeyTracker {
+    /// Sets the sampling rate and clears the keys tracked so far. Setting it
+    /// to zero disables sampling.
+    pub fn set_sample_every(&self, sample_every: u64) {
+        self.sample_every.store(sample_every, Ordering::Relaxed);
+        self.sampled_keys.lock().unwrap().clear();
+    }
+
+    pub fn sample_every(&self) -> u64 {
+        self.sample_every.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, table_name: &str, key: &[u8]) {
+        let sample_every = self.sample_every.load(Ordering::Relaxed);
+        if sample_every == 0 || self.reads.fetch_add(1, Ordering::Relaxed) % sample_every != 0 {
+            return;
+        }
+        self.sampled_keys
+            .lock()
+            .unwrap()
+            .increment((table_name.to_string(), key.to_vec()));
+    }
+
+    /// Returns the `limit` keys with the most sampled reads, in descending
+    /// order.
+    pub fn hot_keys(&self, limit: usize) -> Vec<HotKey> {
+        self.sampled_keys
+            .lock()
+            .unwrap()
+            .by_count
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|(sampled_reads, Reverse((table_name, key)))| HotKey {
+                table_name: table_name.clone(),
+                key: key.clone(),
+                sampled_reads: *sampled_reads,
+            })
+            .collect()
+    }
+}
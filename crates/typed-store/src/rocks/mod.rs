@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod errors;
+pub mod introspection;
 pub(crate) mod iter;
 pub(crate) mod keys;
 pub(crate) mod safe_iter;
@@ -913,6 +914,25 @@ impl<K, V> DBMap<K, V> {
             .iterator_cf(&self.cf(), self.opts.readopts(), IteratorMode::Start)
     }
 
+    /// Iterates over the raw serialized keys and values of the map within the
+    /// given bounds, where `lower_bound` is inclusive and `upper_bound` is
+    /// exclusive.
+    pub fn raw_iterator_cf_with_bounds(
+        &self,
+        lower_bound: Option<Vec<u8>>,
+        upper_bound: Option<Vec<u8>>,
+    ) -> RocksDBIter<'_> {
+        let mut readopts = self.opts.readopts();
+        if let Some(lower_bound) = lower_bound {
+            readopts.set_iterate_lower_bound(lower_bound);
+        }
+        if let Some(upper_bound) = upper_bound {
+            readopts.set_iterate_upper_bound(upper_bound);
+        }
+        self.rocksdb
+            .iterator_cf(&self.cf(), readopts, IteratorMode::Start)
+    }
+
     pub fn flush(&self) -> Result<(), TypedStoreError> {
         self.rocksdb
             .flush_cf(&self.cf())
@@ -959,11 +979,15 @@ impl<K, V> DBMap<K, V> {
             .into_iter()
             .map(|k| be_fix_int_ser(k.borrow()))
             .collect();
+        let keys_bytes = keys_bytes?;
+        for key_bytes in &keys_bytes {
+            self.db_metrics.hot_keys.record(&self.cf, key_bytes);
+        }
         let results: Result<Vec<_>, TypedStoreError> = self
             .rocksdb
             .batched_multi_get_cf_opt(
                 &self.cf(),
-                keys_bytes?,
+                keys_bytes,
                 // sorted_keys=
                 false,
                 &self.opts.readopts(),
@@ -1489,6 +1513,21 @@ impl DBBatch {
         Ok(())
     }
 
+    /// deletes a range of already serialized keys given as an iterator
+    pub fn delete_batch_raw<K, V, B: AsRef<[u8]>>(
+        &mut self,
+        db: &DBMap<K, V>,
+        purged_keys: impl IntoIterator<Item = B>,
+    ) -> Result<(), TypedStoreError> {
+        if !Arc::ptr_eq(&db.rocksdb, &self.rocksdb) {
+            return Err(TypedStoreError::CrossDBBatch);
+        }
+        purged_keys
+            .into_iter()
+            .for_each(|k| self.batch.delete_cf(&db.cf(), k));
+        Ok(())
+    }
+
     /// Deletes a range of keys between `from` (inclusive) and `to`
     /// (non-inclusive) by writing a range delete tombstone in the db map
     /// If the DBMap is configured with ignore_range_deletions set to false,
@@ -1542,6 +1581,29 @@ impl DBBatch {
         Ok(self)
     }
 
+    /// inserts a range of already serialized (key, value) pairs given as an
+    /// iterator
+    pub fn insert_batch_raw<K, V, B: AsRef<[u8]>>(
+        &mut self,
+        db: &DBMap<K, V>,
+        new_vals: impl IntoIterator<Item = (B, B)>,
+    ) -> Result<&mut Self, TypedStoreError> {
+        if !Arc::ptr_eq(&db.rocksdb, &self.rocksdb) {
+            return Err(TypedStoreError::CrossDBBatch);
+        }
+        let mut total = 0usize;
+        new_vals.into_iter().for_each(|(k, v)| {
+            total += k.as_ref().len() + v.as_ref().len();
+            self.batch.put_cf(&db.cf(), k, v);
+        });
+        self.db_metrics
+            .op_metrics
+            .rocksdb_batch_put_bytes
+            .with_label_values(&[&db.cf])
+            .observe(total as f64);
+        Ok(self)
+    }
+
     /// merges a range of (key, value) pairs given as an iterator
     pub fn merge_batch<J: Borrow<K>, K: Serialize, U: Borrow<V>, V: Serialize>(
         &mut self,
@@ -1898,6 +1960,7 @@ where
             None
         };
         let key_buf = be_fix_int_ser(key)?;
+        self.db_metrics.hot_keys.record(&self.cf, &key_buf);
         let res = self
             .rocksdb
             .get_pinned_cf_opt(&self.cf(), &key_buf, &self.opts.readopts())
@@ -1934,6 +1997,7 @@ where
             None
         };
         let key_buf = be_fix_int_ser(key)?;
+        self.db_metrics.hot_keys.record(&self.cf, &key_buf);
         let res = self
             .rocksdb
             .get_pinned_cf_opt(&self.cf(), &key_buf, &self.opts.readopts())
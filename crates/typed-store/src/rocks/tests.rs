@@ -571,6 +571,128 @@ async fn test_insert_batch(#[values(true, false)] is_transactional: bool) {
     }
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_insert_batch_raw(#[values(true, false)] is_transactional: bool) {
+    let db = open_map(temp_dir(), None, is_transactional);
+    let copy = open_map::<_, i32, String>(temp_dir(), None, is_transactional);
+    let keys_vals = (1..100).map(|i| (i, i.to_string()));
+    db.multi_insert(keys_vals.clone())
+        .expect("Failed to multi-insert");
+
+    // Copy the entries within the bounds as raw bytes
+    let lower_bound = be_fix_int_ser(&10).unwrap();
+    let upper_bound = be_fix_int_ser(&20).unwrap();
+    let raw_entries = db
+        .raw_iterator_cf_with_bounds(Some(lower_bound), Some(upper_bound))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to iterate");
+    assert_eq!(raw_entries.len(), 10);
+    let mut insert_batch = copy.batch();
+    insert_batch
+        .insert_batch_raw(&copy, raw_entries)
+        .expect("Failed to batch insert");
+    insert_batch.write().expect("Failed to execute batch");
+    for (k, v) in keys_vals {
+        let val = copy.get(&k).expect("Failed to get inserted key");
+        assert_eq!((10..20).contains(&k).then_some(v), val);
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_storage_introspection(#[values(true, false)] is_transactional: bool) {
+    let rocks = open_rocksdb(temp_dir(), &["First_CF", "Second_CF"], is_transactional);
+    let db = DBMap::<(u8, u32), String>::reopen(
+        &rocks,
+        Some("First_CF"),
+        &ReadWriteOptions::default(),
+        false,
+    )
+    .expect("Failed to open storage");
+    db.multi_insert((0..30).map(|i| ((1, i), "a".repeat(10))))
+        .expect("Failed to multi-insert");
+    db.multi_insert((0..10).map(|i| ((2, i), "b".repeat(100))))
+        .expect("Failed to multi-insert");
+    db.flush().expect("Failed to flush");
+
+    let stats = introspection::table_storage_stats(&rocks).expect("Failed to get stats");
+    let first_cf = stats
+        .iter()
+        .find(|stats| stats.table_name == "First_CF")
+        .expect("Missing table stats");
+    assert!(first_cf.total_sst_files_size > 0);
+    assert_eq!(first_cf.estimated_num_keys, 40);
+    assert!(stats.iter().any(|stats| stats.table_name == "Second_CF"));
+
+    let prefixes = introspection::largest_key_prefixes(&rocks, "First_CF", 1, 1000, 10)
+        .expect("Failed to scan prefixes");
+    assert_eq!(prefixes.len(), 2);
+    assert_eq!(prefixes[0].prefix, vec![2]);
+    assert_eq!(prefixes[0].num_keys, 10);
+    assert_eq!(prefixes[1].prefix, vec![1]);
+    assert_eq!(prefixes[1].num_keys, 30);
+    assert_eq!(prefixes[1].key_bytes, 30 * 5);
+
+    // Keys are sampled across the whole key range and extrapolated to the
+    // estimated number of keys
+    let prefixes = introspection::largest_key_prefixes(&rocks, "First_CF", 1, 8, 10)
+        .expect("Failed to scan prefixes");
+    assert_eq!(prefixes.len(), 2);
+    assert_eq!(prefixes.iter().map(|usage| usage.num_keys).sum::<u64>(), 40);
+    assert!(introspection::largest_key_prefixes(&rocks, "Missing_CF", 1, 5, 10).is_err());
+}
+
+#[test]
+fn test_hot_key_tracker() {
+    let tracker = introspection::HotKeyTracker::default();
+    tracker.record("table", &[1]);
+    assert!(tracker.hot_keys(10).is_empty());
+
+    tracker.set_sample_every(1);
+    for _ in 0..3 {
+        tracker.record("table", &[1]);
+    }
+    tracker.record("table", &[2]);
+    tracker.record("other_table", &[1]);
+    let hot_keys = tracker.hot_keys(2);
+    assert_eq!(hot_keys.len(), 2);
+    assert_eq!(hot_keys[0], introspection::HotKey {
+        table_name: "table".to_string(),
+        key: vec![1],
+        sampled_reads: 3,
+    });
+    assert_eq!(hot_keys[1].sampled_reads, 1);
+
+    // Only one out of every two reads is sampled
+    tracker.set_sample_every(2);
+    for _ in 0..4 {
+        tracker.record("table", &[3]);
+    }
+    assert_eq!(tracker.hot_keys(10)[0].sampled_reads, 2);
+}
+
+#[test]
+fn test_hot_key_tracker_eviction() {
+    let tracker = introspection::HotKeyTracker::default();
+    tracker.set_sample_every(1);
+    for _ in 0..3 {
+        tracker.record("table", &[0]);
+    }
+    for i in 1..introspection::MAX_TRACKED_HOT_KEYS as u64 {
+        tracker.record("table", &i.to_be_bytes());
+    }
+
+    // The new key replaces a key read once and inherits its count
+    tracker.record("other_table", &[0]);
+    let hot_keys = tracker.hot_keys(introspection::MAX_TRACKED_HOT_KEYS + 1);
+    assert_eq!(hot_keys.len(), introspection::MAX_TRACKED_HOT_KEYS);
+    assert_eq!(hot_keys[0].key, vec![0]);
+    assert_eq!(hot_keys[0].sampled_reads, 3);
+    assert_eq!(hot_keys[1].table_name, "other_table");
+    assert_eq!(hot_keys[1].sampled_reads, 2);
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_insert_batch_across_cf(#[values(true, false)] is_transactional: bool) {
@@ -2,23 +2,31 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::{BTreeMap, BTreeSet};
+
 use move_ir_types::location::Loc;
 use move_symbol_pool::Symbol;
 
 use crate::{
     cfgir::visitor::AbstractInterpreterVisitor,
     command_line::compiler::Visitor,
-    diagnostics::codes::WarningFilter,
+    diagnostics::{codes::WarningFilter, WarningFilters},
     expansion::ast as E,
     hlir::ast::{BaseType_, SingleType, SingleType_},
     linters::{LintLevel, LinterDiagnosticCategory, ALLOW_ATTR_CATEGORY, LINT_WARNING_PREFIX},
     naming::ast as N,
-    typing::visitor::TypingVisitor,
+    parser::ast as P,
+    shared::Identifier,
+    typing::{
+        ast as T,
+        visitor::{TypingVisitor, TypingVisitorContext},
+    },
 };
 
 pub mod coin_field;
 pub mod collection_equality;
 pub mod custom_state_change;
+pub mod dynamic_field_in_loop;
 pub mod freeze_wrapped;
 pub mod freezing_capability;
 pub mod missing_key;
@@ -26,9 +34,15 @@ pub mod public_mut_tx_context;
 pub mod public_random;
 pub mod self_transfer;
 pub mod share_owned;
+pub mod shared_object_iteration;
+pub mod table_in_loop;
+pub mod unbounded_growth;
 
+pub const STD_PKG_NAME: &str = "std";
 pub const IOTA_PKG_NAME: &str = "iota";
 
+pub const VECTOR_MOD_NAME: &str = "vector";
+
 pub const TRANSFER_MOD_NAME: &str = "transfer";
 pub const TRANSFER_FUN: &str = "transfer";
 pub const PUBLIC_TRANSFER_FUN: &str = "public_transfer";
@@ -66,6 +80,9 @@ pub const VEC_MAP_STRUCT_NAME: &str = "VecMap";
 pub const VEC_SET_MOD_NAME: &str = "vec_set";
 pub const VEC_SET_STRUCT_NAME: &str = "VecSet";
 
+pub const DYNAMIC_FIELD_MOD_NAME: &str = "dynamic_field";
+pub const DYNAMIC_OBJECT_FIELD_MOD_NAME: &str = "dynamic_object_field";
+
 pub const SHARE_OWNED_FILTER_NAME: &str = "share_owned";
 pub const SELF_TRANSFER_FILTER_NAME: &str = "self_transfer";
 pub const CUSTOM_STATE_CHANGE_FILTER_NAME: &str = "custom_state_change";
@@ -76,6 +93,10 @@ pub const PUBLIC_RANDOM_FILTER_NAME: &str = "public_random";
 pub const MISSING_KEY_FILTER_NAME: &str = "missing_key";
 pub const FREEZING_CAPABILITY_FILTER_NAME: &str = "freezing_capability";
 pub const PREFER_MUTABLE_TX_CONTEXT_FILTER_NAME: &str = "prefer_mut_tx_context";
+pub const UNBOUNDED_GROWTH_FILTER_NAME: &str = "unbounded_growth";
+pub const DYNAMIC_FIELD_IN_LOOP_FILTER_NAME: &str = "dynamic_field_in_loop";
+pub const TABLE_IN_LOOP_FILTER_NAME: &str = "table_in_loop";
+pub const SHARED_OBJECT_ITERATION_FILTER_NAME: &str = "shared_object_iteration";
 
 pub const RANDOM_MOD_NAME: &str = "random";
 pub const RANDOM_STRUCT_NAME: &str = "Random";
@@ -95,6 +116,10 @@ pub enum LinterDiagnosticCode {
     MissingKey,
     FreezingCapability,
     PreferMutableTxContext,
+    UnboundedGrowth,
+    DynamicFieldInLoop,
+    TableInLoop,
+    SharedObjectIteration,
 }
 
 pub fn known_filters() -> (Option<Symbol>, Vec<WarningFilter>) {
@@ -160,6 +185,30 @@ pub fn known_filters() -> (Option<Symbol>, Vec<WarningFilter>) {
             LinterDiagnosticCode::PreferMutableTxContext as u8,
             Some(PREFER_MUTABLE_TX_CONTEXT_FILTER_NAME),
         ),
+        WarningFilter::code(
+            Some(LINT_WARNING_PREFIX),
+            LinterDiagnosticCategory::Iota as u8,
+            LinterDiagnosticCode::UnboundedGrowth as u8,
+            Some(UNBOUNDED_GROWTH_FILTER_NAME),
+        ),
+        WarningFilter::code(
+            Some(LINT_WARNING_PREFIX),
+            LinterDiagnosticCategory::Iota as u8,
+            LinterDiagnosticCode::DynamicFieldInLoop as u8,
+            Some(DYNAMIC_FIELD_IN_LOOP_FILTER_NAME),
+        ),
+        WarningFilter::code(
+            Some(LINT_WARNING_PREFIX),
+            LinterDiagnosticCategory::Iota as u8,
+            LinterDiagnosticCode::TableInLoop as u8,
+            Some(TABLE_IN_LOOP_FILTER_NAME),
+        ),
+        WarningFilter::code(
+            Some(LINT_WARNING_PREFIX),
+            LinterDiagnosticCategory::Iota as u8,
+            LinterDiagnosticCode::SharedObjectIteration as u8,
+            Some(SHARED_OBJECT_ITERATION_FILTER_NAME),
+        ),
     ];
 
     (Some(ALLOW_ATTR_CATEGORY.into()), filters)
@@ -183,6 +232,11 @@ pub fn linter_visitors(level: LintLevel) -> Vec<Visitor> {
             visitors.extend([
                 freezing_capability::WarnFreezeCapability.visitor(),
                 public_mut_tx_context::PreferMutableTxContext.visitor(),
+                // scalability hazards
+                unbounded_growth::UnboundedGrowthVisitor.visitor(),
+                dynamic_field_in_loop::DynamicFieldInLoopVisitor.visitor(),
+                table_in_loop::TableInLoopVisitor.visitor(),
+                shared_object_iteration::SharedObjectIterationVisitor.visitor(),
             ]);
             visitors
         }
@@ -209,3 +263,179 @@ pub fn type_abilities(sp!(_, st_): &SingleType) -> Option<E::AbilitySet> {
     }
     None
 }
+
+/// Functions returning the number of elements of a collection.
+pub const COLLECTION_SIZE_FUNCTIONS: &[(&str, &str, &str)] = &[
+    (STD_PKG_NAME, VECTOR_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, VEC_MAP_MOD_NAME, "size"),
+    (IOTA_PKG_NAME, VEC_SET_MOD_NAME, "size"),
+    (IOTA_PKG_NAME, TABLE_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, OBJECT_TABLE_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, LINKED_TABLE_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, TABLE_VEC_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, BAG_MOD_NAME, "length"),
+    (IOTA_PKG_NAME, OBJECT_BAG_MOD_NAME, "length"),
+];
+
+/// Returns true if `fun` calls one of `functions`.
+pub fn is_call_to(fun: &T::ModuleCall, functions: &[(&str, &str, &str)]) -> bool {
+    functions.iter().any(|(addr, module, fname)| {
+        fun.module.value.is(*addr, *module) && fun.name.value().as_str() == *fname
+    })
+}
+
+/// Returns the arguments of a call, skipping those that are expanded from a
+/// call returning multiple values.
+pub fn call_arguments(fun: &T::ModuleCall) -> Vec<&T::Exp> {
+    use T::UnannotatedExp_ as E;
+    match &fun.arguments.exp.value {
+        E::Unit { .. } => vec![],
+        E::ExpList(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                T::ExpListItem::Single(e, _) => Some(e),
+                T::ExpListItem::Splat(..) => None,
+            })
+            .collect(),
+        _ => vec![&fun.arguments],
+    }
+}
+
+/// Returns the module and name of a (potentially referenced) struct or enum
+/// type.
+pub fn datatype_name(t: &N::Type) -> Option<(E::ModuleIdent, P::DatatypeName)> {
+    let bt = base_type(t)?;
+    let N::Type_::Apply(_, tname, _) = &bt.value else {
+        return None;
+    };
+    let N::TypeName_::ModuleType(mident, name) = tname.value else {
+        return None;
+    };
+    Some((mident, name))
+}
+
+/// Types that the program shares by calling 'iota::transfer::share_object' or
+/// 'iota::transfer::public_share_object'.
+pub type SharedTypes = BTreeSet<(E::ModuleIdent, P::DatatypeName)>;
+
+pub fn shared_types(program: &mut T::Program) -> SharedTypes {
+    let mut collector = SharedTypesCollector(SharedTypes::new());
+    collector.visit(program);
+    collector.0
+}
+
+struct SharedTypesCollector(SharedTypes);
+
+impl TypingVisitorContext for SharedTypesCollector {
+    fn add_warning_filter_scope(&mut self, _filter: WarningFilters) {}
+
+    fn pop_warning_filter_scope(&mut self) {}
+
+    fn visit_exp_custom(&mut self, exp: &mut T::Exp) -> bool {
+        const SHARE_FUNCTIONS: &[(&str, &str, &str)] = &[
+            (IOTA_PKG_NAME, TRANSFER_MOD_NAME, SHARE_FUN),
+            (IOTA_PKG_NAME, TRANSFER_MOD_NAME, PUBLIC_SHARE_FUN),
+        ];
+        if let T::UnannotatedExp_::ModuleCall(fun) = &exp.exp.value {
+            if is_call_to(fun, SHARE_FUNCTIONS) {
+                if let Some(name) = fun.type_arguments.first().and_then(datatype_name) {
+                    self.0.insert(name);
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Returns the parameters of `fdef` whose type, possibly behind a reference,
+/// is shared, along with the name of that type.
+pub fn shared_parameters(
+    shared_types: &SharedTypes,
+    fdef: &T::Function,
+) -> BTreeMap<N::Var_, P::DatatypeName> {
+    fdef.signature
+        .parameters
+        .iter()
+        .filter_map(|(_, var, ty)| {
+            let name = datatype_name(ty)?;
+            shared_types.contains(&name).then_some((var.value, name.1))
+        })
+        .collect()
+}
+
+/// A local and the fields borrowed from it, e.g. `obj` and `[items]` for
+/// `&mut obj.items`.
+pub type AccessPath = (N::Var_, Vec<Symbol>);
+
+/// Tracks the locals bound to borrows of other locals, so that a borrow through
+/// such a local can be traced back to the local it originates from.
+#[derive(Default)]
+pub struct Aliases(BTreeMap<N::Var_, AccessPath>);
+
+impl Aliases {
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Records `let x = <access path>;` bindings.
+    pub fn record(&mut self, seq_item: &T::SequenceItem) {
+        let T::SequenceItem_::Bind(lvalues, _, e) = &seq_item.value else {
+            return;
+        };
+        let [sp!(_, T::LValue_::Var { var, .. })] = &lvalues.value[..] else {
+            return;
+        };
+        if let Some(path) = self.resolve(e) {
+            self.0.insert(var.value, path);
+        }
+    }
+
+    /// Returns the access path of `exp`, starting from the local it
+    /// originates from.
+    pub fn resolve(&self, exp: &T::Exp) -> Option<AccessPath> {
+        use T::UnannotatedExp_ as E;
+        match &exp.exp.value {
+            E::Borrow(_, e, field) => {
+                let (root, mut fields) = self.resolve(e)?;
+                fields.push(field.value());
+                Some((root, fields))
+            }
+            E::TempBorrow(_, e) | E::Dereference(e) | E::Annotate(e, _) => self.resolve(e),
+            E::Move { var, .. } | E::Copy { var, .. } | E::BorrowLocal(_, var) | E::Use(var) => {
+                Some(
+                    self.0
+                        .get(&var.value)
+                        .cloned()
+                        .unwrap_or((var.value, vec![])),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if the condition of a `while` loop orders against a value or a
+/// constant, which is taken as a sign that the number of iterations does not
+/// depend on the size of some data. Inequality is not a bound, as the loop may
+/// step over the constant.
+pub fn is_bounded_loop_condition(cond: &T::Exp) -> bool {
+    use P::BinOp_ as B;
+    use T::UnannotatedExp_ as E;
+    let is_constant = |e: &T::Exp| {
+        let mut e = e;
+        while let E::Annotate(inner, _) | E::Cast(inner, _) = &e.exp.value {
+            e = inner;
+        }
+        matches!(e.exp.value, E::Value(_) | E::Constant(..))
+    };
+    match &cond.exp.value {
+        E::BinopExp(e1, sp!(_, B::And), _, e2) => {
+            is_bounded_loop_condition(e1) || is_bounded_loop_condition(e2)
+        }
+        E::BinopExp(e1, sp!(_, B::Lt | B::Le | B::Gt | B::Ge), _, e2) => {
+            is_constant(e1) || is_constant(e2)
+        }
+        E::Annotate(e, _) => is_bounded_loop_condition(e),
+        _ => false,
+    }
+}
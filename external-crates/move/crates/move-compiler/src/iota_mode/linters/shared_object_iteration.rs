@@ -0,0 +1,166 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! This analysis flags loops in public or entry functions whose number of
+//! iterations is the size of a collection stored in a shared object. Anyone can
+//! grow a shared object, so the worst-case gas cost of such a function is not
+//! under the control of the caller and can grow until every call to it fails.
+
+use std::collections::BTreeMap;
+
+use super::{
+    call_arguments, is_call_to, shared_parameters, shared_types, Aliases, LinterDiagnosticCategory,
+    LinterDiagnosticCode, SharedTypes, COLLECTION_SIZE_FUNCTIONS, LINT_WARNING_PREFIX,
+};
+use crate::{
+    diag,
+    diagnostics::{
+        codes::{custom, DiagnosticInfo, Severity},
+        WarningFilters,
+    },
+    expansion::ast::{ModuleIdent, Visibility},
+    naming::ast as N,
+    parser::ast::{DatatypeName, FunctionName},
+    shared::CompilationEnv,
+    typing::{
+        ast as T,
+        visitor::{TypingVisitorConstructor, TypingVisitorContext},
+    },
+};
+
+const SHARED_OBJECT_ITERATION_DIAG: DiagnosticInfo = custom(
+    LINT_WARNING_PREFIX,
+    Severity::Warning,
+    LinterDiagnosticCategory::Iota as u8,
+    LinterDiagnosticCode::SharedObjectIteration as u8,
+    "gas cost depends on the contents of a shared object",
+);
+
+pub struct SharedObjectIterationVisitor;
+
+pub struct Context<'a> {
+    env: &'a mut CompilationEnv,
+    shared_types: SharedTypes,
+    /// Name of the current function
+    function_name: Option<FunctionName>,
+    /// Parameters of the current function holding a shared object
+    shared_params: BTreeMap<N::Var_, DatatypeName>,
+    aliases: Aliases,
+    /// Locals holding the size of a collection of a shared object
+    size_locals: BTreeMap<N::Var_, DatatypeName>,
+}
+
+impl TypingVisitorConstructor for SharedObjectIterationVisitor {
+    type Context<'a> = Context<'a>;
+
+    fn context<'a>(env: &'a mut CompilationEnv, _program: &T::Program) -> Self::Context<'a> {
+        Context {
+            env,
+            shared_types: SharedTypes::new(),
+            function_name: None,
+            shared_params: BTreeMap::new(),
+            aliases: Aliases::default(),
+            size_locals: BTreeMap::new(),
+        }
+    }
+
+    fn visit(&mut self, env: &mut CompilationEnv, program: &mut T::Program) {
+        let shared_types = shared_types(program);
+        let mut context = Self::context(env, program);
+        context.shared_types = shared_types;
+        context.visit(program);
+    }
+}
+
+impl Context<'_> {
+    /// Returns the shared object whose contents determine the value of `exp`,
+    /// if any.
+    fn shared_size(&self, exp: &T::Exp) -> Option<DatatypeName> {
+        use T::UnannotatedExp_ as E;
+        match &exp.exp.value {
+            E::ModuleCall(fun) => {
+                let args = call_arguments(fun);
+                if is_call_to(fun, COLLECTION_SIZE_FUNCTIONS) {
+                    let (root, _) = self.aliases.resolve(args.first()?)?;
+                    return self.shared_params.get(&root).copied();
+                }
+                args.into_iter().find_map(|arg| self.shared_size(arg))
+            }
+            E::Move { var, .. } | E::Copy { var, .. } | E::Use(var) => {
+                self.size_locals.get(&var.value).copied()
+            }
+            E::BinopExp(e1, _, _, e2) => self.shared_size(e1).or_else(|| self.shared_size(e2)),
+            E::UnaryExp(_, e) | E::Annotate(e, _) | E::Cast(e, _) | E::Dereference(e) => {
+                self.shared_size(e)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TypingVisitorContext for Context<'_> {
+    fn add_warning_filter_scope(&mut self, filter: WarningFilters) {
+        self.env.add_warning_filter_scope(filter)
+    }
+
+    fn pop_warning_filter_scope(&mut self) {
+        self.env.pop_warning_filter_scope()
+    }
+
+    fn visit_module_custom(&mut self, _ident: ModuleIdent, mdef: &mut T::ModuleDefinition) -> bool {
+        // skips if true
+        mdef.attributes.is_test_or_test_only()
+    }
+
+    fn visit_function_custom(
+        &mut self,
+        _module: ModuleIdent,
+        function_name: FunctionName,
+        fdef: &mut T::Function,
+    ) -> bool {
+        if fdef.attributes.is_test_or_test_only()
+            || (!matches!(fdef.visibility, Visibility::Public(_)) && fdef.entry.is_none())
+        {
+            return true;
+        }
+        self.shared_params = shared_parameters(&self.shared_types, fdef);
+        self.function_name = Some(function_name);
+        self.aliases.clear();
+        self.size_locals.clear();
+        // skips if there is no shared object to iterate over
+        self.shared_params.is_empty()
+    }
+
+    fn visit_seq_item_custom(&mut self, seq_item: &mut T::SequenceItem) -> bool {
+        self.aliases.record(seq_item);
+        if let T::SequenceItem_::Bind(lvalues, _, e) = &seq_item.value {
+            if let [sp!(_, T::LValue_::Var { var, .. })] = &lvalues.value[..] {
+                if let Some(name) = self.shared_size(e) {
+                    self.size_locals.insert(var.value, name);
+                }
+            }
+        }
+        false
+    }
+
+    fn visit_exp_custom(&mut self, exp: &mut T::Exp) -> bool {
+        let T::UnannotatedExp_::While(_, cond, _) = &exp.exp.value else {
+            return false;
+        };
+        if let Some(name) = self.shared_size(cond) {
+            let msg = format!(
+                "The number of iterations of this loop depends on the contents of shared object \
+                 '{name}'",
+            );
+            let mut d = diag!(SHARED_OBJECT_ITERATION_DIAG, (cond.exp.loc, msg));
+            d.add_note(format!(
+                "Anyone can add to a shared object, so the gas cost of calling '{}' can grow \
+                 until it exceeds the maximum gas budget. Consider bounding the number of \
+                 iterations, or processing the contents in batches over several transactions",
+                self.function_name.unwrap(),
+            ));
+            self.env.add_diag(d);
+        }
+        false
+    }
+}
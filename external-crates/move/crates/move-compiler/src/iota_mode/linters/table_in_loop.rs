@@ -0,0 +1,150 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! This analysis flags lookups and updates of `Table`s, `Bag`s and the other
+//! collections built on dynamic fields inside loops whose number of iterations
+//! is not bounded by a constant. Each of these operations loads an object from
+//! storage, so the cost of such a loop can exceed the gas budget of a
+//! transaction.
+
+use super::{
+    is_bounded_loop_condition, LinterDiagnosticCategory, LinterDiagnosticCode, BAG_MOD_NAME,
+    IOTA_PKG_NAME, LINKED_TABLE_MOD_NAME, LINT_WARNING_PREFIX, OBJECT_BAG_MOD_NAME,
+    OBJECT_TABLE_MOD_NAME, TABLE_MOD_NAME, TABLE_VEC_MOD_NAME,
+};
+use crate::{
+    diag,
+    diagnostics::{
+        codes::{custom, DiagnosticInfo, Severity},
+        WarningFilters,
+    },
+    expansion::ast::ModuleIdent,
+    parser::ast::FunctionName,
+    shared::{CompilationEnv, Identifier},
+    typing::{
+        ast as T,
+        visitor::{TypingVisitorConstructor, TypingVisitorContext},
+    },
+};
+
+const TABLE_IN_LOOP_DIAG: DiagnosticInfo = custom(
+    LINT_WARNING_PREFIX,
+    Severity::Warning,
+    LinterDiagnosticCategory::Iota as u8,
+    LinterDiagnosticCode::TableInLoop as u8,
+    "table access in an unbounded loop",
+);
+
+const TABLE_MODULES: &[(&str, &str)] = &[
+    (IOTA_PKG_NAME, TABLE_MOD_NAME),
+    (IOTA_PKG_NAME, OBJECT_TABLE_MOD_NAME),
+    (IOTA_PKG_NAME, LINKED_TABLE_MOD_NAME),
+    (IOTA_PKG_NAME, TABLE_VEC_MOD_NAME),
+    (IOTA_PKG_NAME, BAG_MOD_NAME),
+    (IOTA_PKG_NAME, OBJECT_BAG_MOD_NAME),
+];
+
+/// Functions of the table modules that do not access the elements
+const NON_ACCESSING_FUNCTIONS: &[&str] = &[
+    "new",
+    "empty",
+    "length",
+    "is_empty",
+    "destroy_empty",
+    "drop",
+];
+
+pub struct TableInLoopVisitor;
+
+pub struct Context<'a> {
+    env: &'a mut CompilationEnv,
+    /// Number of loops without a constant bound around the current expression
+    unbounded_loops: usize,
+}
+
+impl TypingVisitorConstructor for TableInLoopVisitor {
+    type Context<'a> = Context<'a>;
+
+    fn context<'a>(env: &'a mut CompilationEnv, _program: &T::Program) -> Self::Context<'a> {
+        Context {
+            env,
+            unbounded_loops: 0,
+        }
+    }
+}
+
+impl TypingVisitorContext for Context<'_> {
+    fn add_warning_filter_scope(&mut self, filter: WarningFilters) {
+        self.env.add_warning_filter_scope(filter)
+    }
+
+    fn pop_warning_filter_scope(&mut self) {
+        self.env.pop_warning_filter_scope()
+    }
+
+    fn visit_module_custom(&mut self, ident: ModuleIdent, mdef: &mut T::ModuleDefinition) -> bool {
+        // skips if true
+        mdef.attributes.is_test_or_test_only()
+            || TABLE_MODULES
+                .iter()
+                .any(|(addr, module)| ident.value.is(*addr, *module))
+    }
+
+    fn visit_function_custom(
+        &mut self,
+        _module: ModuleIdent,
+        _function_name: FunctionName,
+        fdef: &mut T::Function,
+    ) -> bool {
+        // skips if true
+        fdef.attributes.is_test_or_test_only()
+    }
+
+    fn visit_exp_custom(&mut self, exp: &mut T::Exp) -> bool {
+        use T::UnannotatedExp_ as E;
+        match &mut exp.exp.value {
+            E::While(_, cond, body) => {
+                let bounded = is_bounded_loop_condition(cond);
+                if !bounded {
+                    self.unbounded_loops += 1;
+                }
+                self.visit_exp(cond);
+                self.visit_exp(body);
+                if !bounded {
+                    self.unbounded_loops -= 1;
+                }
+                true
+            }
+            E::Loop { body, .. } => {
+                self.unbounded_loops += 1;
+                self.visit_exp(body);
+                self.unbounded_loops -= 1;
+                true
+            }
+            E::ModuleCall(fun) if self.unbounded_loops > 0 => {
+                let fname = fun.name.value();
+                if NON_ACCESSING_FUNCTIONS.contains(&fname.as_str()) {
+                    return false;
+                }
+                if let Some((_, module)) = TABLE_MODULES
+                    .iter()
+                    .find(|(addr, module)| fun.module.value.is(*addr, *module))
+                {
+                    let msg = format!(
+                        "'{module}::{fname}' is called in a loop whose number of iterations is \
+                         not bounded by a constant",
+                    );
+                    let mut d = diag!(TABLE_IN_LOOP_DIAG, (exp.exp.loc, msg));
+                    d.add_note(
+                        "Each access to an element of a table or bag loads an object from \
+                         storage, so the gas cost of the loop grows with the number of elements \
+                         it goes over",
+                    );
+                    self.env.add_diag(d);
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
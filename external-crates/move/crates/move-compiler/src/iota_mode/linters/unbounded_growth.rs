@@ -0,0 +1,171 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! This analysis flags vectors, `VecMap`s and `VecSet`s stored in shared
+//! objects that public or entry functions grow without checking their size.
+//! Every transaction using a shared object loads it as a whole, so a collection
+//! that anyone can grow without a bound makes the object more and more
+//! expensive to use, until it no longer fits the object size limit.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_ir_types::location::Loc;
+
+use super::{
+    call_arguments, is_call_to, shared_parameters, shared_types, AccessPath, Aliases,
+    LinterDiagnosticCategory, LinterDiagnosticCode, SharedTypes, COLLECTION_SIZE_FUNCTIONS,
+    IOTA_PKG_NAME, LINT_WARNING_PREFIX, STD_PKG_NAME, VECTOR_MOD_NAME, VEC_MAP_MOD_NAME,
+    VEC_SET_MOD_NAME,
+};
+use crate::{
+    diag,
+    diagnostics::{
+        codes::{custom, DiagnosticInfo, Severity},
+        WarningFilters,
+    },
+    expansion::ast::{ModuleIdent, Visibility},
+    naming::ast as N,
+    parser::ast::{DatatypeName, FunctionName},
+    shared::CompilationEnv,
+    typing::{
+        ast as T,
+        visitor::{TypingVisitorConstructor, TypingVisitorContext},
+    },
+};
+
+const UNBOUNDED_GROWTH_DIAG: DiagnosticInfo = custom(
+    LINT_WARNING_PREFIX,
+    Severity::Warning,
+    LinterDiagnosticCategory::Iota as u8,
+    LinterDiagnosticCode::UnboundedGrowth as u8,
+    "unbounded growth of a shared object",
+);
+
+const GROWTH_FUNCTIONS: &[(&str, &str, &str)] = &[
+    (STD_PKG_NAME, VECTOR_MOD_NAME, "push_back"),
+    (STD_PKG_NAME, VECTOR_MOD_NAME, "append"),
+    (IOTA_PKG_NAME, VEC_MAP_MOD_NAME, "insert"),
+    (IOTA_PKG_NAME, VEC_SET_MOD_NAME, "insert"),
+];
+
+pub struct UnboundedGrowthVisitor;
+
+pub struct Context<'a> {
+    env: &'a mut CompilationEnv,
+    shared_types: SharedTypes,
+    /// Parameters of the current function holding a shared object
+    shared_params: BTreeMap<N::Var_, DatatypeName>,
+    aliases: Aliases,
+    /// Collections of shared objects grown by the current function
+    grown: Vec<(Loc, AccessPath)>,
+    /// Collections whose size the current function reads
+    measured: BTreeSet<AccessPath>,
+}
+
+impl TypingVisitorConstructor for UnboundedGrowthVisitor {
+    type Context<'a> = Context<'a>;
+
+    fn context<'a>(env: &'a mut CompilationEnv, _program: &T::Program) -> Self::Context<'a> {
+        Context {
+            env,
+            shared_types: SharedTypes::new(),
+            shared_params: BTreeMap::new(),
+            aliases: Aliases::default(),
+            grown: vec![],
+            measured: BTreeSet::new(),
+        }
+    }
+
+    fn visit(&mut self, env: &mut CompilationEnv, program: &mut T::Program) {
+        let shared_types = shared_types(program);
+        let mut context = Self::context(env, program);
+        context.shared_types = shared_types;
+        context.visit(program);
+    }
+}
+
+impl TypingVisitorContext for Context<'_> {
+    fn add_warning_filter_scope(&mut self, filter: WarningFilters) {
+        self.env.add_warning_filter_scope(filter)
+    }
+
+    fn pop_warning_filter_scope(&mut self) {
+        self.env.pop_warning_filter_scope()
+    }
+
+    fn visit_module_custom(&mut self, _ident: ModuleIdent, mdef: &mut T::ModuleDefinition) -> bool {
+        // skips if true
+        mdef.attributes.is_test_or_test_only()
+    }
+
+    fn visit_function_custom(
+        &mut self,
+        _module: ModuleIdent,
+        _function_name: FunctionName,
+        fdef: &mut T::Function,
+    ) -> bool {
+        if fdef.attributes.is_test_or_test_only()
+            || (!matches!(fdef.visibility, Visibility::Public(_)) && fdef.entry.is_none())
+        {
+            return true;
+        }
+        self.shared_params = shared_parameters(&self.shared_types, fdef);
+        if self.shared_params.is_empty() {
+            return true;
+        }
+        self.aliases.clear();
+        self.measured.clear();
+        if let T::FunctionBody_::Defined(seq) = &mut fdef.body.value {
+            self.visit_seq(seq);
+        }
+        for (loc, (root, fields)) in std::mem::take(&mut self.grown) {
+            if self.measured.contains(&(root, fields.clone())) {
+                continue;
+            }
+            let msg = format!(
+                "'{}' of shared object '{}' can grow without a bound",
+                fields
+                    .iter()
+                    .map(|f| f.as_str())
+                    .collect::<Vec<_>>()
+                    .join("."),
+                self.shared_params[&root],
+            );
+            let mut d = diag!(UNBOUNDED_GROWTH_DIAG, (loc, msg));
+            d.add_note(
+                "Every transaction using a shared object loads all of its contents. Bound the \
+                 size of the collection by checking it before growing it, or store its elements \
+                 in a collection based on dynamic fields, such as 'iota::table::Table'",
+            );
+            self.env.add_diag(d);
+        }
+        // the body has already been visited
+        true
+    }
+
+    fn visit_seq_item_custom(&mut self, seq_item: &mut T::SequenceItem) -> bool {
+        self.aliases.record(seq_item);
+        false
+    }
+
+    fn visit_exp_custom(&mut self, exp: &mut T::Exp) -> bool {
+        let T::UnannotatedExp_::ModuleCall(fun) = &exp.exp.value else {
+            return false;
+        };
+        let Some(path) = call_arguments(fun)
+            .first()
+            .and_then(|arg| self.aliases.resolve(arg))
+        else {
+            return false;
+        };
+        if is_call_to(fun, GROWTH_FUNCTIONS)
+            && self.shared_params.contains_key(&path.0)
+            && !path.1.is_empty()
+        {
+            self.grown.push((exp.exp.loc, path));
+        } else if is_call_to(fun, COLLECTION_SIZE_FUNCTIONS) {
+            self.measured.insert(path);
+        }
+        false
+    }
+}
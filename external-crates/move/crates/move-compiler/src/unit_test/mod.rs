@@ -7,11 +7,8 @@ use crate::{
     compiled_unit::NamedCompiledModule, shared::files::MappedFiles, shared::NumericalAddress,
 };
 use move_core_types::{
-    account_address::AccountAddress,
-    identifier::Identifier,
-    language_storage::{ModuleId, TypeTag},
-    runtime_value::MoveValue,
-    vm_status::StatusCode,
+    account_address::AccountAddress, annotated_value::MoveTypeLayout, identifier::Identifier,
+    language_storage::ModuleId, runtime_value::MoveValue, vm_status::StatusCode,
 };
 use std::{collections::BTreeMap, fmt};
 
@@ -42,7 +39,9 @@ pub struct TestCase {
 #[derive(Debug, Clone)]
 pub enum TestArgument {
     Value(MoveValue),
-    Generate { generated_type: TypeTag },
+    // The layout of the value to generate, annotated so that well-known types can be generated
+    // with valid values and generated values can be displayed
+    Generate { generated_type: MoveTypeLayout },
 }
 
 #[derive(Debug, Clone)]
@@ -12,7 +12,7 @@ use crate::{
     },
     hlir::{ast as HA, translate::display_var},
     naming::ast as NA,
-    parser::ast::ConstantName,
+    parser::ast::{Ability_, ConstantName, DatatypeName, Field},
     shared::{
         known_attributes::{self, TestingAttribute},
         unique_map::UniqueMap,
@@ -24,7 +24,9 @@ use crate::{
 };
 use move_core_types::{
     account_address::AccountAddress as MoveAddress,
-    language_storage::{ModuleId, TypeTag},
+    annotated_value::{MoveEnumLayout, MoveFieldLayout, MoveStructLayout, MoveTypeLayout},
+    identifier::Identifier as MoveIdentifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
     runtime_value::MoveValue,
     u256::U256,
     vm_status::StatusCode,
@@ -33,13 +35,14 @@ use move_ir_types::location::Loc;
 use move_symbol_pool::Symbol;
 use std::collections::BTreeMap;
 
-struct Context<'env> {
+struct Context<'env, 'prog> {
     env: &'env mut CompilationEnv,
     constants: UniqueMap<ModuleIdent, UniqueMap<ConstantName, (Loc, Option<u64>, Attributes)>>,
+    modules: &'prog UniqueMap<ModuleIdent, G::ModuleDefinition>,
 }
 
-impl<'env> Context<'env> {
-    fn new(compilation_env: &'env mut CompilationEnv, prog: &G::Program) -> Self {
+impl<'env, 'prog> Context<'env, 'prog> {
+    fn new(compilation_env: &'env mut CompilationEnv, prog: &'prog G::Program) -> Self {
         let constants = prog.modules.ref_map(|_mident, module| {
             module.constants.ref_map(|_name, constant| {
                 let v_opt = constant.value.as_ref().and_then(|v| match v {
@@ -52,6 +55,7 @@ impl<'env> Context<'env> {
         Self {
             env: compilation_env,
             constants,
+            modules: &prog.modules,
         }
     }
 
@@ -191,19 +195,20 @@ fn build_test_info<'func>(
         match test_annotation_params.get(&var_) {
             Some(value) => arguments.push(TestArgument::Value(value.clone())),
             None if is_random_test => {
-                let generated_type = match convert_builtin_type_to_typetag(&s_type.value) {
+                let generated_type = match convert_type_to_layout(context, &s_type.value) {
                     Some(generated_type) => generated_type,
                     None => {
-                        let msg = "Unsupported type for generated input for test. Only built-in types \
-                            are supported for generated test inputs";
+                        let msg = "Unsupported type for generated input for test. Only built-in \
+                            types, structs and enums are supported for generated test inputs";
                         let mut diag = diag!(
                             Attributes::InvalidTest,
                             (s_type.loc, msg),
                             (fn_loc, IN_THIS_TEST_MSG),
                         );
                         diag.add_note(
-                            "Supported builti-in types are: bool, u8, u16, u32, u64, \
-                            u128, u256, address, and vector<T> where T is a built-in type",
+                            "Supported types are: bool, u8, u16, u32, u64, u128, u256, address, \
+                            vector<T>, and structs and enums without the 'key' ability, where T \
+                            and all fields are supported types",
                         );
                         context.env.add_diag(diag);
                         return None;
@@ -774,41 +779,125 @@ fn check_location<T>(
     location
 }
 
-fn convert_builtin_type_to_typetag(s_type: &HA::SingleType_) -> Option<TypeTag> {
-    fn get_builtin_type_inner(bt: &HA::BaseType) -> Option<TypeTag> {
-        match &bt.value {
-            HA::BaseType_::Apply(_, sp!(_, HA::TypeName_::Builtin(b)), bts) => {
-                let mut tts = bts
-                    .iter()
-                    .map(get_builtin_type_inner)
-                    .collect::<Option<Vec<_>>>()?;
-                let tag = match b.value {
-                    NA::BuiltinTypeName_::Bool => TypeTag::Bool,
-                    NA::BuiltinTypeName_::Address => TypeTag::Address,
-                    NA::BuiltinTypeName_::U8 => TypeTag::U8,
-                    NA::BuiltinTypeName_::U64 => TypeTag::U64,
-                    NA::BuiltinTypeName_::U128 => TypeTag::U128,
-                    NA::BuiltinTypeName_::U256 => TypeTag::U256,
-                    NA::BuiltinTypeName_::U16 => TypeTag::U16,
-                    NA::BuiltinTypeName_::U32 => TypeTag::U32,
-                    NA::BuiltinTypeName_::Vector => {
-                        if tts.len() != 1 {
-                            return None;
-                        }
-                        TypeTag::Vector(Box::new(tts.remove(0)))
+fn convert_type_to_layout(context: &Context, s_type: &HA::SingleType_) -> Option<MoveTypeLayout> {
+    match s_type {
+        HA::SingleType_::Base(bt) => base_type_to_layout(context, bt, &BTreeMap::new()),
+        _ => None,
+    }
+}
+
+fn base_type_to_layout(
+    context: &Context,
+    bt: &HA::BaseType,
+    subst: &BTreeMap<NA::TParamID, MoveTypeLayout>,
+) -> Option<MoveTypeLayout> {
+    match &bt.value {
+        HA::BaseType_::Apply(_, sp!(_, HA::TypeName_::Builtin(b)), bts) => {
+            let mut layouts = bts
+                .iter()
+                .map(|bt| base_type_to_layout(context, bt, subst))
+                .collect::<Option<Vec<_>>>()?;
+            let layout = match b.value {
+                NA::BuiltinTypeName_::Bool => MoveTypeLayout::Bool,
+                NA::BuiltinTypeName_::Address => MoveTypeLayout::Address,
+                NA::BuiltinTypeName_::U8 => MoveTypeLayout::U8,
+                NA::BuiltinTypeName_::U64 => MoveTypeLayout::U64,
+                NA::BuiltinTypeName_::U128 => MoveTypeLayout::U128,
+                NA::BuiltinTypeName_::U256 => MoveTypeLayout::U256,
+                NA::BuiltinTypeName_::U16 => MoveTypeLayout::U16,
+                NA::BuiltinTypeName_::U32 => MoveTypeLayout::U32,
+                NA::BuiltinTypeName_::Vector => {
+                    if layouts.len() != 1 {
+                        return None;
                     }
-                    NA::BuiltinTypeName_::Signer => TypeTag::Signer,
-                };
-                Some(tag)
-            }
-            HA::BaseType_::Apply(_, _, _) => None,
-            HA::BaseType_::Param(_)
-            | HA::BaseType_::Unreachable
-            | HA::BaseType_::UnresolvedError => None,
+                    MoveTypeLayout::Vector(Box::new(layouts.remove(0)))
+                }
+                // Signers cannot be generated
+                NA::BuiltinTypeName_::Signer => return None,
+            };
+            Some(layout)
         }
+        HA::BaseType_::Apply(_, sp!(_, HA::TypeName_::ModuleType(mident, name)), bts) => {
+            let type_args = bts
+                .iter()
+                .map(|bt| base_type_to_layout(context, bt, subst))
+                .collect::<Option<Vec<_>>>()?;
+            datatype_to_layout(context, mident, name, type_args)
+        }
+        HA::BaseType_::Param(tparam) => subst.get(&tparam.id).cloned(),
+        HA::BaseType_::Unreachable | HA::BaseType_::UnresolvedError => None,
     }
-    match s_type {
-        HA::SingleType_::Base(bt) => get_builtin_type_inner(bt),
-        _ => None,
+}
+
+// Objects cannot be generated, as their IDs need to be unique
+fn datatype_to_layout(
+    context: &Context,
+    mident: &ModuleIdent,
+    name: &DatatypeName,
+    type_args: Vec<MoveTypeLayout>,
+) -> Option<MoveTypeLayout> {
+    let module = context.modules.get(mident)?;
+    let ModuleIdent_ {
+        address,
+        module: module_name,
+    } = &mident.value;
+    let type_ = StructTag {
+        address: context.resolve_address(address).into_inner(),
+        module: MoveIdentifier::new(module_name.value().as_str()).ok()?,
+        name: MoveIdentifier::new(name.value().as_str()).ok()?,
+        type_params: type_args.iter().map(TypeTag::from).collect(),
+    };
+    let fields_to_layout = |type_parameters: &[HA::DatatypeTypeParameter],
+                            fields: &[(Field, HA::BaseType)]| {
+        let subst: BTreeMap<_, _> = type_parameters
+            .iter()
+            .map(|tparam| tparam.param.id)
+            .zip(type_args.iter().cloned())
+            .collect();
+        fields
+            .iter()
+            .map(|(field, bt)| {
+                Some(MoveFieldLayout::new(
+                    field_identifier(field)?,
+                    base_type_to_layout(context, bt, &subst)?,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+
+    if let Some(struct_def) = module.structs.get(name) {
+        if struct_def.abilities.has_ability_(Ability_::Key) {
+            return None;
+        }
+        let HA::StructFields::Defined(fields) = &struct_def.fields else {
+            return None;
+        };
+        let fields = fields_to_layout(&struct_def.type_parameters, fields)?;
+        Some(MoveTypeLayout::Struct(MoveStructLayout { type_, fields }))
+    } else {
+        let enum_def = module.enums.get(name)?;
+        if enum_def.abilities.has_ability_(Ability_::Key) {
+            return None;
+        }
+        let variants = enum_def
+            .variants
+            .key_cloned_iter()
+            .map(|(variant_name, variant)| {
+                let fields = fields_to_layout(&enum_def.type_parameters, &variant.fields)?;
+                let variant_name = MoveIdentifier::new(variant_name.value().as_str()).ok()?;
+                Some(((variant_name, variant.index as u16), fields))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()?;
+        Some(MoveTypeLayout::Enum(MoveEnumLayout { type_, variants }))
+    }
+}
+
+// Positional fields are named `pos{field_idx}` in bytecode
+fn field_identifier(field: &Field) -> Option<MoveIdentifier> {
+    let name = field.value();
+    if name.as_str().parse::<u8>().is_ok() {
+        MoveIdentifier::new(format!("pos{}", name)).ok()
+    } else {
+        MoveIdentifier::new(name.as_str()).ok()
     }
 }
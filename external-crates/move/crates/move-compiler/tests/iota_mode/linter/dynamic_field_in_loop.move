@@ -0,0 +1,69 @@
+// tests the lint for dynamic field accesses in loops without a constant bound
+module a::test {
+    use iota::dynamic_field;
+    use iota::object::UID;
+
+    const MAX_ITERATIONS: u64 = 10;
+
+    struct Obj has key {
+        id: UID,
+    }
+
+    public fun sum(obj: &Obj, n: u64): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < n) {
+            sum = sum + *dynamic_field::borrow<u64, u64>(&obj.id, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun find(obj: &Obj): u64 {
+        let i = 0;
+        loop {
+            if (dynamic_field::exists_(&obj.id, i)) break;
+            i = i + 1;
+        };
+        i
+    }
+
+    public fun sum_bounded(obj: &Obj): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < MAX_ITERATIONS) {
+            sum = sum + *dynamic_field::borrow<u64, u64>(&obj.id, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    #[allow(lint(dynamic_field_in_loop))]
+    public fun sum_suppressed(obj: &Obj, n: u64): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < n) {
+            sum = sum + *dynamic_field::borrow<u64, u64>(&obj.id, i);
+            i = i + 1;
+        };
+        sum
+    }
+}
+
+module iota::object {
+    struct UID has store {
+        id: address,
+    }
+}
+
+module iota::dynamic_field {
+    use iota::object::UID;
+
+    public fun borrow<Name: copy + drop + store, Value: store>(_object: &UID, _name: Name): &Value {
+        abort 0
+    }
+
+    public fun exists_<Name: copy + drop + store>(_object: &UID, _name: Name): bool {
+        abort 0
+    }
+}
@@ -0,0 +1,73 @@
+// tests the lint for loops over the contents of shared objects
+module a::test {
+    use std::vector;
+    use iota::object::UID;
+    use iota::transfer;
+
+    struct Registry has key {
+        id: UID,
+        items: vector<u64>,
+    }
+
+    struct Owned has key {
+        id: UID,
+        items: vector<u64>,
+    }
+
+    public fun share(id: UID) {
+        transfer::share_object(Registry { id, items: vector::empty() })
+    }
+
+    public fun sum(registry: &Registry): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(&registry.items)) {
+            sum = sum + *vector::borrow(&registry.items, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    entry fun reset(registry: &mut Registry) {
+        let items = &mut registry.items;
+        let n = vector::length(items);
+        let i = 0;
+        while (i < n) {
+            *vector::borrow_mut(items, i) = 0;
+            i = i + 1;
+        }
+    }
+
+    public fun sum_owned(owned: &Owned): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(&owned.items)) {
+            sum = sum + *vector::borrow(&owned.items, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    #[allow(lint(shared_object_iteration))]
+    public fun sum_suppressed(registry: &Registry): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(&registry.items)) {
+            sum = sum + *vector::borrow(&registry.items, i);
+            i = i + 1;
+        };
+        sum
+    }
+}
+
+module iota::object {
+    struct UID has store {
+        id: address,
+    }
+}
+
+module iota::transfer {
+    public fun share_object<T: key>(_: T) {
+        abort 0
+    }
+}
@@ -0,0 +1,83 @@
+// tests the lint for table accesses in loops without a constant bound
+module a::test {
+    use std::vector;
+    use iota::table::{Self, Table};
+
+    public fun sum(table: &Table<u64, u64>, keys: &vector<u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < vector::length(keys)) {
+            let key = *vector::borrow(keys, i);
+            if (table::contains(table, key)) {
+                sum = sum + *table::borrow(table, key);
+            };
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun clear(table: &mut Table<u64, u64>, n: u64) {
+        let i = 0;
+        while (i < n && !table::is_empty(table)) {
+            table::remove(table, i);
+            i = i + 1;
+        }
+    }
+
+    public fun sum_bounded(table: &Table<u64, u64>): u64 {
+        let sum = 0;
+        let i = 0;
+        while (i < 10) {
+            sum = sum + *table::borrow(table, i);
+            i = i + 1;
+        };
+        sum
+    }
+
+    public fun drain(table: &mut Table<u64, u64>, start: u64) {
+        let i = start;
+        while (i != 10) {
+            table::remove(table, i);
+            i = i + 1;
+        }
+    }
+
+    #[allow(lint(table_in_loop))]
+    public fun clear_suppressed(table: &mut Table<u64, u64>, n: u64) {
+        let i = 0;
+        while (i < n) {
+            table::remove(table, i);
+            i = i + 1;
+        }
+    }
+}
+
+module iota::object {
+    struct UID has store {
+        id: address,
+    }
+}
+
+module iota::table {
+    use iota::object::UID;
+
+    struct Table<phantom K: copy + drop + store, phantom V: store> has key, store {
+        id: UID,
+    }
+
+    public fun borrow<K: copy + drop + store, V: store>(_table: &Table<K, V>, _k: K): &V {
+        abort 0
+    }
+
+    public fun contains<K: copy + drop + store, V: store>(_table: &Table<K, V>, _k: K): bool {
+        abort 0
+    }
+
+    public fun remove<K: copy + drop + store, V: store>(_table: &mut Table<K, V>, _k: K): V {
+        abort 0
+    }
+
+    public fun is_empty<K: copy + drop + store, V: store>(_table: &Table<K, V>): bool {
+        abort 0
+    }
+}
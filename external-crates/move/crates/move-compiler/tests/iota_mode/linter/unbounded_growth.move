@@ -0,0 +1,78 @@
+// tests the lint for collections of shared objects that grow without a bound
+module a::test {
+    use std::vector;
+    use iota::object::UID;
+    use iota::transfer;
+    use iota::vec_map::{Self, VecMap};
+
+    const MAX_ITEMS: u64 = 100;
+
+    struct Registry has key {
+        id: UID,
+        items: vector<u64>,
+        owners: VecMap<address, u64>,
+    }
+
+    struct Owned has key {
+        id: UID,
+        items: vector<u64>,
+    }
+
+    public fun share(id: UID) {
+        transfer::share_object(Registry { id, items: vector::empty(), owners: vec_map::empty() })
+    }
+
+    public fun add_item(registry: &mut Registry, item: u64) {
+        vector::push_back(&mut registry.items, item);
+    }
+
+    entry fun add_owner(registry: &mut Registry, owner: address, value: u64) {
+        let owners = &mut registry.owners;
+        vec_map::insert(owners, owner, value);
+    }
+
+    public fun add_item_bounded(registry: &mut Registry, item: u64) {
+        assert!(vector::length(&registry.items) < MAX_ITEMS, 0);
+        vector::push_back(&mut registry.items, item);
+    }
+
+    public fun add_owned_item(owned: &mut Owned, item: u64) {
+        vector::push_back(&mut owned.items, item);
+    }
+
+    #[allow(lint(unbounded_growth))]
+    public fun add_item_suppressed(registry: &mut Registry, item: u64) {
+        vector::push_back(&mut registry.items, item);
+    }
+}
+
+module iota::object {
+    struct UID has store {
+        id: address,
+    }
+}
+
+module iota::transfer {
+    public fun share_object<T: key>(_: T) {
+        abort 0
+    }
+}
+
+module iota::vec_map {
+    struct VecMap<K: copy, V> has copy, drop, store {
+        contents: vector<Entry<K, V>>,
+    }
+
+    struct Entry<K: copy, V> has copy, drop, store {
+        key: K,
+        value: V,
+    }
+
+    public fun empty<K: copy, V>(): VecMap<K, V> {
+        abort 0
+    }
+
+    public fun insert<K: copy, V>(_self: &mut VecMap<K, V>, _key: K, _value: V) {
+        abort 0
+    }
+}
@@ -1,5 +1,5 @@
 module 0x1::l {
-    public struct X() has drop;
+    public struct X has key, drop { x: u64 }
 
     #[random_test]
     fun foo() { }
@@ -1,4 +1,12 @@
 module 0x1::l {
+    public struct P(u64, bool) has drop;
+
+    public enum E has drop {
+        A,
+        B(P),
+        C { v: vector<P> },
+    }
+
     #[random_test]
     fun foo(a: u64) { 
         _ = a;
@@ -19,4 +27,10 @@ module 0x1::l {
     fun qux_vec(c: vector<u8>) { 
         _ = c;
     }
+
+    #[random_test]
+    fun datatypes(p: P, e: E) {
+        _ = p;
+        _ = e;
+    }
 }
@@ -5,6 +5,7 @@
 
 pub mod cargo_runner;
 pub mod extensions;
+pub mod random_test;
 pub mod test_reporter;
 pub mod test_runner;
 
@@ -0,0 +1,457 @@
+// Copyright (c) 2024 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generation and shrinking of inputs for `#[random_test]` functions.
+//!
+//! Values are generated from annotated layouts so that well-known standard
+//! library types (strings and options) are generated with valid contents, and
+//! so that failing inputs can be displayed to the user once they have been
+//! shrunk.
+
+use std::ops::{Div, Sub};
+
+use move_core_types::{
+    account_address::AccountAddress,
+    annotated_value::{
+        MoveEnumLayout, MoveStruct, MoveStructLayout, MoveTypeLayout, MoveValue, MoveVariant,
+    },
+    u256::U256,
+};
+use rand::{rngs::StdRng, Rng};
+
+type Candidates<'a, T> = Box<dyn Iterator<Item = T> + 'a>;
+
+/// Generates a random value of the given layout.
+pub fn generate_value(rng: &mut StdRng, layout: &MoveTypeLayout) -> MoveValue {
+    match layout {
+        MoveTypeLayout::Address => {
+            MoveValue::Address(AccountAddress::from_bytes(rng.gen::<[u8; 32]>()).unwrap())
+        }
+        MoveTypeLayout::U8 => MoveValue::U8(rng.gen::<u8>()),
+        MoveTypeLayout::U16 => MoveValue::U16(rng.gen::<u16>()),
+        MoveTypeLayout::U32 => MoveValue::U32(rng.gen::<u32>()),
+        MoveTypeLayout::U64 => MoveValue::U64(rng.gen::<u64>()),
+        MoveTypeLayout::U128 => MoveValue::U128(rng.gen::<u128>()),
+        MoveTypeLayout::U256 => MoveValue::U256(rng.gen::<U256>()),
+        MoveTypeLayout::Vector(layout) => {
+            let len = rng.gen_range(0..1024);
+            let values = (0..len).map(|_| generate_value(rng, layout)).collect();
+            MoveValue::Vector(values)
+        }
+        MoveTypeLayout::Bool => MoveValue::Bool(rng.gen::<bool>()),
+        MoveTypeLayout::Struct(layout) if is_std_string(layout) => {
+            let len = rng.gen_range(0..1024);
+            let string = (0..len).map(|_| rng.gen::<char>()).collect::<String>();
+            wrap_bytes(layout, string.into_bytes())
+        }
+        MoveTypeLayout::Struct(layout) if is_ascii_string(layout) => {
+            let len = rng.gen_range(0..1024);
+            let bytes = (0..len).map(|_| rng.gen_range(0..=0x7F)).collect();
+            wrap_bytes(layout, bytes)
+        }
+        MoveTypeLayout::Struct(layout) if is_option(layout) => {
+            let MoveTypeLayout::Vector(element) = &layout.fields[0].layout else {
+                unreachable!("Option is always represented by a vector")
+            };
+            let values = (0..rng.gen_range(0..=1))
+                .map(|_| generate_value(rng, element))
+                .collect();
+            wrap_field(layout, MoveValue::Vector(values))
+        }
+        MoveTypeLayout::Struct(layout) => MoveValue::Struct(MoveStruct {
+            type_: layout.type_.clone(),
+            fields: layout
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), generate_value(rng, &field.layout)))
+                .collect(),
+        }),
+        MoveTypeLayout::Enum(layout) => {
+            let idx = rng.gen_range(0..layout.variants.len());
+            let ((variant_name, tag), fields) = layout.variants.iter().nth(idx).unwrap();
+            MoveValue::Variant(MoveVariant {
+                type_: layout.type_.clone(),
+                variant_name: variant_name.clone(),
+                tag: *tag,
+                fields: fields
+                    .iter()
+                    .map(|field| (field.name.clone(), generate_value(rng, &field.layout)))
+                    .collect(),
+            })
+        }
+        MoveTypeLayout::Signer => unreachable!("Signer arguments not allowed"),
+    }
+}
+
+/// Shrinks a failing set of generated inputs. Candidates are tried one
+/// argument at a time, and the first one for which `fails` still holds is
+/// kept, until no argument can be shrunk any further or `max_executions`
+/// candidates have been tried.
+pub fn shrink(
+    layouts: &[&MoveTypeLayout],
+    mut values: Vec<MoveValue>,
+    max_executions: usize,
+    mut fails: impl FnMut(&[MoveValue]) -> bool,
+) -> Vec<MoveValue> {
+    let mut executions = 0;
+    'shrink: loop {
+        for (idx, layout) in layouts.iter().enumerate() {
+            let mut shrunk = None;
+            for candidate in shrink_value(layout, &values[idx]) {
+                if executions == max_executions {
+                    break 'shrink;
+                }
+                executions += 1;
+                let mut attempt = values.clone();
+                attempt[idx] = candidate;
+                if fails(&attempt) {
+                    shrunk = Some(attempt);
+                    break;
+                }
+            }
+            if let Some(shrunk) = shrunk {
+                values = shrunk;
+                continue 'shrink;
+            }
+        }
+        break;
+    }
+    values
+}
+
+/// Returns the values that are strictly smaller than `value`, the most
+/// aggressive reductions first.
+fn shrink_value<'a>(layout: &'a MoveTypeLayout, value: &'a MoveValue) -> Candidates<'a, MoveValue> {
+    match (layout, value) {
+        (MoveTypeLayout::Bool, MoveValue::Bool(true)) => {
+            Box::new(std::iter::once(MoveValue::Bool(false)))
+        }
+        (MoveTypeLayout::Address, MoveValue::Address(address))
+            if *address != AccountAddress::ZERO =>
+        {
+            Box::new(std::iter::once(MoveValue::Address(AccountAddress::ZERO)))
+        }
+        (MoveTypeLayout::U8, MoveValue::U8(v)) => {
+            Box::new(shrink_integer(*v, 0, 1, 2).map(MoveValue::U8))
+        }
+        (MoveTypeLayout::U16, MoveValue::U16(v)) => {
+            Box::new(shrink_integer(*v, 0, 1, 2).map(MoveValue::U16))
+        }
+        (MoveTypeLayout::U32, MoveValue::U32(v)) => {
+            Box::new(shrink_integer(*v, 0, 1, 2).map(MoveValue::U32))
+        }
+        (MoveTypeLayout::U64, MoveValue::U64(v)) => {
+            Box::new(shrink_integer(*v, 0, 1, 2).map(MoveValue::U64))
+        }
+        (MoveTypeLayout::U128, MoveValue::U128(v)) => {
+            Box::new(shrink_integer(*v, 0, 1, 2).map(MoveValue::U128))
+        }
+        (MoveTypeLayout::U256, MoveValue::U256(v)) => Box::new(
+            shrink_integer(*v, U256::zero(), U256::one(), U256::from(2u8)).map(MoveValue::U256),
+        ),
+        (MoveTypeLayout::Vector(layout), MoveValue::Vector(values)) => {
+            Box::new(shrink_vector(layout, values).map(MoveValue::Vector))
+        }
+        (MoveTypeLayout::Struct(layout), MoveValue::Struct(s)) if is_std_string(layout) => {
+            // Shrink by characters so that the string stays valid UTF-8
+            let MoveValue::Vector(bytes) = &s.fields[0].1 else {
+                return Box::new(std::iter::empty());
+            };
+            let bytes = bytes
+                .iter()
+                .filter_map(|b| match b {
+                    MoveValue::U8(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let chars = String::from_utf8_lossy(&bytes).chars().collect::<Vec<_>>();
+            Box::new(shrink_sequence(chars).map(|chars| {
+                let string = chars.into_iter().collect::<String>();
+                wrap_bytes(layout, string.into_bytes())
+            }))
+        }
+        (MoveTypeLayout::Struct(layout), MoveValue::Struct(s)) => {
+            Box::new(shrink_fields(layout, s).map(MoveValue::Struct))
+        }
+        (MoveTypeLayout::Enum(layout), MoveValue::Variant(v)) => shrink_variant(layout, v),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn shrink_integer<T>(v: T, zero: T, one: T, two: T) -> impl Iterator<Item = T>
+where
+    T: Copy + PartialEq + Div<Output = T> + Sub<Output = T>,
+{
+    let mut candidates = vec![];
+    if v != zero {
+        candidates.push(zero);
+        let half = v / two;
+        if half != zero {
+            candidates.push(half);
+        }
+        let pred = v - one;
+        if pred != zero && pred != half {
+            candidates.push(pred);
+        }
+    }
+    candidates.into_iter()
+}
+
+/// Shrinks a sequence by removing elements only.
+fn shrink_sequence<'a, T: Clone + 'a>(values: Vec<T>) -> Candidates<'a, Vec<T>> {
+    let len = values.len();
+    let empty = (len > 0).then(Vec::new);
+    let halves = (len > 1)
+        .then(|| [values[..len / 2].to_vec(), values[len / 2..].to_vec()])
+        .into_iter()
+        .flatten();
+    let removals = (0..len).filter(move |_| len > 2).map(move |idx| {
+        let mut values = values.clone();
+        values.remove(idx);
+        values
+    });
+    Box::new(empty.into_iter().chain(halves).chain(removals))
+}
+
+fn shrink_vector<'a>(
+    layout: &'a MoveTypeLayout,
+    values: &'a [MoveValue],
+) -> Candidates<'a, Vec<MoveValue>> {
+    let elements = (0..values.len()).flat_map(move |idx| {
+        shrink_value(layout, &values[idx]).map(move |candidate| {
+            let mut values = values.to_vec();
+            values[idx] = candidate;
+            values
+        })
+    });
+    Box::new(shrink_sequence(values.to_vec()).chain(elements))
+}
+
+fn shrink_fields<'a>(
+    layout: &'a MoveStructLayout,
+    s: &'a MoveStruct,
+) -> Candidates<'a, MoveStruct> {
+    Box::new(
+        layout
+            .fields
+            .iter()
+            .enumerate()
+            .flat_map(move |(idx, field)| {
+                shrink_value(&field.layout, &s.fields[idx].1).map(move |candidate| {
+                    let mut s = s.clone();
+                    s.fields[idx].1 = candidate;
+                    s
+                })
+            }),
+    )
+}
+
+/// Variants declared before the current one are tried first, with minimal
+/// fields, followed by shrinking the fields of the current variant.
+fn shrink_variant<'a>(layout: &'a MoveEnumLayout, v: &'a MoveVariant) -> Candidates<'a, MoveValue> {
+    let mut lower = layout
+        .variants
+        .iter()
+        .filter(|((_, tag), _)| *tag < v.tag)
+        .collect::<Vec<_>>();
+    lower.sort_by_key(|((_, tag), _)| *tag);
+    let lower = lower.into_iter().map(move |((variant_name, tag), fields)| {
+        MoveValue::Variant(MoveVariant {
+            type_: layout.type_.clone(),
+            variant_name: variant_name.clone(),
+            tag: *tag,
+            fields: fields
+                .iter()
+                .map(|field| (field.name.clone(), minimal_value(&field.layout)))
+                .collect(),
+        })
+    });
+    let fields = layout
+        .variants
+        .iter()
+        .find(|((_, tag), _)| *tag == v.tag)
+        .into_iter()
+        .flat_map(move |(_, fields)| {
+            fields.iter().enumerate().flat_map(move |(idx, field)| {
+                shrink_value(&field.layout, &v.fields[idx].1).map(move |candidate| {
+                    let mut v = v.clone();
+                    v.fields[idx].1 = candidate;
+                    MoveValue::Variant(v)
+                })
+            })
+        });
+    Box::new(lower.chain(fields))
+}
+
+/// Returns the smallest value of the given layout.
+fn minimal_value(layout: &MoveTypeLayout) -> MoveValue {
+    match layout {
+        MoveTypeLayout::Bool => MoveValue::Bool(false),
+        MoveTypeLayout::U8 => MoveValue::U8(0),
+        MoveTypeLayout::U16 => MoveValue::U16(0),
+        MoveTypeLayout::U32 => MoveValue::U32(0),
+        MoveTypeLayout::U64 => MoveValue::U64(0),
+        MoveTypeLayout::U128 => MoveValue::U128(0),
+        MoveTypeLayout::U256 => MoveValue::U256(U256::zero()),
+        MoveTypeLayout::Address => MoveValue::Address(AccountAddress::ZERO),
+        MoveTypeLayout::Signer => MoveValue::Signer(AccountAddress::ZERO),
+        MoveTypeLayout::Vector(_) => MoveValue::Vector(vec![]),
+        MoveTypeLayout::Struct(layout) => MoveValue::Struct(MoveStruct {
+            type_: layout.type_.clone(),
+            fields: layout
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), minimal_value(&field.layout)))
+                .collect(),
+        }),
+        MoveTypeLayout::Enum(layout) => {
+            let ((variant_name, tag), fields) = layout
+                .variants
+                .iter()
+                .min_by_key(|((_, tag), _)| *tag)
+                .expect("Enums have at least one variant");
+            MoveValue::Variant(MoveVariant {
+                type_: layout.type_.clone(),
+                variant_name: variant_name.clone(),
+                tag: *tag,
+                fields: fields
+                    .iter()
+                    .map(|field| (field.name.clone(), minimal_value(&field.layout)))
+                    .collect(),
+            })
+        }
+    }
+}
+
+fn is_std_string(layout: &MoveStructLayout) -> bool {
+    layout.type_.is_std_string(&AccountAddress::ONE)
+}
+
+fn is_ascii_string(layout: &MoveStructLayout) -> bool {
+    layout.type_.is_ascii_string(&AccountAddress::ONE)
+}
+
+fn is_option(layout: &MoveStructLayout) -> bool {
+    layout.type_.address == AccountAddress::ONE
+        && layout.type_.module.as_str() == "option"
+        && layout.type_.name.as_str() == "Option"
+}
+
+fn wrap_bytes(layout: &MoveStructLayout, bytes: Vec<u8>) -> MoveValue {
+    wrap_field(
+        layout,
+        MoveValue::Vector(bytes.into_iter().map(MoveValue::U8).collect()),
+    )
+}
+
+fn wrap_field(layout: &MoveStructLayout, value: MoveValue) -> MoveValue {
+    MoveValue::Struct(MoveStruct {
+        type_: layout.type_.clone(),
+        fields: vec![(layout.fields[0].name.clone(), value)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use move_core_types::{
+        annotated_value::MoveFieldLayout, identifier::Identifier, language_storage::StructTag,
+    };
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn std_string_layout() -> MoveTypeLayout {
+        MoveTypeLayout::Struct(MoveStructLayout {
+            type_: StructTag {
+                address: AccountAddress::ONE,
+                module: Identifier::new("string").unwrap(),
+                name: Identifier::new("String").unwrap(),
+                type_params: vec![],
+            },
+            fields: vec![MoveFieldLayout::new(
+                Identifier::new("bytes").unwrap(),
+                MoveTypeLayout::Vector(Box::new(MoveTypeLayout::U8)),
+            )],
+        })
+    }
+
+    fn string_of(value: &MoveValue) -> String {
+        let MoveValue::Struct(s) = value else {
+            panic!("Expected a struct, got {value}");
+        };
+        let MoveValue::Vector(bytes) = &s.fields[0].1 else {
+            panic!("Expected bytes, got {}", s.fields[0].1);
+        };
+        let bytes = bytes
+            .iter()
+            .map(|b| match b {
+                MoveValue::U8(b) => *b,
+                _ => panic!("Expected a byte, got {b}"),
+            })
+            .collect();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn generated_strings_are_utf8() {
+        let layout = std_string_layout();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            string_of(&generate_value(&mut rng, &layout));
+        }
+    }
+
+    #[test]
+    fn shrink_integer_to_threshold() {
+        let layouts = [&MoveTypeLayout::U64];
+        let shrunk = shrink(
+            &layouts,
+            vec![MoveValue::U64(u64::MAX)],
+            1000,
+            |values| matches!(values[0], MoveValue::U64(v) if v >= 42),
+        );
+        assert_eq!(shrunk, vec![MoveValue::U64(42)]);
+    }
+
+    #[test]
+    fn shrink_vector_to_single_element() {
+        let layout = MoveTypeLayout::Vector(Box::new(MoveTypeLayout::U8));
+        let mut rng = StdRng::seed_from_u64(0);
+        let value = MoveValue::Vector((0..100).map(|_| MoveValue::U8(rng.gen())).collect());
+        let shrunk = shrink(
+            &[&layout],
+            vec![value],
+            1000,
+            |values| matches!(&values[0], MoveValue::Vector(v) if !v.is_empty()),
+        );
+        assert_eq!(shrunk, vec![MoveValue::Vector(vec![MoveValue::U8(0)])]);
+    }
+
+    #[test]
+    fn shrink_string_stays_utf8() {
+        let layout = std_string_layout();
+        let value = wrap_bytes(
+            match &layout {
+                MoveTypeLayout::Struct(layout) => layout,
+                _ => unreachable!(),
+            },
+            "aé€😀".as_bytes().to_vec(),
+        );
+        let shrunk = shrink(&[&layout], vec![value], 1000, |values| {
+            string_of(&values[0]).contains('€')
+        });
+        assert_eq!(string_of(&shrunk[0]), "€");
+    }
+
+    #[test]
+    fn shrink_respects_execution_bound() {
+        let layout = MoveTypeLayout::Vector(Box::new(MoveTypeLayout::U8));
+        let value = MoveValue::Vector(vec![MoveValue::U8(1); 100]);
+        let mut executions = 0;
+        shrink(&[&layout], vec![value], 5, |_| {
+            executions += 1;
+            false
+        });
+        assert_eq!(executions, 5);
+    }
+}
@@ -47,6 +47,8 @@ pub struct TestFailure {
     pub vm_error: Option<VMError>,
     pub failure_reason: FailureReason,
     pub prng_seed: Option<u64>,
+    /// The shrunk generated inputs on which a random test still fails
+    pub counterexample: Option<String>,
 }
 
 #[derive(Debug, Clone, Ord, PartialOrd, PartialEq, Eq)]
@@ -149,6 +151,7 @@ impl TestFailure {
             vm_error,
             failure_reason,
             prng_seed,
+            counterexample: None,
         }
     }
 
@@ -356,6 +359,23 @@ impl TestStatistics {
         false
     }
 
+    /// Records the shrunk inputs of the last failure of a random test
+    pub fn add_counterexample(
+        &mut self,
+        test_name: &str,
+        counterexample: String,
+        test_plan: &ModuleTestPlan,
+    ) {
+        if let Some(test_failure) = self
+            .failed
+            .get_mut(&test_plan.module_id)
+            .and_then(|failures| failures.get_mut(test_name))
+            .and_then(|failures| failures.last_mut())
+        {
+            test_failure.counterexample = Some(counterexample);
+        }
+    }
+
     pub fn test_success(
         &mut self,
         test_name: String,
@@ -582,6 +602,13 @@ impl TestResults {
                                 .render_error(&self.test_plan)
                                 .replace('\n', "\n│ ")
                         )?;
+                        if let Some(counterexample) = &test_failure.counterexample {
+                            writeln!(
+                                writer.lock().unwrap(),
+                                "│ Shrunk failing input: {}",
+                                counterexample.bright_red().bold()
+                            )?;
+                        }
                         if let Some(seed) = test_failure.prng_seed {
                             writeln!(writer.lock().unwrap(),
                             "│ {}",
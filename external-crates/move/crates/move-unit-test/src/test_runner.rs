@@ -19,11 +19,11 @@ use move_compiler::{
 };
 use move_core_types::{
     account_address::AccountAddress,
+    annotated_value as A,
     effects::ChangeSet,
     identifier::IdentStr,
-    language_storage::{ModuleId, TypeTag},
+    language_storage::ModuleId,
     runtime_value::{serialize_values, MoveValue},
-    u256::U256,
     vm_status::StatusCode,
 };
 use move_vm_runtime::{
@@ -34,16 +34,20 @@ use move_vm_test_utils::{
     gas_schedule::{unit_cost_schedule, CostTable, Gas, GasStatus},
     InMemoryStorage,
 };
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, SeedableRng};
 use rayon::prelude::*;
 
 use crate::{
-    extensions, format_module_id,
+    extensions, format_module_id, random_test,
     test_reporter::{
         FailureReason, MoveError, TestFailure, TestResults, TestRunInfo, TestStatistics,
     },
 };
 
+/// The maximum number of executions spent shrinking the generated inputs of a
+/// failing random test
+const MAX_SHRINK_EXECUTIONS: usize = 1000;
+
 /// Test state common to all tests
 pub struct SharedTestingConfig {
     report_stacktrace_on_abort: bool,
@@ -312,24 +316,19 @@ impl SharedTestingConfig {
         let mut stats = TestStatistics::new();
 
         for (function_name, test_info) in &test_plan.tests {
-            let arguments = if test_info
+            let generated_layouts = test_info
                 .arguments
                 .iter()
-                .all(|arg| matches!(arg, TestArgument::Value(_)))
-            {
-                let test_arguments = test_info
-                    .arguments
-                    .iter()
-                    .map(|arg| match arg {
-                        TestArgument::Value(v) => v.clone(),
-                        _ => unreachable!(),
-                    })
-                    .collect::<Vec<_>>();
-                vec![(None, test_arguments)]
+                .filter_map(|arg| match arg {
+                    TestArgument::Value(_) => None,
+                    TestArgument::Generate { generated_type } => Some(generated_type),
+                })
+                .collect::<Vec<_>>();
+            let arguments = if generated_layouts.is_empty() {
+                vec![(None, vec![])]
             } else {
                 let mut args = vec![];
                 for i in 0..self.num_iters {
-                    let mut iter_args = vec![];
                     let prng_seed = self.prng_seed.unwrap_or_else(|| {
                         if self.deterministic_generation {
                             i
@@ -338,34 +337,38 @@ impl SharedTestingConfig {
                         }
                     });
                     let mut rng = StdRng::seed_from_u64(prng_seed);
-                    for arg in test_info.arguments.iter() {
-                        match arg {
-                            TestArgument::Value(v) => iter_args.push(v.clone()),
-                            TestArgument::Generate { generated_type } => {
-                                iter_args.push(Self::generate_value_for_typetag(
-                                    &mut rng,
-                                    generated_type,
-                                ));
-                            }
-                        }
-                    }
-                    args.push((Some(prng_seed), iter_args));
+                    let generated = generated_layouts
+                        .iter()
+                        .map(|layout| random_test::generate_value(&mut rng, layout))
+                        .collect::<Vec<_>>();
+                    args.push((Some(prng_seed), generated));
                 }
                 args
             };
             let len = arguments.len();
-            for (i, (prng_seed, args)) in arguments.into_iter().enumerate() {
+            for (i, (prng_seed, generated)) in arguments.into_iter().enumerate() {
                 if !self.exec_test_once(
                     test_plan,
                     global_test_context,
                     output,
                     function_name,
                     test_info,
-                    args,
+                    Self::test_arguments(test_info, &generated),
                     &mut stats,
                     prng_seed,
                     i == len - 1,
                 ) {
+                    if !generated.is_empty() {
+                        let counterexample = self.shrink_generated_arguments(
+                            test_plan,
+                            global_test_context,
+                            function_name,
+                            test_info,
+                            &generated_layouts,
+                            generated,
+                        );
+                        stats.add_counterexample(function_name, counterexample, test_plan);
+                    }
                     break;
                 }
             }
@@ -374,31 +377,69 @@ impl SharedTestingConfig {
         stats
     }
 
-    fn generate_value_for_typetag(rng: &mut StdRng, ty: &TypeTag) -> MoveValue {
-        match ty {
-            TypeTag::Address => {
-                MoveValue::Address(AccountAddress::from_bytes(rng.gen::<[u8; 32]>()).unwrap())
-            }
-            TypeTag::U8 => MoveValue::U8(rng.gen::<u8>()),
-            TypeTag::U16 => MoveValue::U16(rng.gen::<u16>()),
-            TypeTag::U32 => MoveValue::U32(rng.gen::<u32>()),
-            TypeTag::U64 => MoveValue::U64(rng.gen::<u64>()),
-            TypeTag::U128 => MoveValue::U128(rng.gen::<u128>()),
-            TypeTag::U256 => MoveValue::U256(rng.gen::<U256>()),
-            TypeTag::Vector(ty) => {
-                let len = rng.gen_range(0..1024);
-                let values = (0..len)
-                    .map(|_| Self::generate_value_for_typetag(rng, ty))
-                    .collect();
-                MoveValue::Vector(values)
+    // Interleaves the generated values with the values fixed in the test
+    // attribute, in parameter order
+    fn test_arguments(test_info: &TestCase, generated: &[A::MoveValue]) -> Vec<MoveValue> {
+        let mut generated = generated.iter();
+        test_info
+            .arguments
+            .iter()
+            .map(|arg| match arg {
+                TestArgument::Value(v) => v.clone(),
+                TestArgument::Generate { .. } => generated.next().unwrap().clone().undecorate(),
+            })
+            .collect()
+    }
+
+    // Shrinks the generated arguments of a failing random test to a smaller
+    // input on which the test still fails, and renders it for the test report
+    fn shrink_generated_arguments(
+        &self,
+        test_plan: &ModuleTestPlan,
+        global_test_context: &BTreeMap<ModuleId, NamedCompiledModule>,
+        function_name: &str,
+        test_info: &TestCase,
+        layouts: &[&A::MoveTypeLayout],
+        generated: Vec<A::MoveValue>,
+    ) -> String {
+        let shrunk = random_test::shrink(layouts, generated, MAX_SHRINK_EXECUTIONS, |candidate| {
+            let (_cs_result, _ext_result, exec_result, _test_run_info) = self.execute_via_move_vm(
+                test_plan,
+                function_name,
+                Self::test_arguments(test_info, candidate),
+            );
+            !Self::is_expected_outcome(&exec_result, test_info, global_test_context)
+        });
+        shrunk
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // Whether the result of executing a test is the one expected by its
+    // attributes, following the same rules as `exec_test_once`
+    fn is_expected_outcome(
+        exec_result: &VMResult<Vec<Vec<u8>>>,
+        test_info: &TestCase,
+        global_test_context: &BTreeMap<ModuleId, NamedCompiledModule>,
+    ) -> bool {
+        let err = match exec_result {
+            Ok(_) => return test_info.expected_failure.is_none(),
+            Err(err) => err,
+        };
+        let sub_status = err.sub_status().and_then(|status| {
+            convert_clever_move_abort_error(status, err.location(), global_test_context)
+        });
+        match test_info.expected_failure.as_ref() {
+            None => false,
+            Some(ExpectedFailure::Expected) => true,
+            Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
+                expected_err == &MoveError(err.major_status(), sub_status, err.location().clone())
             }
-            TypeTag::Bool => MoveValue::Bool(rng.gen::<bool>()),
-            TypeTag::Struct(_) => {
-                unreachable!(
-                    "Structs are not supported as generated values in unit tests and cannot get to this point"
-                )
+            Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code)) => {
+                err.major_status() == StatusCode::ABORTED && sub_status.as_ref() == Some(code)
             }
-            TypeTag::Signer => unreachable!("Signer arguments not allowed"),
         }
     }
 
@@ -0,0 +1,27 @@
+module 0x1::random_struct_test {
+    use std::option::{Self, Option};
+
+    struct Point has copy, drop {
+        x: u64,
+        y: u64,
+    }
+
+    #[random_test]
+    fun should_fail_struct_threshold(p: Point) {
+        if (p.x >= 10) abort 0
+    }
+
+    #[random_test]
+    fun should_pass_option(o: Option<u64>) {
+        if (option::is_some(&o)) {
+            option::destroy_some(o);
+        } else {
+            option::destroy_none(o);
+        }
+    }
+
+    #[random_test]
+    fun should_pass_struct(p: Point) {
+        let Point { x: _, y: _ } = p;
+    }
+}
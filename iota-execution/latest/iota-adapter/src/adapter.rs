@@ -5,7 +5,7 @@
 pub use checked::*;
 #[iota_macros::with_checked_arithmetic]
 mod checked {
-    use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+    use std::{collections::BTreeMap, sync::Arc};
 
     use anyhow::Result;
     use iota_move_natives::{NativesCostTable, object_runtime, object_runtime::ObjectRuntime};
@@ -24,10 +24,8 @@ mod checked {
     use move_bytecode_verifier::verify_module_with_config_metered;
     use move_bytecode_verifier_meter::{Meter, Scope};
     use move_core_types::account_address::AccountAddress;
-    #[cfg(feature = "gas-profiler")]
-    use move_vm_config::runtime::VMProfilerConfig;
     use move_vm_config::{
-        runtime::{VMConfig, VMRuntimeLimitsConfig},
+        runtime::{VMConfig, VMProfilerConfig, VMRuntimeLimitsConfig},
         verifier::VerifierConfig,
     };
     use move_vm_runtime::{
@@ -41,20 +39,16 @@ mod checked {
     /// that sets limits for vector length, value depth, and other
     /// runtime options based on the provided `ProtocolConfig`. If gas profiling
     /// is enabled, the function configures the profiler with the provided
-    /// path.
+    /// profiler config.
     pub fn new_move_vm(
         natives: NativeFunctionTable,
         protocol_config: &ProtocolConfig,
-        _enable_profiler: Option<PathBuf>,
+        _enable_profiler: Option<VMProfilerConfig>,
     ) -> Result<MoveVM, IotaError> {
         #[cfg(not(feature = "gas-profiler"))]
         let vm_profiler_config = None;
         #[cfg(feature = "gas-profiler")]
-        let vm_profiler_config = _enable_profiler.clone().map(|path| VMProfilerConfig {
-            full_path: path,
-            track_bytecode_instructions: false,
-            use_long_function_name: false,
-        });
+        let vm_profiler_config = _enable_profiler;
         MoveVM::new_with_config(natives, VMConfig {
             verifier: protocol_config.verifier_config(/* for_signing */ false),
             max_binary_format_version: protocol_config.move_binary_format_version(),
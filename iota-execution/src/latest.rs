@@ -2,7 +2,7 @@
 // Modifications Copyright (c) 2024 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use std::{collections::HashSet, sync::Arc};
 
 use iota_adapter_latest::{
     adapter::{new_move_vm, run_metered_move_bytecode_verifier},
@@ -29,7 +29,10 @@ use iota_types::{
 use iota_verifier_latest::meter::IotaVerifierMeter;
 use move_binary_format::CompiledModule;
 use move_bytecode_verifier_meter::Meter;
-use move_vm_config::verifier::{MeterConfig, VerifierConfig};
+use move_vm_config::{
+    runtime::VMProfilerConfig,
+    verifier::{MeterConfig, VerifierConfig},
+};
 use move_vm_runtime_latest::move_vm::MoveVM;
 
 use crate::{executor, verifier};
@@ -45,7 +48,7 @@ impl Executor {
     pub(crate) fn new(
         protocol_config: &ProtocolConfig,
         silent: bool,
-        enable_profiler: Option<PathBuf>,
+        enable_profiler: Option<VMProfilerConfig>,
     ) -> Result<Self, IotaError> {
         Ok(Executor(Arc::new(new_move_vm(
             all_natives(silent, protocol_config),
@@ -4,11 +4,12 @@
 
 // $GENERATED_MESSAGE
 
-use std::{path::PathBuf, sync::Arc};
+use std::sync::Arc;
 
 pub use executor::Executor;
 use iota_protocol_config::ProtocolConfig;
 use iota_types::{error::IotaResult, metrics::BytecodeVerifierMetrics};
+use move_vm_config::runtime::VMProfilerConfig;
 pub use verifier::Verifier;
 
 pub mod executor;
@@ -23,7 +24,7 @@ mod tests;
 pub fn executor(
     protocol_config: &ProtocolConfig,
     silent: bool,
-    enable_profiler: Option<PathBuf>,
+    enable_profiler: Option<VMProfilerConfig>,
 ) -> IotaResult<Arc<dyn Executor + Send + Sync>> {
     let version = protocol_config.execution_version_as_option().unwrap_or(1);
     Ok(match version {